@@ -361,6 +361,8 @@ pub(crate) fn expand_test_or_bench(
                                         cx.expr_path(test_type_path("Unknown"))
                                     }
                                 },),
+                                // item_path: None, only doctests document an item
+                                field("item_path", cx.expr_none(sp)),
                                 // },
                             ],),
                         ),
@@ -255,6 +255,7 @@ pub fn run_compiler(at_args: &[String], callbacks: &mut (dyn Callbacks + Send))
         registry: diagnostics_registry(),
         using_internal_features: &USING_INTERNAL_FEATURES,
         expanded_args: args,
+        early_sarif_results: default_early_dcx.deferred_sarif_results(),
     };
 
     let has_input = match make_input(&default_early_dcx, &matches.free) {
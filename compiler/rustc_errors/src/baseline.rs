@@ -0,0 +1,165 @@
+//! Diagnostic baselines, for `-Z diagnostic-baseline`.
+//!
+//! A baseline lists the lint diagnostics of a compilation, so that later compilations only report
+//! the new ones. Entries are keyed by the lint name, the file of the primary span, and a hash of
+//! the source text of the primary span with its whitespace normalized. Line numbers are left out
+//! on purpose, so that entries survive edits elsewhere in the file.
+//!
+//! Baselines are checked into repositories and shared between toolchains, so the hash is the
+//! 64-bit [FNV-1a] hash of the UTF-8 bytes of the normalized text, rather than one of the hashes
+//! the compiler uses internally, which may change between releases.
+//!
+//! The file has one entry per line, with the three fields separated by tabs. An entry is repeated
+//! once for every diagnostic it matches. Empty lines and lines starting with `#` are ignored.
+//!
+//! [FNV-1a]: https://datatracker.ietf.org/doc/html/draft-eastlake-fnv
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use rustc_span::source_map::SourceMap;
+
+use crate::DiagInner;
+
+#[cfg(test)]
+mod tests;
+
+const HEADER: &str = "# rustc diagnostic baseline: <lint>\t<file>\t<span text hash>";
+
+/// A lint diagnostic, as identified by a baseline.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BaselineEntry {
+    pub lint: String,
+    /// The file of the primary span, or the empty string for diagnostics without one.
+    pub file: String,
+    pub hash: u64,
+}
+
+impl BaselineEntry {
+    /// Returns the entry for `diag`, or `None` if it isn't a lint.
+    fn new(diag: &DiagInner, sm: Option<&SourceMap>) -> Option<BaselineEntry> {
+        let lint = diag.is_lint.as_ref()?.name.clone();
+        let (file, text) = match (sm, diag.span.primary_span()) {
+            (Some(sm), Some(span)) if !span.is_dummy() => {
+                let file = sm.lookup_char_pos(span.lo()).file;
+                let file = sm.filename_for_diagnostics(&file.name).to_string().replace('\\', "/");
+                (file, sm.span_to_snippet(span).unwrap_or_default())
+            }
+            _ => (String::new(), String::new()),
+        };
+
+        let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        Some(BaselineEntry { lint, file, hash: fnv1a_64(normalized.as_bytes()) })
+    }
+
+    fn parse(line: &str) -> Option<BaselineEntry> {
+        let mut fields = line.split('\t');
+        let (lint, file, hash) = (fields.next()?, fields.next()?, fields.next()?);
+        if lint.is_empty() || fields.next().is_some() {
+            return None;
+        }
+        let hash = u64::from_str_radix(hash, 16).ok()?;
+        Some(BaselineEntry { lint: lint.to_owned(), file: file.to_owned(), hash })
+    }
+}
+
+/// The 64-bit FNV-1a hash of `bytes`, see the [module docs](self).
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
+}
+
+/// The baseline of a compilation, see the [module docs](self).
+pub struct DiagnosticBaseline {
+    path: PathBuf,
+    /// Whether this compilation records the baseline (`-Z record-diagnostic-baseline`). Otherwise
+    /// the baseline suppresses the diagnostics it lists.
+    recording: bool,
+    /// When recording, the number of diagnostics seen for each entry. Otherwise, the number of
+    /// diagnostics each entry has yet to suppress.
+    entries: BTreeMap<BaselineEntry, usize>,
+}
+
+impl DiagnosticBaseline {
+    /// Loads the baseline at `path`, to suppress the diagnostics it lists.
+    pub fn load(path: &Path) -> Result<DiagnosticBaseline, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        DiagnosticBaseline::parse(path, &contents)
+    }
+
+    /// Starts recording a baseline, which replaces the file at `path` once it is written.
+    pub fn record(path: &Path) -> DiagnosticBaseline {
+        DiagnosticBaseline { path: path.to_owned(), recording: true, entries: BTreeMap::new() }
+    }
+
+    fn parse(path: &Path, contents: &str) -> Result<DiagnosticBaseline, String> {
+        let mut baseline = DiagnosticBaseline {
+            path: path.to_owned(),
+            recording: false,
+            entries: BTreeMap::new(),
+        };
+        for (i, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = BaselineEntry::parse(line)
+                .ok_or_else(|| format!("malformed entry on line {}: `{line}`", i + 1))?;
+            *baseline.entries.entry(entry).or_default() += 1;
+        }
+        Ok(baseline)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Returns whether `diag` is suppressed by the baseline. When recording, `diag` is added to
+    /// the baseline instead and never suppressed. Only lints are taken into account.
+    pub(crate) fn suppress(&mut self, diag: &DiagInner, sm: Option<&SourceMap>) -> bool {
+        let Some(entry) = BaselineEntry::new(diag, sm) else {
+            return false;
+        };
+        if self.recording {
+            *self.entries.entry(entry).or_default() += 1;
+            return false;
+        }
+        match self.entries.get_mut(&entry) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the entries that didn't match as many diagnostics as they list, with the number of
+    /// missing diagnostics. Always empty when recording.
+    pub fn stale_entries(&self) -> impl Iterator<Item = (&BaselineEntry, usize)> {
+        self.entries
+            .iter()
+            .filter(|&(_, &count)| !self.recording && count > 0)
+            .map(|(entry, &count)| (entry, count))
+    }
+
+    /// Renders the recorded baseline in the file format.
+    fn render(&self) -> String {
+        let mut out = format!("{HEADER}\n");
+        for (entry, &count) in &self.entries {
+            for _ in 0..count {
+                out.push_str(&format!("{}\t{}\t{:016x}\n", entry.lint, entry.file, entry.hash));
+            }
+        }
+        out
+    }
+
+    /// Writes the recorded baseline to its file.
+    pub fn write(&self) -> io::Result<()> {
+        fs::write(&self.path, self.render())
+    }
+}
@@ -0,0 +1,133 @@
+use rustc_span::source_map::FilePathMapping;
+use rustc_span::{BytePos, Span};
+
+use super::*;
+use crate::emitter::{HumanEmitter, stderr_destination};
+use crate::{ColorConfig, DiagCtxt, DiagCtxtHandle};
+
+/// Runs `f` with a `DiagCtxt` whose source map contains `src/lib.rs` with `code`.
+fn with_source(code: &str, f: impl FnOnce(DiagCtxtHandle<'_>, &SourceMap)) {
+    rustc_span::create_default_session_globals_then(|| {
+        let sm = SourceMap::new(FilePathMapping::empty());
+        sm.new_source_file(Path::new("src/lib.rs").to_owned().into(), code.to_owned());
+        let fallback_bundle =
+            crate::fallback_fluent_bundle(vec![crate::DEFAULT_LOCALE_RESOURCE], false);
+        // The diagnostics are only created, never emitted.
+        let emitter = HumanEmitter::new(stderr_destination(ColorConfig::Never), fallback_bundle);
+        let dcx = DiagCtxt::new(Box::new(emitter));
+        f(dcx.handle(), &sm);
+    })
+}
+
+/// Checks a warning at `lo..hi` against `baseline`, as a lint named `lint` if there is one.
+fn suppress(
+    baseline: &mut DiagnosticBaseline,
+    dcx: DiagCtxtHandle<'_>,
+    sm: &SourceMap,
+    lint: Option<&str>,
+    (lo, hi): (u32, u32),
+) -> bool {
+    let mut diag = dcx.struct_span_warn(Span::with_root_ctxt(BytePos(lo), BytePos(hi)), "lint");
+    if let Some(lint) = lint {
+        diag.is_lint(lint.to_owned(), false);
+    }
+    let suppressed = baseline.suppress(&diag, Some(sm));
+    diag.cancel();
+    suppressed
+}
+
+fn recording() -> DiagnosticBaseline {
+    DiagnosticBaseline::record(Path::new("baseline.txt"))
+}
+
+#[test]
+fn record_then_suppress() {
+    with_source("fn f(a: u8, b: u8) {}\nfn g(a: u8) {}\n", |dcx, sm| {
+        let lints = [(5, 6), (12, 13), (27, 28)];
+
+        let mut baseline = recording();
+        for span in lints {
+            assert!(!suppress(&mut baseline, dcx, sm, Some("unused_variables"), span));
+        }
+        // Diagnostics that aren't lints are never part of a baseline.
+        assert!(!suppress(&mut baseline, dcx, sm, None, (0, 2)));
+        assert_eq!(baseline.stale_entries().count(), 0);
+
+        let rendered = baseline.render();
+        // Both `a`s share an entry, since line numbers aren't part of it.
+        assert_eq!(rendered.lines().filter(|line| !line.starts_with('#')).count(), 3);
+
+        let mut baseline = DiagnosticBaseline::parse(Path::new("baseline.txt"), &rendered).unwrap();
+        assert!(!baseline.is_recording());
+        for span in lints {
+            assert!(suppress(&mut baseline, dcx, sm, Some("unused_variables"), span));
+        }
+        // Each entry suppresses as many diagnostics as it was recorded for.
+        assert!(!suppress(&mut baseline, dcx, sm, Some("unused_variables"), (27, 28)));
+        assert!(!suppress(&mut baseline, dcx, sm, Some("dead_code"), (3, 4)));
+        assert_eq!(baseline.stale_entries().count(), 0);
+    });
+}
+
+#[test]
+fn stale_entries() {
+    with_source("fn f(a: u8, b: u8) {}\nfn g(a: u8) {}\n", |dcx, sm| {
+        let mut baseline = recording();
+        for span in [(5, 6), (12, 13), (27, 28)] {
+            suppress(&mut baseline, dcx, sm, Some("unused_variables"), span);
+        }
+
+        let mut baseline =
+            DiagnosticBaseline::parse(Path::new("baseline.txt"), &baseline.render()).unwrap();
+        assert!(suppress(&mut baseline, dcx, sm, Some("unused_variables"), (12, 13)));
+        let stale = baseline.stale_entries().collect::<Vec<_>>();
+        assert_eq!(stale.len(), 1);
+        let (entry, count) = stale[0];
+        assert_eq!(
+            (entry.lint.as_str(), entry.file.as_str(), count),
+            ("unused_variables", "src/lib.rs", 2)
+        );
+    });
+}
+
+#[test]
+fn whitespace_is_normalized() {
+    with_source("let _ = x  +\n    y;\nlet _ = x + y;\n", |dcx, sm| {
+        let mut baseline = recording();
+        suppress(&mut baseline, dcx, sm, Some("clippy::precedence"), (8, 18));
+        suppress(&mut baseline, dcx, sm, Some("clippy::precedence"), (28, 33));
+        assert_eq!(baseline.entries.len(), 1);
+        assert_eq!(baseline.entries.values().next(), Some(&2));
+    });
+}
+
+#[test]
+fn malformed_entries() {
+    let path = Path::new("baseline.txt");
+    let baseline = DiagnosticBaseline::parse(path, "# comment\n\ndead_code\t\t00000000000000ff\n");
+    let baseline = baseline.unwrap();
+    let entries = baseline.entries.keys().collect::<Vec<_>>();
+    assert_eq!(entries, [&BaselineEntry { lint: "dead_code".into(), file: "".into(), hash: 0xff }]);
+
+    for contents in ["dead_code\tsrc/lib.rs\n", "dead_code\tsrc/lib.rs\txyz\n", "\tsrc/lib.rs\t1\n"]
+    {
+        assert_eq!(
+            DiagnosticBaseline::parse(path, contents).err().as_deref(),
+            Some(format!("malformed entry on line 1: `{}`", contents.trim_end()).as_str()),
+        );
+    }
+}
+
+#[test]
+fn hash_is_fnv1a() {
+    // The hashes are stored in baselines, so they must never change.
+    assert_eq!(fnv1a_64(b""), 0xcbf29ce484222325);
+    assert_eq!(fnv1a_64(b"a"), 0xaf63dc4c8601ec8c);
+    assert_eq!(fnv1a_64(b"foobar"), 0x85944171f73967e8);
+}
+
+#[test]
+fn missing_file_is_an_error() {
+    let path = std::env::temp_dir().join(format!("missing-baseline-{}", std::process::id()));
+    assert!(DiagnosticBaseline::load(&path).is_err());
+}
@@ -190,6 +190,10 @@ fn emit_unused_externs(
     ) {
     }
 
+    /// Finish the output once all diagnostics have been emitted.
+    /// Currently only used by the SARIF format, which writes its whole log here.
+    fn finish(&mut self) {}
+
     /// Checks if should show explanations about "rustc --explain"
     fn should_show_explain(&self) -> bool {
         true
@@ -43,6 +43,7 @@
 use std::{fmt, panic};
 
 use Level::*;
+use baseline::DiagnosticBaseline;
 pub use codes::*;
 pub use diagnostic::{
     BugAbort, Diag, DiagArg, DiagArgMap, DiagArgName, DiagArgValue, DiagInner, DiagStyledString,
@@ -80,6 +81,7 @@
 use crate::registry::Registry;
 
 pub mod annotate_snippet_emitter_writer;
+pub mod baseline;
 pub mod codes;
 mod diagnostic;
 mod diagnostic_impls;
@@ -89,6 +91,7 @@
 mod lock;
 pub mod markdown;
 pub mod registry;
+pub mod sarif;
 mod snippet;
 mod styled_buffer;
 #[cfg(test)]
@@ -609,6 +612,13 @@ struct DiagCtxtInner {
     /// The file where the ICE information is stored. This allows delayed_span_bug backtraces to be
     /// stored along side the main panic backtrace.
     ice_file: Option<PathBuf>,
+
+    /// The baseline of lint diagnostics to record or suppress (rustc: see `-Z diagnostic-baseline`).
+    diagnostic_baseline: Option<DiagnosticBaseline>,
+
+    /// The hashes of the diagnostics suppressed by `diagnostic_baseline`, so
+    /// that their deduplicated repeats are suppressed as well.
+    baseline_suppressed: FxHashSet<Hash128>,
 }
 
 /// A key denoting where from a diagnostic was stashed.
@@ -729,6 +739,11 @@ pub fn with_registry(mut self, registry: Registry) -> Self {
         self
     }
 
+    pub fn with_diagnostic_baseline(mut self, baseline: DiagnosticBaseline) -> Self {
+        self.inner.get_mut().diagnostic_baseline = Some(baseline);
+        self
+    }
+
     pub fn new(emitter: Box<DynEmitter>) -> Self {
         Self { inner: Lock::new(DiagCtxtInner::new(emitter)) }
     }
@@ -828,6 +843,8 @@ pub fn reset_err_count(&self) {
             future_breakage_diagnostics,
             fulfilled_expectations,
             ice_file: _,
+            diagnostic_baseline: _,
+            baseline_suppressed,
         } = inner.deref_mut();
 
         // For the `Vec`s and `HashMap`s, we overwrite with an empty container to free the
@@ -843,6 +860,7 @@ pub fn reset_err_count(&self) {
         *taught_diagnostics = Default::default();
         *emitted_diagnostic_codes = Default::default();
         *emitted_diagnostics = Default::default();
+        *baseline_suppressed = Default::default();
         *stashed_diagnostics = Default::default();
         *future_breakage_diagnostics = Default::default();
         *fulfilled_expectations = Default::default();
@@ -1017,6 +1035,60 @@ pub fn has_errors_or_delayed_bugs(&self) -> Option<ErrorGuaranteed> {
         self.inner.borrow().has_errors_or_delayed_bugs()
     }
 
+    /// Lets the emitter finish its output, see [`Emitter::finish`]. Diagnostics emitted
+    /// afterwards may not be part of the output.
+    pub fn finish_emitter(&self) {
+        self.inner.borrow_mut().emitter.finish();
+    }
+
+    /// Writes the diagnostic baseline if it is being recorded, or warns about its stale entries
+    /// otherwise. Does nothing after errors, since those may have kept lints from running.
+    pub fn finish_diagnostic_baseline(&self) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.has_errors_excluding_lint_errors().is_some() {
+            return;
+        }
+        let Some(baseline) = inner.diagnostic_baseline.take() else {
+            return;
+        };
+        let path = baseline.path().display();
+
+        if baseline.is_recording() {
+            if let Err(err) = baseline.write() {
+                let msg = format!("failed to write diagnostic baseline `{path}`: {err}");
+                inner.emit_diagnostic(DiagInner::new(Error, msg), self.tainted_with_errors);
+            }
+            return;
+        }
+
+        let stale = baseline.stale_entries().collect::<Vec<_>>();
+        if stale.is_empty() {
+            return;
+        }
+        let count: usize = stale.iter().map(|&(_, count)| count).sum();
+        let entries = if count == 1 { "entry" } else { "entries" };
+        let mut diag = DiagInner::new(
+            Warning,
+            format!("{count} stale {entries} in diagnostic baseline `{path}`"),
+        );
+        for (entry, count) in stale {
+            let file = if entry.file.is_empty() { "<no file>" } else { &entry.file };
+            let times = if count > 1 { format!(" ({count} times)") } else { String::new() };
+            diag.sub(
+                Note,
+                format!("no `{}` lint matches `{file}` {:016x}{times}", entry.lint, entry.hash),
+                MultiSpan::new(),
+            );
+        }
+        diag.sub(
+            Help,
+            "remove the stale entries, or record a new baseline with \
+             `-Z record-diagnostic-baseline`",
+            MultiSpan::new(),
+        );
+        inner.emit_diagnostic(diag, None);
+    }
+
     pub fn print_error_count(&self) {
         let mut inner = self.inner.borrow_mut();
 
@@ -1479,6 +1551,8 @@ fn new(emitter: Box<DynEmitter>) -> Self {
             future_breakage_diagnostics: Vec::new(),
             fulfilled_expectations: Default::default(),
             ice_file: None,
+            diagnostic_baseline: None,
+            baseline_suppressed: Default::default(),
         }
     }
 
@@ -1596,19 +1670,34 @@ fn emit_diagnostic(
                 self.emitted_diagnostic_codes.insert(code);
             }
 
-            let already_emitted = {
+            let diagnostic_hash = {
                 let mut hasher = StableHasher::new();
                 diagnostic.hash(&mut hasher);
-                let diagnostic_hash = hasher.finish();
-                !self.emitted_diagnostics.insert(diagnostic_hash)
+                hasher.finish()
             };
+            let already_emitted = !self.emitted_diagnostics.insert(diagnostic_hash);
 
             let is_error = diagnostic.is_error();
             let is_lint = diagnostic.is_lint.is_some();
+            let is_duplicate = self.flags.deduplicate_diagnostics && already_emitted;
+
+            // Lints listed in the diagnostic baseline are neither emitted nor
+            // counted, even if they are denied. The same goes for the repeats
+            // of a suppressed lint that are deduplicated away.
+            if is_duplicate {
+                if self.baseline_suppressed.contains(&diagnostic_hash) {
+                    return None;
+                }
+            } else if let Some(baseline) = &mut self.diagnostic_baseline
+                && baseline.suppress(&diagnostic, self.emitter.source_map())
+            {
+                self.baseline_suppressed.insert(diagnostic_hash);
+                return None;
+            }
 
             // Only emit the diagnostic if we've been asked to deduplicate or
             // haven't already emitted an equivalent diagnostic.
-            if !(self.flags.deduplicate_diagnostics && already_emitted) {
+            if !is_duplicate {
                 debug!(?diagnostic);
                 debug!(?self.emitted_diagnostics);
 
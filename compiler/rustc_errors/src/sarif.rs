@@ -0,0 +1,478 @@
+//! An emitter writing diagnostics as a [SARIF 2.1.0] log, the format understood by code scanning
+//! tools.
+//!
+//! Error codes and lint names become the rules of the log, primary spans become the locations of
+//! a result, other spans and sub-diagnostics become its related locations, and machine-applicable
+//! suggestions become its fixes.
+//!
+//! A SARIF log is a single JSON document, so unlike the JSON emitter, this emitter collects the
+//! results and only writes the log once the compilation is finished. The emitters that only exist
+//! until the session is created defer their results to the session's emitter through
+//! [`DeferredResults`], so that a compilation writes a single log.
+//!
+//! [SARIF 2.1.0]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use std::io::{self, Write};
+use std::mem;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use derive_setters::Setters;
+use rustc_data_structures::fx::FxIndexMap;
+use rustc_data_structures::sync::IntoDynSyncSend;
+use rustc_error_messages::FluentArgs;
+use rustc_lint_defs::Applicability;
+use rustc_span::source_map::SourceMap;
+use rustc_span::{FileName, Span};
+use serde::Serialize;
+
+use crate::diagnostic::IsLint;
+use crate::emitter::Emitter;
+use crate::registry::Registry;
+use crate::translation::{Translate, to_fluent_args};
+use crate::{CodeSuggestion, FluentBundle, LazyFallbackBundle, Level, Suggestions};
+
+#[cfg(test)]
+mod tests;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// Results deferred by emitters with `defer_results`, along with the rules they reference, until
+/// an emitter sharing them writes a log.
+#[derive(Clone, Default)]
+pub struct DeferredResults(Arc<Mutex<Vec<(Option<Rule>, SarifResult)>>>);
+
+impl DeferredResults {
+    fn lock(&self) -> MutexGuard<'_, Vec<(Option<Rule>, SarifResult)>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[derive(Setters)]
+pub struct SarifEmitter {
+    #[setters(skip)]
+    dst: IntoDynSyncSend<Box<dyn Write + Send>>,
+    #[setters(skip)]
+    sm: Option<Arc<SourceMap>>,
+    fluent_bundle: Option<Arc<FluentBundle>>,
+    #[setters(skip)]
+    fallback_bundle: LazyFallbackBundle,
+    /// Whether to add the results to `deferred_results`, instead of writing a log. Emitters that
+    /// only exist until the session is created turn this on, so that their warnings end up in
+    /// the log of the session. They still write the log if they emitted an error, as the
+    /// compilation stops before a session is created then.
+    defer_results: bool,
+    /// Results deferred by earlier emitters, which are written before the results of this one.
+    deferred_results: DeferredResults,
+    /// Rules referenced by the results, by their id.
+    #[setters(skip)]
+    rules: FxIndexMap<String, Rule>,
+    #[setters(skip)]
+    results: Vec<SarifResult>,
+    #[setters(skip)]
+    has_errors: bool,
+    /// Whether the log has been written, see [`Emitter::finish`].
+    #[setters(skip)]
+    finished: bool,
+}
+
+impl SarifEmitter {
+    pub fn new(
+        dst: Box<dyn Write + Send>,
+        sm: Option<Arc<SourceMap>>,
+        fallback_bundle: LazyFallbackBundle,
+    ) -> SarifEmitter {
+        SarifEmitter {
+            dst: IntoDynSyncSend(dst),
+            sm,
+            fluent_bundle: None,
+            fallback_bundle,
+            defer_results: false,
+            deferred_results: DeferredResults::default(),
+            rules: FxIndexMap::default(),
+            results: Vec::new(),
+            has_errors: false,
+            finished: false,
+        }
+    }
+
+    fn write_log(&mut self) -> io::Result<()> {
+        let log = Log {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: [Run {
+                tool: Tool {
+                    driver: ToolComponent {
+                        name: "rustc",
+                        information_uri: "https://www.rust-lang.org/",
+                        rules: self.rules.values().collect(),
+                    },
+                },
+                invocations: [Invocation { execution_successful: !self.has_errors }],
+                column_kind: "unicodeCodePoints",
+                results: &self.results,
+            }],
+        };
+        serde_json::to_writer_pretty(&mut *self.dst, &log)?;
+        self.dst.write_all(b"\n")?;
+        self.dst.flush()
+    }
+
+    /// Adds a result to the log, along with the rule it references.
+    fn add_result(&mut self, rule: Option<Rule>, mut result: SarifResult) {
+        self.has_errors |= result.level == "error";
+        if let Some(rule) = rule {
+            let entry = self.rules.entry(rule.id.clone());
+            result.rule_index = Some(entry.index());
+            entry.or_insert(rule);
+        }
+        self.results.push(result);
+    }
+
+    fn location(&self, span: Span, message: Option<String>) -> Option<Location> {
+        let (artifact_location, region) = self.region(span)?;
+        Some(Location {
+            physical_location: PhysicalLocation { artifact_location, region },
+            message: message.map(|text| Message { text }),
+        })
+    }
+
+    /// Converts a span to a region of a source file. Spans that don't point into a source file
+    /// on disk can't be represented in SARIF.
+    fn region(&self, span: Span) -> Option<(ArtifactLocation, Region)> {
+        let sm = self.sm.as_ref()?;
+        if span.is_dummy() {
+            return None;
+        }
+        let start = sm.lookup_char_pos(span.lo());
+        let end = sm.lookup_char_pos(span.hi());
+        if !matches!(start.file.name, FileName::Real(_)) {
+            return None;
+        }
+        let path = sm.filename_for_diagnostics(&start.file.name).to_string();
+        let region = Region {
+            start_line: start.line,
+            start_column: start.col.0 + 1,
+            end_line: end.line,
+            end_column: end.col.0 + 1,
+        };
+        Some((ArtifactLocation { uri: path_to_uri(&path) }, region))
+    }
+
+    fn fix(&self, suggestion: &CodeSuggestion, args: &FluentArgs<'_>) -> Vec<Fix> {
+        let description = self.translate_message(&suggestion.msg, args).unwrap().to_string();
+        suggestion
+            .substitutions
+            .iter()
+            .filter_map(|substitution| {
+                let mut changes: FxIndexMap<String, Vec<Replacement>> = FxIndexMap::default();
+                for part in &substitution.parts {
+                    // A fix that can only be applied in part would break the code.
+                    let (artifact_location, deleted_region) = self.region(part.span)?;
+                    changes.entry(artifact_location.uri).or_default().push(Replacement {
+                        deleted_region,
+                        inserted_content: ArtifactContent { text: part.snippet.clone() },
+                    });
+                }
+                let artifact_changes = changes
+                    .into_iter()
+                    .map(|(uri, replacements)| ArtifactChange {
+                        artifact_location: ArtifactLocation { uri },
+                        replacements,
+                    })
+                    .collect();
+                Some(Fix { description: Message { text: description.clone() }, artifact_changes })
+            })
+            .collect()
+    }
+}
+
+impl Drop for SarifEmitter {
+    fn drop(&mut self) {
+        // Emitters that are never finished, like the ones used before the session is created,
+        // still write their log.
+        self.finish();
+    }
+}
+
+impl Translate for SarifEmitter {
+    fn fluent_bundle(&self) -> Option<&FluentBundle> {
+        self.fluent_bundle.as_deref()
+    }
+
+    fn fallback_fluent_bundle(&self) -> &FluentBundle {
+        &self.fallback_bundle
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit_diagnostic(&mut self, diag: crate::DiagInner, registry: &Registry) {
+        let level = match diag.level {
+            Level::Bug | Level::Fatal | Level::Error | Level::DelayedBug => "error",
+            Level::ForceWarning(_) | Level::Warning => "warning",
+            Level::Note | Level::OnceNote | Level::Help | Level::OnceHelp => "note",
+            // Summaries like "aborting due to 2 previous errors" are not results.
+            Level::FailureNote | Level::Allow | Level::Expect(_) => return,
+        };
+        let args = to_fluent_args(diag.args.iter());
+        let mut text = self.translate_messages(&diag.messages, &args).into_owned();
+
+        let rule = if let Some(code) = diag.code {
+            let help_uri = registry
+                .try_find_description(code)
+                .ok()
+                .map(|_| format!("https://doc.rust-lang.org/error_codes/{code}.html"));
+            Some(Rule { id: code.to_string(), help_uri })
+        } else if let Some(IsLint { name, .. }) = &diag.is_lint {
+            Some(Rule { id: name.clone(), help_uri: None })
+        } else {
+            None
+        };
+
+        let mut locations = Vec::new();
+        let mut related_locations = Vec::new();
+        for span_label in diag.span.span_labels() {
+            let label = span_label
+                .label
+                .as_ref()
+                .map(|label| self.translate_message(label, &args).unwrap().to_string());
+            let location = self.location(span_label.span, label);
+            if span_label.is_primary {
+                locations.extend(location);
+            } else {
+                related_locations.extend(location);
+            }
+        }
+
+        for child in &diag.children {
+            let message =
+                format!("{}: {}", child.level, self.translate_messages(&child.messages, &args));
+            let child_locations: Vec<_> = child
+                .span
+                .primary_spans()
+                .iter()
+                .filter_map(|&span| self.location(span, Some(message.clone())))
+                .collect();
+            if child_locations.is_empty() {
+                text.push('\n');
+                text.push_str(&message);
+            } else {
+                related_locations.extend(child_locations);
+            }
+        }
+
+        let mut fixes = Vec::new();
+        let suggestions = match &diag.suggestions {
+            Suggestions::Enabled(suggestions) => &suggestions[..],
+            Suggestions::Sealed(suggestions) => &suggestions[..],
+            Suggestions::Disabled => &[],
+        };
+        for suggestion in suggestions {
+            if suggestion.applicability == Applicability::MachineApplicable {
+                fixes.extend(self.fix(suggestion, &args));
+            } else {
+                let message = self.translate_message(&suggestion.msg, &args).unwrap();
+                text.push_str("\nhelp: ");
+                text.push_str(&message);
+            }
+        }
+
+        let result = SarifResult {
+            rule_id: rule.as_ref().map(|rule| rule.id.clone()),
+            rule_index: None,
+            level,
+            message: Message { text },
+            locations,
+            related_locations,
+            fixes,
+        };
+        if self.defer_results {
+            self.has_errors |= level == "error";
+            self.deferred_results.lock().push((rule, result));
+        } else {
+            self.add_result(rule, result);
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.finished || (self.defer_results && !self.has_errors) {
+            return;
+        }
+        self.finished = true;
+        // The deferred results were emitted before the results of this emitter.
+        let results = mem::take(&mut self.results);
+        let deferred = mem::take(&mut *self.deferred_results.lock());
+        for (rule, result) in deferred {
+            self.add_result(rule, result);
+        }
+        self.results.extend(results);
+        if let Err(e) = self.write_log() {
+            // Don't turn a panic that is already unwinding into an abort.
+            if !std::thread::panicking() {
+                panic!("failed to print diagnostics: {e:?}");
+            }
+        }
+    }
+
+    fn source_map(&self) -> Option<&SourceMap> {
+        self.sm.as_deref()
+    }
+
+    fn should_show_explain(&self) -> bool {
+        false
+    }
+}
+
+/// Converts a path as shown in diagnostics to a URI reference. Relative paths stay relative, to
+/// be resolved against the directory the compiler was invoked in.
+fn path_to_uri(path: &str) -> String {
+    let is_absolute = Path::new(path).is_absolute();
+    let path = path.replace('\\', "/");
+    let mut uri = String::with_capacity(path.len());
+    if is_absolute {
+        uri.push_str("file://");
+        if !path.starts_with('/') {
+            // A Windows path with a drive letter.
+            uri.push('/');
+        }
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            b':' if is_absolute => uri.push(':'),
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+// The following data types are provided just for serialisation.
+
+#[derive(Serialize)]
+struct Log<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: [Run<'a>; 1],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Run<'a> {
+    tool: Tool<'a>,
+    invocations: [Invocation; 1],
+    /// How `Region` columns are counted.
+    column_kind: &'static str,
+    results: &'a [SarifResult],
+}
+
+#[derive(Serialize)]
+struct Tool<'a> {
+    driver: ToolComponent<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ToolComponent<'a> {
+    name: &'static str,
+    information_uri: &'static str,
+    rules: Vec<&'a Rule>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Invocation {
+    execution_successful: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule {
+    /// The error code (e.g. "E1234") or the lint name.
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help_uri: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_index: Option<usize>,
+    /// "error", "warning" or "note".
+    level: &'static str,
+    message: Message,
+    locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    fixes: Vec<Fix>,
+}
+
+#[derive(Serialize)]
+struct Message {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+    physical_location: PhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+    artifact_location: ArtifactLocation,
+    region: Region,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+    /// 1-based.
+    start_line: usize,
+    /// 1-based, character offset.
+    start_column: usize,
+    end_line: usize,
+    /// 1-based, character offset of the first character after the region.
+    end_column: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Fix {
+    description: Message,
+    artifact_changes: Vec<ArtifactChange>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactChange {
+    artifact_location: ArtifactLocation,
+    replacements: Vec<Replacement>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Replacement {
+    deleted_region: Region,
+    inserted_content: ArtifactContent,
+}
+
+#[derive(Serialize)]
+struct ArtifactContent {
+    text: String,
+}
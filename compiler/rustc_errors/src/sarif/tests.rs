@@ -0,0 +1,200 @@
+use std::sync::Mutex;
+
+use rustc_span::BytePos;
+use rustc_span::source_map::FilePathMapping;
+use serde_json::{Value, json};
+
+use super::*;
+use crate::{DiagCtxt, ErrCode};
+
+struct Shared<T> {
+    data: Arc<Mutex<T>>,
+}
+
+impl<T: Write> Write for Shared<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.data.lock().unwrap().flush()
+    }
+}
+
+/// Emits diagnostics for `code` through a `SarifEmitter`, and returns the written log.
+fn emit_sarif(code: &str, emit: impl FnOnce(&DiagCtxt)) -> Value {
+    emit_sarif_after(DeferredResults::default(), code, emit)
+}
+
+/// Like `emit_sarif`, but the log starts with the results `deferred` by earlier emitters.
+fn emit_sarif_after(deferred: DeferredResults, code: &str, emit: impl FnOnce(&DiagCtxt)) -> Value {
+    rustc_span::create_default_session_globals_then(|| {
+        let sm = Arc::new(SourceMap::new(FilePathMapping::empty()));
+        sm.new_source_file(Path::new("src/main.rs").to_owned().into(), code.to_owned());
+        let fallback_bundle =
+            crate::fallback_fluent_bundle(vec![crate::DEFAULT_LOCALE_RESOURCE], false);
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let emitter =
+            SarifEmitter::new(Box::new(Shared { data: output.clone() }), Some(sm), fallback_bundle)
+                .deferred_results(deferred);
+        let dcx = DiagCtxt::new(Box::new(emitter));
+        emit(&dcx);
+        // The log is written when the emitter is finished, and not again when it is dropped.
+        dcx.handle().finish_emitter();
+        let written = output.lock().unwrap().len();
+        drop(dcx);
+
+        let bytes = output.lock().unwrap();
+        assert_eq!(bytes.len(), written);
+        serde_json::from_slice(&bytes).unwrap()
+    })
+}
+
+fn span(lo: u32, hi: u32) -> Span {
+    Span::with_root_ctxt(BytePos(lo), BytePos(hi))
+}
+
+#[test]
+fn empty_log() {
+    let log = emit_sarif("", |_| {});
+    assert_eq!(log["version"], "2.1.0");
+    assert_eq!(log["runs"][0]["tool"]["driver"]["name"], "rustc");
+    assert_eq!(log["runs"][0]["tool"]["driver"]["rules"], json!([]));
+    assert_eq!(log["runs"][0]["invocations"][0]["executionSuccessful"], true);
+    assert_eq!(log["runs"][0]["results"], json!([]));
+}
+
+#[test]
+fn error_with_fix() {
+    let log = emit_sarif("fn main() {\n    let x: u8 = 1u16;\n}\n", |dcx| {
+        dcx.handle()
+            .struct_span_err(span(28, 32), "mismatched types")
+            .with_code(ErrCode::from_u32(308))
+            .with_span_label(span(23, 25), "expected due to this")
+            .with_note("integer literals have a type")
+            .with_span_suggestion(
+                span(29, 32),
+                "change the type of the literal",
+                "u8",
+                Applicability::MachineApplicable,
+            )
+            .emit();
+    });
+
+    let run = &log["runs"][0];
+    assert_eq!(run["invocations"][0]["executionSuccessful"], false);
+    assert_eq!(run["tool"]["driver"]["rules"], json!([{ "id": "E0308" }]));
+    assert_eq!(
+        run["results"],
+        json!([{
+            "ruleId": "E0308",
+            "ruleIndex": 0,
+            "level": "error",
+            "message": { "text": "mismatched types\nnote: integer literals have a type" },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": "src/main.rs" },
+                    "region": { "startLine": 2, "startColumn": 17, "endLine": 2, "endColumn": 21 }
+                }
+            }],
+            "relatedLocations": [{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": "src/main.rs" },
+                    "region": { "startLine": 2, "startColumn": 12, "endLine": 2, "endColumn": 14 }
+                },
+                "message": { "text": "expected due to this" }
+            }],
+            "fixes": [{
+                "description": { "text": "change the type of the literal" },
+                "artifactChanges": [{
+                    "artifactLocation": { "uri": "src/main.rs" },
+                    "replacements": [{
+                        "deletedRegion": {
+                            "startLine": 2,
+                            "startColumn": 18,
+                            "endLine": 2,
+                            "endColumn": 21
+                        },
+                        "insertedContent": { "text": "u8" }
+                    }]
+                }]
+            }]
+        }])
+    );
+}
+
+#[test]
+fn lints_share_rules() {
+    let log = emit_sarif("fn f(a: u8, b: u8) {}\n", |dcx| {
+        for (lo, hi) in [(5, 6), (12, 13)] {
+            let mut diag = dcx.handle().struct_span_warn(span(lo, hi), "unused variable");
+            diag.is_lint("unused_variables".to_owned(), false);
+            diag.span_suggestion(
+                span(lo, lo),
+                "if this is intentional, prefix it with an underscore",
+                "_",
+                Applicability::MaybeIncorrect,
+            );
+            diag.emit();
+        }
+    });
+
+    let run = &log["runs"][0];
+    assert_eq!(run["invocations"][0]["executionSuccessful"], true);
+    assert_eq!(run["tool"]["driver"]["rules"], json!([{ "id": "unused_variables" }]));
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    for result in results {
+        assert_eq!(result["ruleId"], "unused_variables");
+        assert_eq!(result["ruleIndex"], 0);
+        assert_eq!(result["level"], "warning");
+        assert_eq!(
+            result["message"]["text"],
+            "unused variable\nhelp: if this is intentional, prefix it with an underscore"
+        );
+        // Only machine-applicable suggestions become fixes.
+        assert!(result.get("fixes").is_none());
+    }
+}
+
+#[test]
+fn deferred_results() {
+    let deferred = DeferredResults::default();
+    rustc_span::create_default_session_globals_then(|| {
+        let fallback_bundle =
+            crate::fallback_fluent_bundle(vec![crate::DEFAULT_LOCALE_RESOURCE], false);
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let emitter =
+            SarifEmitter::new(Box::new(Shared { data: output.clone() }), None, fallback_bundle)
+                .defer_results(true)
+                .deferred_results(deferred.clone());
+        let dcx = DiagCtxt::new(Box::new(emitter));
+        dcx.handle().warn("early warning");
+        drop(dcx);
+        // Without errors, the results are left to the next emitter.
+        assert!(output.lock().unwrap().is_empty());
+    });
+
+    let log = emit_sarif_after(deferred.clone(), "", |dcx| dcx.handle().warn("late warning"));
+    let results = log["runs"][0]["results"].as_array().unwrap();
+    let messages: Vec<_> = results.iter().map(|result| &result["message"]["text"]).collect();
+    assert_eq!(messages, ["early warning", "late warning"]);
+
+    // The results are only written once.
+    let log = emit_sarif_after(deferred, "", |_| {});
+    assert_eq!(log["runs"][0]["results"], json!([]));
+    // Emitters that don't share them don't see them at all.
+    assert_eq!(emit_sarif("", |_| {})["runs"][0]["results"], json!([]));
+}
+
+#[test]
+fn uris() {
+    assert_eq!(path_to_uri("src/main.rs"), "src/main.rs");
+    assert_eq!(path_to_uri("src/a b#c.rs"), "src/a%20b%23c.rs");
+    assert_eq!(path_to_uri("src\\lib.rs"), "src/lib.rs");
+    #[cfg(unix)]
+    assert_eq!(path_to_uri("/home/me/src/lib.rs"), "file:///home/me/src/lib.rs");
+    #[cfg(windows)]
+    assert_eq!(path_to_uri("C:\\src\\lib.rs"), "file:///C:/src/lib.rs");
+}
@@ -46,6 +46,13 @@ incremental_delete_partial = failed to delete partly initialized session dir `{$
 
 incremental_delete_workproduct = file-system error deleting outdated file `{$path}`: {$err}
 
+incremental_export =
+    failed to export incremental compilation cache to `{$path}`: {$err}
+
+incremental_export_not_relocatable =
+    the exported incremental compilation cache is only valid in the working directory `{$path}`
+    .note = remap the working directory with `--remap-path-prefix` to import the cache in other directories
+
 incremental_finalize = error finalizing incremental compilation session directory `{$path}`: {$err}
 
 incremental_finalized_gc_failed =
@@ -54,6 +61,9 @@ incremental_finalized_gc_failed =
 incremental_hard_link_failed =
     hard linking files in the incremental compilation cache failed. copying files instead. consider moving the cache directory to a file system which supports hard linking in session dir `{$path}`
 
+incremental_import =
+    failed to import incremental compilation cache from `{$path}`: {$err}
+
 incremental_invalid_gc_failed =
     failed to garbage collect invalid incremental compilation session directory `{$path}`: {$err}
 
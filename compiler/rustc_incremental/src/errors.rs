@@ -228,6 +228,27 @@ pub(crate) struct Finalize<'a> {
     pub err: std::io::Error,
 }
 
+#[derive(Diagnostic)]
+#[diag(incremental_export)]
+pub(crate) struct Export<'a> {
+    pub path: &'a Path,
+    pub err: std::io::Error,
+}
+
+#[derive(Diagnostic)]
+#[diag(incremental_export_not_relocatable)]
+#[note]
+pub(crate) struct ExportNotRelocatable<'a> {
+    pub path: &'a Path,
+}
+
+#[derive(Diagnostic)]
+#[diag(incremental_import)]
+pub(crate) struct Import<'a> {
+    pub path: &'a Path,
+    pub err: std::io::Error,
+}
+
 #[derive(Diagnostic)]
 #[diag(incremental_invalid_gc_failed)]
 pub(crate) struct InvalidGcFailed<'a> {
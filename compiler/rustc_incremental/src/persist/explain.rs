@@ -0,0 +1,198 @@
+//! Explains why things were rebuilt, for `-Z incremental-explain`.
+//!
+//! The report lists the changed inputs of the compilation session: the nodes of the previous
+//! dep-graph that were marked as red without following from another red node, like the HIR of an
+//! edited item. Everything else that had to be recomputed follows from those. For each codegen
+//! unit of the previous session, the report then says whether it was reused, and if not, which of
+//! the changed inputs invalidated it.
+//!
+//! When the previous dep-graph isn't used at all, e.g. because the command-line arguments
+//! changed, that is reported while loading instead.
+
+use rustc_errors::pluralize;
+use rustc_middle::dep_graph::{DepContext, DepNode, DepNodeExt, dep_kinds};
+use rustc_middle::ty::TyCtxt;
+use rustc_session::Session;
+use rustc_session::config::IncrementalExplainFormat;
+use rustc_span::Symbol;
+use serde_json::{Value, json};
+
+/// A changed input, as shown in the report.
+struct ChangedInput {
+    /// The name of the query or dep-kind of the dep-node.
+    kind: &'static str,
+    /// The path of the item the dep-node refers to, if any.
+    item: Option<String>,
+    /// The source file of that item, if it is local.
+    file: Option<String>,
+}
+
+impl ChangedInput {
+    fn new(tcx: TyCtxt<'_>, dep_node: &DepNode) -> ChangedInput {
+        let kind = *tcx.dep_kind_info(dep_node.kind).name;
+        let def_id = dep_node.extract_def_id(tcx);
+        let item = def_id.map(|def_id| {
+            format!(
+                "{}{}",
+                tcx.crate_name(def_id.krate),
+                tcx.def_path(def_id).to_string_no_crate_verbose()
+            )
+        });
+        let file = def_id
+            .and_then(|def_id| def_id.as_local())
+            .map(|def_id| tcx.source_span_untracked(def_id))
+            .filter(|span| !span.is_dummy())
+            .map(|span| {
+                let source_map = tcx.sess.source_map();
+                source_map.filename_for_diagnostics(&source_map.span_to_filename(span)).to_string()
+            });
+        ChangedInput { kind, item, file }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({ "kind": self.kind, "item": self.item, "file": self.file })
+    }
+}
+
+impl std::fmt::Display for ChangedInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.item {
+            Some(item) => write!(f, "{}({item})", self.kind)?,
+            None => write!(f, "{}", self.kind)?,
+        }
+        if let Some(file) = &self.file {
+            write!(f, " in {file}")?;
+        }
+        Ok(())
+    }
+}
+
+/// What became of a codegen unit of the previous session.
+enum CguStatus {
+    Reused,
+    /// Recompiled because of the given changed inputs.
+    Invalidated(Vec<ChangedInput>),
+    /// The codegen unit doesn't exist anymore.
+    Removed,
+}
+
+/// Reports that the dep-graph of the previous session is not used, because of `reason`.
+pub(crate) fn explain_discarded_cache(sess: &Session, reason: &str) {
+    match sess.opts.unstable_opts.incremental_explain_format {
+        IncrementalExplainFormat::Text => {
+            eprintln!("[incremental-explain] previous session discarded: {reason}");
+        }
+        IncrementalExplainFormat::Json => {
+            eprintln!("{}", json!({ "previous_session": "discarded", "reason": reason }));
+        }
+    }
+}
+
+/// Reports the changed inputs of the current session and the codegen units they invalidated.
+/// Must run after codegen, once it has been decided which codegen units are reused.
+pub(crate) fn explain_changes(tcx: TyCtxt<'_>) {
+    let format = tcx.sess.opts.unstable_opts.incremental_explain_format;
+    if !tcx.dep_graph.has_previous_graph() {
+        match format {
+            IncrementalExplainFormat::Text => {
+                eprintln!("[incremental-explain] no previous session to compare with");
+            }
+            IncrementalExplainFormat::Json => {
+                eprintln!("{}", json!({ "previous_session": "none" }));
+            }
+        }
+        return;
+    }
+
+    let mut changed_inputs = tcx
+        .dep_graph
+        .changed_inputs(tcx)
+        .iter()
+        .map(|dep_node| ChangedInput::new(tcx, dep_node))
+        .collect::<Vec<_>>();
+    changed_inputs.sort_by_cached_key(|input| input.to_string());
+
+    let cgu_names = tcx
+        .dep_graph
+        .previous_work_products()
+        .items()
+        .map(|(_, work_product)| work_product.cgu_name.clone())
+        .into_sorted_stable_ord();
+    let cgus = cgu_names
+        .into_iter()
+        .map(|cgu_name| {
+            let dep_node =
+                DepNode::construct(tcx, dep_kinds::CompileCodegenUnit, &Symbol::intern(&cgu_name));
+            let status = if !tcx.dep_graph.dep_node_exists(&dep_node) {
+                CguStatus::Removed
+            } else {
+                let inputs = tcx.dep_graph.changed_inputs_of(tcx, &dep_node).unwrap_or_default();
+                if inputs.is_empty() {
+                    CguStatus::Reused
+                } else {
+                    let mut inputs = inputs
+                        .iter()
+                        .map(|dep_node| ChangedInput::new(tcx, dep_node))
+                        .collect::<Vec<_>>();
+                    inputs.sort_by_cached_key(|input| input.to_string());
+                    CguStatus::Invalidated(inputs)
+                }
+            };
+            (cgu_name, status)
+        })
+        .collect::<Vec<_>>();
+
+    match format {
+        IncrementalExplainFormat::Text => print_text(&changed_inputs, &cgus),
+        IncrementalExplainFormat::Json => print_json(&changed_inputs, &cgus),
+    }
+}
+
+fn print_text(changed_inputs: &[ChangedInput], cgus: &[(String, CguStatus)]) {
+    let len = changed_inputs.len();
+    eprintln!("[incremental-explain] {len} changed input{}", pluralize!(len));
+    for input in changed_inputs {
+        eprintln!("[incremental-explain]     {input}");
+    }
+
+    let count = |f: fn(&CguStatus) -> bool| cgus.iter().filter(|(_, status)| f(status)).count();
+    eprintln!(
+        "[incremental-explain] codegen units: {} reused, {} invalidated, {} removed",
+        count(|status| matches!(status, CguStatus::Reused)),
+        count(|status| matches!(status, CguStatus::Invalidated(_))),
+        count(|status| matches!(status, CguStatus::Removed)),
+    );
+    for (cgu_name, status) in cgus {
+        match status {
+            CguStatus::Reused => {}
+            CguStatus::Invalidated(inputs) => {
+                eprintln!("[incremental-explain]     {cgu_name} invalidated by:");
+                for input in inputs {
+                    eprintln!("[incremental-explain]         {input}");
+                }
+            }
+            CguStatus::Removed => eprintln!("[incremental-explain]     {cgu_name} removed"),
+        }
+    }
+}
+
+fn print_json(changed_inputs: &[ChangedInput], cgus: &[(String, CguStatus)]) {
+    let cgus = cgus
+        .iter()
+        .map(|(cgu_name, status)| match status {
+            CguStatus::Reused => json!({ "name": cgu_name, "status": "reused" }),
+            CguStatus::Invalidated(inputs) => json!({
+                "name": cgu_name,
+                "status": "invalidated",
+                "changed_inputs": inputs.iter().map(ChangedInput::to_json).collect::<Vec<_>>(),
+            }),
+            CguStatus::Removed => json!({ "name": cgu_name, "status": "removed" }),
+        })
+        .collect::<Vec<_>>();
+    let report = json!({
+        "previous_session": "loaded",
+        "changed_inputs": changed_inputs.iter().map(ChangedInput::to_json).collect::<Vec<_>>(),
+        "codegen_units": cgus,
+    });
+    eprintln!("{report}");
+}
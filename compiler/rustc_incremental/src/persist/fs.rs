@@ -122,6 +122,7 @@
 use rustc_span::Symbol;
 use tracing::debug;
 
+use super::relocate::{export_session_directory, import_session_files};
 use crate::errors;
 
 #[cfg(test)]
@@ -259,12 +260,14 @@ pub(crate) fn prepare_session_directory(sess: &Session, crate_name: Symbol) {
         let source_directory = find_source_directory(&crate_dir, &source_directories_already_tried);
 
         let Some(source_directory) = source_directory else {
-            // There's nowhere to copy from, we're done
+            // There's nowhere to copy from, we're done, unless there is an
+            // exported cache to start from.
             debug!(
                 "no source directory found. Continuing with empty session \
                     directory."
             );
 
+            import_session_files(sess, &crate_dir, &session_dir);
             sess.init_incr_comp_session(session_dir, directory_lock);
             return;
         };
@@ -359,6 +362,10 @@ pub fn finalize_session_directory(sess: &Session, svh: Option<Svh>) {
         Ok(_) => {
             debug!("finalize_session_directory() - directory renamed successfully");
 
+            // Export while we still hold the lock, so that the directory can't
+            // be garbage collected in the meantime.
+            export_session_directory(sess, &new_path);
+
             // This unlocks the directory
             sess.finalize_incr_comp_session(new_path);
         }
@@ -16,7 +16,9 @@
 use tracing::{debug, warn};
 
 use super::data::*;
+use super::explain::explain_discarded_cache;
 use super::fs::*;
+use super::relocate::commandline_args_hash;
 use super::save::build_dep_graph;
 use super::{file_format, work_product};
 use crate::errors;
@@ -104,7 +106,7 @@ fn load_dep_graph(sess: &Session) -> LoadResult<(Arc<SerializedDepGraph>, WorkPr
     // Calling `sess.incr_comp_session_dir()` will panic if `sess.opts.incremental.is_none()`.
     // Fortunately, we just checked that this isn't the case.
     let path = dep_graph_path(sess);
-    let expected_hash = sess.opts.dep_tracking_hash(false);
+    let expected_hash = commandline_args_hash(sess);
 
     let mut prev_work_products = UnordMap::default();
 
@@ -164,6 +166,9 @@ fn load_dep_graph(sess: &Session) -> LoadResult<(Arc<SerializedDepGraph>, WorkPr
                                     differing commandline arguments"
                     );
                 }
+                if sess.opts.unstable_opts.incremental_explain {
+                    explain_discarded_cache(sess, "the command-line arguments changed");
+                }
                 // We can't reuse the cache, purge it.
                 debug!("load_dep_graph_new: differing commandline arg hashes");
 
@@ -4,9 +4,11 @@
 
 mod data;
 mod dirty_clean;
+mod explain;
 mod file_format;
 mod fs;
 mod load;
+mod relocate;
 mod save;
 mod work_product;
 
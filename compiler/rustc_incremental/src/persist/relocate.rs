@@ -0,0 +1,141 @@
+//! Sharing incremental compilation caches between directories and machines.
+//!
+//! With `-Z incremental-export=<dir>`, the finalized session directory of a crate is copied to
+//! `<dir>/{crate-name}-{stable-crate-id}`, the name of the crate's directory in the incremental
+//! compilation directory. With `-Z incremental-import=<dir>`, a new session directory starts out
+//! as a copy of that directory when there is no finalized session directory to copy from. A single
+//! directory can thus hold the caches of all crates of a build, e.g. one warmed up by CI.
+//!
+//! The files in a session directory only refer to each other by relative paths, and to source
+//! files by their remapped names. A cache exported from one checkout is therefore valid in another
+//! one, as long as both remap their directory to the same path with `--remap-path-prefix`. The one
+//! exception is the hash of the command-line arguments stored in the dep-graph, which includes the
+//! local side of the remapping. It is left out while exporting or importing, see
+//! [`commandline_args_hash`].
+
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use rand::{RngCore, rng};
+use rustc_hashes::Hash64;
+use rustc_session::Session;
+use rustc_span::RealFileName;
+use tracing::debug;
+
+use crate::errors;
+
+/// Returns whether the cache is shared through `-Z incremental-export` or `-Z incremental-import`.
+fn is_relocatable(sess: &Session) -> bool {
+    sess.opts.unstable_opts.incremental_export.is_some()
+        || sess.opts.unstable_opts.incremental_import.is_some()
+}
+
+/// Returns the hash of the command-line arguments, which the previous dep-graph must match to be
+/// reused.
+///
+/// When the cache is shared, the local prefixes of `--remap-path-prefix` don't take part in the
+/// hash, since they differ between checkouts while the paths they are remapped to don't. Those
+/// are what ends up in the cache, and they are still hashed.
+pub(crate) fn commandline_args_hash(sess: &Session) -> Hash64 {
+    if !is_relocatable(sess) {
+        return sess.opts.dep_tracking_hash(false);
+    }
+
+    let mut opts = sess.opts.clone();
+    for (from, _) in &mut opts.remap_path_prefix {
+        *from = PathBuf::new();
+    }
+    opts.dep_tracking_hash(false)
+}
+
+/// Copies the cache exported for the crate in `crate_dir` into the new, empty `session_dir`, if
+/// `-Z incremental-import` is used and the crate has been exported.
+pub(crate) fn import_session_files(sess: &Session, crate_dir: &Path, session_dir: &Path) {
+    let Some(import_dir) = &sess.opts.unstable_opts.incremental_import else {
+        return;
+    };
+    let source_dir = import_dir.join(crate_dir.file_name().unwrap());
+    if !source_dir.is_dir() {
+        debug!("import_session_files: nothing to import from {}", source_dir.display());
+        return;
+    }
+
+    match copy_files(&source_dir, session_dir) {
+        Ok(files_copied) => {
+            if sess.opts.unstable_opts.incremental_info {
+                eprintln!(
+                    "[incremental] session directory: \
+                     {files_copied} files imported from `{}`",
+                    source_dir.display()
+                );
+            }
+        }
+        Err(err) => {
+            sess.dcx().emit_warn(errors::Import { path: &source_dir, err });
+            // Continue with an empty session directory rather than a partial import.
+            let cleared = session_dir.read_dir().and_then(|entries| {
+                entries.map(|entry| fs::remove_file(entry?.path())).collect::<io::Result<()>>()
+            });
+            if let Err(err) = cleared {
+                sess.dcx().emit_fatal(errors::DeletePartial { path: session_dir, err });
+            }
+        }
+    }
+}
+
+/// Copies the finalized `session_dir` to the `-Z incremental-export` directory, replacing any
+/// previous export of the crate.
+pub(crate) fn export_session_directory(sess: &Session, session_dir: &Path) {
+    let Some(export_dir) = &sess.opts.unstable_opts.incremental_export else {
+        return;
+    };
+    if let RealFileName::LocalPath(working_dir) = &sess.opts.working_dir {
+        sess.dcx().emit_warn(errors::ExportNotRelocatable { path: working_dir });
+    }
+
+    let crate_dir_name = session_dir.parent().unwrap().file_name().unwrap();
+    let target_dir = export_dir.join(crate_dir_name);
+
+    // Copy to a temporary directory first, so that a failed export never leaves a partial
+    // cache behind.
+    let mut temp_dir_name = crate_dir_name.to_owned();
+    temp_dir_name.push(format!("-{:08x}.tmp", rng().next_u32()));
+    let temp_dir = export_dir.join(temp_dir_name);
+
+    let result = copy_files(session_dir, &temp_dir).and_then(|files_copied| {
+        if target_dir.exists() {
+            fs::remove_dir_all(&target_dir)?;
+        }
+        fs::rename(&temp_dir, &target_dir)?;
+        Ok(files_copied)
+    });
+    match result {
+        Ok(files_copied) => {
+            if sess.opts.unstable_opts.incremental_info {
+                eprintln!(
+                    "[incremental] session directory: \
+                     {files_copied} files exported to `{}`",
+                    target_dir.display()
+                );
+            }
+        }
+        Err(err) => {
+            let _ = fs::remove_dir_all(&temp_dir);
+            sess.dcx().emit_warn(errors::Export { path: &target_dir, err });
+        }
+    }
+}
+
+/// Copies the files in `source_dir` to `target_dir`, creating it if needed. The files are copied
+/// rather than hard-linked, since the other side isn't managed by the compiler.
+fn copy_files(source_dir: &Path, target_dir: &Path) -> io::Result<usize> {
+    fs::create_dir_all(target_dir)?;
+    let mut files_copied = 0;
+    for entry in source_dir.read_dir()? {
+        let entry = entry?;
+        debug!("copying {} to {}", entry.path().display(), target_dir.display());
+        fs::copy(entry.path(), target_dir.join(entry.file_name()))?;
+        files_copied += 1;
+    }
+    Ok(files_copied)
+}
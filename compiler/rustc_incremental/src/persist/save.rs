@@ -13,7 +13,9 @@
 use tracing::debug;
 
 use super::data::*;
+use super::explain::explain_changes;
 use super::fs::*;
+use super::relocate::commandline_args_hash;
 use super::{dirty_clean, file_format, work_product};
 use crate::assert_dep_graph::assert_dep_graph;
 use crate::errors;
@@ -47,6 +49,9 @@ pub(crate) fn save_dep_graph(tcx: TyCtxt<'_>) {
         if sess.opts.unstable_opts.incremental_info {
             tcx.dep_graph.print_incremental_info()
         }
+        if sess.opts.unstable_opts.incremental_explain {
+            explain_changes(tcx);
+        }
 
         join(
             move || {
@@ -170,7 +175,7 @@ pub(crate) fn build_dep_graph(
     file_format::write_file_header(&mut encoder, sess);
 
     // First encode the commandline arguments hash
-    sess.opts.dep_tracking_hash(false).encode(&mut encoder);
+    commandline_args_hash(sess).encode(&mut encoder);
 
     Some(DepGraph::new(
         &sess.prof,
@@ -8,6 +8,7 @@
 use rustc_data_structures::jobserver;
 use rustc_data_structures::stable_hasher::StableHasher;
 use rustc_errors::registry::Registry;
+use rustc_errors::sarif::DeferredResults;
 use rustc_errors::{DiagCtxtHandle, ErrorGuaranteed};
 use rustc_lint::LintStore;
 use rustc_middle::ty;
@@ -362,6 +363,10 @@ pub struct Config {
     /// This is mainly useful for other tools that reads that debuginfo to figure out
     /// how to call the compiler with the same arguments.
     pub expanded_args: Vec<String>,
+
+    /// The SARIF results of the diagnostics emitted before the compiler was invoked, see
+    /// [`EarlyDiagCtxt::deferred_sarif_results`]. They are written to the log of the session.
+    pub early_sarif_results: DeferredResults,
 }
 
 /// Initialize jobserver before getting `jobserver::client` and `build_session`.
@@ -385,7 +390,10 @@ pub fn run_compiler<R: Send>(config: Config, f: impl FnOnce(&Compiler) -> R + Se
     rustc_data_structures::sync::set_dyn_thread_safe_mode(config.opts.unstable_opts.threads > 1);
 
     // Check jobserver before run_in_thread_pool_with_globals, which call jobserver::acquire_thread
-    let early_dcx = EarlyDiagCtxt::new(config.opts.error_format);
+    let early_dcx = EarlyDiagCtxt::with_deferred_sarif_results(
+        config.opts.error_format,
+        config.early_sarif_results.clone(),
+    );
     initialize_checked_jobserver(&early_dcx);
 
     crate::callbacks::setup_callbacks();
@@ -405,7 +413,10 @@ pub fn run_compiler<R: Send>(config: Config, f: impl FnOnce(&Compiler) -> R + Se
         |current_gcx| {
             // The previous `early_dcx` can't be reused here because it doesn't
             // impl `Send`. Creating a new one is fine.
-            let early_dcx = EarlyDiagCtxt::new(config.opts.error_format);
+            let early_dcx = EarlyDiagCtxt::with_deferred_sarif_results(
+                config.opts.error_format,
+                config.early_sarif_results,
+            );
 
             let codegen_backend = match config.make_codegen_backend {
                 None => util::get_codegen_backend(
@@ -442,6 +453,7 @@ pub fn run_compiler<R: Send>(config: Config, f: impl FnOnce(&Compiler) -> R + Se
             locale_resources.push(codegen_backend.locale_resource());
 
             let mut sess = rustc_session::build_session(
+                &early_dcx,
                 config.opts,
                 CompilerIO {
                     input: config.input,
@@ -656,6 +656,37 @@ pub(crate) fn prev_node_of(&self, prev_index: SerializedDepNodeIndex) -> DepNode
     pub(crate) fn mark_debug_loaded_from_disk(&self, dep_node: DepNode) {
         self.debug_loaded_from_disk.lock().insert(dep_node);
     }
+
+    /// Returns the dependencies of the previous node `prev_index` that have been marked as red,
+    /// leaving out the forever-red node.
+    fn red_deps_of(
+        &self,
+        prev_index: SerializedDepNodeIndex,
+    ) -> impl Iterator<Item = SerializedDepNodeIndex> {
+        self.previous.edge_targets_from(prev_index).filter(|&dep| {
+            matches!(self.colors.get(dep), Some(DepNodeColor::Red))
+                && self.previous.index_to_node(dep).kind != D::DEP_KIND_RED
+        })
+    }
+
+    /// Returns true if the previous node `prev_index` is one of the inputs that changed since the
+    /// previous compilation session: a red `eval_always` node, or a red node none of whose red
+    /// dependencies are anything but `eval_always` nodes. The latter covers nodes like the HIR
+    /// owners, which only change with the item they belong to, even though the whole crate they
+    /// are read from changes with every edit.
+    fn is_changed_input<Tcx: DepContext<Deps = D>>(
+        &self,
+        tcx: Tcx,
+        prev_index: SerializedDepNodeIndex,
+    ) -> bool {
+        let kind = self.previous.index_to_node(prev_index).kind;
+        matches!(self.colors.get(prev_index), Some(DepNodeColor::Red))
+            && kind != D::DEP_KIND_RED
+            && (tcx.is_eval_always(kind)
+                || self
+                    .red_deps_of(prev_index)
+                    .all(|dep| tcx.is_eval_always(self.previous.index_to_node(dep).kind)))
+    }
 }
 
 impl<D: Deps> DepGraph<D> {
@@ -670,6 +701,11 @@ pub fn previous_work_product(&self, v: &WorkProductId) -> Option<WorkProduct> {
         self.data.as_ref().and_then(|data| data.previous_work_products.get(v).cloned())
     }
 
+    /// Returns whether a dep-graph was loaded from a previous compilation session.
+    pub fn has_previous_graph(&self) -> bool {
+        self.data.as_ref().is_some_and(|data| data.previous.node_count() > 0)
+    }
+
     /// Access the map of work-products created during the cached run. Only
     /// used during saving of the dep-graph.
     pub fn previous_work_products(&self) -> &WorkProductMap {
@@ -926,6 +962,63 @@ pub fn is_green(&self, dep_node: &DepNode) -> bool {
         self.node_color(dep_node).is_some_and(|c| c.is_green())
     }
 
+    /// Returns the nodes of the previous dep-graph that are inputs that changed in the current
+    /// compilation session, from which all the other red nodes follow. Used by
+    /// `-Z incremental-explain`.
+    pub fn changed_inputs<Tcx: DepContext<Deps = D>>(&self, tcx: Tcx) -> Vec<DepNode> {
+        let Some(data) = &self.data else { return Vec::new() };
+        data.colors
+            .values
+            .indices()
+            .filter(|&prev_index| data.is_changed_input(tcx, prev_index))
+            .map(|prev_index| data.previous.index_to_node(prev_index))
+            .collect()
+    }
+
+    /// Returns the changed inputs that kept `dep_node` from being marked as green, by following
+    /// the red dependencies of `dep_node` in the previous dep-graph. When `dep_node` couldn't be
+    /// marked as green even though none of its dependencies are red, the dependency that couldn't
+    /// be forced is returned instead, which usually refers to something that has been removed.
+    ///
+    /// Returns an empty list if all the dependencies of `dep_node` are green, and `None` if
+    /// `dep_node` didn't exist in the previous compilation session.
+    pub fn changed_inputs_of<Tcx: DepContext<Deps = D>>(
+        &self,
+        tcx: Tcx,
+        dep_node: &DepNode,
+    ) -> Option<Vec<DepNode>> {
+        let data = self.data.as_ref()?;
+        let prev_index = data.previous.node_to_index_opt(dep_node)?;
+
+        let mut inputs = Vec::new();
+        if data.red_deps_of(prev_index).next().is_none() {
+            // Dependencies are marked in order, so the first one without a color is where
+            // `try_mark_green` gave up.
+            let dep = data
+                .previous
+                .edge_targets_from(prev_index)
+                .find(|&dep| data.colors.get(dep).is_none());
+            inputs.extend(dep.map(|dep| data.previous.index_to_node(dep)));
+            return Some(inputs);
+        }
+
+        let mut visited = FxHashSet::default();
+        let mut stack = vec![prev_index];
+        while let Some(index) = stack.pop() {
+            for dep in data.red_deps_of(index) {
+                if !visited.insert(dep) {
+                    continue;
+                }
+                if data.is_changed_input(tcx, dep) {
+                    inputs.push(data.previous.index_to_node(dep));
+                } else {
+                    stack.push(dep);
+                }
+            }
+        }
+        Some(inputs)
+    }
+
     /// This method loads all on-disk cacheable query results into memory, so
     /// they can be written out to the new cache file again. Most query results
     /// will already be in memory but in the case where we marked something as
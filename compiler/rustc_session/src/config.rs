@@ -698,6 +698,9 @@ pub enum ErrorOutputType {
         json_rendered: HumanReadableErrorType,
         color_config: ColorConfig,
     },
+    /// Output in the SARIF 2.1.0 format, which is consumed by code scanning tools. The whole log
+    /// is written once the compilation finishes.
+    Sarif,
 }
 
 #[derive(Clone, Hash, Debug)]
@@ -1848,11 +1851,12 @@ pub fn parse_error_format(
                 kind: HumanReadableErrorType::Unicode,
                 color_config,
             },
+            Some("sarif") => ErrorOutputType::Sarif,
             Some(arg) => {
                 early_dcx.set_error_format(ErrorOutputType::HumanReadable { color_config, .. });
                 early_dcx.early_fatal(format!(
                     "argument for `--error-format` must be `human`, `human-annotate-rs`, \
-                    `human-unicode`, `json`, `pretty-json` or `short` (instead was `{arg}`)"
+                    `human-unicode`, `json`, `pretty-json`, `sarif` or `short` (instead was `{arg}`)"
                 ))
             }
         }
@@ -1912,6 +1916,7 @@ fn check_error_format_stability(
     }
     let format = match format {
         ErrorOutputType::Json { pretty: true, .. } => "pretty-json",
+        ErrorOutputType::Sarif => "sarif",
         ErrorOutputType::HumanReadable { kind, .. } => match kind {
             HumanReadableErrorType::AnnotateSnippet => "human-annotate-rs",
             HumanReadableErrorType::Unicode => "human-unicode",
@@ -3212,6 +3217,15 @@ pub fn extension(self) -> &'static str {
     }
 }
 
+/// Which format to use for `-Z incremental-explain`
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum IncrementalExplainFormat {
+    /// Human-readable lines on stderr
+    Text,
+    /// One JSON object per report on stderr
+    Json,
+}
+
 /// `-Z patchable-function-entry` representation - how many nops to put before and after function
 /// entry.
 #[derive(Clone, Copy, PartialEq, Hash, Debug, Default)]
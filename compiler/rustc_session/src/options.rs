@@ -735,6 +735,7 @@ mod desc {
     pub(crate) const parse_strip: &str = "either `none`, `debuginfo`, or `symbols`";
     pub(crate) const parse_linker_flavor: &str = ::rustc_target::spec::LinkerFlavorCli::one_of();
     pub(crate) const parse_dump_mono_stats: &str = "`markdown` (default) or `json`";
+    pub(crate) const parse_incremental_explain_format: &str = "`text` (default) or `json`";
     pub(crate) const parse_instrument_coverage: &str = parse_bool;
     pub(crate) const parse_coverage_options: &str =
         "`block` | `branch` | `condition` | `mcdc` | `no-mir-spans`";
@@ -1339,6 +1340,24 @@ pub(crate) fn parse_dump_mono_stats(slot: &mut DumpMonoStatsFormat, v: Option<&s
         }
     }
 
+    pub(crate) fn parse_incremental_explain_format(
+        slot: &mut IncrementalExplainFormat,
+        v: Option<&str>,
+    ) -> bool {
+        match v {
+            None => true,
+            Some("json") => {
+                *slot = IncrementalExplainFormat::Json;
+                true
+            }
+            Some("text") => {
+                *slot = IncrementalExplainFormat::Text;
+                true
+            }
+            Some(_) => false,
+        }
+    }
+
     pub(crate) fn parse_autodiff(slot: &mut Vec<AutoDiff>, v: Option<&str>) -> bool {
         let Some(v) = v else {
             *slot = vec![];
@@ -2130,6 +2149,8 @@ pub(crate) fn parse_align(slot: &mut Option<Align>, v: Option<&str>) -> bool {
     dep_info_omit_d_target: bool = (false, parse_bool, [TRACKED],
         "in dep-info output, omit targets for tracking dependencies of the dep-info files \
         themselves (default: no)"),
+    diagnostic_baseline: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "suppress the lint diagnostics recorded in this file"),
     direct_access_external_data: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "Direct or use GOT indirect to reference external data symbols"),
     dual_proc_macros: bool = (false, parse_bool, [TRACKED],
@@ -2219,8 +2240,19 @@ pub(crate) fn parse_align(slot: &mut Option<Align>, v: Option<&str>) -> bool {
         "display unnamed regions as `'<id>`, using a non-ident unique id (default: no)"),
     ignore_directory_in_diagnostics_source_blocks: Vec<String> = (Vec::new(), parse_string_push, [UNTRACKED],
         "do not display the source code block in diagnostics for files in the directory"),
+    incremental_explain: bool = (false, parse_bool, [UNTRACKED],
+        "explain which changed inputs caused codegen units to be rebuilt (default: no)"),
+    incremental_explain_format: IncrementalExplainFormat = (IncrementalExplainFormat::Text,
+        parse_incremental_explain_format, [UNTRACKED],
+        "the format to use for -Z incremental-explain (`text` (default) or `json`)"),
+    incremental_export: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "export the incremental compilation cache to this directory, to be imported elsewhere \
+        with `-Z incremental-import`"),
     incremental_ignore_spans: bool = (false, parse_bool, [TRACKED],
         "ignore spans during ICH computation -- used for testing (default: no)"),
+    incremental_import: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "start from the incremental compilation cache exported to this directory when there is \
+        no local one"),
     incremental_info: bool = (false, parse_bool, [UNTRACKED],
         "print high-level information about incremental reuse (or the lack thereof) \
         (default: no)"),
@@ -2406,6 +2438,9 @@ pub(crate) fn parse_align(slot: &mut Option<Align>, v: Option<&str>) -> bool {
         "enable queries of the dependency graph for regression testing (default: no)"),
     randomize_layout: bool = (false, parse_bool, [TRACKED],
         "randomize the layout of types (default: no)"),
+    record_diagnostic_baseline: bool = (false, parse_bool, [UNTRACKED],
+        "record the lint diagnostics into the `-Z diagnostic-baseline` file instead of \
+        suppressing them (default: no)"),
     reg_struct_return: bool = (false, parse_bool, [TRACKED TARGET_MODIFIER],
         "On x86-32 targets, it overrides the default ABI to return small structs in registers.
         It is UNSOUND to link together crates that use different values for this flag!"),
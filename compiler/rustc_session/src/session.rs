@@ -11,11 +11,13 @@
 use rustc_data_structures::profiling::{SelfProfiler, SelfProfilerRef};
 use rustc_data_structures::sync::{DynSend, DynSync, Lock, MappedReadGuard, ReadGuard, RwLock};
 use rustc_errors::annotate_snippet_emitter_writer::AnnotateSnippetEmitter;
+use rustc_errors::baseline::DiagnosticBaseline;
 use rustc_errors::codes::*;
 use rustc_errors::emitter::{
     DynEmitter, HumanEmitter, HumanReadableErrorType, OutputTheme, stderr_destination,
 };
 use rustc_errors::json::JsonEmitter;
+use rustc_errors::sarif::{DeferredResults, SarifEmitter};
 use rustc_errors::{
     Diag, DiagCtxt, DiagCtxtHandle, DiagMessage, Diagnostic, ErrorGuaranteed, FatalAbort,
     FluentBundle, LazyFallbackBundle, TerminalUrl, fallback_fluent_bundle,
@@ -275,10 +277,12 @@ pub fn finish_diagnostics(&self) -> Option<ErrorGuaranteed> {
         let mut guar = None;
         guar = guar.or(self.check_miri_unleashed_features());
         guar = guar.or(self.dcx().emit_stashed_diagnostics());
+        self.dcx().finish_diagnostic_baseline();
         self.dcx().print_error_count();
         if self.opts.json_future_incompat {
             self.dcx().emit_future_breakage_report();
         }
+        self.dcx().finish_emitter();
         guar
     }
 
@@ -892,6 +896,7 @@ fn default_emitter(
     source_map: Arc<SourceMap>,
     bundle: Option<Arc<FluentBundle>>,
     fallback_bundle: LazyFallbackBundle,
+    sarif_results: DeferredResults,
 ) -> Box<DynEmitter> {
     let macro_backtrace = sopts.unstable_opts.macro_backtrace;
     let track_diagnostics = sopts.unstable_opts.track_diagnostics;
@@ -963,6 +968,15 @@ fn default_emitter(
             .track_diagnostics(track_diagnostics)
             .terminal_url(terminal_url),
         ),
+        config::ErrorOutputType::Sarif => Box::new(
+            SarifEmitter::new(
+                Box::new(io::BufWriter::new(io::stderr())),
+                source_map,
+                fallback_bundle,
+            )
+            .fluent_bundle(bundle)
+            .deferred_results(sarif_results),
+        ),
     }
 }
 
@@ -970,6 +984,7 @@ fn default_emitter(
 #[allow(rustc::bad_opt_access)]
 #[allow(rustc::untranslatable_diagnostic)] // FIXME: make this translatable
 pub fn build_session(
+    early_dcx: &EarlyDiagCtxt,
     sopts: config::Options,
     io: CompilerIO,
     bundle: Option<Arc<rustc_errors::FluentBundle>>,
@@ -999,7 +1014,13 @@ pub fn build_session(
         sopts.unstable_opts.translate_directionality_markers,
     );
     let source_map = rustc_span::source_map::get_source_map().unwrap();
-    let emitter = default_emitter(&sopts, Arc::clone(&source_map), bundle, fallback_bundle);
+    let emitter = default_emitter(
+        &sopts,
+        Arc::clone(&source_map),
+        bundle,
+        fallback_bundle,
+        early_dcx.deferred_sarif_results(),
+    );
 
     let mut dcx = DiagCtxt::new(emitter)
         .with_flags(sopts.unstable_opts.dcx_flags(can_emit_warnings))
@@ -1007,6 +1028,22 @@ pub fn build_session(
     if let Some(ice_file) = ice_file {
         dcx = dcx.with_ice_file(ice_file);
     }
+    match &sopts.unstable_opts.diagnostic_baseline {
+        Some(path) if sopts.unstable_opts.record_diagnostic_baseline => {
+            dcx = dcx.with_diagnostic_baseline(DiagnosticBaseline::record(path));
+        }
+        Some(path) => {
+            let baseline = DiagnosticBaseline::load(path).unwrap_or_else(|e| {
+                dcx.handle()
+                    .fatal(format!("failed to load diagnostic baseline `{}`: {e}", path.display()))
+            });
+            dcx = dcx.with_diagnostic_baseline(baseline);
+        }
+        None if sopts.unstable_opts.record_diagnostic_baseline => {
+            dcx.handle().fatal("`-Z record-diagnostic-baseline` requires `-Z diagnostic-baseline`")
+        }
+        None => {}
+    }
 
     let host_triple = TargetTuple::from_tuple(config::host_tuple());
     let (host, target_warnings) = Target::search(&host_triple, &sysroot)
@@ -1361,12 +1398,29 @@ enum IncrCompSession {
 /// A wrapper around an [`DiagCtxt`] that is used for early error emissions.
 pub struct EarlyDiagCtxt {
     dcx: DiagCtxt,
+    /// The SARIF results emitted so far, which are written to the log of the session.
+    sarif_results: DeferredResults,
 }
 
 impl EarlyDiagCtxt {
     pub fn new(output: ErrorOutputType) -> Self {
-        let emitter = mk_emitter(output);
-        Self { dcx: DiagCtxt::new(emitter) }
+        Self::with_deferred_sarif_results(output, DeferredResults::default())
+    }
+
+    /// Like `new`, but continues the SARIF results of an earlier `EarlyDiagCtxt`, see
+    /// [`EarlyDiagCtxt::deferred_sarif_results`].
+    pub fn with_deferred_sarif_results(
+        output: ErrorOutputType,
+        sarif_results: DeferredResults,
+    ) -> Self {
+        let emitter = mk_emitter(output, sarif_results.clone());
+        Self { dcx: DiagCtxt::new(emitter), sarif_results }
+    }
+
+    /// The SARIF results emitted so far. They are handed to the next `EarlyDiagCtxt` and finally
+    /// to the session, so that a compilation writes a single log.
+    pub fn deferred_sarif_results(&self) -> DeferredResults {
+        self.sarif_results.clone()
     }
 
     /// Swap out the underlying dcx once we acquire the user's preference on error emission
@@ -1374,7 +1428,7 @@ pub fn new(output: ErrorOutputType) -> Self {
     pub fn set_error_format(&mut self, output: ErrorOutputType) {
         assert!(self.dcx.handle().has_errors().is_none());
 
-        let emitter = mk_emitter(output);
+        let emitter = mk_emitter(output, self.sarif_results.clone());
         self.dcx = DiagCtxt::new(emitter);
     }
 
@@ -1422,7 +1476,7 @@ pub fn early_struct_warn(&self, msg: impl Into<DiagMessage>) -> Diag<'_, ()> {
     }
 }
 
-fn mk_emitter(output: ErrorOutputType) -> Box<DynEmitter> {
+fn mk_emitter(output: ErrorOutputType, sarif_results: DeferredResults) -> Box<DynEmitter> {
     // FIXME(#100717): early errors aren't translated at the moment, so this is fine, but it will
     // need to reference every crate that might emit an early error for translation to work.
     let fallback_bundle =
@@ -1450,6 +1504,15 @@ fn mk_emitter(output: ErrorOutputType) -> Box<DynEmitter> {
                 color_config,
             ))
         }
+        config::ErrorOutputType::Sarif => Box::new(
+            SarifEmitter::new(
+                Box::new(io::BufWriter::new(io::stderr())),
+                Some(Arc::new(SourceMap::new(FilePathMapping::empty()))),
+                fallback_bundle,
+            )
+            .defer_results(true)
+            .deferred_results(sarif_results),
+        ),
     };
     emitter
 }
@@ -0,0 +1,384 @@
+//! Named benchmark baselines: the samples of a benchmark run are saved with `--save-baseline`,
+//! and later runs are compared against them with `--baseline`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::{env, fs, io};
+
+use super::{BenchSamples, fmt_thousands_sep};
+use crate::formatters::EscapedString;
+use crate::stats::{self, Stats};
+use crate::types::TestDesc;
+
+/// Version of the baseline file format, stored in its `version` field.
+const BASELINE_FORMAT_VERSION: u32 = 1;
+
+/// Confidence level of the interval reported for the change of a benchmark.
+const CONFIDENCE: f64 = 0.95;
+
+/// Number of bootstrap resamples used to estimate the confidence interval.
+const RESAMPLES: usize = 5000;
+
+/// The benchmark samples of a run, saved under a name.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Baseline {
+    /// The ns/iter samples of every benchmark, by benchmark name.
+    benchmarks: BTreeMap<String, Vec<f64>>,
+}
+
+/// The change of a benchmark compared to the baseline.
+#[derive(Debug, PartialEq)]
+pub(crate) struct BenchComparison {
+    pub name: String,
+    /// Median of the new samples, in ns/iter.
+    pub median: f64,
+    /// `None` if the benchmark is not part of the baseline, or if its baseline median is zero so
+    /// that there is no relative change to report.
+    pub change: Option<MedianChange>,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct MedianChange {
+    /// Median of the baseline samples, in ns/iter.
+    pub baseline_median: f64,
+    /// Relative change of the median, as a fraction of `baseline_median`.
+    pub change: f64,
+    /// Bounds of the confidence interval of `change`.
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl MedianChange {
+    /// Whether the benchmark got significantly slower, that is, the whole confidence interval
+    /// lies above zero.
+    pub(crate) fn is_regression(&self) -> bool {
+        self.lower > 0.0
+    }
+
+    /// Whether the benchmark got significantly faster.
+    pub(crate) fn is_improvement(&self) -> bool {
+        self.upper < 0.0
+    }
+
+    pub(crate) fn verdict(&self) -> &'static str {
+        if self.is_regression() {
+            "regressed"
+        } else if self.is_improvement() {
+            "improved"
+        } else {
+            "unchanged"
+        }
+    }
+}
+
+impl Baseline {
+    pub(crate) fn new(benchmarks: &[(TestDesc, BenchSamples)]) -> Baseline {
+        let benchmarks = benchmarks
+            .iter()
+            .filter(|(_, bs)| !bs.samples.is_empty())
+            .map(|(desc, bs)| (desc.name.as_slice().to_owned(), bs.samples.clone()))
+            .collect();
+        Baseline { benchmarks }
+    }
+
+    /// Path of the baseline with the given name. Baselines are stored next to the test binary,
+    /// which places them in Cargo's target directory.
+    fn path(name: &str) -> io::Result<PathBuf> {
+        let mut dir = env::current_exe()?.into_os_string();
+        dir.push(".baselines");
+        Ok(PathBuf::from(dir).join(format!("{name}.json")))
+    }
+
+    pub(crate) fn load(name: &str) -> io::Result<Baseline> {
+        let path = Baseline::path(name)?;
+        let json = fs::read_to_string(&path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("failed to load baseline `{name}` from `{}`: {e}", path.display()),
+            )
+        })?;
+        Baseline::from_json(&json).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid baseline `{name}` in `{}`: {e}", path.display()),
+            )
+        })
+    }
+
+    pub(crate) fn save(&self, name: &str) -> io::Result<()> {
+        let path = Baseline::path(name)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_json())
+    }
+
+    /// Compares the new samples of benchmarks to the baseline.
+    pub(crate) fn compare(&self, benchmarks: &[(TestDesc, BenchSamples)]) -> Vec<BenchComparison> {
+        benchmarks
+            .iter()
+            .filter(|(_, bs)| !bs.samples.is_empty())
+            .map(|(desc, bs)| {
+                let name = desc.name.as_slice();
+                let change = self.benchmarks.get(name).and_then(|baseline| {
+                    let (change, lower, upper) = stats::bootstrap_median_change(
+                        baseline,
+                        &bs.samples,
+                        CONFIDENCE,
+                        RESAMPLES,
+                        0,
+                    )?;
+                    Some(MedianChange { baseline_median: baseline.median(), change, lower, upper })
+                });
+                BenchComparison { name: name.to_owned(), median: bs.samples.median(), change }
+            })
+            .collect()
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        let mut json = format!("{{\n  \"version\": {BASELINE_FORMAT_VERSION},\n");
+        json.push_str("  \"benchmarks\": {");
+        for (i, (name, samples)) in self.benchmarks.iter().enumerate() {
+            let summ = stats::Summary::new(samples);
+            let samples: Vec<String> = samples.iter().map(|s| s.to_string()).collect();
+            write!(
+                json,
+                "{}\n    \"{}\": {{ \"median\": {}, \"deviation\": {}, \"samples\": [{}] }}",
+                if i == 0 { "" } else { "," },
+                EscapedString(name),
+                summ.median,
+                summ.max - summ.min,
+                samples.join(", "),
+            )
+            .unwrap();
+        }
+        json.push_str("\n  }\n}\n");
+        json
+    }
+
+    pub(crate) fn from_json(json: &str) -> Result<Baseline, String> {
+        let mut parser = JsonParser { input: json, pos: 0 };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.pos != json.len() {
+            return Err(parser.error("trailing characters"));
+        }
+
+        let version = value.get("version").and_then(Json::as_number);
+        if version != Some(f64::from(BASELINE_FORMAT_VERSION)) {
+            return Err(format!("unsupported version, expected version {BASELINE_FORMAT_VERSION}"));
+        }
+        let Some(Json::Object(entries)) = value.get("benchmarks") else {
+            return Err("missing `benchmarks`".to_owned());
+        };
+
+        let mut benchmarks = BTreeMap::new();
+        for (name, bench) in entries {
+            let samples = match bench.get("samples") {
+                Some(Json::Array(samples)) => samples
+                    .iter()
+                    .map(|sample| sample.as_number().filter(|s| s.is_finite()))
+                    .collect::<Option<Vec<f64>>>(),
+                _ => None,
+            };
+            match samples {
+                Some(samples) if !samples.is_empty() => {
+                    benchmarks.insert(name.clone(), samples);
+                }
+                _ => return Err(format!("invalid samples for benchmark `{name}`")),
+            }
+        }
+        Ok(Baseline { benchmarks })
+    }
+}
+
+/// Formats a comparison for the human-readable output formats, without its verdict.
+pub(crate) fn fmt_bench_comparison(comparison: &BenchComparison) -> String {
+    let median = fmt_thousands_sep(comparison.median, ',');
+    match comparison.change {
+        Some(ref change) => format!(
+            "{:>14} ns/iter (was {} ns/iter): {:+.2}% ({:.0}% CI {:+.2}% .. {:+.2}%)",
+            median,
+            fmt_thousands_sep(change.baseline_median, ','),
+            change.change * 100.0,
+            CONFIDENCE * 100.0,
+            change.lower * 100.0,
+            change.upper * 100.0,
+        ),
+        None => format!("{median:>14} ns/iter (not compared)"),
+    }
+}
+
+/// A JSON value, as far as baselines need it: strings, booleans and `null` are parsed, but their
+/// values are not kept.
+enum Json {
+    Other,
+    Number(f64),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_number(&self) -> Option<f64> {
+        match *self {
+            Json::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+/// Parser for the JSON subset of RFC 8259 that doesn't need arbitrary precision. libtest can't
+/// depend on a JSON library.
+struct JsonParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, msg: &str) -> String {
+        format!("{msg} at byte {}", self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.input[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.eat(token) { Ok(()) } else { Err(self.error(&format!("expected `{token}`"))) }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                if !self.eat("}") {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.parse_string()?;
+                        self.expect(":")?;
+                        entries.push((key, self.parse_value()?));
+                        if !self.eat(",") {
+                            break;
+                        }
+                    }
+                    self.expect("}")?;
+                }
+                Ok(Json::Object(entries))
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut values = Vec::new();
+                if !self.eat("]") {
+                    loop {
+                        values.push(self.parse_value()?);
+                        if !self.eat(",") {
+                            break;
+                        }
+                    }
+                    self.expect("]")?;
+                }
+                Ok(Json::Array(values))
+            }
+            Some(b'"') => self.parse_string().map(|_| Json::Other),
+            Some(b't' | b'f' | b'n')
+                if self.eat("true") || self.eat("false") || self.eat("null") =>
+            {
+                Ok(Json::Other)
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.pos;
+                while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+                    self.pos += 1;
+                }
+                self.input[start..self.pos]
+                    .parse()
+                    .map(Json::Number)
+                    .map_err(|_| format!("invalid number at byte {start}"))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        if self.peek() != Some(b'"') {
+            return Err(self.error("expected a string"));
+        }
+        self.pos += 1;
+
+        let mut s = String::new();
+        loop {
+            let rest = &self.input[self.pos..];
+            let Some(end) = rest.find(['"', '\\']) else {
+                return Err(self.error("unterminated string"));
+            };
+            s.push_str(&rest[..end]);
+            self.pos += end + 1;
+            if rest.as_bytes()[end] == b'"' {
+                return Ok(s);
+            }
+
+            let escape = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match escape {
+                b'"' => s.push('"'),
+                b'\\' => s.push('\\'),
+                b'/' => s.push('/'),
+                b'b' => s.push('\u{8}'),
+                b'f' => s.push('\u{c}'),
+                b'n' => s.push('\n'),
+                b'r' => s.push('\r'),
+                b't' => s.push('\t'),
+                b'u' => {
+                    let mut c = self.parse_hex4()?;
+                    if (0xD800..0xDC00).contains(&c) && self.input[self.pos..].starts_with("\\u") {
+                        // A surrogate pair.
+                        self.pos += 2;
+                        let low = self.parse_hex4()?;
+                        if !(0xDC00..0xE000).contains(&low) {
+                            return Err(self.error("invalid unicode escape"));
+                        }
+                        c = 0x10000 + ((c - 0xD800) << 10) + (low - 0xDC00);
+                    }
+                    s.push(char::from_u32(c).ok_or_else(|| self.error("invalid unicode escape"))?);
+                }
+                _ => return Err(self.error("invalid escape")),
+            }
+        }
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, String> {
+        let hex =
+            self.input.get(self.pos..self.pos + 4).ok_or_else(|| self.error("invalid escape"))?;
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(self.error("invalid escape"));
+        }
+        let c = u32::from_str_radix(hex, 16).unwrap();
+        self.pos += 4;
+        Ok(c)
+    }
+}
@@ -1,10 +1,11 @@
 //! Module converting command-line arguments into test configuration.
 
-use std::env;
 use std::io::{self, IsTerminal, Write};
 use std::path::PathBuf;
+use std::time::Duration;
+use std::{env, fs};
 
-use super::options::{ColorConfig, Options, OutputFormat, RunIgnored};
+use super::options::{ColorConfig, Options, OutputFormat, RunIgnored, TestShard};
 use super::time::TestTimeOptions;
 
 #[derive(Debug)]
@@ -25,7 +26,20 @@ pub struct TestOpts {
     pub shuffle_seed: Option<u64>,
     pub test_threads: Option<usize>,
     pub skip: Vec<String>,
+    /// Only run the tests of this shard. Applied after all other filters.
+    pub shard: Option<TestShard>,
+    /// Only run the tests with these names, which failed in a previous run.
+    pub rerun_failed: Option<Vec<String>>,
+    /// Write the names of the tests that failed to this file.
+    pub record_failed: Option<PathBuf>,
+    /// Save the benchmark results as the baseline with this name.
+    pub save_baseline: Option<String>,
+    /// Compare the benchmark results to the baseline with this name.
+    pub baseline: Option<String>,
     pub time_options: Option<TestTimeOptions>,
+    /// Kill tests that are still running after this duration and report them as timed out.
+    /// Only supported when tests are run in subprocesses.
+    pub test_timeout: Option<Duration>,
     /// Stop at first failing test.
     /// May run a few more tests due to threading, but will
     /// abort as soon as possible.
@@ -79,6 +93,20 @@ fn optgroups() -> getopts::Options {
              be used multiple times)",
             "FILTER",
         )
+        .optopt(
+            "",
+            "shard-index",
+            "Run only the tests of the shard with index N, in 0..M
+            (requires --shard-count)",
+            "N",
+        )
+        .optopt(
+            "",
+            "shard-count",
+            "Split the tests into M disjoint shards, based on a hash of
+            their names (requires --shard-index)",
+            "M",
+        )
         .optflag(
             "q",
             "quiet",
@@ -102,8 +130,10 @@ fn optgroups() -> getopts::Options {
             pretty = Print verbose output;
             terse  = Display one character per test;
             json   = Output a json document;
-            junit  = Output a JUnit document",
-            "pretty|terse|json|junit",
+            junit  = Output a JUnit document;
+            tap    = Output a TAP version 14 document;
+            github = Print verbose output with GitHub Actions annotations",
+            "pretty|terse|json|junit|tap|github",
         )
         .optflag("", "show-output", "Show captured stdout of successful tests")
         .optopt(
@@ -142,6 +172,49 @@ fn optgroups() -> getopts::Options {
             `CRITICAL_TIME` here means the limit that should not be exceeded by test.
             ",
         )
+        .optopt(
+            "",
+            "test-timeout",
+            "Kill tests that are still running after SECS seconds and
+            report them as failed.
+
+            Only supported when tests are run in subprocesses, i.e. when
+            the test binary is built with `-Zpanic-abort-tests`.",
+            "SECS",
+        )
+        .optflag(
+            "",
+            "record-failed",
+            "Write the names of the failed tests to the failed tests file,
+            see --failed-tests-file",
+        )
+        .optflag(
+            "",
+            "rerun-failed",
+            "Only run the tests listed in the failed tests file, and update
+            it with the tests that still fail",
+        )
+        .optopt(
+            "",
+            "failed-tests-file",
+            "Path of the file used by --record-failed and --rerun-failed
+            (default: the path of the test binary with a `.failed-tests`
+            extension added)",
+            "PATH",
+        )
+        .optopt(
+            "",
+            "save-baseline",
+            "Save the benchmark results as a baseline with this name",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "baseline",
+            "Compare the benchmark results to the baseline with this name,
+            as saved by --save-baseline",
+            "NAME",
+        )
         .optflag("", "shuffle", "Run tests in random order")
         .optopt(
             "",
@@ -259,8 +332,15 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
     let force_run_in_process = unstable_optflag!(matches, allow_unstable, "force-run-in-process");
     let exclude_should_panic = unstable_optflag!(matches, allow_unstable, "exclude-should-panic");
     let time_options = get_time_options(&matches, allow_unstable)?;
+    let test_timeout = get_test_timeout(&matches, allow_unstable)?;
     let shuffle = get_shuffle(&matches, allow_unstable)?;
     let shuffle_seed = get_shuffle_seed(&matches, allow_unstable)?;
+    let shard = get_shard(&matches, allow_unstable)?;
+    let (rerun_failed, record_failed) = get_failed_tests_options(&matches, allow_unstable)?;
+    let save_baseline = unstable_optopt!(matches, allow_unstable, "save-baseline");
+    let save_baseline = check_baseline_name(save_baseline, "save-baseline")?;
+    let baseline = unstable_optopt!(matches, allow_unstable, "baseline");
+    let baseline = check_baseline_name(baseline, "baseline")?;
 
     let include_ignored = matches.opt_present("include-ignored");
     let quiet = matches.opt_present("quiet");
@@ -302,7 +382,13 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
         shuffle_seed,
         test_threads,
         skip,
+        shard,
+        rerun_failed,
+        record_failed,
+        save_baseline,
+        baseline,
         time_options,
+        test_timeout,
         options,
         fail_fast: false,
     };
@@ -340,6 +426,26 @@ fn get_time_options(
     Ok(options)
 }
 
+fn get_test_timeout(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<Duration>> {
+    let test_timeout = match unstable_optopt!(matches, allow_unstable, "test-timeout") {
+        Some(secs) => match secs.parse::<f64>().map(Duration::try_from_secs_f64) {
+            Ok(Ok(timeout)) if !timeout.is_zero() => Some(timeout),
+            _ => {
+                return Err(format!(
+                    "argument for --test-timeout must be a positive number of seconds \
+                     (was {secs})"
+                ));
+            }
+        },
+        None => None,
+    };
+
+    Ok(test_timeout)
+}
+
 fn get_shuffle(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<bool> {
     let mut shuffle = unstable_optflag!(matches, allow_unstable, "shuffle");
     if !shuffle && allow_unstable {
@@ -379,6 +485,107 @@ fn get_shuffle_seed(matches: &getopts::Matches, allow_unstable: bool) -> OptPart
     Ok(shuffle_seed)
 }
 
+fn get_shard(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<Option<TestShard>> {
+    let index = unstable_optopt!(matches, allow_unstable, "shard-index");
+    let count = unstable_optopt!(matches, allow_unstable, "shard-count");
+    let (index, count) = match (index, count) {
+        (None, None) => return Ok(None),
+        (Some(index), Some(count)) => (index, count),
+        _ => return Err("the options --shard-index and --shard-count must be used together".into()),
+    };
+
+    let count = match count.parse::<usize>() {
+        Ok(0) => return Err("argument for --shard-count must not be 0".to_string()),
+        Ok(n) => n,
+        Err(e) => {
+            return Err(format!(
+                "argument for --shard-count must be a number > 0 \
+                 (error: {e})"
+            ));
+        }
+    };
+    let index = match index.parse::<usize>() {
+        Ok(n) if n < count => n,
+        Ok(n) => {
+            return Err(format!(
+                "argument for --shard-index must be less than --shard-count (was {n})"
+            ));
+        }
+        Err(e) => {
+            return Err(format!(
+                "argument for --shard-index must be a number \
+                 (error: {e})"
+            ));
+        }
+    };
+
+    Ok(Some(TestShard { index, count }))
+}
+
+fn get_failed_tests_options(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<(Option<Vec<String>>, Option<PathBuf>)> {
+    let record_failed = unstable_optflag!(matches, allow_unstable, "record-failed");
+    let rerun_failed = unstable_optflag!(matches, allow_unstable, "rerun-failed");
+    let failed_tests_file = unstable_optopt!(matches, allow_unstable, "failed-tests-file");
+    if !record_failed && !rerun_failed {
+        if failed_tests_file.is_some() {
+            return Err(
+                "the option --failed-tests-file requires --record-failed or --rerun-failed".into(),
+            );
+        }
+        return Ok((None, None));
+    }
+
+    let path = match failed_tests_file {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let mut path = env::current_exe()
+                .map_err(|e| format!("failed to get the path of the test binary: {e}"))?
+                .into_os_string();
+            path.push(".failed-tests");
+            PathBuf::from(path)
+        }
+    };
+
+    let rerun_failed = if rerun_failed {
+        let failed = fs::read_to_string(&path).map_err(|e| {
+            format!(
+                "failed to read the failed tests from `{}` (run the tests with \
+                 --record-failed first): {e}",
+                path.display()
+            )
+        })?;
+        Some(failed.lines().map(str::to_owned).collect())
+    } else {
+        None
+    };
+
+    // Rerunning the failed tests records which of them still fail, so that they can be rerun
+    // again after the next fix.
+    Ok((rerun_failed, Some(path)))
+}
+
+fn check_baseline_name(name: Option<String>, option: &str) -> OptPartRes<Option<String>> {
+    let Some(name) = name else {
+        return Ok(None);
+    };
+
+    // The name becomes part of a file name.
+    let is_valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !is_valid {
+        return Err(format!(
+            "argument for --{option} must only contain ASCII letters, digits, `-`, `_` and `.`, \
+             and must not start with `.` (got {name})"
+        ));
+    }
+
+    Ok(Some(name))
+}
+
 fn get_test_threads(matches: &getopts::Matches) -> OptPartRes<Option<usize>> {
     let test_threads = match matches.opt_str("test-threads") {
         Some(n_str) => match n_str.parse::<usize>() {
@@ -418,9 +625,21 @@ fn get_format(
             }
             OutputFormat::Junit
         }
+        Some("tap") => {
+            if !allow_unstable {
+                return Err("The \"tap\" format is only accepted on the nightly compiler with -Z unstable-options".into());
+            }
+            OutputFormat::Tap
+        }
+        Some("github") => {
+            if !allow_unstable {
+                return Err("The \"github\" format is only accepted on the nightly compiler with -Z unstable-options".into());
+            }
+            OutputFormat::Github
+        }
         Some(v) => {
             return Err(format!(
-                "argument for --format must be pretty, terse, json or junit (was \
+                "argument for --format must be pretty, terse, json, junit, tap or github (was \
                  {v})"
             ));
         }
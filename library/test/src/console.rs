@@ -1,15 +1,19 @@
 //! Module providing interface for running tests in the console.
 
-use std::fs::File;
+use std::collections::{BTreeSet, HashSet};
+use std::fs::{self, File};
 use std::io;
 use std::io::prelude::Write;
+use std::path::Path;
 use std::time::Instant;
 
-use super::bench::fmt_bench_samples;
+use super::bench::baseline::Baseline;
+use super::bench::{BenchSamples, fmt_bench_samples};
 use super::cli::TestOpts;
 use super::event::{CompletedTest, TestEvent};
 use super::formatters::{
-    JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TerseFormatter,
+    GithubFormatter, JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TapFormatter,
+    TerseFormatter,
 };
 use super::helpers::concurrency::get_concurrency;
 use super::helpers::metrics::MetricMap;
@@ -84,6 +88,7 @@ pub(crate) struct ConsoleTestState {
     pub measured: usize,
     pub exec_time: Option<TestSuiteExecTime>,
     pub metrics: MetricMap,
+    pub benchmarks: Vec<(TestDesc, BenchSamples)>,
     pub failures: Vec<(TestDesc, Vec<u8>)>,
     pub not_failures: Vec<(TestDesc, Vec<u8>)>,
     pub ignores: Vec<(TestDesc, Vec<u8>)>,
@@ -108,6 +113,7 @@ pub(crate) fn new(opts: &TestOpts) -> io::Result<ConsoleTestState> {
             measured: 0,
             exec_time: None,
             metrics: MetricMap::new(),
+            benchmarks: Vec::new(),
             failures: Vec::new(),
             not_failures: Vec::new(),
             ignores: Vec::new(),
@@ -154,6 +160,7 @@ pub(crate) fn write_log_result(
                     }
                     TestResult::TrBench(ref bs) => fmt_bench_samples(bs),
                     TestResult::TrTimedFail => "failed (time limit exceeded)".to_owned(),
+                    TestResult::TrTimedOut => "failed (timed out)".to_owned(),
                 },
                 name,
             )
@@ -167,6 +174,36 @@ pub(crate) fn write_log_result(
     fn current_test_count(&self) -> usize {
         self.passed + self.failed + self.ignored + self.measured
     }
+
+    /// Updates the names of the failed tests in `path`, one per line, for `--rerun-failed`. Tests
+    /// that failed in this run are added and tests that passed are removed, but tests that didn't
+    /// run, e.g. because they were filtered out, keep their entry from previous runs.
+    pub(crate) fn write_failed_tests(&self, path: &Path) -> io::Result<()> {
+        let previous = match fs::read_to_string(path) {
+            Ok(previous) => previous,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let passed: HashSet<&str> = self
+            .not_failures
+            .iter()
+            .map(|(desc, _)| desc.name.as_slice())
+            .chain(self.benchmarks.iter().map(|(desc, _)| desc.name.as_slice()))
+            .collect();
+
+        let mut names: BTreeSet<&str> =
+            previous.lines().filter(|name| !passed.contains(name)).collect();
+        names.extend(
+            self.failures.iter().chain(&self.time_failures).map(|(desc, _)| desc.name.as_slice()),
+        );
+
+        let mut contents = String::new();
+        for name in names {
+            contents.push_str(name);
+            contents.push('\n');
+        }
+        fs::write(path, contents)
+    }
 }
 
 // List the tests to console, and optionally to logfile. Filters are honored.
@@ -177,11 +214,12 @@ pub(crate) fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) ->
     };
 
     let mut out: Box<dyn OutputFormatter> = match opts.format {
-        OutputFormat::Pretty | OutputFormat::Junit => {
+        OutputFormat::Pretty | OutputFormat::Junit | OutputFormat::Github => {
             Box::new(PrettyFormatter::new(output, false, 0, false, None))
         }
         OutputFormat::Terse => Box::new(TerseFormatter::new(output, false, 0, false)),
-        OutputFormat::Json => Box::new(JsonFormatter::new(output)),
+        OutputFormat::Json => Box::new(JsonFormatter::new(output, opts.shard)),
+        OutputFormat::Tap => Box::new(TapFormatter::new(output)),
     };
     let mut st = ConsoleTestDiscoveryState::new(opts)?;
 
@@ -230,7 +268,8 @@ fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest)
                 bs.ns_iter_summ.median,
                 bs.ns_iter_summ.max - bs.ns_iter_summ.min,
             );
-            st.measured += 1
+            st.measured += 1;
+            st.benchmarks.push((test, bs));
         }
         TestResult::TrFailed => {
             st.failed += 1;
@@ -246,6 +285,17 @@ fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest)
             st.failed += 1;
             st.time_failures.push((test, stdout));
         }
+        TestResult::TrTimedOut => {
+            st.failed += 1;
+            let mut stdout = stdout;
+            if stdout.last().is_some_and(|&c| c != b'\n') {
+                stdout.push(b'\n');
+            }
+            stdout.extend_from_slice(
+                b"note: test did not finish within the time limit and was killed\n",
+            );
+            st.failures.push((test, stdout));
+        }
     }
 }
 
@@ -308,11 +358,39 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
         OutputFormat::Terse => {
             Box::new(TerseFormatter::new(output, opts.use_color(), max_name_len, is_multithreaded))
         }
-        OutputFormat::Json => Box::new(JsonFormatter::new(output)),
-        OutputFormat::Junit => Box::new(JunitFormatter::new(output)),
+        OutputFormat::Json => Box::new(JsonFormatter::new(output, opts.shard)),
+        OutputFormat::Junit => Box::new(JunitFormatter::new(output, opts.shard)),
+        OutputFormat::Tap => Box::new(TapFormatter::new(output)),
+        OutputFormat::Github => Box::new(GithubFormatter::new(PrettyFormatter::new(
+            output,
+            opts.use_color(),
+            max_name_len,
+            is_multithreaded,
+            opts.time_options,
+        ))),
     };
     let mut st = ConsoleTestState::new(opts)?;
 
+    // Load the baseline before running anything, so that a missing baseline is reported early.
+    let baseline = match opts.baseline {
+        Some(ref name) => Some((name, Baseline::load(name)?)),
+        None => None,
+    };
+
+    if let Some(ref rerun_failed) = opts.rerun_failed {
+        let names: HashSet<&str> = tests.iter().map(|test| test.desc.name.as_slice()).collect();
+        let missing: Vec<&str> =
+            rerun_failed.iter().map(|name| &**name).filter(|name| !names.contains(name)).collect();
+        if !missing.is_empty() {
+            // Goes to stderr, so that machine-readable output on stdout stays valid.
+            let mut stderr = io::stderr().lock();
+            writeln!(stderr, "warning: recorded failed tests no longer exist:")?;
+            for name in missing {
+                writeln!(stderr, "    {name}")?;
+            }
+        }
+    }
+
     // Prevent the usage of `Instant` in some cases:
     // - It's currently not supported for wasm targets without Emscripten nor WASI.
     // - It's currently not supported for zkvm targets.
@@ -325,6 +403,17 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
 
     assert!(opts.fail_fast || st.current_test_count() == st.total);
 
+    if let Some(ref path) = opts.record_failed {
+        st.write_failed_tests(path)?;
+    }
+
+    if let Some((name, baseline)) = baseline {
+        out.write_baseline_comparison(name, &baseline.compare(&st.benchmarks))?;
+    }
+    if let Some(ref name) = opts.save_baseline {
+        Baseline::new(&st.benchmarks).save(name)?;
+    }
+
     out.write_run_finish(&st)
 }
 
@@ -0,0 +1,148 @@
+use std::io;
+use std::io::prelude::Write;
+
+use super::{OutputFormatter, PrettyFormatter};
+use crate::bench::baseline::{BenchComparison, fmt_bench_comparison};
+use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState};
+use crate::test_result::TestResult;
+use crate::time;
+use crate::types::TestDesc;
+
+/// Formatter for GitHub Actions logs: the usual `pretty` output, plus a
+/// [workflow command](https://docs.github.com/en/actions/reference/workflow-commands-for-github-actions)
+/// for every failing test, which GitHub turns into an annotation at the test's source location.
+pub(crate) struct GithubFormatter<T> {
+    pretty: PrettyFormatter<T>,
+}
+
+impl<T: Write> GithubFormatter<T> {
+    pub(crate) fn new(pretty: PrettyFormatter<T>) -> Self {
+        Self { pretty }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn output_location(&self) -> &crate::console::OutputLocation<T> {
+        self.pretty.output_location()
+    }
+
+    fn write_annotation(
+        &mut self,
+        command: &str,
+        desc: &TestDesc,
+        title: &str,
+        message: &str,
+    ) -> io::Result<()> {
+        let mut properties = Vec::new();
+        if !desc.source_file.is_empty() {
+            properties.push(format!("file={}", escape_property(desc.source_file)));
+            if desc.start_line != 0 {
+                properties.push(format!("line={}", desc.start_line));
+                properties.push(format!("endLine={}", desc.end_line));
+                properties.push(format!("col={}", desc.start_col));
+                properties.push(format!("endColumn={}", desc.end_col));
+            }
+        }
+        properties.push(format!("title={}", escape_property(title)));
+
+        self.pretty.write_plain(format!(
+            "::{command} {}::{}\n",
+            properties.join(","),
+            escape_data(message)
+        ))
+    }
+}
+
+impl<T: Write> OutputFormatter for GithubFormatter<T> {
+    fn write_discovery_start(&mut self) -> io::Result<()> {
+        self.pretty.write_discovery_start()
+    }
+
+    fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Result<()> {
+        self.pretty.write_test_discovered(desc, test_type)
+    }
+
+    fn write_discovery_finish(&mut self, state: &ConsoleTestDiscoveryState) -> io::Result<()> {
+        self.pretty.write_discovery_finish(state)
+    }
+
+    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+        self.pretty.write_run_start(test_count, shuffle_seed)
+    }
+
+    fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
+        self.pretty.write_test_start(desc)
+    }
+
+    fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
+        self.pretty.write_timeout(desc)?;
+        self.write_annotation(
+            "warning",
+            desc,
+            &format!("test {} is slow", desc.name),
+            &format!("test has been running for over {} seconds", time::TEST_WARN_TIMEOUT_S),
+        )
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&time::TestExecTime>,
+        stdout: &[u8],
+        state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        self.pretty.write_result(desc, result, exec_time, stdout, state)?;
+
+        let reason = match *result {
+            TestResult::TrOk | TestResult::TrIgnored | TestResult::TrBench(_) => return Ok(()),
+            TestResult::TrFailed => None,
+            TestResult::TrFailedMsg(ref m) => Some(m.as_str()),
+            TestResult::TrTimedFail => Some("time limit exceeded"),
+            TestResult::TrTimedOut => Some("timed out"),
+        };
+        let mut message = String::from_utf8_lossy(stdout).into_owned();
+        if let Some(reason) = reason {
+            if !message.is_empty() && !message.ends_with('\n') {
+                message.push('\n');
+            }
+            message.push_str(reason);
+        }
+        if message.is_empty() {
+            message.push_str("test failed");
+        }
+        self.write_annotation("error", desc, &format!("test {} failed", desc.name), &message)
+    }
+
+    fn write_baseline_comparison(
+        &mut self,
+        baseline: &str,
+        comparisons: &[BenchComparison],
+    ) -> io::Result<()> {
+        self.pretty.write_baseline_comparison(baseline, comparisons)?;
+        for comparison in comparisons {
+            if comparison.change.as_ref().is_some_and(|change| change.is_regression()) {
+                let title = format!("benchmark {} regressed", comparison.name);
+                self.pretty.write_plain(format!(
+                    "::warning title={}::{}\n",
+                    escape_property(&title),
+                    escape_data(fmt_bench_comparison(comparison).trim_start())
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        self.pretty.write_run_finish(state)
+    }
+}
+
+/// Escapes the message of a workflow command.
+fn escape_data(s: &str) -> String {
+    s.trim_end_matches('\n').replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escapes the value of a workflow command property.
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
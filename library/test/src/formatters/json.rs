@@ -3,18 +3,36 @@
 use std::io::prelude::Write;
 
 use super::OutputFormatter;
+use crate::bench::baseline::BenchComparison;
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::options::TestShard;
 use crate::test_result::TestResult;
 use crate::time;
-use crate::types::TestDesc;
+use crate::types::{TestDesc, TestType};
+
+/// Version of the JSON event stream, reported in the `version` field of the first event of a
+/// run or a test listing.
+///
+/// This must be incremented whenever an event or field is changed or removed, so that consumers
+/// can detect that they are reading a format they don't understand. Adding a new event, or a new
+/// optional field to an existing event, is not considered a breaking change. The format is
+/// documented in `src/doc/rustc/src/tests/index.md`, and locked down by the
+/// `json_formatter_schema` test.
+pub(crate) const JSON_FORMAT_VERSION: u32 = 1;
 
 pub(crate) struct JsonFormatter<T> {
     out: OutputLocation<T>,
+    shard: Option<TestShard>,
 }
 
 impl<T: Write> JsonFormatter<T> {
-    pub(crate) fn new(out: OutputLocation<T>) -> Self {
-        Self { out }
+    pub(crate) fn new(out: OutputLocation<T>, shard: Option<TestShard>) -> Self {
+        Self { out, shard }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
     }
 
     fn writeln_message(&mut self, s: &str) -> io::Result<()> {
@@ -26,17 +44,41 @@ fn writeln_message(&mut self, s: &str) -> io::Result<()> {
         self.out.write_all(s.as_ref())
     }
 
+    fn shard_json(&self) -> String {
+        if let Some(TestShard { index, count }) = self.shard {
+            format!(r#", "shard_index": {index}, "shard_count": {count}"#)
+        } else {
+            String::new()
+        }
+    }
+
+    /// The location of a doctest, which is only included in the name of other tests.
+    fn doctest_json(desc: &TestDesc) -> String {
+        if desc.test_type != TestType::DocTest || desc.source_file.is_empty() {
+            return String::new();
+        }
+        let source_path = EscapedString(desc.source_file);
+        let start_line = desc.start_line;
+        let item_path_json = if let Some(item_path) = desc.doctest_item_path() {
+            format!(r#", "item_path": "{}""#, EscapedString(item_path))
+        } else {
+            String::new()
+        };
+        format!(r#", "source_path": "{source_path}", "start_line": {start_line}{item_path_json}"#)
+    }
+
     fn write_event(
         &mut self,
         ty: &str,
-        name: &str,
+        desc: &TestDesc,
         event: &str,
         exec_time: Option<&time::TestExecTime>,
         stdout: Option<Cow<'_, str>>,
         extra: Option<&str>,
     ) -> io::Result<()> {
         // A doc test's name includes a filename which must be escaped for correct json.
-        let name = EscapedString(name);
+        let name = EscapedString(desc.name.as_slice());
+        let doctest_json = Self::doctest_json(desc);
         let exec_time_json = if let Some(exec_time) = exec_time {
             format!(r#", "exec_time": {}"#, exec_time.0.as_secs_f64())
         } else {
@@ -52,13 +94,27 @@ fn write_event(
         let newline = "\n";
 
         self.writeln_message(&format!(
-                r#"{{ "type": "{ty}", "name": "{name}", "event": "{event}"{exec_time_json}{stdout_json}{extra_json} }}{newline}"#))
+                r#"{{ "type": "{ty}", "name": "{name}", "event": "{event}"{doctest_json}{exec_time_json}{stdout_json}{extra_json} }}{newline}"#))
+    }
+
+    /// Reports the captured output of a test, before its result.
+    fn write_stdout(&mut self, desc: &TestDesc, stdout: &[u8]) -> io::Result<()> {
+        let name = EscapedString(desc.name.as_slice());
+        let stdout = EscapedString(String::from_utf8_lossy(stdout));
+        let newline = "\n";
+        self.writeln_message(&format!(
+            r#"{{ "type": "test", "event": "stdout", "name": "{name}", "stdout": "{stdout}" }}{newline}"#
+        ))
     }
 }
 
 impl<T: Write> OutputFormatter for JsonFormatter<T> {
     fn write_discovery_start(&mut self) -> io::Result<()> {
-        self.writeln_message(concat!(r#"{ "type": "suite", "event": "discovery" }"#, "\n"))
+        let shard_json = self.shard_json();
+        let newline = "\n";
+        self.writeln_message(&format!(
+            r#"{{ "type": "suite", "event": "discovery", "version": {JSON_FORMAT_VERSION}{shard_json} }}{newline}"#
+        ))
     }
 
     fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Result<()> {
@@ -100,17 +156,19 @@ fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> i
         } else {
             String::new()
         };
+        let shard_json = self.shard_json();
         let newline = "\n";
         self.writeln_message(&format!(
-            r#"{{ "type": "suite", "event": "started", "test_count": {test_count}{shuffle_seed_json} }}{newline}"#
+            r#"{{ "type": "suite", "event": "started", "version": {JSON_FORMAT_VERSION}, "test_count": {test_count}{shuffle_seed_json}{shard_json} }}{newline}"#
             ))
     }
 
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
         let name = EscapedString(desc.name.as_slice());
+        let doctest_json = Self::doctest_json(desc);
         let newline = "\n";
         self.writeln_message(&format!(
-            r#"{{ "type": "test", "event": "started", "name": "{name}" }}{newline}"#
+            r#"{{ "type": "test", "event": "started", "name": "{name}"{doctest_json} }}{newline}"#
         ))
     }
 
@@ -122,6 +180,11 @@ fn write_result(
         stdout: &[u8],
         state: &ConsoleTestState,
     ) -> io::Result<()> {
+        if !stdout.is_empty() && !matches!(result, TestResult::TrBench(_)) {
+            self.write_stdout(desc, stdout)?;
+        }
+        // The result event only includes the output where the other formats would display it,
+        // as it did before the `stdout` event existed.
         let display_stdout = state.options.display_output || *result != TestResult::TrOk;
         let stdout = if display_stdout && !stdout.is_empty() {
             Some(String::from_utf8_lossy(stdout))
@@ -129,26 +192,33 @@ fn write_result(
             None
         };
         match *result {
-            TestResult::TrOk => {
-                self.write_event("test", desc.name.as_slice(), "ok", exec_time, stdout, None)
-            }
+            TestResult::TrOk => self.write_event("test", desc, "ok", exec_time, stdout, None),
 
             TestResult::TrFailed => {
-                self.write_event("test", desc.name.as_slice(), "failed", exec_time, stdout, None)
+                self.write_event("test", desc, "failed", exec_time, stdout, None)
             }
 
             TestResult::TrTimedFail => self.write_event(
                 "test",
-                desc.name.as_slice(),
+                desc,
                 "failed",
                 exec_time,
                 stdout,
                 Some(r#""reason": "time limit exceeded""#),
             ),
 
+            TestResult::TrTimedOut => self.write_event(
+                "test",
+                desc,
+                "failed",
+                exec_time,
+                stdout,
+                Some(r#""reason": "timed out""#),
+            ),
+
             TestResult::TrFailedMsg(ref m) => self.write_event(
                 "test",
-                desc.name.as_slice(),
+                desc,
                 "failed",
                 exec_time,
                 stdout,
@@ -157,7 +227,7 @@ fn write_result(
 
             TestResult::TrIgnored => self.write_event(
                 "test",
-                desc.name.as_slice(),
+                desc,
                 "ignored",
                 exec_time,
                 stdout,
@@ -195,6 +265,38 @@ fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
         ))
     }
 
+    fn write_baseline_comparison(
+        &mut self,
+        baseline: &str,
+        comparisons: &[BenchComparison],
+    ) -> io::Result<()> {
+        let baseline = EscapedString(baseline);
+        for comparison in comparisons {
+            let name = EscapedString(&comparison.name);
+            let median = comparison.median;
+            let change_json = if let Some(ref change) = comparison.change {
+                format!(
+                    r#", "baseline_median": {}, "change": {}, "change_lower": {}, "change_upper": {}, "verdict": "{}""#,
+                    change.baseline_median,
+                    change.change,
+                    change.lower,
+                    change.upper,
+                    change.verdict()
+                )
+            } else {
+                String::new()
+            };
+            self.writeln_message(&format!(
+                "{{ \"type\": \"bench\", \
+                 \"event\": \"compared\", \
+                 \"name\": \"{name}\", \
+                 \"baseline\": \"{baseline}\", \
+                 \"median\": {median}{change_json} }}\n",
+            ))?;
+        }
+        Ok(())
+    }
+
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         let event = if state.failed == 0 { "ok" } else { "failed" };
         let passed = state.passed;
@@ -219,7 +321,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
 
 /// A formatting utility used to print strings with characters in need of escaping.
 /// Base code taken form `libserialize::json::escape_str`
-struct EscapedString<S: AsRef<str>>(S);
+pub(crate) struct EscapedString<S: AsRef<str>>(pub(crate) S);
 
 impl<S: AsRef<str>> std::fmt::Display for EscapedString<S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> ::std::fmt::Result {
@@ -3,7 +3,9 @@
 use std::time::Duration;
 
 use super::OutputFormatter;
+use crate::bench::baseline::BenchComparison;
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::options::TestShard;
 use crate::test_result::TestResult;
 use crate::time;
 use crate::types::{TestDesc, TestType};
@@ -11,11 +13,17 @@
 pub(crate) struct JunitFormatter<T> {
     out: OutputLocation<T>,
     results: Vec<(TestDesc, TestResult, Duration, Vec<u8>)>,
+    shard: Option<TestShard>,
 }
 
 impl<T: Write> JunitFormatter<T> {
-    pub(crate) fn new(out: OutputLocation<T>) -> Self {
-        Self { out, results: Vec::new() }
+    pub(crate) fn new(out: OutputLocation<T>, shard: Option<TestShard>) -> Self {
+        Self { out, results: Vec::new(), shard }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
     }
 
     fn write_message(&mut self, s: &str) -> io::Result<()> {
@@ -84,6 +92,15 @@ fn write_result(
         self.results.push((desc.clone(), result.clone(), duration, stdout.to_vec()));
         Ok(())
     }
+    fn write_baseline_comparison(
+        &mut self,
+        _baseline: &str,
+        _comparisons: &[BenchComparison],
+    ) -> io::Result<()> {
+        // JUnit reports have no place for benchmark comparisons.
+        Ok(())
+    }
+
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         self.write_message("<testsuites>")?;
 
@@ -96,14 +113,23 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
              >",
             state.failed, state.total, state.ignored
         ))?;
+        if let Some(TestShard { index, count }) = self.shard {
+            self.write_message(&format!(
+                "<properties>\
+                 <property name=\"shard_index\" value=\"{index}\"/>\
+                 <property name=\"shard_count\" value=\"{count}\"/>\
+                 </properties>"
+            ))?;
+        }
         for (desc, result, duration, stdout) in std::mem::take(&mut self.results) {
             let (class_name, test_name) = parse_class_name(&desc);
+            let location = doctest_location(&desc);
             match result {
                 TestResult::TrIgnored => { /* no-op */ }
                 TestResult::TrFailed => {
                     self.write_message(&format!(
                         "<testcase classname=\"{}\" \
-                         name=\"{}\" time=\"{}\">",
+                         name=\"{}\" time=\"{}\"{location}>",
                         class_name,
                         test_name,
                         duration.as_secs_f64()
@@ -120,7 +146,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                 TestResult::TrFailedMsg(ref m) => {
                     self.write_message(&format!(
                         "<testcase classname=\"{}\" \
-                         name=\"{}\" time=\"{}\">",
+                         name=\"{}\" time=\"{}\"{location}>",
                         class_name,
                         test_name,
                         duration.as_secs_f64()
@@ -137,7 +163,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                 TestResult::TrTimedFail => {
                     self.write_message(&format!(
                         "<testcase classname=\"{}\" \
-                         name=\"{}\" time=\"{}\">",
+                         name=\"{}\" time=\"{}\"{location}>",
                         class_name,
                         test_name,
                         duration.as_secs_f64()
@@ -146,6 +172,23 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                     self.write_message("</testcase>")?;
                 }
 
+                TestResult::TrTimedOut => {
+                    self.write_message(&format!(
+                        "<testcase classname=\"{}\" \
+                         name=\"{}\" time=\"{}\"{location}>",
+                        class_name,
+                        test_name,
+                        duration.as_secs_f64()
+                    ))?;
+                    self.write_message("<failure message=\"timed out\" type=\"timeout\"/>")?;
+                    if !stdout.is_empty() {
+                        self.write_message("<system-out>")?;
+                        self.write_message(&str_to_cdata(&String::from_utf8_lossy(&stdout)))?;
+                        self.write_message("</system-out>")?;
+                    }
+                    self.write_message("</testcase>")?;
+                }
+
                 TestResult::TrBench(ref b) => {
                     self.write_message(&format!(
                         "<testcase classname=\"benchmark::{}\" \
@@ -157,7 +200,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
                 TestResult::TrOk => {
                     self.write_message(&format!(
                         "<testcase classname=\"{}\" \
-                         name=\"{}\" time=\"{}\"",
+                         name=\"{}\" time=\"{}\"{location}",
                         class_name,
                         test_name,
                         duration.as_secs_f64()
@@ -207,13 +250,26 @@ fn parse_class_name_unit(desc: &TestDesc) -> (String, String) {
 
 fn parse_class_name_doc(desc: &TestDesc) -> (String, String) {
     // File path => classname
-    // Line # => test name
-    let segments: Vec<&str> = desc.name.as_slice().split(" - ").collect();
-    let (class_name, test_name) = match segments[..] {
-        [file, line] => (String::from(file.trim()), String::from(line.trim())),
-        [..] => unreachable!(),
-    };
-    (class_name, test_name)
+    // Item path and line # => test name
+    // The file path may itself contain ` - `, but the item path can't.
+    match desc.name.as_slice().rsplit_once(" - ") {
+        Some((file, line)) => (String::from(file.trim()), String::from(line.trim())),
+        None => (String::from("doctest"), String::from(desc.name.as_slice())),
+    }
+}
+
+/// The `file` and `line` attributes of a doctest, which many CI systems use to link a test case
+/// to its source.
+fn doctest_location(desc: &TestDesc) -> String {
+    if desc.test_type != TestType::DocTest || desc.source_file.is_empty() {
+        return String::new();
+    }
+    format!(" file=\"{}\" line=\"{}\"", escape_attribute(desc.source_file), desc.start_line)
+}
+
+/// Escapes `s` for use in a double-quoted XML attribute value.
+fn escape_attribute(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
 }
 
 fn parse_class_name_integration(desc: &TestDesc) -> (String, String) {
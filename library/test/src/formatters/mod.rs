@@ -1,19 +1,24 @@
 use std::io;
 use std::io::prelude::Write;
 
+use crate::bench::baseline::{BenchComparison, fmt_bench_comparison};
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState};
 use crate::test_result::TestResult;
-use crate::time;
 use crate::types::{TestDesc, TestName};
+use crate::{term, time};
 
+mod github;
 mod json;
 mod junit;
 mod pretty;
+mod tap;
 mod terse;
 
-pub(crate) use self::json::JsonFormatter;
+pub(crate) use self::github::GithubFormatter;
+pub(crate) use self::json::{EscapedString, JsonFormatter};
 pub(crate) use self::junit::JunitFormatter;
 pub(crate) use self::pretty::PrettyFormatter;
+pub(crate) use self::tap::TapFormatter;
 pub(crate) use self::terse::TerseFormatter;
 
 pub(crate) trait OutputFormatter {
@@ -32,6 +37,11 @@ fn write_result(
         stdout: &[u8],
         state: &ConsoleTestState,
     ) -> io::Result<()>;
+    fn write_baseline_comparison(
+        &mut self,
+        baseline: &str,
+        comparisons: &[BenchComparison],
+    ) -> io::Result<()>;
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool>;
 }
 
@@ -43,3 +53,34 @@ pub(crate) fn write_stderr_delimiter(test_output: &mut Vec<u8>, test_name: &Test
     }
     writeln!(test_output, "---- {test_name} stderr ----").unwrap();
 }
+
+/// Writes the comparison of benchmarks to `baseline` as an aligned table, for the human readable
+/// formats. Every line starts with `line_prefix`. The text is passed to `write` along with the
+/// color to highlight it in, which is only set for the verdicts of regressions and improvements.
+pub(crate) fn write_baseline_comparison_table(
+    baseline: &str,
+    comparisons: &[BenchComparison],
+    line_prefix: &str,
+    mut write: impl FnMut(&str, Option<term::color::Color>) -> io::Result<()>,
+) -> io::Result<()> {
+    write(&format!("{line_prefix}benchmarks compared to baseline `{baseline}`:\n"), None)?;
+    let name_width = comparisons.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    for comparison in comparisons {
+        let name = &comparison.name;
+        let line = format!("{line_prefix}{name:<name_width$} {}", fmt_bench_comparison(comparison));
+        write(&line, None)?;
+        if let Some(ref change) = comparison.change {
+            let color = if change.is_regression() {
+                Some(term::color::RED)
+            } else if change.is_improvement() {
+                Some(term::color::GREEN)
+            } else {
+                None
+            };
+            write(" ", None)?;
+            write(change.verdict(), color)?;
+        }
+        write("\n", None)?;
+    }
+    Ok(())
+}
@@ -2,6 +2,7 @@
 use std::io::prelude::Write;
 
 use super::OutputFormatter;
+use crate::bench::baseline::BenchComparison;
 use crate::bench::fmt_bench_samples;
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
 use crate::test_result::TestResult;
@@ -55,6 +56,10 @@ pub(crate) fn write_time_failed(&mut self) -> io::Result<()> {
         self.write_short_result("FAILED (time limit exceeded)", term::color::RED)
     }
 
+    pub(crate) fn write_timed_out(&mut self) -> io::Result<()> {
+        self.write_short_result("FAILED (timed out)", term::color::RED)
+    }
+
     pub(crate) fn write_bench(&mut self) -> io::Result<()> {
         self.write_pretty("bench", term::color::CYAN)
     }
@@ -249,6 +254,7 @@ fn write_result(
                 self.write_plain(format!(": {}", fmt_bench_samples(bs)))?;
             }
             TestResult::TrTimedFail => self.write_time_failed()?,
+            TestResult::TrTimedOut => self.write_timed_out()?,
         }
 
         self.write_time(desc, exec_time)?;
@@ -263,6 +269,18 @@ fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
         ))
     }
 
+    fn write_baseline_comparison(
+        &mut self,
+        baseline: &str,
+        comparisons: &[BenchComparison],
+    ) -> io::Result<()> {
+        self.write_plain("\n")?;
+        super::write_baseline_comparison_table(baseline, comparisons, "", |s, color| match color {
+            Some(color) => self.write_pretty(s, color),
+            None => self.write_plain(s),
+        })
+    }
+
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         if state.options.display_output {
             self.write_successes(state)?;
@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io;
+use std::io::prelude::Write;
+
+use super::OutputFormatter;
+use super::json::EscapedString;
+use crate::bench::baseline::BenchComparison;
+use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
+use crate::test_result::TestResult;
+use crate::time;
+use crate::types::{TestDesc, TestType};
+
+/// Formatter emitting a [TAP version 14](https://testanything.org/tap-version-14-specification.html)
+/// document. Additional information about a test point (timing, failure message, captured output)
+/// is attached to it as a YAML diagnostics block.
+///
+/// The tests of each module are written as a subtest named after the module, and doctests are
+/// grouped by their source file. As tests run concurrently, a subtest can only be written once
+/// all of its tests finished, so like the JUnit report, the test points are written at the end of
+/// the run.
+pub(crate) struct TapFormatter<T> {
+    out: OutputLocation<T>,
+    /// The finished tests, by the subtest they belong to and their description within it. Tests
+    /// that are not part of a module are under the empty name, and written at the top level.
+    results: BTreeMap<String, BTreeMap<String, TestPoint>>,
+}
+
+/// The result of a test, which is written as a test point.
+struct TestPoint {
+    ok: bool,
+    directive: Option<String>,
+    diagnostics: YamlBlock,
+}
+
+impl<T: Write> TapFormatter<T> {
+    pub(crate) fn new(out: OutputLocation<T>) -> Self {
+        Self { out, results: BTreeMap::new() }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
+    }
+
+    fn write_plain<S: AsRef<str>>(&mut self, s: S) -> io::Result<()> {
+        let s = s.as_ref();
+        self.out.write_all(s.as_bytes())?;
+        self.out.flush()
+    }
+
+    fn add_test_point(
+        &mut self,
+        ok: bool,
+        desc: &TestDesc,
+        directive: Option<String>,
+        diagnostics: YamlBlock,
+    ) -> io::Result<()> {
+        let name = desc.name.as_slice();
+        let (subtest, description) = match desc.test_type {
+            TestType::DocTest if !desc.source_file.is_empty() => (desc.source_file, name),
+            _ => name.rsplit_once("::").unwrap_or(("", name)),
+        };
+        self.results
+            .entry(subtest.to_owned())
+            .or_default()
+            .insert(description.to_owned(), TestPoint { ok, directive, diagnostics });
+        Ok(())
+    }
+}
+
+/// Appends a test point to `out`, indented by `indent`.
+fn write_test_point(
+    out: &mut String,
+    indent: &str,
+    number: usize,
+    description: &str,
+    point: &TestPoint,
+) {
+    let status = if point.ok { "ok" } else { "not ok" };
+    write!(out, "{indent}{status} {number} - {}", TapDescription(description)).unwrap();
+    if let Some(ref directive) = point.directive {
+        // Directives are terminated by the end of the line.
+        write!(out, " # {}", directive.replace(['\n', '\r'], " ")).unwrap();
+    }
+    out.push('\n');
+
+    if !point.diagnostics.is_empty() {
+        writeln!(out, "{indent}  ---").unwrap();
+        for line in point.diagnostics.buf.lines() {
+            if line.is_empty() {
+                out.push('\n');
+            } else {
+                writeln!(out, "{indent}{line}").unwrap();
+            }
+        }
+        writeln!(out, "{indent}  ...").unwrap();
+    }
+}
+
+impl<T: Write> OutputFormatter for TapFormatter<T> {
+    fn write_discovery_start(&mut self) -> io::Result<()> {
+        self.write_plain("TAP version 14\n")
+    }
+
+    fn write_test_discovered(&mut self, desc: &TestDesc, test_type: &str) -> io::Result<()> {
+        // Listing the tests doesn't run any, so they are only written as comments.
+        self.write_plain(format!("# {}: {test_type}\n", TapDescription(desc.name.as_slice())))
+    }
+
+    fn write_discovery_finish(&mut self, state: &ConsoleTestDiscoveryState) -> io::Result<()> {
+        fn plural(count: usize, s: &str) -> String {
+            match count {
+                1 => format!("1 {s}"),
+                n => format!("{n} {s}s"),
+            }
+        }
+
+        self.write_plain(format!(
+            "# {}, {}\n1..0 # SKIP the tests were only listed\n",
+            plural(state.tests, "test"),
+            plural(state.benchmarks, "benchmark")
+        ))
+    }
+
+    fn write_run_start(&mut self, _test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+        self.write_plain("TAP version 14\n")?;
+        if let Some(shuffle_seed) = shuffle_seed {
+            self.write_plain(format!("# shuffle seed: {shuffle_seed}\n"))?;
+        }
+        Ok(())
+    }
+
+    fn write_test_start(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // TAP has no notion of a started test point.
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
+        self.write_plain(format!(
+            "# test {} has been running for over {} seconds\n",
+            TapDescription(desc.name.as_slice()),
+            time::TEST_WARN_TIMEOUT_S
+        ))
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&time::TestExecTime>,
+        stdout: &[u8],
+        state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        let mut diagnostics = YamlBlock::new();
+        if let Some(exec_time) = exec_time {
+            diagnostics
+                .number("duration_ms", format_args!("{:.3}", exec_time.0.as_secs_f64() * 1e3));
+        }
+
+        let display_stdout = state.options.display_output || *result != TestResult::TrOk;
+        let stdout = if display_stdout && !stdout.is_empty() {
+            Some(String::from_utf8_lossy(stdout))
+        } else {
+            None
+        };
+
+        match *result {
+            TestResult::TrOk => {
+                if let Some(ref stdout) = stdout {
+                    diagnostics.text("stdout", stdout);
+                }
+                self.add_test_point(true, desc, None, diagnostics)
+            }
+
+            TestResult::TrFailed => {
+                if let Some(ref stdout) = stdout {
+                    diagnostics.text("stdout", stdout);
+                }
+                self.add_test_point(false, desc, None, diagnostics)
+            }
+
+            TestResult::TrFailedMsg(ref m) => {
+                diagnostics.text("message", m);
+                if let Some(ref stdout) = stdout {
+                    diagnostics.text("stdout", stdout);
+                }
+                self.add_test_point(false, desc, None, diagnostics)
+            }
+
+            TestResult::TrTimedFail => {
+                diagnostics.text("message", "time limit exceeded");
+                if let Some(ref stdout) = stdout {
+                    diagnostics.text("stdout", stdout);
+                }
+                self.add_test_point(false, desc, None, diagnostics)
+            }
+
+            TestResult::TrTimedOut => {
+                diagnostics.text("message", "timed out");
+                if let Some(ref stdout) = stdout {
+                    diagnostics.text("stdout", stdout);
+                }
+                self.add_test_point(false, desc, None, diagnostics)
+            }
+
+            TestResult::TrIgnored => {
+                let directive = match desc.ignore_message {
+                    Some(msg) => format!("SKIP {msg}"),
+                    None => "SKIP".to_owned(),
+                };
+                self.add_test_point(true, desc, Some(directive), diagnostics)
+            }
+
+            TestResult::TrBench(ref bs) => {
+                diagnostics.number("ns_per_iter", bs.ns_iter_summ.median);
+                diagnostics.number("deviation", bs.ns_iter_summ.max - bs.ns_iter_summ.min);
+                if bs.mb_s != 0 {
+                    diagnostics.number("mib_per_second", bs.mb_s);
+                }
+                self.add_test_point(true, desc, None, diagnostics)
+            }
+        }
+    }
+
+    fn write_baseline_comparison(
+        &mut self,
+        baseline: &str,
+        comparisons: &[BenchComparison],
+    ) -> io::Result<()> {
+        super::write_baseline_comparison_table(baseline, comparisons, "# ", |s, _| {
+            self.write_plain(s)
+        })
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        let mut document = String::new();
+        let mut number = 0;
+        let mut written = 0;
+        for (subtest, points) in std::mem::take(&mut self.results) {
+            written += points.len();
+            if subtest.is_empty() {
+                for (description, point) in &points {
+                    number += 1;
+                    write_test_point(&mut document, "", number, description, point);
+                }
+                continue;
+            }
+
+            writeln!(document, "# Subtest: {}", TapDescription(&subtest)).unwrap();
+            writeln!(document, "    1..{}", points.len()).unwrap();
+            for (subtest_number, (description, point)) in points.iter().enumerate() {
+                write_test_point(&mut document, "    ", subtest_number + 1, description, point);
+            }
+            number += 1;
+            let ok = points.values().all(|point| point.ok);
+            let point = TestPoint { ok, directive: None, diagnostics: YamlBlock::new() };
+            write_test_point(&mut document, "", number, &subtest, &point);
+        }
+
+        if written < state.total {
+            // Not all tests were run, which is only possible when stopping at the first failure.
+            document.push_str("Bail out! Stopped at the first failing test\n");
+        } else {
+            writeln!(document, "1..{number}").unwrap();
+        }
+        self.write_plain(document)?;
+
+        let success = state.failed == 0;
+        let mut summary = format!(
+            "# test result: {}. {} passed; {} failed; {} ignored; {} measured; {} filtered out",
+            if success { "ok" } else { "FAILED" },
+            state.passed,
+            state.failed,
+            state.ignored,
+            state.measured,
+            state.filtered_out,
+        );
+        if let Some(ref exec_time) = state.exec_time {
+            write!(summary, "; finished in {exec_time}").unwrap();
+        }
+        summary.push('\n');
+        self.write_plain(summary)?;
+
+        Ok(success)
+    }
+}
+
+/// A test point description, escaped so that `#` can not start a directive.
+struct TapDescription<'a>(&'a str);
+
+impl std::fmt::Display for TapDescription<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '\\' => f.write_str("\\\\")?,
+                '#' => f.write_str("\\#")?,
+                // A test point has to fit on a single line.
+                '\n' | '\r' => f.write_char(' ')?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builder for the contents of a YAML diagnostics block attached to a test point.
+///
+/// All keys are written with the two space indentation required by TAP.
+struct YamlBlock {
+    buf: String,
+}
+
+impl YamlBlock {
+    fn new() -> Self {
+        Self { buf: String::new() }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    fn number(&mut self, key: &str, value: impl std::fmt::Display) {
+        writeln!(self.buf, "  {key}: {value}").unwrap();
+    }
+
+    /// Writes a string value. Multi-line strings are written as a literal block scalar so that
+    /// they stay readable, everything that can not be represented that way is double-quoted.
+    fn text(&mut self, key: &str, value: &str) {
+        let needs_quoting = value.is_empty()
+            || value.chars().any(|c| {
+                (c.is_control() && c != '\n' && c != '\t') || matches!(c, '\u{2028}' | '\u{2029}')
+            });
+        if needs_quoting || !value.contains('\n') {
+            // JSON string escapes are a subset of the escapes of YAML double-quoted scalars.
+            writeln!(self.buf, "  {key}: \"{}\"", EscapedString(value)).unwrap();
+            return;
+        }
+
+        // The content of the block is indented by two spaces relative to the key. That has to be
+        // spelled out if the first line of the content starts with a space, as it would
+        // otherwise be considered part of the indentation.
+        let indentation_indicator = match value.lines().find(|line| !line.is_empty()) {
+            Some(line) if line.starts_with(' ') => "2",
+            _ => "",
+        };
+        let chomping_indicator = if value.ends_with("\n\n") {
+            "+"
+        } else if value.ends_with('\n') {
+            ""
+        } else {
+            "-"
+        };
+        writeln!(self.buf, "  {key}: |{indentation_indicator}{chomping_indicator}").unwrap();
+        for line in value.lines() {
+            if line.is_empty() {
+                self.buf.push('\n');
+            } else {
+                writeln!(self.buf, "    {line}").unwrap();
+            }
+        }
+    }
+}
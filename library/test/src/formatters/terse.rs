@@ -2,6 +2,7 @@
 use std::io::prelude::Write;
 
 use super::OutputFormatter;
+use crate::bench::baseline::BenchComparison;
 use crate::bench::fmt_bench_samples;
 use crate::console::{ConsoleTestDiscoveryState, ConsoleTestState, OutputLocation};
 use crate::test_result::TestResult;
@@ -230,9 +231,10 @@ fn write_result(
     ) -> io::Result<()> {
         match *result {
             TestResult::TrOk => self.write_ok(),
-            TestResult::TrFailed | TestResult::TrFailedMsg(_) | TestResult::TrTimedFail => {
-                self.write_failed(desc.name.as_slice())
-            }
+            TestResult::TrFailed
+            | TestResult::TrFailedMsg(_)
+            | TestResult::TrTimedFail
+            | TestResult::TrTimedOut => self.write_failed(desc.name.as_slice()),
             TestResult::TrIgnored => self.write_ignored(),
             TestResult::TrBench(ref bs) => {
                 if self.is_multithreaded {
@@ -252,6 +254,18 @@ fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
         ))
     }
 
+    fn write_baseline_comparison(
+        &mut self,
+        baseline: &str,
+        comparisons: &[BenchComparison],
+    ) -> io::Result<()> {
+        self.write_plain("\n")?;
+        super::write_baseline_comparison_table(baseline, comparisons, "", |s, color| match color {
+            Some(color) => self.write_pretty(s, color),
+            None => self.write_plain(s),
+        })
+    }
+
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
         if state.options.display_output {
             self.write_outputs(state)?;
@@ -0,0 +1,16 @@
+use crate::options::TestShard;
+
+/// Returns whether the test with the given name belongs to `shard`.
+///
+/// Tests are assigned to shards by a hash of their name. FNV-1a is used instead of `std`'s hashers
+/// as their output may change between releases, while all test runs of a sharded test suite have
+/// to agree on the partitioning, even if they use binaries built by different toolchains.
+pub(crate) fn is_in_shard(shard: TestShard, name: &str) -> bool {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let hash = name
+        .bytes()
+        .fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME));
+    hash % shard.count as u64 == shard.index as u64
+}
@@ -39,17 +39,19 @@ fn randomize_first<T>(rng: &mut Rng, slice: &mut [T]) {
     }
 }
 
-struct Rng {
+/// A small deterministic pseudo random number generator, also used for bootstrap resampling of
+/// benchmark samples.
+pub(crate) struct Rng {
     state: u64,
     extra: u64,
 }
 
 impl Rng {
-    fn new(seed: u64, extra: u64) -> Self {
+    pub(crate) fn new(seed: u64, extra: u64) -> Self {
         Self { state: seed, extra }
     }
 
-    fn rand_range(&mut self, range: core::ops::Range<u64>) -> u64 {
+    pub(crate) fn rand_range(&mut self, range: core::ops::Range<u64>) -> u64 {
         self.rand_u64() % (range.end - range.start) + range.start
     }
 
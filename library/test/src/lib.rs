@@ -35,7 +35,7 @@
 pub use self::ColorConfig::*;
 pub use self::bench::{Bencher, black_box};
 pub use self::console::run_tests_console;
-pub use self::options::{ColorConfig, Options, OutputFormat, RunIgnored, ShouldPanic};
+pub use self::options::{ColorConfig, Options, OutputFormat, RunIgnored, ShouldPanic, TestShard};
 pub use self::types::TestName::*;
 pub use self::types::*;
 
@@ -44,7 +44,7 @@ pub mod test {
     pub use crate::bench::Bencher;
     pub use crate::cli::{TestOpts, parse_opts};
     pub use crate::helpers::metrics::{Metric, MetricMap};
-    pub use crate::options::{Options, RunIgnored, RunStrategy, ShouldPanic};
+    pub use crate::options::{Options, RunIgnored, RunStrategy, ShouldPanic, TestShard};
     pub use crate::test_result::{TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk};
     pub use crate::time::{TestExecTime, TestTimeOptions};
     pub use crate::types::{
@@ -54,8 +54,8 @@ pub mod test {
     pub use crate::{assert_test_result, filter_tests, run_test, test_main, test_main_static};
 }
 
-use std::collections::VecDeque;
-use std::io::prelude::Write;
+use std::collections::{HashSet, VecDeque};
+use std::io::prelude::{Read, Write};
 use std::mem::ManuallyDrop;
 use std::panic::{self, AssertUnwindSafe, PanicHookInfo, catch_unwind};
 use std::process::{self, Command, Termination};
@@ -84,6 +84,7 @@ pub mod test {
 
 use event::{CompletedTest, TestEvent};
 use helpers::concurrency::get_concurrency;
+use helpers::shard::is_in_shard;
 use helpers::shuffle::{get_shuffle_seed, shuffle_tests};
 use options::RunStrategy;
 use test_result::*;
@@ -309,6 +310,20 @@ struct TimeoutEntry {
         timeout: Instant,
     }
 
+    let run_strategy = if opts.options.panic_abort && !opts.force_run_in_process {
+        RunStrategy::SpawnPrimary
+    } else {
+        RunStrategy::InProcess
+    };
+
+    if opts.test_timeout.is_some() && matches!(run_strategy, RunStrategy::InProcess) {
+        return Err(io::const_error!(
+            io::ErrorKind::Unsupported,
+            "`--test-timeout` is only supported when tests are run in subprocesses \
+             (with `-Zpanic-abort-tests`)",
+        ));
+    }
+
     let tests_len = tests.len();
 
     let mut filtered = FilteredTests { tests: Vec::new(), benches: Vec::new(), next_id: 0 };
@@ -353,11 +368,6 @@ struct TimeoutEntry {
     let mut pending = 0;
 
     let (tx, rx) = channel::<CompletedTest>();
-    let run_strategy = if opts.options.panic_abort && !opts.force_run_in_process {
-        RunStrategy::SpawnPrimary
-    } else {
-        RunStrategy::InProcess
-    };
 
     let mut running_tests: TestMap = HashMap::default();
     let mut timeout_queue: VecDeque<TimeoutEntry> = VecDeque::new();
@@ -399,7 +409,7 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
 
             let fail_fast = match completed_test.result {
                 TrIgnored | TrOk | TrBench(_) => false,
-                TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
+                TrFailed | TrFailedMsg(_) | TrTimedFail | TrTimedOut => opts.fail_fast,
             };
 
             let event = TestEvent::TeResult(completed_test);
@@ -455,7 +465,7 @@ fn calc_timeout(timeout_queue: &VecDeque<TimeoutEntry>) -> Option<Duration> {
 
             let fail_fast = match completed_test.result {
                 TrIgnored | TrOk | TrBench(_) => false,
-                TrFailed | TrFailedMsg(_) | TrTimedFail => opts.fail_fast,
+                TrFailed | TrFailedMsg(_) | TrTimedFail | TrTimedOut => opts.fail_fast,
             };
 
             let event = TestEvent::TeResult(completed_test);
@@ -508,6 +518,12 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
         filtered.retain(|test| !opts.skip.iter().any(|sf| matches_filter(test, sf)));
     }
 
+    // Only rerun the tests that failed previously
+    if let Some(ref rerun_failed) = opts.rerun_failed {
+        let rerun_failed: HashSet<&str> = rerun_failed.iter().map(|name| &**name).collect();
+        filtered.retain(|test| rerun_failed.contains(test.desc.name.as_slice()));
+    }
+
     // Excludes #[should_panic] tests
     if opts.exclude_should_panic {
         filtered.retain(|test| test.desc.should_panic == ShouldPanic::No);
@@ -525,6 +541,11 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
         RunIgnored::No => {}
     }
 
+    // Keep only the tests of the selected shard
+    if let Some(shard) = opts.shard {
+        filtered.retain(|test| is_in_shard(shard, test.desc.name.as_slice()));
+    }
+
     filtered
 }
 
@@ -576,14 +597,17 @@ pub fn run_test(
             let name = desc.name.clone();
             let nocapture = opts.nocapture;
             let time_options = opts.time_options;
+            // The TAP output always includes the execution time of each test.
+            let report_time = time_options.is_some() || opts.format == OutputFormat::Tap;
             let bench_benchmarks = opts.bench_benchmarks;
+            let test_timeout = opts.test_timeout;
 
             let runtest = move || match strategy {
                 RunStrategy::InProcess => run_test_in_process(
                     id,
                     desc,
                     nocapture,
-                    time_options.is_some(),
+                    report_time,
                     runnable_test,
                     monitor_ch,
                     time_options,
@@ -592,10 +616,11 @@ pub fn run_test(
                     id,
                     desc,
                     nocapture,
-                    time_options.is_some(),
+                    report_time,
                     monitor_ch,
                     time_options,
                     bench_benchmarks,
+                    test_timeout,
                 ),
             };
 
@@ -696,6 +721,7 @@ fn spawn_test_subprocess(
     monitor_ch: Sender<CompletedTest>,
     time_opts: Option<time::TestTimeOptions>,
     bench_benchmarks: bool,
+    test_timeout: Option<Duration>,
 ) {
     let (result, test_output, exec_time) = (|| {
         let args = env::args().collect::<Vec<_>>();
@@ -709,10 +735,17 @@ fn spawn_test_subprocess(
         if nocapture {
             command.stdout(process::Stdio::inherit());
             command.stderr(process::Stdio::inherit());
+        } else {
+            command.stdout(process::Stdio::piped());
+            command.stderr(process::Stdio::piped());
         }
 
         let start = report_time.then(Instant::now);
-        let output = match command.output() {
+        let output = match test_timeout {
+            None => command.output().map(|output| (output, false)),
+            Some(timeout) => command.spawn().and_then(|child| wait_with_timeout(child, timeout)),
+        };
+        let (output, timed_out) = match output {
             Ok(out) => out,
             Err(e) => {
                 let err = format!("Failed to spawn {} as child for test: {:?}", args[0], e);
@@ -729,8 +762,11 @@ fn spawn_test_subprocess(
         formatters::write_stderr_delimiter(&mut test_output, &desc.name);
         test_output.extend_from_slice(&stderr);
 
-        let result =
-            get_result_from_exit_code(&desc, status, time_opts.as_ref(), exec_time.as_ref());
+        let result = if timed_out {
+            TrTimedOut
+        } else {
+            get_result_from_exit_code(&desc, status, time_opts.as_ref(), exec_time.as_ref())
+        };
         (result, test_output, exec_time)
     })();
 
@@ -738,6 +774,66 @@ fn spawn_test_subprocess(
     monitor_ch.send(message).unwrap();
 }
 
+/// Waits for `child` to exit like `Child::wait_with_output`, but kills it once `timeout` has
+/// elapsed. Also returns whether the child was killed.
+fn wait_with_timeout(
+    mut child: process::Child,
+    timeout: Duration,
+) -> io::Result<(process::Output, bool)> {
+    // Collect the output on separate threads, so that the child can't block on a full pipe.
+    // The output is shared rather than returned by the threads, as a killed test may leave
+    // behind grandchildren that keep the pipes open.
+    type SharedOutput = Arc<Mutex<Vec<u8>>>;
+    fn read_output(
+        pipe: Option<impl Read + Send + 'static>,
+    ) -> (SharedOutput, Option<thread::JoinHandle<()>>) {
+        let output = SharedOutput::default();
+        let reader = pipe.map(|mut pipe| {
+            let output = output.clone();
+            thread::spawn(move || {
+                let mut buf = [0; 4096];
+                while let Ok(n @ 1..) = pipe.read(&mut buf) {
+                    output.lock().unwrap().extend_from_slice(&buf[..n]);
+                }
+            })
+        });
+        (output, reader)
+    }
+    let stdout = read_output(child.stdout.take());
+    let stderr = read_output(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let (status, timed_out) = loop {
+        if let Some(status) = child.try_wait()? {
+            break (status, false);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            child.kill()?;
+            break (child.wait()?, true);
+        }
+        thread::sleep((deadline - now).min(Duration::from_millis(10)));
+    };
+
+    // The readers reach the end of the output once the child is gone, and are joined so that no
+    // output is lost. A killed test may however leave behind grandchildren that keep the pipes
+    // open, so after a kill the readers are only given a moment to finish.
+    let grace_deadline = Instant::now() + Duration::from_secs(1);
+    let take = |(output, reader): (SharedOutput, Option<thread::JoinHandle<()>>)| {
+        if let Some(reader) = reader {
+            while timed_out && !reader.is_finished() && Instant::now() < grace_deadline {
+                thread::sleep(Duration::from_millis(1));
+            }
+            if !timed_out || reader.is_finished() {
+                let _ = reader.join();
+            }
+        }
+        std::mem::take(&mut *output.lock().unwrap())
+    };
+    let output = process::Output { status, stdout: take(stdout), stderr: take(stderr) };
+    Ok((output, timed_out))
+}
+
 fn run_test_in_spawned_subprocess(desc: TestDesc, runnable_test: RunnableTest) -> ! {
     let builtin_panic_hook = panic::take_hook();
     let record_result = Arc::new(move |panic_info: Option<&'_ PanicHookInfo<'_>>| {
@@ -36,6 +36,19 @@ pub enum OutputFormat {
     Json,
     /// JUnit output
     Junit,
+    /// TAP (Test Anything Protocol) version 14 output
+    Tap,
+    /// Verbose output with GitHub Actions annotations for failures
+    Github,
+}
+
+/// Selects one of `count` disjoint subsets of the tests, so that a test suite can be split
+/// across several test runs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TestShard {
+    /// Index of the selected shard, in `0..count`.
+    pub index: usize,
+    pub count: usize,
 }
 
 /// Whether ignored test should be run or not
@@ -1,6 +1,8 @@
 #![allow(missing_docs)]
 
-use std::mem;
+use std::{cmp, mem};
+
+use crate::helpers::shuffle::Rng;
 
 #[cfg(test)]
 mod tests;
@@ -279,6 +281,63 @@ fn percentile_of_sorted(sorted_samples: &[f64], pct: f64) -> f64 {
     lo + (hi - lo) * d
 }
 
+/// Estimates the relative change of the median from the `before` to the `after` samples, along
+/// with a confidence interval for that change at the given `confidence` level (e.g. `0.95`).
+///
+/// The interval is obtained by bootstrap resampling: both sample sets are resampled with
+/// replacement `resamples` times, and the interval is read off the percentiles of the changes of
+/// the resampled medians. Resampling is seeded with `seed`, so the result is deterministic.
+///
+/// Returns `(change, lower, upper)`, each as a fraction of the `before` median, or `None` if the
+/// `before` median is zero, in which case the relative change is undefined. Resamples with a zero
+/// `before` median are skipped for the same reason.
+///
+/// See: <https://en.wikipedia.org/wiki/Bootstrapping_(statistics)>
+pub(crate) fn bootstrap_median_change(
+    before: &[f64],
+    after: &[f64],
+    confidence: f64,
+    resamples: usize,
+    seed: u64,
+) -> Option<(f64, f64, f64)> {
+    assert!(!before.is_empty() && !after.is_empty());
+    assert!(0.0 < confidence && confidence < 1.0);
+    assert!(resamples > 0);
+
+    let before_median = before.median();
+    if before_median == 0.0 {
+        return None;
+    }
+    let change = after.median() / before_median - 1.0;
+
+    let mut rng = Rng::new(seed, (before.len() as u64) << 32 | after.len() as u64);
+    let mut resample = |samples: &[f64], buf: &mut Vec<f64>| {
+        buf.clear();
+        buf.extend(
+            (0..samples.len()).map(|_| samples[rng.rand_range(0..samples.len() as u64) as usize]),
+        );
+        local_sort(buf);
+        percentile_of_sorted(buf, 50.0)
+    };
+    let mut buf = Vec::with_capacity(cmp::max(before.len(), after.len()));
+    let mut changes: Vec<f64> = (0..resamples)
+        .filter_map(|_| {
+            let before_median = resample(before, &mut buf);
+            let after_median = resample(after, &mut buf);
+            (before_median != 0.0).then(|| after_median / before_median - 1.0)
+        })
+        .collect();
+    if changes.is_empty() {
+        return None;
+    }
+    local_sort(&mut changes);
+
+    let tail_pct = (1.0 - confidence) / 2.0 * 100.0;
+    let lower = percentile_of_sorted(&changes, tail_pct);
+    let upper = percentile_of_sorted(&changes, 100.0 - tail_pct);
+    Some((change, lower, upper))
+}
+
 /// Winsorize a set of samples, replacing values above the `100-pct` percentile
 /// and below the `pct` percentile with those percentiles themselves. This is a
 /// way of minimizing the effect of outliers, at the cost of biasing the sample.
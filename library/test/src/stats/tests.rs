@@ -572,6 +572,28 @@ fn test_sum_f64_between_ints_that_sum_to_0() {
     assert_eq!([1e30f64, 1.2f64, -1e30f64].sum(), 1.2);
 }
 
+#[test]
+fn test_bootstrap_median_change() {
+    let before: Vec<f64> = (0..50).map(|i| 100.0 + (i % 7) as f64).collect();
+
+    let (change, lower, upper) = bootstrap_median_change(&before, &before, 0.95, 1000, 0).unwrap();
+    assert_eq!(change, 0.0);
+    assert!(lower <= 0.0 && 0.0 <= upper, "{lower} .. {upper}");
+
+    let after: Vec<f64> = before.iter().map(|s| s * 1.1).collect();
+    let (change, lower, upper) = bootstrap_median_change(&before, &after, 0.95, 1000, 0).unwrap();
+    assert_approx_eq!(change, 0.1);
+    assert!(0.0 < lower && lower <= change && change <= upper, "{lower} .. {upper}");
+    // The resampling is deterministic.
+    assert_eq!(
+        bootstrap_median_change(&before, &after, 0.95, 1000, 0),
+        Some((change, lower, upper))
+    );
+
+    // The relative change to a zero median is undefined.
+    assert_eq!(bootstrap_median_change(&[0.0, 0.0, 1.0], &after, 0.95, 1000, 0), None);
+}
+
 #[bench]
 fn sum_three_items(b: &mut Bencher) {
     b.iter(|| {
@@ -34,7 +34,10 @@ pub enum TestResult {
     TrFailedMsg(String),
     TrIgnored,
     TrBench(BenchSamples),
+    /// The test finished, but took longer than allowed by `--ensure-time`.
     TrTimedFail,
+    /// The test did not finish within `--test-timeout` and was killed.
+    TrTimedOut,
 }
 
 /// Creates a `TestResult` depending on the raw result of test execution
@@ -1,7 +1,11 @@
 use super::*;
 use crate::{
+    cli::OptRes,
     console::OutputLocation,
-    formatters::PrettyFormatter,
+    formatters::{
+        GithubFormatter, JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter,
+        TapFormatter,
+    },
     test::{
         MetricMap,
         // FIXME (introduced by #65251)
@@ -9,7 +13,7 @@
         // TestType, TrFailedMsg, TrIgnored, TrOk,
         parse_opts,
     },
-    time::{TestTimeOptions, TimeThreshold},
+    time::{TestSuiteExecTime, TestTimeOptions, TimeThreshold},
 };
 
 impl TestOpts {
@@ -31,7 +35,13 @@ fn new() -> TestOpts {
             shuffle_seed: None,
             test_threads: None,
             skip: vec![],
+            shard: None,
+            rerun_failed: None,
+            record_failed: None,
+            save_baseline: None,
+            baseline: None,
             time_options: None,
+            test_timeout: None,
             options: Options::new(),
             fail_fast: false,
         }
@@ -54,6 +64,8 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                #[cfg(not(bootstrap))]
+                item_path: None,
             },
             testfn: DynTestFn(Box::new(move || Ok(()))),
         },
@@ -71,6 +83,8 @@ fn one_ignored_one_unignored_test() -> Vec<TestDescAndFn> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                #[cfg(not(bootstrap))]
+                item_path: None,
             },
             testfn: DynTestFn(Box::new(move || Ok(()))),
         },
@@ -96,6 +110,8 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            item_path: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -124,6 +140,8 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            item_path: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -153,6 +171,8 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            item_path: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -182,6 +202,8 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            item_path: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -216,6 +238,8 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            item_path: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -255,6 +279,8 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            item_path: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -287,6 +313,8 @@ fn f() -> Result<(), String> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                #[cfg(not(bootstrap))]
+                item_path: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -320,6 +348,8 @@ fn f() -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            item_path: None,
         },
         testfn: DynTestFn(Box::new(f)),
     };
@@ -468,6 +498,34 @@ fn parse_include_ignored_flag() {
     assert_eq!(opts.run_ignored, RunIgnored::Yes);
 }
 
+/// Parses the command line `args`, with unstable options enabled.
+fn parse_unstable(args: &[&str]) -> OptRes {
+    let mut all_args = vec!["progname".to_string(), "-Zunstable-options".to_string()];
+    all_args.extend(args.iter().map(|arg| arg.to_string()));
+    parse_opts(&all_args).unwrap()
+}
+
+#[test]
+fn parse_test_timeout_option() {
+    let opts = parse_unstable(&["--test-timeout", "1.5"]).unwrap();
+    assert_eq!(opts.test_timeout, Some(Duration::from_millis(1500)));
+
+    assert!(parse_unstable(&["--test-timeout", "0"]).is_err());
+    assert!(parse_unstable(&["--test-timeout", "-1"]).is_err());
+    assert!(parse_unstable(&["--test-timeout", "forever"]).is_err());
+
+    let args = vec!["progname".to_string(), "--test-timeout=1".to_string()];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
+#[test]
+fn test_timeout_requires_subprocesses() {
+    let opts =
+        TestOpts { run_tests: true, test_timeout: Some(Duration::from_secs(1)), ..TestOpts::new() };
+    let err = run_tests(&opts, one_ignored_one_unignored_test(), |_| Ok(())).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+}
+
 #[test]
 fn filter_for_ignored_option() {
     // When we run ignored tests the test filter should filter out all the
@@ -523,6 +581,8 @@ fn exclude_should_panic_option() {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            item_path: None,
         },
         testfn: DynTestFn(Box::new(move || Ok(()))),
     });
@@ -552,6 +612,8 @@ fn tests() -> Vec<TestDescAndFn> {
                     compile_fail: false,
                     no_run: false,
                     test_type: TestType::Unknown,
+                    #[cfg(not(bootstrap))]
+                    item_path: None,
                 },
                 testfn: DynTestFn(Box::new(move || Ok(()))),
             })
@@ -648,6 +710,8 @@ fn testfn() -> Result<(), String> {
                 compile_fail: false,
                 no_run: false,
                 test_type: TestType::Unknown,
+                #[cfg(not(bootstrap))]
+                item_path: None,
             },
             testfn: DynTestFn(Box::new(testfn)),
         };
@@ -721,6 +785,334 @@ fn order_depends_on_more_than_seed() {
     assert!(left.iter().zip(right).any(|(a, b)| a.0 != b.0));
 }
 
+#[test]
+fn shards_partition_filtered_tests() {
+    let all_names = |opts: &TestOpts| -> Vec<String> {
+        filter_tests(opts, sample_tests()).into_iter().map(|t| t.desc.name.to_string()).collect()
+    };
+    let opts = TestOpts { skip: vec!["isize".into()], ..TestOpts::new() };
+    let expected = all_names(&opts);
+
+    for count in 1..=4 {
+        let mut sharded = Vec::new();
+        for index in 0..count {
+            let shard = Some(TestShard { index, count });
+            let names = all_names(&TestOpts { shard, skip: opts.skip.clone(), ..TestOpts::new() });
+            // The same shard is selected every time.
+            assert_eq!(
+                names,
+                all_names(&TestOpts { shard, skip: opts.skip.clone(), ..TestOpts::new() })
+            );
+            sharded.extend(names);
+        }
+        // Every test that passes the filters is run in exactly one shard.
+        sharded.sort();
+        let mut expected = expected.clone();
+        expected.sort();
+        assert_eq!(sharded, expected);
+    }
+}
+
+#[test]
+fn parse_shard_options() {
+    let opts = parse_unstable(&["--shard-index", "2", "--shard-count", "3"]).unwrap();
+    assert_eq!(opts.shard, Some(TestShard { index: 2, count: 3 }));
+    assert_eq!(parse_unstable(&[]).unwrap().shard, None);
+
+    assert!(parse_unstable(&["--shard-index", "3", "--shard-count", "3"]).is_err());
+    assert!(parse_unstable(&["--shard-index", "0", "--shard-count", "0"]).is_err());
+    assert!(parse_unstable(&["--shard-index", "0"]).is_err());
+    assert!(parse_unstable(&["--shard-count", "2"]).is_err());
+}
+
+#[test]
+fn rerun_failed_selects_exact_names() {
+    let names = |rerun_failed: Vec<&str>| -> Vec<String> {
+        let rerun_failed = Some(rerun_failed.into_iter().map(str::to_owned).collect());
+        let opts = TestOpts { rerun_failed, ..TestOpts::new() };
+        filter_tests(&opts, sample_tests()).into_iter().map(|t| t.desc.name.to_string()).collect()
+    };
+
+    assert_eq!(
+        names(vec!["isize::test_pow", "sha1", "test::sort_tests", "removed::test"]),
+        ["isize::test_pow", "test::sort_tests"]
+    );
+    // Nothing failed in the previous run, so there is nothing to rerun.
+    assert!(names(vec![]).is_empty());
+}
+
+#[test]
+fn failed_tests_file_round_trip() {
+    let path = std::env::temp_dir()
+        .join(format!("libtest-failed-tests-round-trip-{}", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    let opts = parse_unstable(&["--record-failed", "--failed-tests-file", path_arg]).unwrap();
+    assert_eq!(opts.record_failed.as_deref(), Some(&*path));
+    assert_eq!(opts.rerun_failed, None);
+
+    let mut st = console::ConsoleTestState::new(&opts).unwrap();
+    st.failures.push((formatter_test_desc("b::failed"), Vec::new()));
+    st.time_failures.push((formatter_test_desc("a::failed"), Vec::new()));
+    st.write_failed_tests(&path).unwrap();
+
+    let opts = parse_unstable(&["--rerun-failed", "--failed-tests-file", path_arg]).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(opts.rerun_failed, Some(vec!["a::failed".to_owned(), "b::failed".to_owned()]));
+    // Rerunning records the tests that still fail.
+    assert_eq!(opts.record_failed.as_deref(), Some(&*path));
+
+    // Nothing to rerun without a recorded run.
+    assert!(parse_unstable(&["--rerun-failed", "--failed-tests-file", path_arg]).is_err());
+    assert!(parse_unstable(&["--failed-tests-file", path_arg]).is_err());
+}
+
+#[test]
+fn failed_tests_file_keeps_tests_that_did_not_run() {
+    let path =
+        std::env::temp_dir().join(format!("libtest-failed-tests-filtered-{}", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    let run = |opts: &TestOpts, failing: &[&'static str]| {
+        let tests = ["a::failed", "b::failed", "c::passed"].map(|name| TestDescAndFn {
+            desc: formatter_test_desc(name),
+            testfn: DynTestFn(Box::new(|| Ok(()))),
+        });
+        let mut st = console::ConsoleTestState::new(opts).unwrap();
+        for test in filter_tests(opts, tests.into()) {
+            if failing.contains(&test.desc.name.as_slice()) {
+                st.failures.push((test.desc, Vec::new()));
+            } else {
+                st.not_failures.push((test.desc, Vec::new()));
+            }
+        }
+        st.write_failed_tests(&path).unwrap();
+        std::fs::read_to_string(&path).unwrap()
+    };
+
+    let opts = parse_unstable(&["--record-failed", "--failed-tests-file", path_arg]).unwrap();
+    assert_eq!(run(&opts, &["a::failed", "b::failed"]), "a::failed\nb::failed\n");
+
+    // `a::failed` was fixed, and only it is rerun. `b::failed` didn't run, so it is kept.
+    let opts = parse_unstable(&["--rerun-failed", "--failed-tests-file", path_arg, "a::"]).unwrap();
+    assert_eq!(opts.rerun_failed, Some(vec!["a::failed".to_owned(), "b::failed".to_owned()]));
+    assert_eq!(run(&opts, &[]), "b::failed\n");
+
+    // A filtered run that records failures doesn't forget the failures of other tests either.
+    let opts =
+        parse_unstable(&["--record-failed", "--failed-tests-file", path_arg, "c::"]).unwrap();
+    assert_eq!(run(&opts, &["c::passed"]), "b::failed\nc::passed\n");
+    let opts =
+        parse_unstable(&["--record-failed", "--failed-tests-file", path_arg, "--skip", "b::"])
+            .unwrap();
+    assert_eq!(run(&opts, &[]), "b::failed\n");
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn formatters_report_shard() {
+    let shard = Some(TestShard { index: 1, count: 4 });
+    let st = console::ConsoleTestState::new(&TestOpts::new()).unwrap();
+
+    let mut out = JsonFormatter::new(OutputLocation::Raw(Vec::new()), shard);
+    out.write_run_start(3, None).unwrap();
+    let s = raw_output(out.output_location());
+    assert_eq!(
+        s,
+        "{ \"type\": \"suite\", \"event\": \"started\", \"version\": 1, \"test_count\": 3, \
+         \"shard_index\": 1, \"shard_count\": 4 }\n"
+    );
+
+    let mut out = JunitFormatter::new(OutputLocation::Raw(Vec::new()), shard);
+    out.write_run_finish(&st).unwrap();
+    let s = raw_output(out.output_location());
+    assert!(s.contains(
+        "<properties>\
+         <property name=\"shard_index\" value=\"1\"/>\
+         <property name=\"shard_count\" value=\"4\"/>\
+         </properties>"
+    ));
+}
+
+#[test]
+#[cfg(not(bootstrap))]
+fn formatters_report_doctest_location() {
+    let doctest = TestDesc {
+        name: StaticTestName("src/a - b.rs - foo::Bar (line 12)"),
+        source_file: "src/a - b.rs",
+        test_type: TestType::DocTest,
+        item_path: Some("foo::Bar"),
+        ..formatter_test_desc("")
+    };
+    let markdown_doctest = TestDesc {
+        name: StaticTestName("README.md - (line 3)"),
+        item_path: None,
+        ..doctest.clone()
+    };
+
+    let mut st = console::ConsoleTestState::new(&TestOpts::new()).unwrap();
+    let mut out = JsonFormatter::new(OutputLocation::Raw(Vec::new()), None);
+    out.write_test_start(&doctest).unwrap();
+    out.write_result(&doctest, &TrOk, None, b"", &st).unwrap();
+    out.write_result(&markdown_doctest, &TrFailed, None, b"", &st).unwrap();
+    let s = raw_output(out.output_location());
+    assert_eq!(
+        s,
+        r#"{ "type": "test", "event": "started", "name": "src/a - b.rs - foo::Bar (line 12)", "source_path": "src/a - b.rs", "start_line": 12, "item_path": "foo::Bar" }
+{ "type": "test", "name": "src/a - b.rs - foo::Bar (line 12)", "event": "ok", "source_path": "src/a - b.rs", "start_line": 12, "item_path": "foo::Bar" }
+{ "type": "test", "name": "README.md - (line 3)", "event": "failed", "source_path": "src/a - b.rs", "start_line": 12 }
+"#
+    );
+
+    let mut out = JunitFormatter::new(OutputLocation::Raw(Vec::new()), None);
+    out.write_result(&doctest, &TrFailed, None, b"", &st).unwrap();
+    st.failed = 1;
+    st.total = 1;
+    out.write_run_finish(&st).unwrap();
+    let s = raw_output(out.output_location());
+    assert!(s.contains(
+        "<testcase classname=\"src/a - b.rs\" name=\"foo::Bar (line 12)\" time=\"0\" \
+         file=\"src/a - b.rs\" line=\"12\"><failure type=\"assert\"/></testcase>"
+    ));
+}
+
+#[test]
+fn junit_formatter_escapes_doctest_file() {
+    let doctest = TestDesc {
+        name: StaticTestName("src/a&b<\"c\".rs - foo (line 12)"),
+        source_file: "src/a&b<\"c\".rs",
+        test_type: TestType::DocTest,
+        ..formatter_test_desc("")
+    };
+
+    let mut st = console::ConsoleTestState::new(&TestOpts::new()).unwrap();
+    let mut out = JunitFormatter::new(OutputLocation::Raw(Vec::new()), None);
+    out.write_result(&doctest, &TrFailed, None, b"", &st).unwrap();
+    st.failed = 1;
+    st.total = 1;
+    out.write_run_finish(&st).unwrap();
+    let s = raw_output(out.output_location());
+    assert!(s.contains(" file=\"src/a&amp;b&lt;&quot;c&quot;.rs\" line=\"12\">"));
+}
+
+fn bench_samples(name: &'static str, samples: Vec<f64>) -> (TestDesc, bench::BenchSamples) {
+    let bs = bench::BenchSamples { ns_iter_summ: stats::Summary::new(&samples), mb_s: 0, samples };
+    (formatter_test_desc(name), bs)
+}
+
+#[test]
+fn baseline_json_round_trip() {
+    use crate::bench::baseline::Baseline;
+
+    let baseline = Baseline::new(&[
+        bench_samples("bench::a", vec![10.0, 10.5, 11.25]),
+        bench_samples("bench::\"quoted\"", vec![0.125, 2e-7]),
+    ]);
+    let json = baseline.to_json();
+    assert_eq!(
+        json,
+        "{\n  \"version\": 1,\n  \"benchmarks\": {\n    \
+         \"bench::\\\"quoted\\\"\": { \"median\": 0.0625001, \"deviation\": 0.1249998, \"samples\": [0.125, 0.0000002] },\n    \
+         \"bench::a\": { \"median\": 10.5, \"deviation\": 1.25, \"samples\": [10, 10.5, 11.25] }\n  \
+         }\n}\n"
+    );
+    assert_eq!(Baseline::from_json(&json), Ok(baseline));
+
+    assert!(Baseline::from_json(r#"{ "version": 2, "benchmarks": {} }"#).is_err());
+    assert!(Baseline::from_json(r#"{ "version": 1, "benchmarks": { "a": {} } }"#).is_err());
+    assert!(Baseline::from_json(r#"{ "version": 1, "benchmarks": {} } x"#).is_err());
+    assert_eq!(
+        Baseline::from_json(r#"{"version":1,"benchmarks":{"\u0061":{"samples":[1E1],"x":null}}}"#),
+        Ok(Baseline::new(&[bench_samples("a", vec![10.0])]))
+    );
+}
+
+#[test]
+fn baseline_comparison_flags_regressions() {
+    use crate::bench::baseline::Baseline;
+
+    let samples = |median: f64| (0..50).map(|i| median + (i % 5) as f64).collect::<Vec<_>>();
+    let baseline = Baseline::new(&[
+        bench_samples("regressed", samples(100.0)),
+        bench_samples("improved", samples(100.0)),
+        bench_samples("unchanged", samples(100.0)),
+    ]);
+    let comparisons = baseline.compare(&[
+        bench_samples("regressed", samples(120.0)),
+        bench_samples("improved", samples(80.0)),
+        bench_samples("unchanged", samples(100.0)),
+        bench_samples("new", samples(10.0)),
+    ]);
+
+    let verdicts: Vec<_> = comparisons
+        .iter()
+        .map(|c| (&*c.name, c.change.as_ref().map(|change| change.verdict())))
+        .collect();
+    assert_eq!(
+        verdicts,
+        [
+            ("regressed", Some("regressed")),
+            ("improved", Some("improved")),
+            ("unchanged", Some("unchanged")),
+            ("new", None),
+        ]
+    );
+
+    let mut out = JsonFormatter::new(OutputLocation::Raw(Vec::new()), None);
+    out.write_baseline_comparison("main", &comparisons[3..]).unwrap();
+    let s = raw_output(out.output_location());
+    assert_eq!(
+        s,
+        "{ \"type\": \"bench\", \"event\": \"compared\", \"name\": \"new\", \
+         \"baseline\": \"main\", \"median\": 12 }\n"
+    );
+}
+
+#[test]
+fn baseline_comparison_skips_zero_medians() {
+    use crate::bench::baseline::Baseline;
+
+    // Trivial benchmarks commonly measure 0 ns/iter, and there is no relative change to that.
+    let baseline = Baseline::new(&[
+        bench_samples("zero", vec![0.0; 50]),
+        bench_samples("mostly-zero", (0..50).map(|i| (i % 5 / 4) as f64).collect()),
+    ]);
+    let comparisons = baseline.compare(&[
+        bench_samples("zero", vec![1.0; 50]),
+        bench_samples("mostly-zero", vec![1.0; 50]),
+    ]);
+    assert!(comparisons.iter().all(|c| c.change.is_none()), "{comparisons:?}");
+
+    let mut out = JsonFormatter::new(OutputLocation::Raw(Vec::new()), None);
+    out.write_baseline_comparison("main", &comparisons[..1]).unwrap();
+    let s = raw_output(out.output_location());
+    assert_eq!(
+        s,
+        "{ \"type\": \"bench\", \"event\": \"compared\", \"name\": \"zero\", \
+         \"baseline\": \"main\", \"median\": 1 }\n"
+    );
+
+    let mut out = PrettyFormatter::new(OutputLocation::Raw(Vec::new()), false, 0, false, None);
+    out.write_baseline_comparison("main", &comparisons).unwrap();
+    let s = raw_output(out.output_location());
+    assert_eq!(
+        s,
+        "\nbenchmarks compared to baseline `main`:\n\
+         zero                  1.00 ns/iter (not compared)\n\
+         mostly-zero           1.00 ns/iter (not compared)\n"
+    );
+}
+
+#[test]
+fn parse_baseline_options() {
+    let opts = parse_unstable(&["--save-baseline", "new-1.0", "--baseline", "main"]).unwrap();
+    assert_eq!(opts.save_baseline.as_deref(), Some("new-1.0"));
+    assert_eq!(opts.baseline.as_deref(), Some("main"));
+
+    assert!(parse_unstable(&["--baseline", "../main"]).is_err());
+    assert!(parse_unstable(&["--save-baseline", ".hidden"]).is_err());
+    assert!(parse_unstable(&["--save-baseline", ""]).is_err());
+}
+
 #[test]
 fn test_metricmap_compare() {
     let mut m1 = MetricMap::new();
@@ -782,6 +1174,8 @@ fn f(_: &mut Bencher) -> Result<(), String> {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        #[cfg(not(bootstrap))]
+        item_path: None,
     };
 
     crate::bench::benchmark(TestId(0), desc, tx, true, f);
@@ -810,6 +1204,8 @@ fn f(b: &mut Bencher) -> Result<(), String> {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        #[cfg(not(bootstrap))]
+        item_path: None,
     };
 
     crate::bench::benchmark(TestId(0), desc, tx, true, f);
@@ -831,6 +1227,8 @@ fn should_sort_failures_before_printing_them() {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        #[cfg(not(bootstrap))]
+        item_path: None,
     };
 
     let test_b = TestDesc {
@@ -846,6 +1244,8 @@ fn should_sort_failures_before_printing_them() {
         compile_fail: false,
         no_run: false,
         test_type: TestType::Unknown,
+        #[cfg(not(bootstrap))]
+        item_path: None,
     };
 
     let mut out = PrettyFormatter::new(OutputLocation::Raw(Vec::new()), false, 10, false, None);
@@ -860,6 +1260,7 @@ fn should_sort_failures_before_printing_them() {
         measured: 0,
         exec_time: None,
         metrics: MetricMap::new(),
+        benchmarks: Vec::new(),
         failures: vec![(test_b, Vec::new()), (test_a, Vec::new())],
         options: Options::new(),
         not_failures: Vec::new(),
@@ -898,6 +1299,8 @@ fn f(_: &mut Bencher) -> Result<(), String> {
             compile_fail: false,
             no_run: false,
             test_type: TestType::Unknown,
+            #[cfg(not(bootstrap))]
+            item_path: None,
         },
         testfn: DynBenchFn(Box::new(f)),
     };
@@ -912,3 +1315,274 @@ fn f(_: &mut Bencher) -> Result<(), String> {
     let result = rx.recv().unwrap().result;
     assert_eq!(result, TrFailed);
 }
+
+fn formatter_test_desc(name: &'static str) -> TestDesc {
+    TestDesc {
+        name: StaticTestName(name),
+        ignore: false,
+        ignore_message: None,
+        source_file: "src/lib.rs",
+        start_line: 12,
+        start_col: 4,
+        end_line: 12,
+        end_col: 10,
+        should_panic: ShouldPanic::No,
+        compile_fail: false,
+        no_run: false,
+        test_type: TestType::UnitTest,
+        #[cfg(not(bootstrap))]
+        item_path: None,
+    }
+}
+
+/// The output written to a formatter with a `Raw` output location.
+fn raw_output(out: &OutputLocation<Vec<u8>>) -> String {
+    match out {
+        OutputLocation::Raw(m) => String::from_utf8_lossy(m).into_owned(),
+        OutputLocation::Pretty(_) => unreachable!(),
+    }
+}
+
+#[test]
+fn tap_formatter_writes_yaml_diagnostics() {
+    let mut out = TapFormatter::new(OutputLocation::Raw(Vec::new()));
+    let mut st = console::ConsoleTestState::new(&TestOpts::new()).unwrap();
+
+    out.write_run_start(4, None).unwrap();
+    out.write_result(&formatter_test_desc("ok #1"), &TrOk, None, b"ignored", &st).unwrap();
+    out.write_result(
+        &formatter_test_desc("failed"),
+        &TrFailedMsg("expected `1`".to_string()),
+        Some(&TestExecTime(Duration::from_micros(1500))),
+        b"  indented\nno trailing newline",
+        &st,
+    )
+    .unwrap();
+    out.write_result(&formatter_test_desc("control"), &TrFailed, None, b"bell\x07\n", &st).unwrap();
+    let ignored = TestDesc { ignore_message: Some("slow"), ..formatter_test_desc("ignored") };
+    out.write_result(&ignored, &TrIgnored, None, b"", &st).unwrap();
+    st.total = 4;
+    st.failed = 2;
+    out.write_run_finish(&st).unwrap();
+
+    let s = raw_output(out.output_location());
+    assert_eq!(
+        s,
+        r#"TAP version 14
+not ok 1 - control
+  ---
+  stdout: "bell\u0007\n"
+  ...
+not ok 2 - failed
+  ---
+  duration_ms: 1.500
+  message: "expected `1`"
+  stdout: |2-
+      indented
+    no trailing newline
+  ...
+ok 3 - ignored # SKIP slow
+ok 4 - ok \#1
+1..4
+# test result: FAILED. 0 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out
+"#
+    );
+}
+
+#[test]
+fn tap_formatter_writes_subtests() {
+    let mut out = TapFormatter::new(OutputLocation::Raw(Vec::new()));
+    let mut st = console::ConsoleTestState::new(&TestOpts::new()).unwrap();
+
+    out.write_run_start(5, None).unwrap();
+    out.write_result(&formatter_test_desc("b::y"), &TrOk, None, b"", &st).unwrap();
+    out.write_result(&formatter_test_desc("a::nested::x"), &TrOk, None, b"", &st).unwrap();
+    out.write_result(&formatter_test_desc("root"), &TrOk, None, b"", &st).unwrap();
+    out.write_result(&formatter_test_desc("b::x"), &TrFailed, None, b"multi\n\nline\n", &st)
+        .unwrap();
+    let doctest = TestDesc {
+        name: StaticTestName("src/lib.rs - foo::Bar (line 12)"),
+        test_type: TestType::DocTest,
+        ..formatter_test_desc("")
+    };
+    out.write_result(&doctest, &TrOk, None, b"", &st).unwrap();
+    st.total = 5;
+    st.passed = 4;
+    st.failed = 1;
+    out.write_run_finish(&st).unwrap();
+
+    let s = raw_output(out.output_location());
+    assert_eq!(
+        s,
+        "TAP version 14
+ok 1 - root
+# Subtest: a::nested
+    1..1
+    ok 1 - x
+ok 2 - a::nested
+# Subtest: b
+    1..2
+    not ok 1 - x
+      ---
+      stdout: |
+        multi
+
+        line
+
+      ...
+    ok 2 - y
+not ok 3 - b
+# Subtest: src/lib.rs
+    1..1
+    ok 1 - src/lib.rs - foo::Bar (line 12)
+ok 4 - src/lib.rs
+1..4
+# test result: FAILED. 4 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out
+"
+    );
+}
+
+#[test]
+fn tap_formatter_lists_tests() {
+    let mut out = TapFormatter::new(OutputLocation::Raw(Vec::new()));
+    let mut st = console::ConsoleTestDiscoveryState::new(&TestOpts::new()).unwrap();
+
+    out.write_discovery_start().unwrap();
+    out.write_test_discovered(&formatter_test_desc("a::b"), "test").unwrap();
+    out.write_test_discovered(&formatter_test_desc("bench #1"), "benchmark").unwrap();
+    st.tests = 1;
+    st.benchmarks = 1;
+    out.write_discovery_finish(&st).unwrap();
+
+    let s = raw_output(out.output_location());
+    assert_eq!(
+        s,
+        "TAP version 14\n\
+         # a::b: test\n\
+         # bench \\#1: benchmark\n\
+         # 1 test, 1 benchmark\n\
+         1..0 # SKIP the tests were only listed\n"
+    );
+}
+
+#[test]
+fn github_formatter_writes_annotations() {
+    let mut out = GithubFormatter::new(PrettyFormatter::new(
+        OutputLocation::Raw(Vec::new()),
+        false,
+        0,
+        true,
+        None,
+    ));
+    let st = console::ConsoleTestState::new(&TestOpts::new()).unwrap();
+
+    out.write_result(&formatter_test_desc("ok"), &TrOk, None, b"", &st).unwrap();
+    out.write_timeout(&formatter_test_desc("slow")).unwrap();
+    out.write_result(
+        &formatter_test_desc("tests::failed"),
+        &TrFailedMsg("100% wrong".to_string()),
+        None,
+        b"line 1\nline 2\n",
+        &st,
+    )
+    .unwrap();
+
+    let s = raw_output(out.output_location());
+    assert_eq!(
+        s,
+        "test ok ... ok\n\
+         test slow has been running for over 60 seconds\n\
+         ::warning file=src/lib.rs,line=12,endLine=12,col=4,endColumn=10,\
+         title=test slow is slow::test has been running for over 60 seconds\n\
+         test tests::failed ... FAILED\n\
+         ::error file=src/lib.rs,line=12,endLine=12,col=4,endColumn=10,\
+         title=test tests%3A%3Afailed failed::line 1%0Aline 2%0A100%25 wrong\n"
+    );
+}
+
+/// Locks down the JSON event stream. If this test has to be changed, the change most likely has
+/// to be reflected in the documentation of the format, and `JSON_FORMAT_VERSION` bumped if the
+/// change is not backwards compatible.
+#[test]
+#[cfg(not(bootstrap))]
+fn json_formatter_schema() {
+    use crate::bench::baseline::{BenchComparison, MedianChange};
+
+    let mut out = JsonFormatter::new(OutputLocation::Raw(Vec::new()), None);
+    let mut st = console::ConsoleTestState::new(&TestOpts::new()).unwrap();
+    let mut discovery = console::ConsoleTestDiscoveryState::new(&TestOpts::new()).unwrap();
+    let exec_time = TestExecTime(Duration::from_millis(250));
+    let bench = bench::BenchSamples {
+        ns_iter_summ: stats::Summary::new(&[10.0, 20.0, 30.0]),
+        mb_s: 8,
+        samples: vec![10.0, 20.0, 30.0],
+    };
+    let ignored =
+        TestDesc { ignore: true, ignore_message: Some("slow"), ..formatter_test_desc("d") };
+    let doctest = TestDesc {
+        name: StaticTestName("src/lib.rs - foo::Bar (line 12)"),
+        test_type: TestType::DocTest,
+        item_path: Some("foo::Bar"),
+        ..formatter_test_desc("")
+    };
+
+    out.write_discovery_start().unwrap();
+    out.write_test_discovered(&formatter_test_desc("a"), "test").unwrap();
+    out.write_test_discovered(&ignored, "test").unwrap();
+    discovery.tests = 2;
+    discovery.ignored = 1;
+    out.write_discovery_finish(&discovery).unwrap();
+
+    out.write_run_start(8, Some(42)).unwrap();
+    out.write_test_start(&formatter_test_desc("a")).unwrap();
+    out.write_timeout(&formatter_test_desc("a")).unwrap();
+    out.write_result(&formatter_test_desc("a"), &TrOk, Some(&exec_time), b"hi\n", &st).unwrap();
+    out.write_result(&formatter_test_desc("b"), &TrFailed, None, b"\"out\"\n", &st).unwrap();
+    out.write_result(&formatter_test_desc("c"), &TrFailedMsg("msg".to_string()), None, b"", &st)
+        .unwrap();
+    out.write_result(&formatter_test_desc("e"), &TrTimedFail, Some(&exec_time), b"", &st).unwrap();
+    out.write_result(&formatter_test_desc("i"), &TrTimedOut, None, b"", &st).unwrap();
+    out.write_test_start(&doctest).unwrap();
+    out.write_result(&doctest, &TrOk, None, b"", &st).unwrap();
+    out.write_result(&ignored, &TrIgnored, None, b"", &st).unwrap();
+    out.write_result(&formatter_test_desc("f"), &TrBench(bench), None, b"", &st).unwrap();
+    let change = MedianChange { baseline_median: 25.0, change: -0.2, lower: -0.3, upper: -0.1 };
+    let comparisons = [
+        BenchComparison { name: "f".to_string(), median: 20.0, change: Some(change) },
+        BenchComparison { name: "g".to_string(), median: 5.0, change: None },
+    ];
+    out.write_baseline_comparison("main", &comparisons).unwrap();
+    st.passed = 2;
+    st.failed = 4;
+    st.ignored = 1;
+    st.measured = 1;
+    st.exec_time = Some(TestSuiteExecTime(Duration::from_secs(1)));
+    out.write_run_finish(&st).unwrap();
+
+    let s = raw_output(out.output_location());
+    assert_eq!(
+        s,
+        r#"{ "type": "suite", "event": "discovery", "version": 1 }
+{ "type": "test", "event": "discovered", "name": "a", "ignore": false, "ignore_message": "", "source_path": "src/lib.rs", "start_line": 12, "start_col": 4, "end_line": 12, "end_col": 10 }
+{ "type": "test", "event": "discovered", "name": "d", "ignore": true, "ignore_message": "slow", "source_path": "src/lib.rs", "start_line": 12, "start_col": 4, "end_line": 12, "end_col": 10 }
+{ "type": "suite", "event": "completed", "tests": 2, "benchmarks": 0, "total": 2, "ignored": 1 }
+{ "type": "suite", "event": "started", "version": 1, "test_count": 8, "shuffle_seed": 42 }
+{ "type": "test", "event": "started", "name": "a" }
+{ "type": "test", "event": "timeout", "name": "a" }
+{ "type": "test", "event": "stdout", "name": "a", "stdout": "hi\n" }
+{ "type": "test", "name": "a", "event": "ok", "exec_time": 0.25 }
+{ "type": "test", "event": "stdout", "name": "b", "stdout": "\"out\"\n" }
+{ "type": "test", "name": "b", "event": "failed", "stdout": "\"out\"\n" }
+{ "type": "test", "name": "c", "event": "failed", "message": "msg" }
+{ "type": "test", "name": "e", "event": "failed", "exec_time": 0.25, "reason": "time limit exceeded" }
+{ "type": "test", "name": "i", "event": "failed", "reason": "timed out" }
+{ "type": "test", "event": "started", "name": "src/lib.rs - foo::Bar (line 12)", "source_path": "src/lib.rs", "start_line": 12, "item_path": "foo::Bar" }
+{ "type": "test", "name": "src/lib.rs - foo::Bar (line 12)", "event": "ok", "source_path": "src/lib.rs", "start_line": 12, "item_path": "foo::Bar" }
+{ "type": "test", "name": "d", "event": "ignored", "message": "slow" }
+{ "type": "bench", "name": "f", "median": 20, "deviation": 20, "mib_per_second": 8 }
+{ "type": "bench", "event": "compared", "name": "f", "baseline": "main", "median": 20, "baseline_median": 25, "change": -0.2, "change_lower": -0.3, "change_upper": -0.1, "verdict": "improved" }
+{ "type": "bench", "event": "compared", "name": "g", "baseline": "main", "median": 5 }
+{ "type": "suite", "event": "failed", "passed": 2, "failed": 4, "ignored": 1, "measured": 1, "filtered_out": 0, "exec_time": 1 }
+"#
+    );
+}
@@ -207,6 +207,10 @@ pub struct TestDesc {
     pub compile_fail: bool,
     pub no_run: bool,
     pub test_type: TestType,
+    /// Path of the item a doctest documents, e.g. `foo::Bar`. `None` for other tests, and for
+    /// doctests that don't document an item, like those in Markdown files.
+    #[cfg(not(bootstrap))]
+    pub item_path: Option<&'static str>,
 }
 
 impl TestDesc {
@@ -243,6 +247,14 @@ pub fn test_mode(&self) -> Option<&'static str> {
         }
         None
     }
+
+    /// Returns the path of the item a doctest documents, see [`TestDesc::item_path`].
+    pub(crate) fn doctest_item_path(&self) -> Option<&'static str> {
+        #[cfg(not(bootstrap))]
+        return self.item_path;
+        #[cfg(bootstrap)]
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -252,11 +264,13 @@ pub struct TestDescAndFn {
 }
 
 impl TestDescAndFn {
+    #[cfg_attr(bootstrap, allow(unused_variables))]
     pub const fn new_doctest(
         test_name: &'static str,
         ignore: bool,
         source_file: &'static str,
         start_line: usize,
+        item_path: Option<&'static str>,
         no_run: bool,
         should_panic: bool,
         testfn: TestFn,
@@ -279,6 +293,8 @@ pub const fn new_doctest(
                     options::ShouldPanic::No
                 },
                 test_type: TestType::DocTest,
+                #[cfg(not(bootstrap))]
+                item_path,
             },
             testfn,
         }
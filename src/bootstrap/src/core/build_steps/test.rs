@@ -52,6 +52,7 @@ fn should_run(run: ShouldRun<'_>) -> ShouldRun<'_> {
         // command-line (e.g. `./x test suggest-tests`) will test only the
         // specified tools.
         run.path("src/tools/jsondoclint")
+            .path("src/tools/jsondocdiff")
             .path("src/tools/suggest-tests")
             .path("src/tools/replace-version-placeholder")
             // We want `./x test tidy` to _run_ the tidy tool, not its tests.
@@ -929,6 +929,7 @@ macro_rules! describe {
                 clippy::Compiletest,
                 clippy::CoverageDump,
                 clippy::Jsondocck,
+                clippy::Jsondocdiff,
                 clippy::Jsondoclint,
                 clippy::LintDocs,
                 clippy::LlvmBitcodeLinker,
@@ -355,6 +355,8 @@ fn render_message(&mut self, message: Message) {
                 println!("test {name} has been running for a long time");
             }
             Message::Test(TestMessage::Started) => {} // Not useful
+            // The output of failed tests is also part of their result.
+            Message::Test(TestMessage::Stdout) => {}
         }
     }
 }
@@ -465,6 +467,7 @@ enum TestMessage {
     Ignored(TestOutcome),
     Timeout { name: String },
     Started,
+    Stdout,
 }
 
 #[derive(serde_derive::Deserialize)]
@@ -79,6 +79,7 @@ fn main() {
         registry: registry::Registry::new(rustc_errors::codes::DIAGNOSTICS),
         make_codegen_backend: None,
         expanded_args: Vec::new(),
+        early_sarif_results: Default::default(),
         ice_file: None,
         hash_untracked_state: None,
         using_internal_features: &rustc_driver::USING_INTERNAL_FEATURES,
@@ -34,6 +34,7 @@ pub(crate) enum OutputFormat {
     #[default]
     Html,
     Doctest,
+    Markdown,
 }
 
 impl OutputFormat {
@@ -50,6 +51,7 @@ fn try_from(value: &str) -> Result<Self, Self::Error> {
             "json" => Ok(OutputFormat::Json),
             "html" => Ok(OutputFormat::Html),
             "doctest" => Ok(OutputFormat::Doctest),
+            "markdown" => Ok(OutputFormat::Markdown),
             _ => Err(format!("unknown output format `{value}`")),
         }
     }
@@ -287,6 +289,11 @@ pub(crate) struct RenderOptions {
     pub(crate) generate_redirect_map: bool,
     /// Show the memory layout of types in the docs.
     pub(crate) show_type_layout: bool,
+    /// With `--show-coverage`, list every item that is missing documentation or an example.
+    pub(crate) coverage_details: bool,
+    /// With `--show-coverage`, the documentation percentage below which the crate or any of its
+    /// modules is reported as an error.
+    pub(crate) coverage_threshold: Option<f64>,
     /// Note: this field is duplicated in `Options` because it's useful to have
     /// it in both places.
     pub(crate) unstable_features: rustc_feature::UnstableFeatures,
@@ -503,6 +510,11 @@ fn println_condition(condition: Condition) {
                     "the -Z unstable-options flag must be passed to enable --output-format for documentation generation (see https://github.com/rust-lang/rust/issues/134529)",
                 );
             }
+            (Some(OutputFormat::Markdown), false, false) => {
+                dcx.fatal(
+                    "the -Z unstable-options flag must be passed to enable --output-format for documentation generation",
+                );
+            }
         }
 
         let to_check = matches.opt_strs("check-theme");
@@ -787,6 +799,21 @@ fn println_condition(condition: Condition) {
         let run_check = matches.opt_present("check");
         let generate_redirect_map = matches.opt_present("generate-redirect-map");
         let show_type_layout = matches.opt_present("show-type-layout");
+        let coverage_details = matches.opt_present("coverage-details");
+        let coverage_threshold =
+            matches.opt_str("coverage-threshold").map(|threshold| match threshold.parse::<f64>() {
+                Ok(threshold) if (0.0..=100.0).contains(&threshold) => threshold,
+                _ => dcx.fatal(format!(
+                    "`--coverage-threshold` must be a percentage between 0 and 100, \
+                     found `{threshold}`"
+                )),
+            });
+        if !show_coverage && (coverage_details || coverage_threshold.is_some()) {
+            dcx.fatal(
+                "`--coverage-details` and `--coverage-threshold` can only be used with \
+                 `--show-coverage`",
+            );
+        }
         let nocapture = matches.opt_present("nocapture");
         let generate_link_to_definition = matches.opt_present("generate-link-to-definition");
         let extern_html_root_takes_precedence =
@@ -882,6 +909,8 @@ fn println_condition(condition: Condition) {
             document_hidden,
             generate_redirect_map,
             show_type_layout,
+            coverage_details,
+            coverage_threshold,
             unstable_features,
             emit,
             generate_link_to_definition,
@@ -196,6 +196,7 @@ pub(crate) fn run(dcx: DiagCtxtHandle<'_>, input: Input, options: RustdocOptions
         ice_file: None,
         using_internal_features: &rustc_driver::USING_INTERNAL_FEATURES,
         expanded_args: options.expanded_args.clone(),
+        early_sarif_results: Default::default(),
     };
 
     let externs = options.externs.clone();
@@ -324,6 +325,11 @@ pub(crate) fn run_tests(
     if rustdoc_options.nocapture {
         test_args.push("--nocapture".to_string());
     }
+    // Reports meant for other tools include the execution time of every doctest.
+    if wants_machine_readable_report(&test_args) && !test_args.iter().any(|a| a == "--report-time")
+    {
+        test_args.push("--report-time".to_string());
+    }
 
     let mut nb_errors = 0;
     let mut ran_edition_tests = 0;
@@ -391,6 +397,22 @@ pub(crate) fn run_tests(
     }
 }
 
+/// Whether `test_args` ask libtest for a report that is meant to be read by other tools.
+fn wants_machine_readable_report(test_args: &[String]) -> bool {
+    let mut args = test_args.iter();
+    while let Some(arg) = args.next() {
+        let format = match arg.strip_prefix("--format") {
+            Some("") => args.next().map(String::as_str),
+            Some(rest) => rest.strip_prefix('='),
+            None => None,
+        };
+        if matches!(format, Some("json" | "junit" | "tap")) {
+            return true;
+        }
+    }
+    false
+}
+
 // Look for `#![doc(test(no_crate_inject))]`, used by crates in the std facade.
 fn scrape_test_config(
     crate_name: String,
@@ -873,6 +895,8 @@ pub(crate) struct ScrapedDocTest {
     langstr: LangString,
     text: String,
     name: String,
+    /// Path of the documented item, `None` for doctests in Markdown files and crate docs.
+    item_path: Option<String>,
 }
 
 impl ScrapedDocTest {
@@ -885,13 +909,14 @@ fn new(
     ) -> Self {
         let mut item_path = logical_path.join("::");
         item_path.retain(|c| c != ' ');
-        if !item_path.is_empty() {
-            item_path.push(' ');
-        }
-        let name =
-            format!("{} - {item_path}(line {line})", filename.prefer_remapped_unconditionaly());
+        let item_path = (!item_path.is_empty()).then_some(item_path);
+        let name = format!(
+            "{} - {}(line {line})",
+            filename.prefer_remapped_unconditionaly(),
+            item_path.as_ref().map_or(String::new(), |item_path| format!("{item_path} ")),
+        );
 
-        Self { filename, line, langstr, text, name }
+        Self { filename, line, langstr, text, name, item_path }
     }
     fn edition(&self, opts: &RustdocOptions) -> Edition {
         self.langstr.edition.unwrap_or(opts.edition)
@@ -1033,8 +1058,10 @@ fn generate_test_desc_and_fn(
                 Ignore::Some(ref ignores) => ignores.iter().any(|s| target_str.contains(s)),
             },
             ignore_message: None,
-            source_file: "",
-            start_line: 0,
+            // Like merged doctests, which embed it in the test binary. `TestDesc` only borrows the
+            // file name, but the tests live until rustdoc exits anyway.
+            source_file: Box::leak(scraped_test.path().display().to_string().into_boxed_str()),
+            start_line: scraped_test.line,
             start_col: 0,
             end_line: 0,
             end_col: 0,
@@ -1043,6 +1070,11 @@ fn generate_test_desc_and_fn(
             compile_fail: scraped_test.langstr.compile_fail,
             no_run: scraped_test.no_run(&rustdoc_options),
             test_type: test::TestType::DocTest,
+            #[cfg(not(bootstrap))]
+            item_path: scraped_test
+                .item_path
+                .as_ref()
+                .map(|item_path| &*Box::leak(item_path.clone().into_boxed_str())),
         },
         testfn: test::DynTestFn(Box::new(move || {
             doctest_run_fn(
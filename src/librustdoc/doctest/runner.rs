@@ -236,7 +236,7 @@ fn main() {returns_result} {{
         "
 mod {test_id} {{
 pub const TEST: test::TestDescAndFn = test::TestDescAndFn::new_doctest(
-{test_name:?}, {ignore}, {file:?}, {line}, {no_run}, {should_panic},
+{test_name:?}, {ignore}, {file:?}, {line}, {item_path:?}, {no_run}, {should_panic},
 test::StaticTestFn(
     || {{{runner}}},
 ));
@@ -244,6 +244,7 @@ mod {test_id} {{
         test_name = scraped_test.name,
         file = scraped_test.path(),
         line = scraped_test.line,
+        item_path = scraped_test.item_path,
         no_run = scraped_test.langstr.no_run,
         should_panic = !scraped_test.langstr.no_run && scraped_test.langstr.should_panic,
         // Setting `no_run` to `true` in `TestDesc` still makes the test run, so we simply
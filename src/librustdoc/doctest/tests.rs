@@ -401,3 +401,20 @@ fn compare(input: &str, expected: &[&str]) {
     compare("a\n\t \rb", &["a", "b"]);
     compare("a\n\t1 \rb", &["a", "1", "b"]);
 }
+
+#[test]
+fn check_wants_machine_readable_report() {
+    fn check(input: &[&str], expected: bool) {
+        let args = input.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(super::wants_machine_readable_report(&args), expected, "{input:?}");
+    }
+
+    check(&["rustdoctest"], false);
+    check(&["rustdoctest", "--format", "json"], true);
+    check(&["rustdoctest", "-Zunstable-options", "--format=junit"], true);
+    check(&["rustdoctest", "--format=tap", "--nocapture"], true);
+    check(&["rustdoctest", "--format", "pretty"], false);
+    check(&["rustdoctest", "--format=terse"], false);
+    check(&["rustdoctest", "--format"], false);
+    check(&["rustdoctest", "--formats", "json"], false);
+}
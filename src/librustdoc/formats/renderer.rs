@@ -15,7 +15,7 @@ pub(crate) trait FormatRenderer<'tcx>: Sized {
 
     /// Whether to call `item` recursively for modules
     ///
-    /// This is true for html, and false for json. See #80664
+    /// This is true for html and markdown, and false for json. See #80664
     const RUN_ON_MODULE: bool;
 
     /// This associated type is the type where the current module information is stored.
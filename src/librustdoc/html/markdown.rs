@@ -1162,7 +1162,7 @@ fn default() -> Self {
 }
 
 impl LangString {
-    fn parse_without_check(
+    pub(crate) fn parse_without_check(
         string: &str,
         allow_error_code_check: ErrorCodes,
         enable_per_target_ignores: bool,
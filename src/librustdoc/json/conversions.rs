@@ -336,6 +336,7 @@ fn from_clean(struct_: clean::Struct, renderer: &JsonRenderer<'_>) -> Self {
             kind,
             generics: generics.into_json(renderer),
             impls: Vec::new(), // Added in JsonRenderer::item
+            layout: None,      // Added in JsonRenderer::item
         }
     }
 }
@@ -349,6 +350,7 @@ fn from_clean(union_: clean::Union, renderer: &JsonRenderer<'_>) -> Self {
             has_stripped_fields,
             fields: renderer.ids(fields),
             impls: Vec::new(), // Added in JsonRenderer::item
+            layout: None,      // Added in JsonRenderer::item
         }
     }
 }
@@ -704,6 +706,7 @@ fn from_clean(enum_: clean::Enum, renderer: &JsonRenderer<'_>) -> Self {
             has_stripped_variants,
             variants: renderer.ids(variants),
             impls: Vec::new(), // Added in JsonRenderer::item
+            layout: None,      // Added in JsonRenderer::item
         }
     }
 }
@@ -0,0 +1,104 @@
+//! Computes the memory layout of structs, enums and unions for `--show-type-layout`.
+//!
+//! This mirrors what the HTML backend shows in its "Layout" section, but exposes the
+//! details (field offsets, tags and niches) in a form that tools can consume.
+
+use rustc_abi::{TagEncoding, Variants};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::layout::{LayoutCx, TyAndLayout};
+use rustc_middle::ty::{self, TyCtxt};
+use rustdoc_json_types as types;
+
+use super::JsonRenderer;
+
+impl JsonRenderer<'_> {
+    /// Returns the layout of the ADT `def_id`, or `None` if layouts were not requested or the
+    /// layout could not be computed, e.g. because it depends on generic parameters.
+    pub(super) fn type_layout(&self, def_id: DefId) -> Option<types::TypeLayout> {
+        if !self.show_type_layout {
+            return None;
+        }
+
+        let tcx = self.tcx;
+        let typing_env = ty::TypingEnv::post_analysis(tcx, def_id);
+        let ty = tcx.type_of(def_id).instantiate_identity();
+        let layout = tcx.layout_of(typing_env.as_query_input(ty)).ok()?;
+        let ty::Adt(adt, _) = layout.ty.kind() else { return None };
+        let cx = LayoutCx::new(tcx, typing_env);
+
+        let (fields, variants) = if adt.is_enum() {
+            let variants = adt
+                .discriminants(tcx)
+                .map(|(idx, discr)| {
+                    let variant_layout = layout.for_variant(&cx, idx);
+                    types::VariantLayout {
+                        name: adt.variant(idx).name.to_string(),
+                        discriminant: discr.to_string(),
+                        size: variant_layout.size.bytes(),
+                        is_uninhabited: variant_layout.is_uninhabited(),
+                        fields: field_layouts(&cx, adt.variant(idx), variant_layout),
+                    }
+                })
+                .collect();
+            (Vec::new(), variants)
+        } else {
+            (field_layouts(&cx, adt.non_enum_variant(), layout), Vec::new())
+        };
+
+        Some(types::TypeLayout {
+            size: layout.size.bytes(),
+            align: layout.align.abi.bytes(),
+            is_unsized: layout.is_unsized(),
+            is_uninhabited: layout.is_uninhabited(),
+            fields,
+            variants,
+            tag: tag_layout(tcx, layout),
+            niche: layout.largest_niche.map(|niche| types::Niche {
+                offset: niche.offset.bytes(),
+                size: niche.value.size(&tcx).bytes(),
+                valid_range_start: niche.valid_range.start.to_string(),
+                valid_range_end: niche.valid_range.end.to_string(),
+                available: niche.available(&tcx).to_string(),
+            }),
+        })
+    }
+}
+
+/// The layout of the fields of `variant`, given the layout of that variant.
+fn field_layouts<'tcx>(
+    cx: &LayoutCx<'tcx>,
+    variant: &ty::VariantDef,
+    layout: TyAndLayout<'tcx>,
+) -> Vec<types::FieldLayout> {
+    variant
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| types::FieldLayout {
+            name: field.name.to_string(),
+            offset: layout.fields.offset(i).bytes(),
+            size: layout.field(cx, i).size.bytes(),
+        })
+        .collect()
+}
+
+fn tag_layout<'tcx>(tcx: TyCtxt<'tcx>, layout: TyAndLayout<'tcx>) -> Option<types::TagLayout> {
+    let Variants::Multiple { tag, ref tag_encoding, tag_field, .. } = layout.variants else {
+        return None;
+    };
+    let encoding = match tag_encoding {
+        TagEncoding::Direct => types::TagEncoding::Direct,
+        TagEncoding::Niche { untagged_variant, niche_variants, niche_start } => {
+            types::TagEncoding::Niche {
+                untagged_variant: untagged_variant.as_u32(),
+                niche_variants: (niche_variants.start().as_u32(), niche_variants.end().as_u32()),
+                niche_start: niche_start.to_string(),
+            }
+        }
+    };
+    Some(types::TagLayout {
+        offset: layout.fields.offset(tag_field).bytes(),
+        size: tag.size(&tcx).bytes(),
+        encoding,
+    })
+}
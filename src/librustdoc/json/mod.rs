@@ -7,6 +7,7 @@
 mod conversions;
 mod ids;
 mod import_finder;
+mod layout;
 
 use std::cell::RefCell;
 use std::fs::{File, create_dir_all};
@@ -48,6 +49,8 @@ pub(crate) struct JsonRenderer<'tcx> {
     cache: Rc<Cache>,
     imported_items: DefIdSet,
     id_interner: Rc<RefCell<ids::IdInterner>>,
+    /// Whether to include [`types::TypeLayout`]s, set by `--show-type-layout`.
+    show_type_layout: bool,
 }
 
 impl<'tcx> JsonRenderer<'tcx> {
@@ -149,6 +152,7 @@ fn init(
                 cache: Rc::new(cache),
                 imported_items,
                 id_interner: Default::default(),
+                show_type_layout: options.show_type_layout,
             },
             krate,
         ))
@@ -187,14 +191,17 @@ fn item(&mut self, item: clean::Item) -> Result<(), Error> {
                 }
                 types::ItemEnum::Struct(ref mut s) => {
                     s.impls = self.get_impls(item_id.expect_def_id());
+                    s.layout = self.type_layout(item_id.expect_def_id());
                     false
                 }
                 types::ItemEnum::Enum(ref mut e) => {
                     e.impls = self.get_impls(item_id.expect_def_id());
+                    e.layout = self.type_layout(item_id.expect_def_id());
                     false
                 }
                 types::ItemEnum::Union(ref mut u) => {
                     u.impls = self.get_impls(item_id.expect_def_id());
+                    u.layout = self.type_layout(item_id.expect_def_id());
                     false
                 }
                 types::ItemEnum::Primitive(ref mut p) => {
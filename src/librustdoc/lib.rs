@@ -117,6 +117,7 @@ macro_rules! map {
 mod json;
 pub(crate) mod lint;
 mod markdown;
+mod md;
 mod passes;
 mod scrape_examples;
 mod theme;
@@ -506,6 +507,22 @@ fn opts() -> Vec<RustcOptGroup> {
             "calculate percentage of public items with documentation",
             "",
         ),
+        opt(
+            Unstable,
+            FlagMulti,
+            "",
+            "coverage-details",
+            "with --show-coverage, list the items missing documentation or examples",
+            "",
+        ),
+        opt(
+            Unstable,
+            Opt,
+            "",
+            "coverage-threshold",
+            "with --show-coverage, fail if the crate or a module has less documented items",
+            "PERCENT",
+        ),
         opt(
             Unstable,
             FlagMulti,
@@ -909,6 +926,9 @@ fn main_args(early_dcx: &mut EarlyDiagCtxt, at_args: &[String]) {
                 config::OutputFormat::Json => sess.time("render_json", || {
                     run_renderer::<json::JsonRenderer<'_>>(krate, render_opts, cache, tcx)
                 }),
+                config::OutputFormat::Markdown => sess.time("render_markdown", || {
+                    run_renderer::<md::MarkdownRenderer<'_>>(krate, render_opts, cache, tcx)
+                }),
                 // Already handled above with doctest runners.
                 config::OutputFormat::Doctest => unreachable!(),
             }
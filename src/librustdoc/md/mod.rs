@@ -0,0 +1,588 @@
+//! Rustdoc's Markdown backend
+//!
+//! This module renders a crate as a tree of Markdown files rather than HTML pages. The tree has the
+//! same layout as the HTML output: every module gets an `index.md` listing its items, and every
+//! other item gets a page of its own (like `std/vec/struct.Vec.md`) with its signature, its
+//! documentation and its implementations. Intra-doc links become relative links between these
+//! pages, or links into the HTML documentation of external crates whose location is known.
+
+mod print;
+#[cfg(test)]
+mod tests;
+
+use std::fmt::Write as _;
+use std::fs::{self, create_dir_all};
+use std::path::PathBuf;
+
+use pulldown_cmark::{BrokenLink, CowStr, Event, LinkType, Parser, Tag, TagEnd};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Symbol;
+use tracing::debug;
+
+use crate::clean::types::ExternalLocation;
+use crate::config::RenderOptions;
+use crate::docfs::PathError;
+use crate::error::Error;
+use crate::formats::cache::Cache;
+use crate::formats::item_type::ItemType;
+use crate::formats::{FormatRenderer, Impl};
+use crate::html::markdown::{
+    ErrorCodes, LangString, Line, main_body_opts, map_line, plain_text_summary,
+};
+use crate::html::render::{ItemSection, item_ty_to_section};
+use crate::md::print::{item_kind_title, print_impl_header, print_item_signature, print_type};
+use crate::{clean, try_err};
+
+pub(crate) struct MarkdownRenderer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    /// The root of the output; the pages of a crate go into a directory named after it.
+    out_dir: PathBuf,
+    /// The path of the module being rendered, starting with the crate name.
+    current: Vec<Symbol>,
+    /// Whether the module being rendered is stripped. Stripped modules are still visited for the
+    /// impls they contain, but neither they nor their items get pages.
+    in_stripped_module: bool,
+    cache: Cache,
+}
+
+impl<'tcx> MarkdownRenderer<'tcx> {
+    fn write_page(&self, file_name: &str, page: String) -> Result<(), Error> {
+        let mut dir = self.out_dir.clone();
+        dir.extend(self.current.iter().map(|name| name.as_str()));
+        try_err!(create_dir_all(&dir), &dir);
+        let path = dir.join(file_name);
+        debug!("writing {}", path.display());
+        try_err!(fs::write(&path, page), &path);
+        Ok(())
+    }
+
+    fn module_page(&self, item: &clean::Item) -> String {
+        let (clean::StrippedItem(box clean::ModuleItem(module)) | clean::ModuleItem(module)) =
+            &item.kind
+        else {
+            unreachable!()
+        };
+        let mut page = if item.is_crate() {
+            format!("# Crate `{}`\n", item.name.unwrap())
+        } else {
+            format!("# Module `{}`\n", join_path(&self.current))
+        };
+        self.push_docs(&mut page, item, 1);
+
+        for &section in ItemSection::ALL {
+            let mut children = module
+                .items
+                .iter()
+                .filter(|child| {
+                    !child.is_stripped()
+                        && (child.name.is_some() || child.is_import())
+                        && item_ty_to_section(child.type_()) == section
+                })
+                .peekable();
+            if children.peek().is_none() {
+                continue;
+            }
+            write!(page, "\n## {}\n\n", section.name()).unwrap();
+            for child in children {
+                if child.is_import() || child.is_extern_crate() {
+                    let signature = print_item_signature(child, self.tcx).unwrap();
+                    writeln!(page, "- `{signature}`").unwrap();
+                    continue;
+                }
+                let name = child.name.unwrap();
+                let file = if child.is_mod() {
+                    format!("{name}/index.md")
+                } else {
+                    format!("{}.{name}.md", child.type_())
+                };
+                write!(page, "- [`{name}`]({file})").unwrap();
+                let summary =
+                    plain_text_summary(&child.doc_value(), &child.link_names(&self.cache));
+                if !summary.is_empty() {
+                    write!(page, ": {summary}").unwrap();
+                }
+                page.push('\n');
+            }
+        }
+        page
+    }
+
+    fn item_page(&self, item: &clean::Item) -> String {
+        let name = item.name.unwrap();
+        let item_type = item.type_();
+        let mut page = format!(
+            "# {} `{name}`\n\n`{}::{name}`\n",
+            item_kind_title(item_type),
+            join_path(&self.current)
+        );
+        if let Some(signature) = print_item_signature(item, self.tcx) {
+            write!(page, "\n```rust\n{signature}\n```\n").unwrap();
+        }
+        self.push_docs(&mut page, item, 1);
+
+        match &item.kind {
+            clean::StructItem(s) => self.push_fields(&mut page, &s.fields),
+            clean::UnionItem(u) => self.push_fields(&mut page, &u.fields),
+            clean::EnumItem(e) => self.push_variants(&mut page, e),
+            clean::TraitItem(t) => self.push_trait_items(&mut page, t),
+            _ => {}
+        }
+        match item.kind {
+            clean::StructItem(_)
+            | clean::UnionItem(_)
+            | clean::EnumItem(_)
+            | clean::TypeAliasItem(_)
+            | clean::ForeignTypeItem
+            | clean::PrimitiveItem(_) => self.push_impls(&mut page, item.item_id.expect_def_id()),
+            clean::TraitItem(_) => {
+                let did = item.item_id.expect_def_id();
+                self.push_impls(&mut page, did);
+                self.push_implementors(&mut page, did);
+            }
+            _ => {}
+        }
+        page
+    }
+
+    /// Appends the documentation of an item, with its headings moved down by `heading_offset`
+    /// levels.
+    fn push_docs(&self, page: &mut String, item: &clean::Item, heading_offset: usize) {
+        let docs = item.doc_value();
+        if docs.trim().is_empty() {
+            return;
+        }
+        page.push('\n');
+        page.push_str(&render_docs(&docs, &self.links(item), heading_offset));
+    }
+
+    /// Appends a heading for a field, variant or associated item, preceded by the anchor that
+    /// intra-doc links to the item point to.
+    fn push_member(&self, page: &mut String, level: usize, member: &clean::Item, title: &str) {
+        write!(
+            page,
+            "\n<a id=\"{}.{}\"></a>\n\n{} `{title}`\n",
+            member.type_(),
+            member.name.unwrap(),
+            "#".repeat(level)
+        )
+        .unwrap();
+        self.push_docs(page, member, level);
+    }
+
+    fn push_fields(&self, page: &mut String, fields: &[clean::Item]) {
+        let mut fields = fields.iter().filter(|field| !field.is_stripped()).peekable();
+        if fields.peek().is_none() {
+            return;
+        }
+        page.push_str("\n## Fields\n");
+        for field in fields {
+            let clean::StructFieldItem(ty) = &field.kind else { continue };
+            let title = format!("{}: {}", field.name.unwrap(), print_type(ty, self.tcx));
+            self.push_member(page, 3, field, &title);
+        }
+    }
+
+    fn push_variants(&self, page: &mut String, e: &clean::Enum) {
+        let mut variants = e.variants().peekable();
+        if variants.peek().is_none() {
+            return;
+        }
+        page.push_str("\n## Variants\n");
+        for variant in variants {
+            let variant_name = variant.name.unwrap();
+            let clean::VariantItem(clean::Variant {
+                kind: clean::VariantKind::Struct(clean::VariantStruct { fields }),
+                ..
+            }) = &variant.kind
+            else {
+                let title = print_item_signature(variant, self.tcx).unwrap();
+                self.push_member(page, 3, variant, &title);
+                continue;
+            };
+            // The fields of struct-like variants get headings of their own.
+            self.push_member(page, 3, variant, variant_name.as_str());
+            for field in fields.iter().filter(|field| !field.is_stripped()) {
+                let clean::StructFieldItem(ty) = &field.kind else { continue };
+                let field_name = field.name.unwrap();
+                write!(
+                    page,
+                    "\n<a id=\"variant.{variant_name}.field.{field_name}\"></a>\n\n#### `{field_name}: {}`\n",
+                    print_type(ty, self.tcx)
+                )
+                .unwrap();
+                self.push_docs(page, field, 4);
+            }
+        }
+    }
+
+    fn push_trait_items(&self, page: &mut String, t: &clean::Trait) {
+        let sections = [
+            ("Associated Types", ItemType::AssocType),
+            ("Associated Constants", ItemType::AssocConst),
+            ("Required Methods", ItemType::TyMethod),
+            ("Provided Methods", ItemType::Method),
+        ];
+        for (title, item_type) in sections {
+            let mut items = t
+                .items
+                .iter()
+                .filter(|item| !item.is_stripped() && item.type_() == item_type)
+                .peekable();
+            if items.peek().is_none() {
+                continue;
+            }
+            write!(page, "\n## {title}\n").unwrap();
+            for item in items {
+                let signature = print_item_signature(item, self.tcx).unwrap();
+                self.push_member(page, 3, item, &signature);
+            }
+        }
+    }
+
+    /// Appends the impls of a type or trait, grouped the same way as on HTML pages.
+    fn push_impls(&self, page: &mut String, did: DefId) {
+        let Some(impls) = self.cache.impls.get(&did) else { return };
+        let (inherent, trait_impls): (Vec<&Impl>, Vec<&Impl>) =
+            impls.iter().partition(|i| i.trait_did().is_none());
+        let (synthetic, concrete): (Vec<&Impl>, Vec<&Impl>) = trait_impls
+            .into_iter()
+            .partition(|i| i.inner_impl().kind.is_auto() || i.inner_impl().kind.is_blanket());
+        let (auto, blanket): (Vec<&Impl>, Vec<&Impl>) =
+            synthetic.into_iter().partition(|i| i.inner_impl().kind.is_auto());
+
+        for (title, impls, show_items) in [
+            ("Implementations", inherent, true),
+            ("Trait Implementations", concrete, true),
+            ("Auto Trait Implementations", auto, false),
+            ("Blanket Implementations", blanket, false),
+        ] {
+            if impls.is_empty() {
+                continue;
+            }
+            write!(page, "\n## {title}\n").unwrap();
+            for i in impls {
+                write!(page, "\n### `{}`\n", print_impl_header(i.inner_impl(), self.tcx)).unwrap();
+                self.push_docs(page, &i.impl_item, 3);
+                if !show_items {
+                    continue;
+                }
+                for item in i.inner_impl().items.iter().filter(|item| !item.is_stripped()) {
+                    let Some(signature) = print_item_signature(item, self.tcx) else { continue };
+                    self.push_member(page, 4, item, &signature);
+                }
+            }
+        }
+    }
+
+    fn push_implementors(&self, page: &mut String, did: DefId) {
+        let Some(implementors) = self.cache.implementors.get(&did) else { return };
+        page.push_str("\n## Implementors\n\n");
+        for i in implementors {
+            writeln!(page, "- `{}`", print_impl_header(i.inner_impl(), self.tcx)).unwrap();
+        }
+    }
+
+    /// Resolves the intra-doc links of an item to hrefs relative to the current page.
+    fn links(&self, item: &clean::Item) -> Vec<(Box<str>, String)> {
+        let Some(links) = self.cache.intra_doc_links.get(&item.item_id) else {
+            return vec![];
+        };
+        links
+            .iter()
+            .filter_map(|link| {
+                let mut href = self.href(link.page_id)?;
+                if let Some(fragment) = &link.fragment {
+                    fragment.render(&mut href, self.tcx);
+                }
+                Some((link.link.clone(), href))
+            })
+            .collect()
+    }
+
+    /// Returns the href of the page of an item, or `None` if the item has no known page.
+    ///
+    /// Items of crates documented into the same output directory are assumed to have Markdown
+    /// pages as well; for crates with a known remote location, the HTML page is linked.
+    fn href(&self, did: DefId) -> Option<String> {
+        let current: Vec<&str> = self.current.iter().map(|name| name.as_str()).collect();
+        if let Some((fqp, item_type)) = self.cache.paths.get(&did) {
+            return Some(relative_href(&current, &page_path(fqp, *item_type, "md")));
+        }
+        let (fqp, item_type) = self.cache.external_paths.get(&did)?;
+        match self.cache.extern_locations.get(&did.krate)? {
+            ExternalLocation::Remote(url) => Some(format!(
+                "{}/{}",
+                url.trim_end_matches('/'),
+                page_path(fqp, *item_type, "html").join("/")
+            )),
+            ExternalLocation::Local => {
+                Some(relative_href(&current, &page_path(fqp, *item_type, "md")))
+            }
+            ExternalLocation::Unknown => None,
+        }
+    }
+}
+
+impl<'tcx> FormatRenderer<'tcx> for MarkdownRenderer<'tcx> {
+    fn descr() -> &'static str {
+        "markdown"
+    }
+
+    const RUN_ON_MODULE: bool = true;
+    type ModuleData = bool;
+
+    fn init(
+        krate: clean::Crate,
+        options: RenderOptions,
+        cache: Cache,
+        tcx: TyCtxt<'tcx>,
+    ) -> Result<(Self, clean::Crate), Error> {
+        debug!("Initializing markdown renderer");
+
+        Ok((
+            MarkdownRenderer {
+                tcx,
+                out_dir: options.output,
+                current: Vec::new(),
+                in_stripped_module: false,
+                cache,
+            },
+            krate,
+        ))
+    }
+
+    fn save_module_data(&mut self) -> Self::ModuleData {
+        self.in_stripped_module
+    }
+
+    fn restore_module_data(&mut self, info: Self::ModuleData) {
+        self.in_stripped_module = info;
+    }
+
+    fn item(&mut self, item: clean::Item) -> Result<(), Error> {
+        if self.in_stripped_module || item.is_stripped() || item.is_import() {
+            return Ok(());
+        }
+        let file_name = format!("{}.{}.md", item.type_(), item.name.unwrap());
+        let page = self.item_page(&item);
+        self.write_page(&file_name, page)
+    }
+
+    fn mod_item_in(&mut self, item: &clean::Item) -> Result<(), Error> {
+        self.current.push(item.name.unwrap());
+        if !self.in_stripped_module {
+            self.in_stripped_module = item.is_stripped();
+        }
+        if self.in_stripped_module {
+            return Ok(());
+        }
+        let page = self.module_page(item);
+        self.write_page("index.md", page)
+    }
+
+    fn mod_item_out(&mut self) -> Result<(), Error> {
+        self.current.pop();
+        Ok(())
+    }
+
+    fn after_krate(&mut self) -> Result<(), Error> {
+        debug!("Done with crate");
+        Ok(())
+    }
+
+    fn cache(&self) -> &Cache {
+        &self.cache
+    }
+}
+
+fn join_path(path: &[Symbol]) -> String {
+    path.iter().map(|name| name.as_str()).collect::<Vec<_>>().join("::")
+}
+
+/// Returns the path of the page of an item, relative to the root of the output.
+fn page_path(fqp: &[Symbol], item_type: ItemType, extension: &str) -> Vec<String> {
+    let (name, parents) = fqp.split_last().unwrap();
+    let mut path: Vec<String> = parents.iter().map(|name| name.to_string()).collect();
+    if item_type == ItemType::Module {
+        path.push(name.to_string());
+        path.push(format!("index.{extension}"));
+    } else {
+        path.push(format!("{item_type}.{name}.{extension}"));
+    }
+    path
+}
+
+/// Returns the href of the page at `path` from a page in the directory `current`, both relative to
+/// the root of the output.
+fn relative_href(current: &[&str], path: &[String]) -> String {
+    let (dir, file) = path.split_last().unwrap();
+    let common = current.iter().zip(dir).take_while(|(a, b)| **a == b.as_str()).count();
+    let mut href = "../".repeat(current.len() - common);
+    for name in &dir[common..] {
+        href.push_str(name);
+        href.push('/');
+    }
+    href.push_str(file);
+    href
+}
+
+/// Prepares the documentation of an item for a Markdown page.
+///
+/// The headings are moved down by `heading_offset` levels to nest them under the headings of the
+/// page. Rust code blocks lose their hidden lines and get an explicit `rust` info string, since
+/// other renderers do not treat unmarked code blocks as Rust. The resolved intra-doc links, given
+/// as pairs of the original link and its href, are rewritten in place where they are written as
+/// inline links or link reference definitions; for the shortcut and collapsed links, which rely on
+/// a definition of the same name, definitions are appended.
+fn render_docs(docs: &str, links: &[(Box<str>, String)], heading_offset: usize) -> String {
+    let (docs, undefined) = rewrite_links(docs, links);
+    let mut out = String::with_capacity(docs.len());
+    // The character and length of the fence of the code block we are in, and whether the code is
+    // Rust.
+    let mut fence: Option<(char, usize, bool)> = None;
+    for line in docs.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if let Some((fence_char, fence_len, is_rust)) = fence {
+            let closing = trimmed.trim_end();
+            if indent < 4 && closing.len() >= fence_len && closing.chars().all(|c| c == fence_char)
+            {
+                fence = None;
+            } else if is_rust {
+                if let Line::Shown(line) = map_line(line) {
+                    out.push_str(&line);
+                    out.push('\n');
+                }
+                continue;
+            }
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+
+        if indent < 4
+            && let Some(fence_char @ ('`' | '~')) = trimmed.chars().next()
+        {
+            let fence_len = trimmed.chars().take_while(|&c| c == fence_char).count();
+            if fence_len >= 3 {
+                let info = trimmed[fence_len..].trim();
+                let is_rust = LangString::parse_without_check(info, ErrorCodes::No, false).rust;
+                fence = Some((fence_char, fence_len, is_rust));
+                if is_rust {
+                    out.push_str(&line[..indent + fence_len]);
+                    out.push_str("rust\n");
+                } else {
+                    out.push_str(line);
+                    out.push('\n');
+                }
+                continue;
+            }
+        }
+
+        let mut line = line.to_owned();
+        if indent < 4 && trimmed.starts_with('#') {
+            let level = trimmed.chars().take_while(|&c| c == '#').count();
+            let rest = &trimmed[level..];
+            if level <= 6 && (rest.is_empty() || rest.starts_with([' ', '\t'])) {
+                line = format!("{}{rest}", "#".repeat((level + heading_offset).min(6)));
+            }
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    if let Some((fence_char, fence_len, _)) = fence {
+        // Close the code block, so that it does not swallow the rest of the page.
+        out.extend(std::iter::repeat_n(fence_char, fence_len));
+        out.push('\n');
+    }
+
+    if !undefined.is_empty() {
+        out.push('\n');
+        for (reference, href) in undefined {
+            writeln!(out, "[{reference}]: {href}").unwrap();
+        }
+    }
+    out
+}
+
+/// Replaces the destinations of the inline links and link reference definitions in `docs` that
+/// are intra-doc links with their targets in the Markdown output.
+///
+/// Reference links without a definition (like ``[`Vec`]``) are left as they are; the references
+/// of those that are intra-doc links are returned along with their targets, so that the caller
+/// can define them.
+fn rewrite_links<'md, 'a>(
+    docs: &'md str,
+    links: &'a [(Box<str>, String)],
+) -> (String, Vec<(String, &'a str)>) {
+    let href = |dest: &str| links.iter().find(|(original, _)| **original == *dest).map(|l| &*l.1);
+
+    let mut undefined: Vec<(String, &str)> = Vec::new();
+    let mut broken_link_callback = |link: BrokenLink<'md>| -> Option<(CowStr<'md>, CowStr<'md>)> {
+        if let Some(href) = href(&link.reference)
+            && !undefined.iter().any(|(reference, _)| **reference == *link.reference)
+        {
+            undefined.push((link.reference.to_string(), href));
+        }
+        None
+    };
+    let events = Parser::new_with_broken_link_callback(
+        docs,
+        main_body_opts(),
+        Some(&mut broken_link_callback),
+    )
+    .into_offset_iter();
+
+    // The byte ranges to replace, and their replacements.
+    let mut edits = Vec::new();
+    for (_, definition) in events.reference_definitions().iter() {
+        if let Some(href) = href(&definition.dest)
+            && let Some(colon) = docs[definition.span.clone()].find("]:")
+            && let Some(start) =
+                docs[definition.span.start + colon..definition.span.end].find(&*definition.dest)
+        {
+            let start = definition.span.start + colon + start;
+            edits.push((start..start + definition.dest.len(), href));
+        }
+    }
+    // The inline link we are in, if its destination is an intra-doc link, along with where the
+    // events of its text end so far.
+    let mut inline_link = None;
+    for (event, range) in events {
+        match event {
+            Event::Start(Tag::Link { link_type: LinkType::Inline, dest_url, .. }) => {
+                inline_link =
+                    href(&dest_url).map(|href| (range.clone(), dest_url, href, range.start));
+            }
+            Event::End(TagEnd::Link) => {
+                // The destination follows the `](` that ends the text of the link.
+                if let Some((link_range, dest, href, text_end)) = inline_link.take() {
+                    let rest = &docs[text_end..link_range.end];
+                    if let Some(open) = rest.find("](")
+                        && let Some(start) = rest[open..].find(&*dest)
+                    {
+                        let start = text_end + open + start;
+                        edits.push((start..start + dest.len(), href));
+                    }
+                }
+            }
+            _ => {
+                if let Some((_, _, _, text_end)) = &mut inline_link {
+                    *text_end = (*text_end).max(range.end);
+                }
+            }
+        }
+    }
+
+    edits.sort_by_key(|(range, _)| range.start);
+    let mut out = String::with_capacity(docs.len());
+    let mut last = 0;
+    for (range, href) in edits {
+        out.push_str(&docs[last..range.start]);
+        out.push_str(href);
+        last = range.end;
+    }
+    out.push_str(&docs[last..]);
+    (out, undefined)
+}
@@ -0,0 +1,699 @@
+//! Plain-text printing of the signatures shown on Markdown pages.
+//!
+//! The `Display` implementations in `html::format` need an HTML [`Context`] even for their text
+//! mode, so the Markdown backend prints the cleaned types itself. The output matches the text
+//! mode of the HTML backend, except that long declarations are not wrapped.
+//!
+//! [`Context`]: crate::html::render::Context
+
+use std::fmt::{self, Display, Write as _};
+
+use itertools::Either;
+use rustc_hir as hir;
+use rustc_hir::def::CtorKind;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_span::hygiene::MacroKind;
+use rustc_span::symbol::kw;
+
+use crate::clean;
+use crate::clean::utils::find_nearest_parent_module;
+use crate::display::Joined as _;
+use crate::formats::item_type::ItemType;
+use crate::html::format::{PrintWithSpace, print_abi_with_space, print_default_space};
+
+pub(super) fn print_type(ty: &clean::Type, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| match ty {
+        clean::Type::Path { path } => print_path(path, tcx).fmt(f),
+        clean::Type::DynTrait(bounds, lifetime) => {
+            f.write_str("dyn ")?;
+            bounds.iter().map(|bound| print_poly_trait(bound, tcx)).joined(" + ", f)?;
+            if let Some(lifetime) = lifetime {
+                write!(f, " + {}", lifetime.print())?;
+            }
+            Ok(())
+        }
+        clean::Type::Generic(name) => f.write_str(name.as_str()),
+        clean::Type::SelfTy => f.write_str("Self"),
+        clean::Type::Primitive(prim) => f.write_str(prim.as_sym().as_str()),
+        clean::Type::BareFunction(decl) => {
+            print_higher_ranked_params(&decl.generic_params, tcx).fmt(f)?;
+            write!(f, "{}{:#}fn", decl.safety.print_with_space(), print_abi_with_space(decl.abi))?;
+            print_fn_decl(&decl.decl, tcx).fmt(f)
+        }
+        clean::Type::Tuple(tys) => match &tys[..] {
+            [] => f.write_str("()"),
+            [ty] => write!(f, "({},)", print_type(ty, tcx)),
+            tys => {
+                f.write_str("(")?;
+                tys.iter().map(|ty| print_type(ty, tcx)).joined(", ", f)?;
+                f.write_str(")")
+            }
+        },
+        clean::Type::Slice(ty) => write!(f, "[{}]", print_type(ty, tcx)),
+        clean::Type::Array(ty, len) => write!(f, "[{}; {len}]", print_type(ty, tcx)),
+        clean::Type::Pat(ty, pat) => write!(f, "{} is {pat}", print_type(ty, tcx)),
+        clean::Type::RawPointer(mutability, ty) => {
+            let kind = match mutability {
+                hir::Mutability::Not => "const",
+                hir::Mutability::Mut => "mut",
+            };
+            write!(f, "*{kind} ")?;
+            print_pointee(ty, tcx).fmt(f)
+        }
+        clean::Type::BorrowedRef { lifetime, mutability, type_ } => {
+            f.write_str("&")?;
+            if let Some(lifetime) = lifetime {
+                write!(f, "{} ", lifetime.print())?;
+            }
+            f.write_str(mutability.print_with_space())?;
+            print_pointee(type_, tcx).fmt(f)
+        }
+        clean::Type::QPath(qpath) => {
+            match &qpath.trait_ {
+                Some(trait_) if qpath.should_show_cast => write!(
+                    f,
+                    "<{} as {}>::",
+                    print_type(&qpath.self_type, tcx),
+                    print_path(trait_, tcx)
+                )?,
+                _ => write!(f, "{}::", print_type(&qpath.self_type, tcx))?,
+            }
+            print_path_segment(&qpath.assoc, tcx).fmt(f)
+        }
+        clean::Type::Infer => f.write_str("_"),
+        clean::Type::ImplTrait(bounds) => write!(f, "impl {}", print_bounds(bounds, tcx)),
+        clean::Type::UnsafeBinder(binder) => {
+            print_higher_ranked_params(&binder.generic_params, tcx).fmt(f)?;
+            print_type(&binder.ty, tcx).fmt(f)
+        }
+    })
+}
+
+/// Prints the target of a pointer or reference, adding the parentheses that `&(dyn A + B)` and
+/// `&(impl A + B)` need.
+fn print_pointee(ty: &clean::Type, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| {
+        let needs_parens = match ty {
+            clean::Type::DynTrait(bounds, lifetime) => bounds.len() > 1 || lifetime.is_some(),
+            clean::Type::ImplTrait(bounds) => bounds.len() > 1,
+            _ => false,
+        };
+        if needs_parens {
+            write!(f, "({})", print_type(ty, tcx))
+        } else {
+            print_type(ty, tcx).fmt(f)
+        }
+    })
+}
+
+/// Prints a path the way the HTML backend does: only the last segment, unless the path names an
+/// associated type like `T::Item`.
+pub(super) fn print_path(path: &clean::Path, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| {
+        let segments = if path.is_assoc_ty() {
+            &path.segments[..]
+        } else {
+            &path.segments[path.segments.len().saturating_sub(1)..]
+        };
+        segments
+            .iter()
+            .filter(|segment| segment.name != kw::PathRoot)
+            .map(|segment| print_path_segment(segment, tcx))
+            .joined("::", f)
+    })
+}
+
+fn print_path_segment(segment: &clean::PathSegment, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| {
+        f.write_str(segment.name.as_str())?;
+        print_generic_args(&segment.args, tcx).fmt(f)
+    })
+}
+
+fn print_generic_args(args: &clean::GenericArgs, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| match args {
+        clean::GenericArgs::AngleBracketed { args, constraints } => {
+            if args.is_empty() && constraints.is_empty() {
+                return Ok(());
+            }
+            f.write_str("<")?;
+            args.iter()
+                .map(|arg| Either::Left(print_generic_arg(arg, tcx)))
+                .chain(constraints.iter().map(|c| Either::Right(print_constraint(c, tcx))))
+                .joined(", ", f)?;
+            f.write_str(">")
+        }
+        clean::GenericArgs::Parenthesized { inputs, output } => {
+            f.write_str("(")?;
+            inputs.iter().map(|ty| print_type(ty, tcx)).joined(", ", f)?;
+            f.write_str(")")?;
+            if let Some(output) = output {
+                write!(f, " -> {}", print_type(output, tcx))?;
+            }
+            Ok(())
+        }
+        clean::GenericArgs::ReturnTypeNotation => f.write_str("(..)"),
+    })
+}
+
+fn print_generic_arg(arg: &clean::GenericArg, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| match arg {
+        clean::GenericArg::Lifetime(lifetime) => lifetime.print().fmt(f),
+        clean::GenericArg::Type(ty) => print_type(ty, tcx).fmt(f),
+        clean::GenericArg::Const(ct) => write!(f, "{:#}", ct.print(tcx)),
+        clean::GenericArg::Infer => f.write_str("_"),
+    })
+}
+
+fn print_constraint(constraint: &clean::AssocItemConstraint, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| {
+        print_path_segment(&constraint.assoc, tcx).fmt(f)?;
+        match &constraint.kind {
+            clean::AssocItemConstraintKind::Equality { term } => {
+                write!(f, " = {}", print_term(term, tcx))
+            }
+            clean::AssocItemConstraintKind::Bound { bounds } if bounds.is_empty() => Ok(()),
+            clean::AssocItemConstraintKind::Bound { bounds } => {
+                write!(f, ": {}", print_bounds(bounds, tcx))
+            }
+        }
+    })
+}
+
+fn print_term(term: &clean::Term, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| match term {
+        clean::Term::Type(ty) => print_type(ty, tcx).fmt(f),
+        clean::Term::Constant(ct) => write!(f, "{:#}", ct.print(tcx)),
+    })
+}
+
+pub(super) fn print_bounds(bounds: &[clean::GenericBound], tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| bounds.iter().map(|bound| print_bound(bound, tcx)).joined(" + ", f))
+}
+
+fn print_bound(bound: &clean::GenericBound, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| match bound {
+        clean::GenericBound::Outlives(lifetime) => lifetime.print().fmt(f),
+        clean::GenericBound::TraitBound(poly_trait, modifiers) => {
+            // `const` and `~const` trait bounds are experimental; don't render them.
+            f.write_str(match modifiers.polarity {
+                hir::BoundPolarity::Positive => "",
+                hir::BoundPolarity::Maybe(_) => "?",
+                hir::BoundPolarity::Negative(_) => "!",
+            })?;
+            print_poly_trait(poly_trait, tcx).fmt(f)
+        }
+        clean::GenericBound::Use(args) => {
+            f.write_str("use<")?;
+            args.iter().map(|arg| arg.name()).joined(", ", f)?;
+            f.write_str(">")
+        }
+    })
+}
+
+fn print_poly_trait(poly_trait: &clean::PolyTrait, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| {
+        print_higher_ranked_params(&poly_trait.generic_params, tcx).fmt(f)?;
+        print_path(&poly_trait.trait_, tcx).fmt(f)
+    })
+}
+
+fn print_higher_ranked_params(params: &[clean::GenericParamDef], tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| {
+        if params.is_empty() {
+            return Ok(());
+        }
+        f.write_str("for<")?;
+        params.iter().map(|param| print_generic_param(param, tcx)).joined(", ", f)?;
+        f.write_str("> ")
+    })
+}
+
+fn print_generic_param(param: &clean::GenericParamDef, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| match &param.kind {
+        clean::GenericParamDefKind::Lifetime { outlives } => {
+            f.write_str(param.name.as_str())?;
+            if !outlives.is_empty() {
+                f.write_str(": ")?;
+                outlives.iter().map(|lifetime| lifetime.print()).joined(" + ", f)?;
+            }
+            Ok(())
+        }
+        clean::GenericParamDefKind::Type { bounds, default, .. } => {
+            f.write_str(param.name.as_str())?;
+            if !bounds.is_empty() {
+                write!(f, ": {}", print_bounds(bounds, tcx))?;
+            }
+            if let Some(default) = default {
+                write!(f, " = {}", print_type(default, tcx))?;
+            }
+            Ok(())
+        }
+        clean::GenericParamDefKind::Const { ty, default, .. } => {
+            write!(f, "const {}: {}", param.name, print_type(ty, tcx))?;
+            if let Some(default) = default {
+                write!(f, " = {default}")?;
+            }
+            Ok(())
+        }
+    })
+}
+
+pub(super) fn print_generics(generics: &clean::Generics, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| {
+        let mut params = generics.params.iter().filter(|p| !p.is_synthetic_param()).peekable();
+        if params.peek().is_none() {
+            return Ok(());
+        }
+        f.write_str("<")?;
+        params.map(|param| print_generic_param(param, tcx)).joined(", ", f)?;
+        f.write_str(">")
+    })
+}
+
+pub(super) fn print_where_clause(generics: &clean::Generics, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| {
+        if generics.where_predicates.is_empty() {
+            return Ok(());
+        }
+        f.write_str(" where ")?;
+        generics
+            .where_predicates
+            .iter()
+            .map(|predicate| print_where_predicate(predicate, tcx))
+            .joined(", ", f)
+    })
+}
+
+fn print_where_predicate(predicate: &clean::WherePredicate, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| match predicate {
+        clean::WherePredicate::BoundPredicate { ty, bounds, bound_params } => {
+            print_higher_ranked_params(bound_params, tcx).fmt(f)?;
+            write!(f, "{}:", print_type(ty, tcx))?;
+            if !bounds.is_empty() {
+                write!(f, " {}", print_bounds(bounds, tcx))?;
+            }
+            Ok(())
+        }
+        clean::WherePredicate::RegionPredicate { lifetime, bounds } => {
+            write!(f, "{}:", lifetime.print())?;
+            if !bounds.is_empty() {
+                write!(f, " {}", print_bounds(bounds, tcx))?;
+            }
+            Ok(())
+        }
+        clean::WherePredicate::EqPredicate { lhs, rhs } => {
+            write!(f, "{} == {}", print_type(lhs, tcx), print_term(rhs, tcx))
+        }
+    })
+}
+
+fn print_fn_decl(decl: &clean::FnDecl, tcx: TyCtxt<'_>) -> impl Display {
+    fmt::from_fn(move |f| {
+        f.write_str("(")?;
+        decl.inputs
+            .values
+            .iter()
+            .map(|input| {
+                fmt::from_fn(move |f| {
+                    if let Some(self_ty) = input.to_receiver() {
+                        return match self_ty {
+                            clean::Type::SelfTy => f.write_str("self"),
+                            clean::Type::BorrowedRef {
+                                lifetime,
+                                mutability,
+                                type_: box clean::Type::SelfTy,
+                            } => {
+                                f.write_str("&")?;
+                                if let Some(lifetime) = lifetime {
+                                    write!(f, "{} ", lifetime.print())?;
+                                }
+                                write!(f, "{}self", mutability.print_with_space())
+                            }
+                            _ => write!(f, "self: {}", print_type(self_ty, tcx)),
+                        };
+                    }
+                    if input.is_const {
+                        f.write_str("const ")?;
+                    }
+                    if !input.name.is_empty() {
+                        write!(f, "{}: ", input.name)?;
+                    }
+                    print_type(&input.type_, tcx).fmt(f)
+                })
+            })
+            .joined(", ", f)?;
+        if decl.c_variadic {
+            f.write_str(", ...")?;
+        }
+        f.write_str(")")?;
+        if !decl.output.is_unit() {
+            write!(f, " -> {}", print_type(&decl.output, tcx))?;
+        }
+        Ok(())
+    })
+}
+
+/// Prints the visibility of an item, followed by a space unless it is inherited.
+pub(super) fn print_visibility(item: &clean::Item, tcx: TyCtxt<'_>) -> String {
+    match item.visibility(tcx) {
+        None => String::new(),
+        Some(ty::Visibility::Public) => "pub ".to_owned(),
+        Some(ty::Visibility::Restricted(vis_did)) => {
+            let parent_module = find_nearest_parent_module(tcx, item.item_id.expect_def_id());
+            if vis_did.is_crate_root() {
+                "pub(crate) ".to_owned()
+            } else if parent_module == Some(vis_did) {
+                // `pub(in foo)` where `foo` is the parent module
+                // is the same as no visibility modifier
+                String::new()
+            } else if parent_module.and_then(|parent| find_nearest_parent_module(tcx, parent))
+                == Some(vis_did)
+            {
+                "pub(super) ".to_owned()
+            } else {
+                let path = tcx.def_path(vis_did);
+                let path = path
+                    .data
+                    .iter()
+                    .filter_map(|segment| segment.data.get_opt_name())
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>()
+                    .join("::");
+                format!("pub(in {path}) ")
+            }
+        }
+    }
+}
+
+fn print_fn_signature(
+    item: &clean::Item,
+    func: &clean::Function,
+    tcx: TyCtxt<'_>,
+    defaultness: Option<hir::Defaultness>,
+) -> String {
+    let mut out = print_visibility(item, tcx);
+    if let Some(header) = item.fn_header(tcx) {
+        out.push_str(print_default_space(defaultness.is_some_and(|d| d.is_default())));
+        if header.is_const() {
+            out.push_str("const ");
+        }
+        out.push_str(header.asyncness.print_with_space());
+        out.push_str(header.safety.print_with_space());
+        write!(out, "{:#}", print_abi_with_space(header.abi)).unwrap();
+    }
+    write!(
+        out,
+        "fn {}{}{}{}",
+        item.name.unwrap(),
+        print_generics(&func.generics, tcx),
+        print_fn_decl(&func.decl, tcx),
+        print_where_clause(&func.generics, tcx),
+    )
+    .unwrap();
+    out
+}
+
+/// Prints the fields of a struct, union or struct-like variant as the body of a declaration,
+/// indented by `indent` spaces.
+fn print_fields(fields: &[clean::Item], indent: usize, tcx: TyCtxt<'_>) -> String {
+    let pad = " ".repeat(indent + 4);
+    let mut out = " {\n".to_owned();
+    for field in fields {
+        if let clean::StructFieldItem(ty) = &field.kind {
+            writeln!(
+                out,
+                "{pad}{}{}: {},",
+                print_visibility(field, tcx),
+                field.name.unwrap(),
+                print_type(ty, tcx)
+            )
+            .unwrap();
+        }
+    }
+    if fields.iter().any(|field| field.is_stripped()) {
+        writeln!(out, "{pad}/* private fields */").unwrap();
+    }
+    write!(out, "{}}}", " ".repeat(indent)).unwrap();
+    out
+}
+
+/// Prints the fields of a tuple struct or tuple variant, including the parentheses.
+fn print_tuple_fields(fields: &[clean::Item], tcx: TyCtxt<'_>) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| match &field.kind {
+            clean::StructFieldItem(ty) => {
+                format!("{}{}", print_visibility(field, tcx), print_type(ty, tcx))
+            }
+            _ => "_".to_owned(),
+        })
+        .collect();
+    format!("({})", fields.join(", "))
+}
+
+fn print_variant(variant: &clean::Item, indent: usize, tcx: TyCtxt<'_>) -> String {
+    let name = variant.name.unwrap();
+    let clean::VariantItem(inner) = &variant.kind else {
+        return name.to_string();
+    };
+    let mut out = match &inner.kind {
+        clean::VariantKind::CLike => name.to_string(),
+        clean::VariantKind::Tuple(fields) => format!("{name}{}", print_tuple_fields(fields, tcx)),
+        clean::VariantKind::Struct(s) => format!("{name}{}", print_fields(&s.fields, indent, tcx)),
+    };
+    if let Some(expr) = inner.discriminant.as_ref().and_then(|d| d.expr(tcx)) {
+        write!(out, " = {expr}").unwrap();
+    }
+    out
+}
+
+/// Prints the associated items of a trait as the body of its declaration.
+fn print_trait_items(items: &[clean::Item], tcx: TyCtxt<'_>) -> String {
+    if items.is_empty() {
+        return " {}".to_owned();
+    }
+    let mut out = " {\n".to_owned();
+    for item in items {
+        if item.is_stripped() {
+            continue;
+        }
+        let Some(signature) = print_item_signature(item, tcx) else { continue };
+        let body = match item.kind {
+            clean::MethodItem(..) => " { ... }",
+            _ => ";",
+        };
+        writeln!(out, "    {signature}{body}").unwrap();
+    }
+    out.push('}');
+    out
+}
+
+/// Prints the header of an impl block, like `impl<T: Clone> Clone for Vec<T>`.
+pub(super) fn print_impl_header(impl_: &clean::Impl, tcx: TyCtxt<'_>) -> String {
+    let mut out =
+        format!("{}impl{} ", impl_.safety.print_with_space(), print_generics(&impl_.generics, tcx));
+    if let Some(trait_) = &impl_.trait_ {
+        if impl_.is_negative_trait_impl() {
+            out.push('!');
+        }
+        write!(out, "{} for ", print_path(trait_, tcx)).unwrap();
+    }
+    let for_ = impl_.kind.as_blanket_ty().unwrap_or(&impl_.for_);
+    write!(out, "{}{}", print_type(for_, tcx), print_where_clause(&impl_.generics, tcx)).unwrap();
+    out
+}
+
+/// Prints the declaration of an item, or returns `None` for items that have none, like modules
+/// and primitive types.
+pub(super) fn print_item_signature(item: &clean::Item, tcx: TyCtxt<'_>) -> Option<String> {
+    let vis = print_visibility(item, tcx);
+    let name = item.name.unwrap_or(kw::Empty);
+    let signature = match &item.kind {
+        clean::StructItem(s) => {
+            let mut out = format!("{vis}struct {name}{}", print_generics(&s.generics, tcx));
+            match s.ctor_kind {
+                None => {
+                    write!(out, "{}", print_where_clause(&s.generics, tcx)).unwrap();
+                    out.push_str(&print_fields(&s.fields, 0, tcx));
+                }
+                Some(CtorKind::Fn) => write!(
+                    out,
+                    "{}{};",
+                    print_tuple_fields(&s.fields, tcx),
+                    print_where_clause(&s.generics, tcx)
+                )
+                .unwrap(),
+                Some(CtorKind::Const) => {
+                    write!(out, "{};", print_where_clause(&s.generics, tcx)).unwrap()
+                }
+            }
+            out
+        }
+        clean::UnionItem(u) => format!(
+            "{vis}union {name}{}{}{}",
+            print_generics(&u.generics, tcx),
+            print_where_clause(&u.generics, tcx),
+            print_fields(&u.fields, 0, tcx)
+        ),
+        clean::EnumItem(e) => {
+            let mut out = format!(
+                "{vis}enum {name}{}{} {{\n",
+                print_generics(&e.generics, tcx),
+                print_where_clause(&e.generics, tcx)
+            );
+            for variant in e.variants() {
+                writeln!(out, "    {},", print_variant(variant, 4, tcx)).unwrap();
+            }
+            if e.has_stripped_entries() {
+                out.push_str("    // some variants omitted\n");
+            }
+            out.push('}');
+            out
+        }
+        clean::VariantItem(_) => print_variant(item, 0, tcx),
+        clean::StructFieldItem(ty) => format!("{vis}{name}: {}", print_type(ty, tcx)),
+        clean::FunctionItem(func)
+        | clean::RequiredMethodItem(func)
+        | clean::ForeignFunctionItem(func, _) => print_fn_signature(item, func, tcx, None),
+        clean::MethodItem(func, defaultness) => print_fn_signature(item, func, tcx, *defaultness),
+        clean::TypeAliasItem(alias) => format!(
+            "{vis}type {name}{} = {}{};",
+            print_generics(&alias.generics, tcx),
+            print_type(&alias.type_, tcx),
+            print_where_clause(&alias.generics, tcx)
+        ),
+        clean::StaticItem(s) | clean::ForeignStaticItem(s, _) => format!(
+            "{vis}static {}{name}: {};",
+            s.mutability.print_with_space(),
+            print_type(&s.type_, tcx)
+        ),
+        clean::ConstantItem(c) => {
+            format!("{vis}const {name}: {} = {:#};", print_type(&c.type_, tcx), c.kind.print(tcx))
+        }
+        clean::TraitItem(t) => {
+            let mut out = format!(
+                "{vis}{}{}trait {name}{}",
+                t.safety(tcx).print_with_space(),
+                if t.is_auto(tcx) { "auto " } else { "" },
+                print_generics(&t.generics, tcx)
+            );
+            if !t.bounds.is_empty() {
+                write!(out, ": {}", print_bounds(&t.bounds, tcx)).unwrap();
+            }
+            write!(out, "{}", print_where_clause(&t.generics, tcx)).unwrap();
+            out.push_str(&print_trait_items(&t.items, tcx));
+            out
+        }
+        clean::TraitAliasItem(alias) => format!(
+            "{vis}trait {name}{} = {}{};",
+            print_generics(&alias.generics, tcx),
+            print_bounds(&alias.bounds, tcx),
+            print_where_clause(&alias.generics, tcx)
+        ),
+        clean::ImplItem(impl_) => print_impl_header(impl_, tcx),
+        clean::ForeignTypeItem => format!("{vis}type {name};"),
+        clean::MacroItem(m) => m.source.clone(),
+        clean::ProcMacroItem(m) => match m.kind {
+            MacroKind::Bang => format!("{vis}macro_rules! {name} {{ /* proc-macro */ }}"),
+            MacroKind::Attr => format!("#[{name}]"),
+            MacroKind::Derive => {
+                let mut out = format!("#[derive({name})]");
+                if !m.helpers.is_empty() {
+                    out.push_str("\n\n// Attributes available to this derive:");
+                    for helper in &m.helpers {
+                        write!(out, "\n#[{helper}]").unwrap();
+                    }
+                }
+                out
+            }
+        },
+        clean::RequiredAssocConstItem(generics, ty) => format!(
+            "const {name}{}: {}{}",
+            print_generics(generics, tcx),
+            print_type(ty, tcx),
+            print_where_clause(generics, tcx)
+        ),
+        clean::ProvidedAssocConstItem(c) | clean::ImplAssocConstItem(c) => format!(
+            "{vis}const {name}{}: {} = {:#}{}",
+            print_generics(&c.generics, tcx),
+            print_type(&c.type_, tcx),
+            c.kind.print(tcx),
+            print_where_clause(&c.generics, tcx)
+        ),
+        clean::RequiredAssocTypeItem(generics, bounds) => {
+            let mut out = format!("type {name}{}", print_generics(generics, tcx));
+            if !bounds.is_empty() {
+                write!(out, ": {}", print_bounds(bounds, tcx)).unwrap();
+            }
+            write!(out, "{}", print_where_clause(generics, tcx)).unwrap();
+            out
+        }
+        clean::AssocTypeItem(alias, bounds) => {
+            let mut out = format!("type {name}{}", print_generics(&alias.generics, tcx));
+            if !bounds.is_empty() {
+                write!(out, ": {}", print_bounds(bounds, tcx)).unwrap();
+            }
+            write!(
+                out,
+                " = {}{}",
+                print_type(&alias.type_, tcx),
+                print_where_clause(&alias.generics, tcx)
+            )
+            .unwrap();
+            out
+        }
+        clean::ImportItem(import) => format!("{vis}{}", print_import(import)),
+        clean::ExternCrateItem { src } => match src {
+            Some(src) if *src != name => format!("{vis}extern crate {src} as {name};"),
+            _ => format!("{vis}extern crate {name};"),
+        },
+        clean::ModuleItem(_)
+        | clean::PrimitiveItem(_)
+        | clean::KeywordItem
+        | clean::StrippedItem(_) => return None,
+    };
+    Some(signature)
+}
+
+/// Prints a re-export, with the full path of the re-exported item.
+fn print_import(import: &clean::Import) -> String {
+    let source = import.source.path.whole_name();
+    match import.kind {
+        clean::ImportKind::Simple(name) if name == import.source.path.last() => {
+            format!("use {source};")
+        }
+        clean::ImportKind::Simple(name) => format!("use {source} as {name};"),
+        clean::ImportKind::Glob if source.is_empty() => "use *;".to_owned(),
+        clean::ImportKind::Glob => format!("use {source}::*;"),
+    }
+}
+
+/// The name of a kind of item, as used in page titles.
+pub(super) fn item_kind_title(item_type: ItemType) -> &'static str {
+    match item_type {
+        ItemType::Module => "Module",
+        ItemType::ExternCrate => "Extern Crate",
+        ItemType::Import => "Re-export",
+        ItemType::Struct => "Struct",
+        ItemType::Enum => "Enum",
+        ItemType::Function => "Function",
+        ItemType::TypeAlias => "Type Alias",
+        ItemType::Static => "Static",
+        ItemType::Trait => "Trait",
+        ItemType::Impl => "Implementation",
+        ItemType::TyMethod | ItemType::Method => "Method",
+        ItemType::StructField => "Field",
+        ItemType::Variant => "Variant",
+        ItemType::Macro => "Macro",
+        ItemType::Primitive => "Primitive Type",
+        ItemType::AssocType => "Associated Type",
+        ItemType::Constant | ItemType::AssocConst => "Constant",
+        ItemType::ForeignType => "Foreign Type",
+        ItemType::Keyword => "Keyword",
+        ItemType::ProcAttribute => "Attribute Macro",
+        ItemType::ProcDerive => "Derive Macro",
+        ItemType::TraitAlias => "Trait Alias",
+        ItemType::Union => "Union",
+    }
+}
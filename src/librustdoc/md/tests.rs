@@ -0,0 +1,60 @@
+use super::{relative_href, render_docs};
+
+fn path(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|part| part.to_string()).collect()
+}
+
+#[test]
+fn relative_hrefs() {
+    let current = ["krate", "a"];
+    assert_eq!(relative_href(&current, &path(&["krate", "a", "struct.S.md"])), "struct.S.md");
+    assert_eq!(relative_href(&current, &path(&["krate", "a", "b", "index.md"])), "b/index.md");
+    assert_eq!(relative_href(&current, &path(&["krate", "fn.f.md"])), "../fn.f.md");
+    assert_eq!(
+        relative_href(&current, &path(&["other", "c", "trait.T.md"])),
+        "../../other/c/trait.T.md"
+    );
+}
+
+#[test]
+fn hidden_lines() {
+    let docs = "```\n# fn main() {\nlet x = 1;\n## not hidden\n# }\n```\n\n```text\n# kept\n```";
+    assert_eq!(
+        render_docs(docs, &[], 0),
+        "```rust\nlet x = 1;\n# not hidden\n```\n\n```text\n# kept\n```\n"
+    );
+    assert_eq!(render_docs("~~~~ignore\n# hidden\n~~~~", &[], 0), "~~~~rust\n~~~~\n");
+    assert_eq!(render_docs("```\nunclosed", &[], 0), "```rust\nunclosed\n```\n");
+}
+
+#[test]
+fn headings() {
+    let docs = "# Examples\n\n#not a heading\n\n    # code\n\n##### Deep";
+    assert_eq!(
+        render_docs(docs, &[], 2),
+        "### Examples\n\n#not a heading\n\n    # code\n\n###### Deep\n"
+    );
+    assert_eq!(render_docs("```\n# hidden\n# Shown\n```", &[], 1), "```rust\n```\n");
+}
+
+#[test]
+fn links() {
+    let links = [
+        ("`Vec`".into(), "struct.Vec.md".to_owned()),
+        ("crate::f".into(), "fn.f.md".to_owned()),
+        ("g".into(), "fn.g.md#method.g".to_owned()),
+    ];
+    let docs = "See [`Vec`], [f](crate::f) and [the g][g].\n\n[g]: g";
+    assert_eq!(
+        render_docs(docs, &links, 0),
+        "See [`Vec`], [f](fn.f.md) and [the g][g].\n\n[g]: fn.g.md#method.g\n\n\
+         [`Vec`]: struct.Vec.md\n"
+    );
+    // Links in code are not links.
+    let docs = "Write `[f](crate::f)` for [`Vec`][] and [`Vec`].\n\n```text\n[g]: g\n```";
+    assert_eq!(
+        render_docs(docs, &links, 0),
+        "Write `[f](crate::f)` for [`Vec`][] and [`Vec`].\n\n```text\n[g]: g\n```\n\n\
+         [`Vec`]: struct.Vec.md\n"
+    );
+}
@@ -4,10 +4,13 @@
 use std::ops;
 
 use rustc_hir as hir;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
 use rustc_lint::builtin::MISSING_DOCS;
 use rustc_middle::lint::LintLevelSource;
+use rustc_middle::ty::TyCtxt;
 use rustc_session::lint;
-use rustc_span::FileName;
+use rustc_span::{FileName, Symbol};
 use serde::Serialize;
 use tracing::debug;
 
@@ -25,10 +28,20 @@
 };
 
 fn calculate_doc_coverage(krate: clean::Crate, ctx: &mut DocContext<'_>) -> clean::Crate {
-    let mut calc = CoverageCalculator { items: Default::default(), ctx };
+    let mut calc = CoverageCalculator {
+        items: Default::default(),
+        modules: Default::default(),
+        module_path: Vec::new(),
+        gaps: Default::default(),
+        ctx,
+    };
     calc.visit_crate(&krate);
+    for gaps in calc.gaps.values_mut() {
+        gaps.sort_by_key(|gap| (gap.line, gap.column));
+    }
 
     calc.print_results();
+    calc.check_threshold();
 
     krate
 }
@@ -103,8 +116,32 @@ fn add_assign(&mut self, rhs: Self) {
     }
 }
 
+/// An item that is missing documentation or an example, listed with `--coverage-details`.
+#[derive(Serialize, Debug)]
+struct Gap {
+    item: String,
+    kind: &'static str,
+    line: usize,
+    column: usize,
+    missing_docs: bool,
+    missing_example: bool,
+}
+
+/// The JSON output for a file, which only includes the gaps with `--coverage-details`.
+#[derive(Serialize)]
+struct FileCoverage<'a> {
+    #[serde(flatten)]
+    count: &'a ItemCount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gaps: Option<&'a [Gap]>,
+}
+
 struct CoverageCalculator<'a, 'b> {
     items: BTreeMap<FileName, ItemCount>,
+    /// The items directly in each module and the module itself, keyed by the path of the module.
+    modules: BTreeMap<String, ItemCount>,
+    module_path: Vec<Symbol>,
+    gaps: BTreeMap<FileName, Vec<Gap>>,
     ctx: &'a mut DocContext<'b>,
 }
 
@@ -118,14 +155,34 @@ fn limit_filename_len(filename: String) -> String {
     }
 }
 
+/// The path of an item as shown in the list of gaps. Inherent impls are named after their self
+/// type, so that methods are shown as `krate::Type::method`.
+fn item_path(tcx: TyCtxt<'_>, def_id: DefId) -> String {
+    let mut names: Vec<String> = std::iter::successors(Some(def_id), |&id| tcx.opt_parent(id))
+        .map(|id| match tcx.def_kind(id) {
+            DefKind::Impl { of_trait: false } => {
+                tcx.type_of(id).skip_binder().ty_adt_def().map_or(id, |adt| adt.did())
+            }
+            _ => id,
+        })
+        .filter_map(|id| tcx.opt_item_name(id))
+        .map(|name| name.to_string())
+        .collect();
+    names.reverse();
+    names.join("::")
+}
+
 impl CoverageCalculator<'_, '_> {
     fn to_json(&self) -> String {
         serde_json::to_string(
             &self
                 .items
                 .iter()
-                .map(|(k, v)| (k.prefer_local().to_string(), v))
-                .collect::<BTreeMap<String, &ItemCount>>(),
+                .map(|(k, count)| {
+                    let gaps = self.gaps.get(k).map(|gaps| &gaps[..]);
+                    (k.prefer_local().to_string(), FileCoverage { count, gaps })
+                })
+                .collect::<BTreeMap<String, FileCoverage<'_>>>(),
         )
         .expect("failed to convert JSON data to string")
     }
@@ -185,6 +242,57 @@ fn print_table_record(
             total.examples_percentage().unwrap_or(0.0),
         );
         print_table_line();
+
+        if !self.gaps.is_empty() {
+            println!();
+            println!("Items missing documentation or examples:");
+            for (file, gaps) in &self.gaps {
+                for gap in gaps {
+                    let missing = match (gap.missing_docs, gap.missing_example) {
+                        (true, true) => "documentation and example",
+                        (true, false) => "documentation",
+                        (false, _) => "example",
+                    };
+                    println!(
+                        "{}:{}:{}: {} `{}` is missing {missing}",
+                        file.prefer_local(),
+                        gap.line,
+                        gap.column,
+                        gap.kind,
+                        gap.item,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Emits an error for the crate and every module whose documentation percentage is below
+    /// `--coverage-threshold`.
+    fn check_threshold(&self) {
+        let Some(threshold) = self.ctx.render_options.coverage_threshold else { return };
+        let dcx = self.ctx.tcx.dcx();
+        for (module, count) in &self.modules {
+            if let Some(percentage) = count.percentage()
+                && percentage < threshold
+            {
+                dcx.err(format!(
+                    "documentation coverage of module `{module}` is {percentage:.1}%, \
+                     below the threshold of {threshold}%"
+                ));
+            }
+        }
+        let mut total = ItemCount::default();
+        for &count in self.items.values() {
+            total += count;
+        }
+        if let Some(percentage) = total.percentage()
+            && percentage < threshold
+        {
+            dcx.err(format!(
+                "documentation coverage of the crate is {percentage:.1}%, \
+                 below the threshold of {threshold}%"
+            ));
+        }
     }
 }
 
@@ -196,6 +304,16 @@ fn visit_item(&mut self, i: &clean::Item) {
             return;
         }
 
+        // The documentation of a module is counted towards the module itself.
+        let entered_module = if let clean::ModuleItem(_) = i.kind
+            && let Some(name) = i.name
+        {
+            self.module_path.push(name);
+            true
+        } else {
+            false
+        };
+
         match i.kind {
             clean::StrippedItem(..) => {
                 // don't count items in stripped modules
@@ -256,16 +374,40 @@ fn visit_item(&mut self, i: &clean::Item) {
                 if let Some(span) = i.span(self.ctx.tcx) {
                     let filename = span.filename(self.ctx.sess());
                     debug!("counting {:?} {:?} in {filename:?}", i.type_(), i.name);
-                    self.items.entry(filename).or_default().count_item(
+                    let should_have_doc_example = should_have_doc_example(self.ctx, i);
+                    let mut count = ItemCount::default();
+                    count.count_item(
                         has_docs,
                         has_doc_example,
-                        should_have_doc_example(self.ctx, i),
+                        should_have_doc_example,
                         should_have_docs,
                     );
+                    *self.items.entry(filename.clone()).or_default() += count;
+                    let module = self.module_path.iter().map(Symbol::as_str).collect::<Vec<_>>();
+                    *self.modules.entry(module.join("::")).or_default() += count;
+
+                    let missing_docs = should_have_docs && !has_docs;
+                    let missing_example = should_have_doc_example && !has_doc_example;
+                    if self.ctx.render_options.coverage_details && (missing_docs || missing_example)
+                    {
+                        let lo = span.lo(self.ctx.sess());
+                        self.gaps.entry(filename).or_default().push(Gap {
+                            item: item_path(self.ctx.tcx, i.item_id.expect_def_id()),
+                            kind: i.type_().as_str(),
+                            line: lo.line,
+                            column: lo.col.0 + 1,
+                            missing_docs,
+                            missing_example,
+                        });
+                    }
                 }
             }
         }
 
-        self.visit_item_recur(i)
+        self.visit_item_recur(i);
+
+        if entered_module {
+            self.module_path.pop();
+        }
     }
 }
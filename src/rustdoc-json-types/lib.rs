@@ -30,7 +30,7 @@
 /// This integer is incremented with every breaking change to the API,
 /// and is returned along with the JSON blob as [`Crate::format_version`].
 /// Consuming code should assert that this value matches the format version(s) that it supports.
-pub const FORMAT_VERSION: u32 = 42;
+pub const FORMAT_VERSION: u32 = 43;
 
 /// The root of the emitted JSON blob.
 ///
@@ -545,6 +545,10 @@ pub struct Union {
     ///
     /// All of the corresponding [`Item`]s are of kind [`ItemEnum::Impl`].
     pub impls: Vec<Id>,
+    /// The memory layout of this union, if `--show-type-layout` was passed.
+    ///
+    /// This is `None` if the layout depends on generic parameters or could not be computed.
+    pub layout: Option<TypeLayout>,
 }
 
 /// A `struct`.
@@ -558,6 +562,10 @@ pub struct Struct {
     /// All impls (both of traits and inherent) for this struct.
     /// All of the corresponding [`Item`]s are of kind [`ItemEnum::Impl`].
     pub impls: Vec<Id>,
+    /// The memory layout of this struct, if `--show-type-layout` was passed.
+    ///
+    /// This is `None` if the layout depends on generic parameters or could not be computed.
+    pub layout: Option<TypeLayout>,
 }
 
 /// The kind of a [`Struct`] and the data specific to it, i.e. fields.
@@ -610,6 +618,10 @@ pub struct Enum {
     pub variants: Vec<Id>,
     /// `impl`s for the enum.
     pub impls: Vec<Id>,
+    /// The memory layout of this enum, if `--show-type-layout` was passed.
+    ///
+    /// This is `None` if the layout depends on generic parameters or could not be computed.
+    pub layout: Option<TypeLayout>,
 }
 
 /// A variant of an enum.
@@ -682,6 +694,117 @@ pub struct Discriminant {
     pub value: String,
 }
 
+/// The memory layout of a [`Struct`], [`Union`] or [`Enum`] on the documented target.
+///
+/// Layouts are not a stable guarantee unless the type has a `#[repr]` attribute that
+/// fixes them, and may change between compiler versions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TypeLayout {
+    /// The size of the type in bytes.
+    ///
+    /// For unsized types, this is the size of the sized prefix.
+    pub size: u64,
+    /// The ABI alignment of the type in bytes.
+    pub align: u64,
+    /// Whether the type is dynamically sized.
+    pub is_unsized: bool,
+    /// Whether the type has no valid values.
+    pub is_uninhabited: bool,
+    /// The layout of each field of a struct or union, in declaration order.
+    ///
+    /// This includes private and `#[doc(hidden)]` fields. Empty for enums.
+    pub fields: Vec<FieldLayout>,
+    /// The layout of each variant of an enum, in declaration order. Empty for structs and unions.
+    pub variants: Vec<VariantLayout>,
+    /// How the active variant of an enum is stored, if it has more than one variant in memory.
+    pub tag: Option<TagLayout>,
+    /// The largest niche of the type, i.e. the range of invalid values that an enclosing
+    /// type may use to store its own tag.
+    pub niche: Option<Niche>,
+}
+
+/// The layout of a field within a [`TypeLayout`] or [`VariantLayout`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FieldLayout {
+    /// The name of the field, or its index for tuple-like fields.
+    pub name: String,
+    /// The offset of the field from the start of the containing type, in bytes.
+    pub offset: u64,
+    /// The size of the field in bytes.
+    pub size: u64,
+}
+
+/// The layout of an enum variant within a [`TypeLayout`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VariantLayout {
+    /// The name of the variant.
+    pub name: String,
+    /// The value of the discriminant, which may be implicit. Stored as a string for the same
+    /// reason as [`Discriminant::value`].
+    pub discriminant: String,
+    /// The size of the variant in bytes, including the tag.
+    pub size: u64,
+    /// Whether the variant has no valid values.
+    pub is_uninhabited: bool,
+    /// The layout of the variant's fields, with offsets relative to the start of the enum.
+    pub fields: Vec<FieldLayout>,
+}
+
+/// Where and how an enum stores which of its variants is active.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TagLayout {
+    /// The offset of the tag from the start of the enum, in bytes.
+    pub offset: u64,
+    /// The size of the tag in bytes.
+    pub size: u64,
+    /// How variants are mapped to values of the tag.
+    pub encoding: TagEncoding,
+}
+
+/// How variants of an enum are mapped to values of its tag.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagEncoding {
+    /// The tag stores the discriminant of the active variant directly.
+    Direct,
+    /// The tag is stored in the niche of a field of one variant.
+    ///
+    /// ```rust
+    /// // `None` is represented by the null pointer.
+    /// enum Demo<'a> {
+    ///     Some(&'a u8),
+    ///     None,
+    /// }
+    /// ```
+    Niche {
+        /// The index of the variant whose field holds the niche. It has no tag value.
+        untagged_variant: u32,
+        /// The first and last index of the variants encoded in the niche.
+        niche_variants: (u32, u32),
+        /// The tag value of the first variant in `niche_variants`. Stored as a string because
+        /// it may be up to [`u128::MAX`].
+        niche_start: String,
+    },
+}
+
+/// A range of invalid values in a [`TypeLayout`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Niche {
+    /// The offset of the niche from the start of the type, in bytes.
+    pub offset: u64,
+    /// The size of the value holding the niche, in bytes.
+    pub size: u64,
+    /// The first valid value, as an unsigned integer. Stored as a string because it may be
+    /// up to [`u128::MAX`].
+    ///
+    /// The valid range wraps around if `valid_range_start` is greater than `valid_range_end`.
+    pub valid_range_start: String,
+    /// The last valid value, as an unsigned integer.
+    pub valid_range_end: String,
+    /// The number of invalid values available to enclosing types.
+    pub available: String,
+}
+
 /// A set of fundamental properties of a function.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct FunctionHeader {
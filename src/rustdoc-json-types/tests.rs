@@ -6,6 +6,7 @@ fn test_struct_info_roundtrip() {
         generics: Generics { params: vec![], where_predicates: vec![] },
         kind: StructKind::Plain { fields: vec![], has_stripped_fields: false },
         impls: vec![],
+        layout: None,
     });
 
     // JSON
@@ -26,6 +27,7 @@ fn test_union_info_roundtrip() {
         has_stripped_fields: false,
         fields: vec![],
         impls: vec![],
+        layout: None,
     });
 
     // JSON
@@ -1429,6 +1429,8 @@ macro_rules! decision {
         compile_fail: false,
         no_run: false,
         test_type: test::TestType::Unknown,
+        #[cfg(not(bootstrap))]
+        item_path: None,
     }
 }
 
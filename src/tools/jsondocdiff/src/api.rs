@@ -0,0 +1,190 @@
+//! Collects the public API of a crate, keyed by the path it can be named by.
+
+use std::collections::{BTreeMap, HashSet};
+
+use rustdoc_json_types::{Crate, Id, Item, ItemEnum, StructKind, VariantKind, Visibility};
+
+use crate::print::Printer;
+
+/// Every publicly reachable item of a crate.
+///
+/// Items are keyed by the path a user would name them by, e.g. `krate::module::Struct::field`.
+/// Trait impls are keyed by their header, e.g. `impl Clone for krate::Struct`.
+pub(crate) struct Api<'a> {
+    pub(crate) krate: &'a Crate,
+    pub(crate) items: BTreeMap<String, Entry<'a>>,
+}
+
+#[derive(Clone)]
+pub(crate) struct Entry<'a> {
+    pub(crate) item: &'a Item,
+    /// The struct, enum, variant, union or trait this item is a member of.
+    pub(crate) parent: Option<&'a Item>,
+    /// The path of the item this one belongs to: the parent, or the type an impl or
+    /// inherent method was found on.
+    pub(crate) owner: Option<String>,
+}
+
+impl<'a> Api<'a> {
+    pub(crate) fn new(krate: &'a Crate) -> Self {
+        let mut collector =
+            Collector { krate, items: BTreeMap::new(), seen_modules: HashSet::new() };
+        let root = &krate.index[&krate.root];
+        let name = root.name.clone().expect("crate root must have a name");
+        collector.module(root, &name);
+        Api { krate, items: collector.items }
+    }
+
+    pub(crate) fn printer(&self) -> Printer<'a> {
+        Printer::new(self.krate)
+    }
+}
+
+struct Collector<'a> {
+    krate: &'a Crate,
+    items: BTreeMap<String, Entry<'a>>,
+    /// Modules that were already walked, so glob re-exports of a parent can't loop forever.
+    seen_modules: HashSet<Id>,
+}
+
+impl<'a> Collector<'a> {
+    fn insert(&mut self, path: String, item: &'a Item, parent: Option<&'a Item>, owner: &str) {
+        // The first path found wins, so an item re-exported at several paths is compared at the
+        // same one in both crates.
+        let owner = if owner.is_empty() { None } else { Some(owner.to_owned()) };
+        self.items.entry(path).or_insert(Entry { item, parent, owner });
+    }
+
+    fn module(&mut self, module: &'a Item, path: &str) {
+        let krate = self.krate;
+        let ItemEnum::Module(m) = &module.inner else { return };
+        if !self.seen_modules.insert(module.id) {
+            return;
+        }
+        for item in m.items.iter().filter_map(|id| krate.index.get(id)) {
+            if item.visibility != Visibility::Public {
+                continue;
+            }
+            match &item.inner {
+                ItemEnum::Use(use_) => {
+                    let target = use_.id.as_ref().and_then(|id| krate.index.get(id));
+                    match (target, use_.is_glob) {
+                        (Some(target), true) => match &target.inner {
+                            ItemEnum::Module(_) => self.module(target, path),
+                            ItemEnum::Enum(e) => {
+                                for variant in
+                                    e.variants.iter().filter_map(|id| krate.index.get(id))
+                                {
+                                    let name = variant.name.as_deref().unwrap();
+                                    self.insert(
+                                        format!("{path}::{name}"),
+                                        variant,
+                                        Some(target),
+                                        "",
+                                    );
+                                }
+                            }
+                            _ => {}
+                        },
+                        (Some(target), false) => {
+                            self.item(target, format!("{path}::{}", use_.name))
+                        }
+                        // Re-exports of items from other crates are compared by their source.
+                        (None, _) => {
+                            let name = if use_.is_glob { "*" } else { &use_.name };
+                            self.insert(format!("{path}::{name}"), item, None, "");
+                        }
+                    }
+                }
+                _ => {
+                    let Some(name) = &item.name else { continue };
+                    self.item(item, format!("{path}::{name}"));
+                }
+            }
+        }
+    }
+
+    fn item(&mut self, item: &'a Item, path: String) {
+        let krate = self.krate;
+        match &item.inner {
+            ItemEnum::Module(_) => self.module(item, &path),
+            ItemEnum::Struct(s) => {
+                let fields: Vec<&Id> = match &s.kind {
+                    StructKind::Unit => Vec::new(),
+                    StructKind::Tuple(fields) => fields.iter().flatten().collect(),
+                    StructKind::Plain { fields, .. } => fields.iter().collect(),
+                };
+                self.fields(&fields, item, &path);
+                self.impls(&s.impls, &path);
+            }
+            ItemEnum::Union(u) => {
+                self.fields(&u.fields.iter().collect::<Vec<_>>(), item, &path);
+                self.impls(&u.impls, &path);
+            }
+            ItemEnum::Enum(e) => {
+                for variant in e.variants.iter().filter_map(|id| krate.index.get(id)) {
+                    let ItemEnum::Variant(v) = &variant.inner else { continue };
+                    let variant_path = format!("{path}::{}", variant.name.as_deref().unwrap());
+                    let fields: Vec<&Id> = match &v.kind {
+                        VariantKind::Plain => Vec::new(),
+                        VariantKind::Tuple(fields) => fields.iter().flatten().collect(),
+                        VariantKind::Struct { fields, .. } => fields.iter().collect(),
+                    };
+                    self.fields(&fields, variant, &variant_path);
+                    self.insert(variant_path, variant, Some(item), &path);
+                }
+                self.impls(&e.impls, &path);
+            }
+            ItemEnum::Trait(t) => {
+                for member in t.items.iter().filter_map(|id| krate.index.get(id)) {
+                    let Some(name) = &member.name else { continue };
+                    self.insert(format!("{path}::{name}"), member, Some(item), &path);
+                }
+            }
+            _ => {}
+        }
+        self.insert(path, item, None, "");
+    }
+
+    fn fields(&mut self, fields: &[&Id], parent: &'a Item, path: &str) {
+        let krate = self.krate;
+        for field in fields.iter().filter_map(|id| krate.index.get(id)) {
+            // Variant fields inherit the visibility of the enum.
+            if field.visibility == Visibility::Public || field.visibility == Visibility::Default {
+                let name = field.name.as_deref().unwrap();
+                self.insert(format!("{path}::{name}"), field, Some(parent), path);
+            }
+        }
+    }
+
+    fn impls(&mut self, impls: &[Id], path: &str) {
+        let krate = self.krate;
+        let printer = Printer::new(krate);
+        for item in impls.iter().filter_map(|id| krate.index.get(id)) {
+            let ItemEnum::Impl(impl_) = &item.inner else { continue };
+            match &impl_.trait_ {
+                None => {
+                    for member in impl_.items.iter().filter_map(|id| krate.index.get(id)) {
+                        if member.visibility != Visibility::Public {
+                            continue;
+                        }
+                        let Some(name) = &member.name else { continue };
+                        self.insert(format!("{path}::{name}"), member, None, path);
+                    }
+                }
+                // Blanket impls come and go with the impls of other traits, so they are not
+                // reported on their own.
+                Some(_) if impl_.blanket_impl.is_some() => {}
+                Some(trait_) => {
+                    let negative = if impl_.is_negative { "!" } else { "" };
+                    let header = format!(
+                        "impl {negative}{} for {}",
+                        printer.path(trait_),
+                        printer.ty(&impl_.for_)
+                    );
+                    self.insert(header, item, None, path);
+                }
+            }
+        }
+    }
+}
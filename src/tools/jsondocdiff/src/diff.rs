@@ -0,0 +1,497 @@
+//! Compares the public APIs of two versions of a crate.
+//!
+//! Each difference is classified by the version bump it requires under [semver], following the
+//! [Cargo SemVer compatibility guidelines][guide] where they give an answer.
+//!
+//! [semver]: https://semver.org
+//! [guide]: https://doc.rust-lang.org/cargo/reference/semver.html
+
+use std::collections::HashSet;
+use std::fmt;
+use std::mem::discriminant;
+
+use rustdoc_json_types::{
+    GenericParamDefKind, Generics, Item, ItemEnum, StructKind, Type, VariantKind,
+};
+use serde::Serialize;
+
+use crate::api::{Api, Entry};
+use crate::print::{Printer, abi};
+
+/// The version bump a change requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Severity {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Patch => "patch",
+            Severity::Minor => "minor",
+            Severity::Major => "major",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct Change {
+    /// The path of the item, or the header of a trait impl.
+    pub(crate) path: String,
+    pub(crate) kind: ChangeKind,
+    pub(crate) severity: Severity,
+    pub(crate) message: String,
+}
+
+/// Returns every difference between `old` and `new`, ordered by path.
+pub(crate) fn diff(old: &Api<'_>, new: &Api<'_>) -> Vec<Change> {
+    let removed: HashSet<&str> =
+        old.items.keys().filter(|path| !new.items.contains_key(*path)).map(|p| &p[..]).collect();
+    let added: HashSet<&str> =
+        new.items.keys().filter(|path| !old.items.contains_key(*path)).map(|p| &p[..]).collect();
+    let mut differ = Differ { old: old.printer(), new: new.printer(), changes: Vec::new() };
+
+    for (path, entry) in &old.items {
+        match new.items.get(path) {
+            Some(new_entry) => differ.compare(path, entry, new_entry),
+            // Removing an item also removes its fields, methods and impls; only report the item.
+            None if entry.owner.as_deref().is_some_and(|owner| removed.contains(owner)) => {}
+            None => {
+                let message = format!("{} was removed", describe(entry));
+                differ.push(path, ChangeKind::Removed, Severity::Major, message);
+            }
+        }
+    }
+    for (path, entry) in &new.items {
+        if !added.contains(&path[..])
+            || entry.owner.as_deref().is_some_and(|owner| added.contains(owner))
+        {
+            continue;
+        }
+        let (severity, message) = added_severity(entry);
+        differ.push(path, ChangeKind::Added, severity, message);
+    }
+
+    differ.changes.sort_by(|a, b| a.path.cmp(&b.path));
+    differ.changes
+}
+
+/// Describes an item for messages, e.g. "struct" or "trait method".
+fn describe(entry: &Entry<'_>) -> &'static str {
+    let in_trait = matches!(entry.parent.map(|p| &p.inner), Some(ItemEnum::Trait(_)));
+    match &entry.item.inner {
+        ItemEnum::Module(_) => "module",
+        ItemEnum::ExternCrate { .. } => "extern crate",
+        ItemEnum::Use(_) => "re-export",
+        ItemEnum::Union(_) => "union",
+        ItemEnum::Struct(_) => "struct",
+        ItemEnum::StructField(_) => "field",
+        ItemEnum::Enum(_) => "enum",
+        ItemEnum::Variant(_) => "variant",
+        ItemEnum::Function(_) if in_trait => "trait method",
+        ItemEnum::Function(_) if entry.owner.is_some() => "method",
+        ItemEnum::Function(_) => "function",
+        ItemEnum::Trait(_) => "trait",
+        ItemEnum::TraitAlias(_) => "trait alias",
+        ItemEnum::Impl(_) => "trait impl",
+        ItemEnum::TypeAlias(_) => "type alias",
+        ItemEnum::Constant { .. } => "constant",
+        ItemEnum::Static(_) => "static",
+        ItemEnum::ExternType => "extern type",
+        ItemEnum::Macro(_) => "macro",
+        ItemEnum::ProcMacro(_) => "proc macro",
+        ItemEnum::Primitive(_) => "primitive",
+        ItemEnum::AssocConst { .. } => "associated constant",
+        ItemEnum::AssocType { .. } => "associated type",
+    }
+}
+
+fn is_non_exhaustive(item: &Item) -> bool {
+    item.attrs.iter().any(|attr| attr == "#[non_exhaustive]")
+}
+
+/// Whether some fields of a struct, union or variant are hidden, so it can't be built with a
+/// literal outside of its crate.
+fn has_private_fields(item: &Item) -> bool {
+    match &item.inner {
+        ItemEnum::Struct(s) => match &s.kind {
+            StructKind::Unit => false,
+            StructKind::Tuple(fields) => fields.iter().any(Option::is_none),
+            StructKind::Plain { has_stripped_fields, .. } => *has_stripped_fields,
+        },
+        ItemEnum::Union(u) => u.has_stripped_fields,
+        ItemEnum::Variant(v) => match &v.kind {
+            VariantKind::Plain => false,
+            VariantKind::Tuple(fields) => fields.iter().any(Option::is_none),
+            VariantKind::Struct { has_stripped_fields, .. } => *has_stripped_fields,
+        },
+        _ => false,
+    }
+}
+
+fn added_severity(entry: &Entry<'_>) -> (Severity, String) {
+    let kind = describe(entry);
+    let Some(parent) = entry.parent else {
+        return (Severity::Minor, format!("{kind} was added"));
+    };
+    match (&parent.inner, &entry.item.inner) {
+        (ItemEnum::Struct(_) | ItemEnum::Variant(_), ItemEnum::StructField(_))
+            if !is_non_exhaustive(parent) && !has_private_fields(parent) =>
+        {
+            let message = "public field was added, which breaks struct literals and patterns";
+            (Severity::Major, message.to_owned())
+        }
+        (ItemEnum::Enum(_), ItemEnum::Variant(_)) if !is_non_exhaustive(parent) => {
+            let message = "variant was added to an enum that is not `#[non_exhaustive]`";
+            (Severity::Major, message.to_owned())
+        }
+        (ItemEnum::Trait(_), inner) => {
+            let required = match inner {
+                ItemEnum::Function(f) => !f.has_body,
+                ItemEnum::AssocConst { value, .. } => value.is_none(),
+                ItemEnum::AssocType { type_, .. } => type_.is_none(),
+                _ => false,
+            };
+            if required {
+                (Severity::Major, format!("required {kind} was added"))
+            } else {
+                (Severity::Minor, format!("{kind} with a default was added"))
+            }
+        }
+        _ => (Severity::Minor, format!("{kind} was added")),
+    }
+}
+
+struct Differ<'a> {
+    old: Printer<'a>,
+    new: Printer<'a>,
+    changes: Vec<Change>,
+}
+
+impl Differ<'_> {
+    fn push(&mut self, path: &str, kind: ChangeKind, severity: Severity, message: String) {
+        self.changes.push(Change { path: path.to_owned(), kind, severity, message });
+    }
+
+    fn changed(&mut self, path: &str, severity: Severity, message: String) {
+        self.push(path, ChangeKind::Changed, severity, message);
+    }
+
+    /// Reports a change of `what` if the two printed forms differ.
+    fn compare_printed(
+        &mut self,
+        path: &str,
+        severity: Severity,
+        what: &str,
+        old: String,
+        new: String,
+    ) {
+        if old != new {
+            self.changed(path, severity, format!("{what} changed from `{old}` to `{new}`"));
+        }
+    }
+
+    fn compare_ty(&mut self, path: &str, what: &str, old: &Type, new: &Type) {
+        self.compare_printed(path, Severity::Major, what, self.old.ty(old), self.new.ty(new));
+    }
+
+    /// Adding generic parameters with defaults is a minor change; anything else is major.
+    fn compare_generics(&mut self, path: &str, old: &Generics, new: &Generics) {
+        let (old_printed, new_printed) = (self.old.generics(old), self.new.generics(new));
+        if old_printed == new_printed {
+            return;
+        }
+        let old_params: Vec<_> = old.params.iter().map(|p| self.old.param(p)).collect();
+        let new_params: Vec<_> = new.params.iter().map(|p| self.new.param(p)).collect();
+        let only_defaults_added = new_params.starts_with(&old_params)
+            && new.params[old_params.len()..].iter().all(|param| match &param.kind {
+                GenericParamDefKind::Type { default, .. } => default.is_some(),
+                GenericParamDefKind::Const { default, .. } => default.is_some(),
+                GenericParamDefKind::Lifetime { .. } => false,
+            })
+            && self.old.generics(&Generics { params: Vec::new(), ..old.clone() })
+                == self.new.generics(&Generics { params: Vec::new(), ..new.clone() });
+        if only_defaults_added {
+            let message = "generic parameters with defaults were added".to_owned();
+            self.changed(path, Severity::Minor, message);
+        } else {
+            let or_none = |s: String| if s.is_empty() { "<>".to_owned() } else { s };
+            let (old_printed, new_printed) = (or_none(old_printed), or_none(new_printed));
+            self.compare_printed(path, Severity::Major, "generics", old_printed, new_printed);
+        }
+    }
+
+    /// Reports a flag that is a breaking change to set, but not to unset, or vice versa.
+    fn compare_flag(&mut self, path: &str, old: bool, new: bool, set: Severity, flag: &str) {
+        match (old, new) {
+            (false, true) => self.changed(path, set, format!("is now {flag}")),
+            (true, false) => {
+                let unset = if set == Severity::Major { Severity::Minor } else { Severity::Major };
+                self.changed(path, unset, format!("is no longer {flag}"));
+            }
+            _ => {}
+        }
+    }
+
+    fn compare(&mut self, path: &str, old: &Entry<'_>, new: &Entry<'_>) {
+        let (old_item, new_item) = (old.item, new.item);
+        if discriminant(&old_item.inner) != discriminant(&new_item.inner) {
+            let message = format!("changed from a {} to a {}", describe(old), describe(new));
+            self.changed(path, Severity::Major, message);
+            return;
+        }
+
+        if old_item.deprecation.is_none() && new_item.deprecation.is_some() {
+            self.changed(path, Severity::Minor, "was deprecated".to_owned());
+        }
+        if matches!(old_item.inner, ItemEnum::Struct(_) | ItemEnum::Enum(_) | ItemEnum::Variant(_))
+        {
+            let (old_ne, new_ne) = (is_non_exhaustive(old_item), is_non_exhaustive(new_item));
+            self.compare_flag(path, old_ne, new_ne, Severity::Major, "`#[non_exhaustive]`");
+        }
+
+        let in_trait = matches!(new.parent.map(|p| &p.inner), Some(ItemEnum::Trait(_)));
+        match (&old_item.inner, &new_item.inner) {
+            (ItemEnum::Function(old_fn), ItemEnum::Function(new_fn)) => {
+                let (old_h, new_h) = (&old_fn.header, &new_fn.header);
+                self.compare_flag(path, old_h.is_const, new_h.is_const, Severity::Minor, "`const`");
+                self.compare_flag(
+                    path,
+                    old_h.is_unsafe,
+                    new_h.is_unsafe,
+                    Severity::Major,
+                    "`unsafe`",
+                );
+                self.compare_flag(path, old_h.is_async, new_h.is_async, Severity::Major, "`async`");
+                self.compare_printed(
+                    path,
+                    Severity::Major,
+                    "ABI",
+                    abi(&old_h.abi),
+                    abi(&new_h.abi),
+                );
+                self.compare_printed(
+                    path,
+                    Severity::Major,
+                    "signature",
+                    self.old.fn_sig(&old_fn.sig, &old_fn.generics),
+                    self.new.fn_sig(&new_fn.sig, &new_fn.generics),
+                );
+                if in_trait {
+                    let message = match (old_fn.has_body, new_fn.has_body) {
+                        (true, false) => Some((Severity::Major, "no longer has a default body")),
+                        (false, true) => Some((Severity::Minor, "now has a default body")),
+                        _ => None,
+                    };
+                    if let Some((severity, message)) = message {
+                        self.changed(path, severity, message.to_owned());
+                    }
+                }
+            }
+            (ItemEnum::Struct(old_s), ItemEnum::Struct(new_s)) => {
+                let shape = |kind: &StructKind| match kind {
+                    StructKind::Unit => "unit struct",
+                    StructKind::Tuple(_) => "tuple struct",
+                    StructKind::Plain { .. } => "struct with named fields",
+                };
+                let (old_shape, new_shape) = (shape(&old_s.kind), shape(&new_s.kind));
+                if old_shape != new_shape {
+                    let message = format!("changed from a {old_shape} to a {new_shape}");
+                    self.changed(path, Severity::Major, message);
+                }
+                self.compare_private_fields(path, old_item, new_item);
+                self.compare_generics(path, &old_s.generics, &new_s.generics);
+            }
+            (ItemEnum::Union(old_u), ItemEnum::Union(new_u)) => {
+                self.compare_private_fields(path, old_item, new_item);
+                self.compare_generics(path, &old_u.generics, &new_u.generics);
+            }
+            (ItemEnum::Enum(old_e), ItemEnum::Enum(new_e)) => {
+                self.compare_generics(path, &old_e.generics, &new_e.generics);
+            }
+            (ItemEnum::Variant(old_v), ItemEnum::Variant(new_v)) => {
+                if discriminant(&old_v.kind) != discriminant(&new_v.kind) {
+                    let message = "changed between a unit, tuple and struct variant".to_owned();
+                    self.changed(path, Severity::Major, message);
+                }
+                self.compare_private_fields(path, old_item, new_item);
+                let value = |v: &rustdoc_json_types::Variant| {
+                    v.discriminant.as_ref().map_or("implicit".to_owned(), |d| d.value.clone())
+                };
+                self.compare_printed(
+                    path,
+                    Severity::Major,
+                    "discriminant",
+                    value(old_v),
+                    value(new_v),
+                );
+            }
+            (ItemEnum::StructField(old_ty), ItemEnum::StructField(new_ty)) => {
+                self.compare_ty(path, "type", old_ty, new_ty);
+            }
+            (ItemEnum::Trait(old_t), ItemEnum::Trait(new_t)) => {
+                self.compare_flag(
+                    path,
+                    old_t.is_unsafe,
+                    new_t.is_unsafe,
+                    Severity::Major,
+                    "`unsafe`",
+                );
+                self.compare_flag(
+                    path,
+                    old_t.is_dyn_compatible,
+                    new_t.is_dyn_compatible,
+                    Severity::Minor,
+                    "dyn compatible",
+                );
+                self.compare_printed(
+                    path,
+                    Severity::Major,
+                    "supertraits",
+                    self.old.bounds(&old_t.bounds),
+                    self.new.bounds(&new_t.bounds),
+                );
+                self.compare_generics(path, &old_t.generics, &new_t.generics);
+            }
+            (ItemEnum::TraitAlias(old_t), ItemEnum::TraitAlias(new_t)) => {
+                self.compare_printed(
+                    path,
+                    Severity::Major,
+                    "bounds",
+                    self.old.bounds(&old_t.params),
+                    self.new.bounds(&new_t.params),
+                );
+                self.compare_generics(path, &old_t.generics, &new_t.generics);
+            }
+            (ItemEnum::TypeAlias(old_t), ItemEnum::TypeAlias(new_t)) => {
+                self.compare_ty(path, "aliased type", &old_t.type_, &new_t.type_);
+                self.compare_generics(path, &old_t.generics, &new_t.generics);
+            }
+            (
+                ItemEnum::Constant { type_: old_ty, const_: old_c },
+                ItemEnum::Constant { type_: new_ty, const_: new_c },
+            ) => {
+                let (old_ty, new_ty) = (self.old.ty(old_ty), self.new.ty(new_ty));
+                if old_ty == new_ty && old_c.value != new_c.value {
+                    self.changed(path, Severity::Patch, "value changed".to_owned());
+                }
+                self.compare_printed(path, Severity::Major, "type", old_ty, new_ty);
+            }
+            (ItemEnum::Static(old_s), ItemEnum::Static(new_s)) => {
+                self.compare_ty(path, "type", &old_s.type_, &new_s.type_);
+                let (old_mut, new_mut) = (old_s.is_mutable, new_s.is_mutable);
+                if old_mut != new_mut {
+                    let message =
+                        format!("is {}mutable", if new_mut { "now " } else { "no longer " });
+                    self.changed(path, Severity::Major, message);
+                }
+                self.compare_flag(
+                    path,
+                    old_s.is_unsafe,
+                    new_s.is_unsafe,
+                    Severity::Major,
+                    "`unsafe`",
+                );
+            }
+            (ItemEnum::Macro(old_m), ItemEnum::Macro(new_m)) if old_m != new_m => {
+                self.changed(path, Severity::Minor, "definition changed".to_owned());
+            }
+            (ItemEnum::ProcMacro(old_m), ItemEnum::ProcMacro(new_m)) => {
+                if old_m.kind != new_m.kind {
+                    self.changed(path, Severity::Major, "kind of proc macro changed".to_owned());
+                }
+                for helper in old_m.helpers.iter().filter(|h| !new_m.helpers.contains(h)) {
+                    self.changed(path, Severity::Major, format!("helper `{helper}` was removed"));
+                }
+                for helper in new_m.helpers.iter().filter(|h| !old_m.helpers.contains(h)) {
+                    self.changed(path, Severity::Minor, format!("helper `{helper}` was added"));
+                }
+            }
+            (
+                ItemEnum::AssocConst { type_: old_ty, value: old_value },
+                ItemEnum::AssocConst { type_: new_ty, value: new_value },
+            ) => {
+                self.compare_ty(path, "type", old_ty, new_ty);
+                match (old_value, new_value) {
+                    (None, Some(_)) => {
+                        self.changed(path, Severity::Minor, "now has a default value".to_owned())
+                    }
+                    (Some(_), None) => {
+                        let message = "no longer has a default value".to_owned();
+                        self.changed(path, Severity::Major, message);
+                    }
+                    (Some(old_value), Some(new_value)) if old_value != new_value => {
+                        self.changed(path, Severity::Patch, "value changed".to_owned())
+                    }
+                    _ => {}
+                }
+            }
+            (
+                ItemEnum::AssocType { generics: old_g, bounds: old_b, type_: old_ty },
+                ItemEnum::AssocType { generics: new_g, bounds: new_b, type_: new_ty },
+            ) => {
+                self.compare_printed(
+                    path,
+                    Severity::Major,
+                    "bounds",
+                    self.old.bounds(old_b),
+                    self.new.bounds(new_b),
+                );
+                self.compare_generics(path, old_g, new_g);
+                match (old_ty, new_ty) {
+                    (None, Some(_)) => {
+                        self.changed(path, Severity::Minor, "now has a default type".to_owned())
+                    }
+                    (Some(_), None) => {
+                        let message = "no longer has a default type".to_owned();
+                        self.changed(path, Severity::Major, message);
+                    }
+                    // Changing a default only affects implementors that rely on it.
+                    (Some(old_ty), Some(new_ty)) if in_trait => self.compare_printed(
+                        path,
+                        Severity::Minor,
+                        "default type",
+                        self.old.ty(old_ty),
+                        self.new.ty(new_ty),
+                    ),
+                    (Some(old_ty), Some(new_ty)) => self.compare_ty(path, "type", old_ty, new_ty),
+                    (None, None) => {}
+                }
+            }
+            (ItemEnum::Use(old_use), ItemEnum::Use(new_use)) => {
+                self.compare_printed(
+                    path,
+                    Severity::Major,
+                    "re-exported item",
+                    old_use.source.clone(),
+                    new_use.source.clone(),
+                );
+            }
+            _ => {}
+        }
+
+        if old_item.docs != new_item.docs {
+            self.changed(path, Severity::Patch, "documentation changed".to_owned());
+        }
+    }
+
+    /// Reports a struct, union or variant that gained private fields.
+    fn compare_private_fields(&mut self, path: &str, old: &Item, new: &Item) {
+        let (old_private, new_private) = (has_private_fields(old), has_private_fields(new));
+        self.compare_flag(path, old_private, new_private, Severity::Major, "partly private");
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,350 @@
+use rustdoc_json_types::{
+    Abi, Crate, Enum, FORMAT_VERSION, Function, FunctionHeader, FunctionSignature, FxHashMap,
+    GenericParamDef, GenericParamDefKind, Generics, Id, Item, ItemEnum, Module, Struct, StructKind,
+    Trait, Type, Variant, VariantKind, Visibility,
+};
+
+use super::*;
+
+/// Builds a crate named `krate` whose root module contains the items added with `item`.
+#[derive(Default)]
+struct CrateBuilder {
+    index: FxHashMap<Id, Item>,
+    root_items: Vec<Id>,
+    /// Offsets the ids, so tests can check that ids don't need to match between crates.
+    id_offset: u32,
+}
+
+impl CrateBuilder {
+    fn with_id_offset(id_offset: u32) -> Self {
+        CrateBuilder { id_offset, ..Default::default() }
+    }
+
+    fn member(&mut self, name: &str, visibility: Visibility, inner: ItemEnum) -> Id {
+        let id = Id(self.id_offset + self.index.len() as u32 + 1);
+        let item = Item {
+            id,
+            crate_id: 0,
+            name: Some(name.to_owned()),
+            span: None,
+            visibility,
+            docs: None,
+            links: FxHashMap::default(),
+            attrs: Vec::new(),
+            deprecation: None,
+            inner,
+        };
+        self.index.insert(id, item);
+        id
+    }
+
+    fn item(&mut self, name: &str, inner: ItemEnum) -> Id {
+        let id = self.member(name, Visibility::Public, inner);
+        self.root_items.push(id);
+        id
+    }
+
+    fn non_exhaustive(&mut self, id: Id) {
+        self.index.get_mut(&id).unwrap().attrs.push("#[non_exhaustive]".to_owned());
+    }
+
+    fn build(mut self) -> Crate {
+        let root = Id(self.id_offset);
+        let module = Module { is_crate: true, items: self.root_items, is_stripped: false };
+        self.index.insert(
+            root,
+            Item {
+                id: root,
+                crate_id: 0,
+                name: Some("krate".to_owned()),
+                span: None,
+                visibility: Visibility::Public,
+                docs: None,
+                links: FxHashMap::default(),
+                attrs: Vec::new(),
+                deprecation: None,
+                inner: ItemEnum::Module(module),
+            },
+        );
+        Crate {
+            root,
+            crate_version: None,
+            includes_private: false,
+            index: self.index,
+            paths: FxHashMap::default(),
+            external_crates: FxHashMap::default(),
+            format_version: FORMAT_VERSION,
+        }
+    }
+}
+
+fn generics(params: Vec<GenericParamDef>) -> Generics {
+    Generics { params, where_predicates: Vec::new() }
+}
+
+fn function(inputs: &[&str], has_body: bool) -> ItemEnum {
+    ItemEnum::Function(Function {
+        sig: FunctionSignature {
+            inputs: inputs
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| (format!("arg{i}"), Type::Primitive(ty.to_string())))
+                .collect(),
+            output: None,
+            is_c_variadic: false,
+        },
+        generics: generics(Vec::new()),
+        header: FunctionHeader {
+            is_const: false,
+            is_unsafe: false,
+            is_async: false,
+            abi: Abi::Rust,
+        },
+        has_body,
+    })
+}
+
+fn unit_struct(generics: Generics) -> ItemEnum {
+    ItemEnum::Struct(Struct { kind: StructKind::Unit, generics, impls: Vec::new(), layout: None })
+}
+
+fn plain_struct(fields: Vec<Id>, has_stripped_fields: bool) -> ItemEnum {
+    ItemEnum::Struct(Struct {
+        kind: StructKind::Plain { fields, has_stripped_fields },
+        generics: generics(Vec::new()),
+        impls: Vec::new(),
+        layout: None,
+    })
+}
+
+fn enum_(variants: Vec<Id>) -> ItemEnum {
+    ItemEnum::Enum(Enum {
+        generics: generics(Vec::new()),
+        has_stripped_variants: false,
+        variants,
+        impls: Vec::new(),
+        layout: None,
+    })
+}
+
+fn plain_variant() -> ItemEnum {
+    ItemEnum::Variant(Variant { kind: VariantKind::Plain, discriminant: None })
+}
+
+fn trait_(items: Vec<Id>) -> ItemEnum {
+    ItemEnum::Trait(Trait {
+        is_auto: false,
+        is_unsafe: false,
+        is_dyn_compatible: true,
+        items,
+        generics: generics(Vec::new()),
+        bounds: Vec::new(),
+        implementations: Vec::new(),
+    })
+}
+
+fn type_param(name: &str, default: Option<Type>) -> GenericParamDef {
+    GenericParamDef {
+        name: name.to_owned(),
+        kind: GenericParamDefKind::Type { bounds: Vec::new(), default, is_synthetic: false },
+    }
+}
+
+#[track_caller]
+fn check(old: CrateBuilder, new: CrateBuilder, expected: &[(&str, ChangeKind, Severity, &str)]) {
+    let (old, new) = (old.build(), new.build());
+    let changes = diff(&Api::new(&old), &Api::new(&new));
+    let changes: Vec<_> =
+        changes.iter().map(|c| (&c.path[..], c.kind, c.severity, &c.message[..])).collect();
+    assert_eq!(changes, expected);
+}
+
+#[test]
+fn identical_apis_with_different_ids() {
+    let build = |id_offset| {
+        let mut krate = CrateBuilder::with_id_offset(id_offset);
+        let field = krate.member("x", Visibility::Public, ItemEnum::StructField(Type::Infer));
+        krate.item("S", plain_struct(vec![field], false));
+        krate.item("f", function(&["u8"], true));
+        krate
+    };
+    check(build(0), build(100), &[]);
+}
+
+#[test]
+fn added_and_removed_items() {
+    let mut old = CrateBuilder::default();
+    let field = old.member("x", Visibility::Public, ItemEnum::StructField(Type::Infer));
+    old.item("Removed", plain_struct(vec![field], false));
+    let mut new = CrateBuilder::default();
+    new.item("added", function(&[], true));
+
+    // The field is removed with its struct, and isn't reported on its own.
+    check(
+        old,
+        new,
+        &[
+            ("krate::Removed", ChangeKind::Removed, Severity::Major, "struct was removed"),
+            ("krate::added", ChangeKind::Added, Severity::Minor, "function was added"),
+        ],
+    );
+}
+
+#[test]
+fn changed_signature() {
+    let mut old = CrateBuilder::default();
+    old.item("f", function(&["u8", "bool"], true));
+    let mut new = CrateBuilder::default();
+    new.item("f", function(&["u16", "bool"], true));
+
+    check(
+        old,
+        new,
+        &[(
+            "krate::f",
+            ChangeKind::Changed,
+            Severity::Major,
+            "signature changed from `fn(u8, bool)` to `fn(u16, bool)`",
+        )],
+    );
+}
+
+#[test]
+fn added_fields() {
+    let build = |fields: &[&str], private_fields| {
+        let mut krate = CrateBuilder::default();
+        let fields = fields
+            .iter()
+            .map(|name| krate.member(name, Visibility::Public, ItemEnum::StructField(Type::Infer)))
+            .collect();
+        krate.item("S", plain_struct(fields, private_fields));
+        krate
+    };
+
+    check(
+        build(&["a"], false),
+        build(&["a", "b"], false),
+        &[(
+            "krate::S::b",
+            ChangeKind::Added,
+            Severity::Major,
+            "public field was added, which breaks struct literals and patterns",
+        )],
+    );
+    check(
+        build(&["a"], true),
+        build(&["a", "b"], true),
+        &[("krate::S::b", ChangeKind::Added, Severity::Minor, "field was added")],
+    );
+}
+
+#[test]
+fn added_variants() {
+    let build = |variants: &[&str], non_exhaustive| {
+        let mut krate = CrateBuilder::default();
+        let variants =
+            variants.iter().map(|name| krate.member(name, Visibility::Default, plain_variant()));
+        let variants = variants.collect();
+        let e = krate.item("E", enum_(variants));
+        if non_exhaustive {
+            krate.non_exhaustive(e);
+        }
+        krate
+    };
+
+    check(
+        build(&["A"], false),
+        build(&["A", "B"], false),
+        &[(
+            "krate::E::B",
+            ChangeKind::Added,
+            Severity::Major,
+            "variant was added to an enum that is not `#[non_exhaustive]`",
+        )],
+    );
+    check(
+        build(&["A"], true),
+        build(&["A", "B"], true),
+        &[("krate::E::B", ChangeKind::Added, Severity::Minor, "variant was added")],
+    );
+    check(
+        build(&["A"], false),
+        build(&["A"], true),
+        &[("krate::E", ChangeKind::Changed, Severity::Major, "is now `#[non_exhaustive]`")],
+    );
+}
+
+#[test]
+fn added_trait_items() {
+    let build = |items: &[(&str, bool)]| {
+        let mut krate = CrateBuilder::default();
+        let items = items
+            .iter()
+            .map(|&(name, has_body)| {
+                krate.member(name, Visibility::Default, function(&["u8"], has_body))
+            })
+            .collect();
+        krate.item("Trait", trait_(items));
+        krate
+    };
+
+    check(
+        build(&[]),
+        build(&[("required", false), ("provided", true)]),
+        &[
+            (
+                "krate::Trait::provided",
+                ChangeKind::Added,
+                Severity::Minor,
+                "trait method with a default was added",
+            ),
+            (
+                "krate::Trait::required",
+                ChangeKind::Added,
+                Severity::Major,
+                "required trait method was added",
+            ),
+        ],
+    );
+    check(
+        build(&[("method", true)]),
+        build(&[("method", false)]),
+        &[(
+            "krate::Trait::method",
+            ChangeKind::Changed,
+            Severity::Major,
+            "no longer has a default body",
+        )],
+    );
+}
+
+#[test]
+fn changed_generics() {
+    let build = |params| {
+        let mut krate = CrateBuilder::default();
+        krate.item("S", unit_struct(generics(params)));
+        krate
+    };
+    let u8_ = || Some(Type::Primitive("u8".to_owned()));
+
+    check(
+        build(vec![type_param("T", None)]),
+        build(vec![type_param("T", None), type_param("U", u8_())]),
+        &[(
+            "krate::S",
+            ChangeKind::Changed,
+            Severity::Minor,
+            "generic parameters with defaults were added",
+        )],
+    );
+    check(
+        build(vec![]),
+        build(vec![type_param("T", None)]),
+        &[(
+            "krate::S",
+            ChangeKind::Changed,
+            Severity::Major,
+            "generics changed from `<>` to `<T>`",
+        )],
+    );
+}
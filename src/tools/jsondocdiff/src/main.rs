@@ -0,0 +1,74 @@
+//! Compares the public API of two versions of a crate, as documented by rustdoc JSON, and
+//! reports which version bump the differences require under semver.
+
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use clap::Parser;
+use fs_err as fs;
+use rustdoc_json_types::{Crate, FORMAT_VERSION};
+use serde::Serialize;
+
+mod api;
+mod diff;
+mod print;
+
+use crate::diff::{Change, Severity};
+
+#[derive(Debug, Serialize)]
+struct JsonOutput {
+    /// The largest version bump required by any change, or `None` if the APIs are identical.
+    required_bump: Option<Severity>,
+    changes: Vec<Change>,
+}
+
+#[derive(Parser)]
+struct Cli {
+    /// The rustdoc JSON of the old version of the crate
+    old: PathBuf,
+
+    /// The rustdoc JSON of the new version of the crate
+    new: PathBuf,
+
+    /// Also write the changes as JSON to this file
+    #[arg(long)]
+    json_output: Option<PathBuf>,
+}
+
+fn load(path: &PathBuf) -> Result<Crate> {
+    let krate: Crate = serde_json::from_str(&fs::read_to_string(path)?)?;
+    if krate.format_version != FORMAT_VERSION {
+        bail!(
+            "{} has format version {}, but this tool supports version {FORMAT_VERSION}",
+            path.display(),
+            krate.format_version,
+        );
+    }
+    Ok(krate)
+}
+
+fn main() -> Result<()> {
+    let Cli { old, new, json_output } = Cli::parse();
+
+    let (old, new) = (load(&old)?, load(&new)?);
+    let changes = diff::diff(&api::Api::new(&old), &api::Api::new(&new));
+    let required_bump = changes.iter().map(|change| change.severity).max();
+
+    for change in &changes {
+        println!("{}: {}: {}", change.severity, change.path, change.message);
+    }
+    match required_bump {
+        Some(bump) => println!("required version bump: {bump}"),
+        None => println!("no changes to the public API"),
+    }
+
+    if let Some(json_output) = json_output {
+        let output = JsonOutput { required_bump, changes };
+        let mut f = BufWriter::new(fs::File::create(json_output)?);
+        serde_json::to_writer(&mut f, &output)?;
+        f.flush()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,371 @@
+//! Prints types and signatures as Rust source, so they can be compared between two crates.
+//!
+//! [`Id`]s are not stable between rustdoc invocations, so two versions of a crate can only be
+//! compared through the paths that the ids resolve to. Paths are printed fully qualified where
+//! the crate knows about them, and as written otherwise.
+
+use std::fmt::Write;
+
+use rustdoc_json_types::{
+    Abi, Crate, FunctionHeader, FunctionSignature, GenericArg, GenericArgs, GenericBound,
+    GenericParamDef, GenericParamDefKind, Generics, Path, PreciseCapturingArg, Term,
+    TraitBoundModifier, Type, WherePredicate,
+};
+
+#[derive(Clone, Copy)]
+pub(crate) struct Printer<'a> {
+    krate: &'a Crate,
+}
+
+impl<'a> Printer<'a> {
+    pub(crate) fn new(krate: &'a Crate) -> Self {
+        Printer { krate }
+    }
+
+    pub(crate) fn ty(self, ty: &Type) -> String {
+        let mut s = String::new();
+        self.write_ty(&mut s, ty);
+        s
+    }
+
+    pub(crate) fn path(self, path: &Path) -> String {
+        let mut s = String::new();
+        self.write_path(&mut s, path);
+        s
+    }
+
+    pub(crate) fn bounds(self, bounds: &[GenericBound]) -> String {
+        let mut s = String::new();
+        self.write_bounds(&mut s, bounds);
+        s
+    }
+
+    /// The generic parameters and where clause, e.g. `<T: Clone> where T: Send`.
+    pub(crate) fn generics(self, generics: &Generics) -> String {
+        let mut s = String::new();
+        self.write_params(&mut s, &generics.params, true);
+        self.write_where(&mut s, &generics.where_predicates);
+        s
+    }
+
+    /// A function signature without its name, e.g. `fn<T>(&T, u8) -> bool where T: Send`.
+    pub(crate) fn fn_sig(self, sig: &FunctionSignature, generics: &Generics) -> String {
+        let mut s = "fn".to_owned();
+        self.write_params(&mut s, &generics.params, false);
+        self.write_sig(&mut s, sig);
+        self.write_where(&mut s, &generics.where_predicates);
+        s
+    }
+
+    fn write_path(self, s: &mut String, path: &Path) {
+        match self.krate.paths.get(&path.id) {
+            Some(summary) => s.push_str(&summary.path.join("::")),
+            None => s.push_str(&path.path),
+        }
+        if let Some(args) = &path.args {
+            self.write_args(s, args);
+        }
+    }
+
+    fn write_ty(self, s: &mut String, ty: &Type) {
+        match ty {
+            Type::ResolvedPath(path) => self.write_path(s, path),
+            Type::DynTrait(dyn_trait) => {
+                s.push_str("dyn ");
+                for (i, poly) in dyn_trait.traits.iter().enumerate() {
+                    if i > 0 {
+                        s.push_str(" + ");
+                    }
+                    self.write_hrtb(s, &poly.generic_params);
+                    self.write_path(s, &poly.trait_);
+                }
+                if let Some(lifetime) = &dyn_trait.lifetime {
+                    write!(s, " + {lifetime}").unwrap();
+                }
+            }
+            Type::Generic(name) | Type::Primitive(name) => s.push_str(name),
+            Type::FunctionPointer(fn_ptr) => {
+                self.write_hrtb(s, &fn_ptr.generic_params);
+                write_header(s, &fn_ptr.header);
+                s.push_str("fn");
+                self.write_sig(s, &fn_ptr.sig);
+            }
+            Type::Tuple(types) => {
+                s.push('(');
+                self.write_list(s, types, |s, ty| self.write_ty(s, ty));
+                if types.len() == 1 {
+                    s.push(',');
+                }
+                s.push(')');
+            }
+            Type::Slice(ty) => {
+                s.push('[');
+                self.write_ty(s, ty);
+                s.push(']');
+            }
+            Type::Array { type_, len } => {
+                s.push('[');
+                self.write_ty(s, type_);
+                write!(s, "; {len}]").unwrap();
+            }
+            Type::Pat { type_, __pat_unstable_do_not_use: pat } => {
+                self.write_ty(s, type_);
+                write!(s, " is {pat}").unwrap();
+            }
+            Type::ImplTrait(bounds) => {
+                s.push_str("impl ");
+                self.write_bounds(s, bounds);
+            }
+            Type::Infer => s.push('_'),
+            Type::RawPointer { is_mutable, type_ } => {
+                s.push_str(if *is_mutable { "*mut " } else { "*const " });
+                self.write_ty(s, type_);
+            }
+            Type::BorrowedRef { lifetime, is_mutable, type_ } => {
+                s.push('&');
+                if let Some(lifetime) = lifetime {
+                    write!(s, "{lifetime} ").unwrap();
+                }
+                if *is_mutable {
+                    s.push_str("mut ");
+                }
+                self.write_ty(s, type_);
+            }
+            Type::QualifiedPath { name, args, self_type, trait_ } => {
+                match trait_ {
+                    Some(trait_) => {
+                        s.push('<');
+                        self.write_ty(s, self_type);
+                        s.push_str(" as ");
+                        self.write_path(s, trait_);
+                        s.push('>');
+                    }
+                    None => self.write_ty(s, self_type),
+                }
+                write!(s, "::{name}").unwrap();
+                self.write_args(s, args);
+            }
+        }
+    }
+
+    fn write_args(self, s: &mut String, args: &GenericArgs) {
+        match args {
+            GenericArgs::AngleBracketed { args, constraints } => {
+                if args.is_empty() && constraints.is_empty() {
+                    return;
+                }
+                s.push('<');
+                self.write_list(s, args, |s, arg| match arg {
+                    GenericArg::Lifetime(lifetime) => s.push_str(lifetime),
+                    GenericArg::Type(ty) => self.write_ty(s, ty),
+                    GenericArg::Const(constant) => s.push_str(&constant.expr),
+                    GenericArg::Infer => s.push('_'),
+                });
+                for (i, constraint) in constraints.iter().enumerate() {
+                    if i > 0 || !args.is_empty() {
+                        s.push_str(", ");
+                    }
+                    s.push_str(&constraint.name);
+                    self.write_args(s, &constraint.args);
+                    match &constraint.binding {
+                        rustdoc_json_types::AssocItemConstraintKind::Equality(term) => {
+                            s.push_str(" = ");
+                            self.write_term(s, term);
+                        }
+                        rustdoc_json_types::AssocItemConstraintKind::Constraint(bounds) => {
+                            s.push_str(": ");
+                            self.write_bounds(s, bounds);
+                        }
+                    }
+                }
+                s.push('>');
+            }
+            GenericArgs::Parenthesized { inputs, output } => {
+                s.push('(');
+                self.write_list(s, inputs, |s, ty| self.write_ty(s, ty));
+                s.push(')');
+                if let Some(output) = output {
+                    s.push_str(" -> ");
+                    self.write_ty(s, output);
+                }
+            }
+            GenericArgs::ReturnTypeNotation => s.push_str("(..)"),
+        }
+    }
+
+    fn write_term(self, s: &mut String, term: &Term) {
+        match term {
+            Term::Type(ty) => self.write_ty(s, ty),
+            Term::Constant(constant) => s.push_str(&constant.expr),
+        }
+    }
+
+    fn write_bounds(self, s: &mut String, bounds: &[GenericBound]) {
+        for (i, bound) in bounds.iter().enumerate() {
+            if i > 0 {
+                s.push_str(" + ");
+            }
+            match bound {
+                GenericBound::TraitBound { trait_, generic_params, modifier } => {
+                    self.write_hrtb(s, generic_params);
+                    s.push_str(match modifier {
+                        TraitBoundModifier::None => "",
+                        TraitBoundModifier::Maybe => "?",
+                        TraitBoundModifier::MaybeConst => "~const ",
+                    });
+                    self.write_path(s, trait_);
+                }
+                GenericBound::Outlives(lifetime) => s.push_str(lifetime),
+                GenericBound::Use(args) => {
+                    s.push_str("use<");
+                    self.write_list(s, args, |s, arg| match arg {
+                        PreciseCapturingArg::Lifetime(name) | PreciseCapturingArg::Param(name) => {
+                            s.push_str(name)
+                        }
+                    });
+                    s.push('>');
+                }
+            }
+        }
+    }
+
+    /// Writes `for<'a, 'b> `, if there are any higher-ranked parameters.
+    fn write_hrtb(self, s: &mut String, params: &[GenericParamDef]) {
+        if !params.is_empty() {
+            s.push_str("for");
+            self.write_params(s, params, true);
+            s.push(' ');
+        }
+    }
+
+    /// Writes `<'a, T: Bound = Default, const N: usize>`. Synthetic parameters, which come from
+    /// `impl Trait` in argument position, are only included if `synthetic` is set, because they
+    /// are already printed as part of the argument types.
+    fn write_params(self, s: &mut String, params: &[GenericParamDef], synthetic: bool) {
+        let params: Vec<_> = params
+            .iter()
+            .filter(|param| {
+                synthetic
+                    || !matches!(param.kind, GenericParamDefKind::Type { is_synthetic: true, .. })
+            })
+            .collect();
+        if params.is_empty() {
+            return;
+        }
+        s.push('<');
+        self.write_list(s, &params, |s, param| self.write_param(s, param));
+        s.push('>');
+    }
+
+    pub(crate) fn param(self, param: &GenericParamDef) -> String {
+        let mut s = String::new();
+        self.write_param(&mut s, param);
+        s
+    }
+
+    fn write_param(self, s: &mut String, param: &GenericParamDef) {
+        match &param.kind {
+            GenericParamDefKind::Lifetime { outlives } => {
+                s.push_str(&param.name);
+                if !outlives.is_empty() {
+                    write!(s, ": {}", outlives.join(" + ")).unwrap();
+                }
+            }
+            GenericParamDefKind::Type { bounds, default, is_synthetic: _ } => {
+                s.push_str(&param.name);
+                if !bounds.is_empty() {
+                    s.push_str(": ");
+                    self.write_bounds(s, bounds);
+                }
+                if let Some(default) = default {
+                    s.push_str(" = ");
+                    self.write_ty(s, default);
+                }
+            }
+            GenericParamDefKind::Const { type_, default } => {
+                write!(s, "const {}: ", param.name).unwrap();
+                self.write_ty(s, type_);
+                if let Some(default) = default {
+                    write!(s, " = {default}").unwrap();
+                }
+            }
+        }
+    }
+
+    fn write_where(self, s: &mut String, predicates: &[WherePredicate]) {
+        if predicates.is_empty() {
+            return;
+        }
+        s.push_str(" where ");
+        self.write_list(s, predicates, |s, predicate| match predicate {
+            WherePredicate::BoundPredicate { type_, bounds, generic_params } => {
+                self.write_hrtb(s, generic_params);
+                self.write_ty(s, type_);
+                s.push_str(": ");
+                self.write_bounds(s, bounds);
+            }
+            WherePredicate::LifetimePredicate { lifetime, outlives } => {
+                write!(s, "{lifetime}: {}", outlives.join(" + ")).unwrap();
+            }
+            WherePredicate::EqPredicate { lhs, rhs } => {
+                self.write_ty(s, lhs);
+                s.push_str(" = ");
+                self.write_term(s, rhs);
+            }
+        });
+    }
+
+    fn write_sig(self, s: &mut String, sig: &FunctionSignature) {
+        s.push('(');
+        self.write_list(s, &sig.inputs, |s, (_name, ty)| self.write_ty(s, ty));
+        if sig.is_c_variadic {
+            s.push_str(if sig.inputs.is_empty() { "..." } else { ", ..." });
+        }
+        s.push(')');
+        if let Some(output) = &sig.output {
+            s.push_str(" -> ");
+            self.write_ty(s, output);
+        }
+    }
+
+    fn write_list<T>(self, s: &mut String, list: &[T], mut f: impl FnMut(&mut String, &T)) {
+        for (i, elem) in list.iter().enumerate() {
+            if i > 0 {
+                s.push_str(", ");
+            }
+            f(s, elem);
+        }
+    }
+}
+
+fn write_header(s: &mut String, header: &FunctionHeader) {
+    if header.is_const {
+        s.push_str("const ");
+    }
+    if header.is_async {
+        s.push_str("async ");
+    }
+    if header.is_unsafe {
+        s.push_str("unsafe ");
+    }
+    if header.abi != Abi::Rust {
+        write!(s, "extern {} ", abi(&header.abi)).unwrap();
+    }
+}
+
+/// The ABI string as written after `extern`, e.g. `"C-unwind"`.
+pub(crate) fn abi(abi: &Abi) -> String {
+    let (name, unwind) = match abi {
+        Abi::Rust => ("Rust", false),
+        Abi::C { unwind } => ("C", *unwind),
+        Abi::Cdecl { unwind } => ("cdecl", *unwind),
+        Abi::Stdcall { unwind } => ("stdcall", *unwind),
+        Abi::Fastcall { unwind } => ("fastcall", *unwind),
+        Abi::Aapcs { unwind } => ("aapcs", *unwind),
+        Abi::Win64 { unwind } => ("win64", *unwind),
+        Abi::SysV64 { unwind } => ("sysv64", *unwind),
+        Abi::System { unwind } => ("system", *unwind),
+        Abi::Other(name) => return format!("{name:?}"),
+    };
+    if unwind { format!("\"{name}-unwind\"") } else { format!("\"{name}\"") }
+}
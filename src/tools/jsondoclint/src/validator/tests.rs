@@ -174,6 +174,7 @@ fn errors_on_missing_path() {
                         kind: StructKind::Unit,
                         generics: generics.clone(),
                         impls: vec![],
+                        layout: None,
                     }),
                 },
             ),
@@ -257,6 +257,11 @@ fn addr_from_alloc_id(
                 let base_addr =
                     self.addr_from_alloc_id_uncached(global_state, alloc_id, memory_kind)?;
                 trace!("Assigning base address {:#x} to allocation {:?}", base_addr, alloc_id);
+                // With a native library, addresses come from the host allocator and cannot be
+                // expected to match a recording.
+                if this.machine.native_lib.is_none() {
+                    this.machine.recorder.alloc_address(base_addr);
+                }
 
                 // Store address in cache.
                 global_state.base_addr.try_insert(alloc_id, base_addr).unwrap();
@@ -432,6 +437,22 @@ fn ptr_get_alloc(
         Some((alloc_id, Size::from_bytes(rel_offset)))
     }
 
+    /// Returns the live allocation that contains `addr`, and the offset of `addr` in it. Unlike
+    /// an int2ptr cast, this considers all allocations, not just the exposed ones, so it must only
+    /// be used to inspect memory from outside the program (e.g. by a debugger).
+    fn alloc_containing_addr(&self, addr: u64) -> Option<(AllocId, Size)> {
+        let this = self.eval_context_ref();
+        let global_state = this.machine.alloc_addresses.borrow();
+        global_state.base_addr.iter().find_map(|(&alloc_id, &base_addr)| {
+            let offset = addr.checked_sub(base_addr)?;
+            if !this.is_alloc_live(alloc_id) || offset >= this.get_alloc_info(alloc_id).size.bytes()
+            {
+                return None;
+            }
+            Some((alloc_id, Size::from_bytes(offset)))
+        })
+    }
+
     /// Prepare all exposed memory for a native call.
     /// This overapproximates the modifications which external code might make to memory:
     /// We set all reachable allocations as initialized, mark all reachable provenances as exposed
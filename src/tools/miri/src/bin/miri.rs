@@ -27,7 +27,7 @@
 use std::env::{self, VarError};
 use std::num::NonZero;
 use std::ops::Range;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicI32, AtomicU32, Ordering};
 use std::sync::{Arc, Once};
@@ -60,6 +60,8 @@
 struct MiriCompilerCalls {
     miri_config: Option<MiriConfig>,
     many_seeds: Option<ManySeedsConfig>,
+    /// The preemption bound of schedule exploration, if enabled.
+    explore_schedules: Option<u32>,
 }
 
 struct ManySeedsConfig {
@@ -68,8 +70,12 @@ struct ManySeedsConfig {
 }
 
 impl MiriCompilerCalls {
-    fn new(miri_config: MiriConfig, many_seeds: Option<ManySeedsConfig>) -> Self {
-        Self { miri_config: Some(miri_config), many_seeds }
+    fn new(
+        miri_config: MiriConfig,
+        many_seeds: Option<ManySeedsConfig>,
+        explore_schedules: Option<u32>,
+    ) -> Self {
+        Self { miri_config: Some(miri_config), many_seeds, explore_schedules }
     }
 }
 
@@ -205,6 +211,34 @@ fn after_analysis<'tcx>(
                 eprintln!("{num_failed}/{total} SEEDS FAILED", total = many_seeds.seeds.count());
             }
             std::process::exit(exit_code.0.into_inner());
+        } else if let Some(preemption_bound) = self.explore_schedules.take() {
+            assert!(config.fixed_schedule.is_none());
+            let mut explorer = miri::ScheduleExplorer::new(preemption_bound);
+            let mut num_explored = 0u64;
+            let mut diverged = false;
+            while let Some(schedule) = explorer.next_schedule() {
+                let mut config = config.clone();
+                config.fixed_schedule = Some(schedule.clone());
+                let (return_code, trace) =
+                    miri::eval_entry_with_schedule_trace(tcx, entry_def_id, entry_type, config);
+                let return_code = return_code.unwrap_or(rustc_driver::EXIT_FAILURE);
+                if return_code != rustc_driver::EXIT_SUCCESS {
+                    eprintln!("FAILING SCHEDULE: {schedule}");
+                    eprintln!("note: pass `-Zmiri-fixed-schedule={schedule}` to replay it");
+                    std::process::exit(return_code);
+                }
+                num_explored += 1;
+                let trace = trace.expect("executions following a fixed schedule have a trace");
+                diverged |= trace.diverged;
+                explorer.add_trace(trace.points);
+            }
+            if diverged {
+                eprintln!(
+                    "warning: the program does not behave deterministically, so some schedules may not have been explored"
+                );
+            }
+            eprintln!("{num_explored} SCHEDULES EXPLORED");
+            std::process::exit(rustc_driver::EXIT_SUCCESS);
         } else {
             let return_code = miri::eval_entry(tcx, entry_def_id, entry_type, config)
                 .unwrap_or_else(|| {
@@ -504,6 +538,7 @@ fn main() {
     // Parse our arguments and split them across `rustc` and `miri`.
     let mut many_seeds: Option<Range<u32>> = None;
     let mut many_seeds_keep_going = false;
+    let mut explore_schedules: Option<u32> = None;
     let mut miri_config = MiriConfig::default();
     miri_config.env = env_snapshot;
 
@@ -596,6 +631,35 @@ fn main() {
             many_seeds = Some(0..64);
         } else if arg == "-Zmiri-many-seeds-keep-going" {
             many_seeds_keep_going = true;
+        } else if arg == "-Zmiri-explore-schedules" {
+            explore_schedules = Some(2);
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-explore-schedules=") {
+            let bound = param.parse::<u32>().unwrap_or_else(|err| {
+                show_error!("-Zmiri-explore-schedules requires a `u32`: {}", err)
+            });
+            explore_schedules = Some(bound);
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-fixed-schedule=") {
+            let schedule = param.parse::<miri::Schedule>().unwrap_or_else(|err| {
+                show_error!("-Zmiri-fixed-schedule requires a comma separated list of `point:thread` pairs: {err}")
+            });
+            miri_config.fixed_schedule = Some(schedule);
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-record=") {
+            miri_config.record = Some(param.into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-replay=") {
+            let recording = miri::Recording::load(Path::new(param)).unwrap_or_else(|err| {
+                show_error!("-Zmiri-replay could not load the recording `{param}`: {err}")
+            });
+            miri_config.replay = Some(recording);
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-coverage=") {
+            miri_config.coverage = Some(param.into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-heap-profile=") {
+            miri_config.heap_profile = Some(param.into());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-gdbserver=") {
+            miri_config.gdbserver = Some(param.to_owned());
+        } else if let Some(param) = arg.strip_prefix("-Zmiri-vfs=") {
+            let snapshot = miri::VfsSnapshot::load(Path::new(param))
+                .unwrap_or_else(|err| show_error!("-Zmiri-vfs could not load `{param}`: {err}"));
+            miri_config.vfs = Some(snapshot);
         } else if let Some(param) = arg.strip_prefix("-Zmiri-env-forward=") {
             miri_config.forwarded_env_vars.push(param.to_owned());
         } else if let Some(param) = arg.strip_prefix("-Zmiri-env-set=") {
@@ -713,11 +777,71 @@ fn main() {
     if miri_config.native_lib.is_some() && miri_config.provenance_mode == ProvenanceMode::Strict {
         show_error!("strict provenance is not compatible with calling native functions");
     }
+    // The virtual filesystem replaces the host filesystem under isolation only.
+    if miri_config.vfs.is_some() && miri_config.isolated_op == miri::IsolatedOp::Allow {
+        show_error!("`-Zmiri-vfs` cannot be combined with `-Zmiri-disable-isolation`");
+    }
     // You can set either one seed or many.
     if many_seeds.is_some() && miri_config.seed.is_some() {
         show_error!("Only one of `-Zmiri-seed` and `-Zmiri-many-seeds can be set");
     }
 
+    // Schedule exploration runs the program once per schedule, so it cannot be combined with
+    // other ways of picking the schedule.
+    if explore_schedules.is_some() && many_seeds.is_some() {
+        show_error!("Only one of `-Zmiri-explore-schedules` and `-Zmiri-many-seeds` can be set");
+    }
+    if explore_schedules.is_some() && miri_config.fixed_schedule.is_some() {
+        show_error!(
+            "Only one of `-Zmiri-explore-schedules` and `-Zmiri-fixed-schedule` can be set"
+        );
+    }
+    // Schedules only describe an execution if there are no random preemptions.
+    if explore_schedules.is_some() || miri_config.fixed_schedule.is_some() {
+        miri_config.preemption_rate = 0.0;
+    }
+
+    // A recording describes a single execution.
+    if miri_config.record.is_some() || miri_config.replay.is_some() {
+        if many_seeds.is_some() || explore_schedules.is_some() {
+            show_error!(
+                "`-Zmiri-record` and `-Zmiri-replay` cannot be combined with `-Zmiri-many-seeds` or `-Zmiri-explore-schedules`"
+            );
+        }
+        if miri_config.record.is_some() && miri_config.replay.is_some() {
+            show_error!("Only one of `-Zmiri-record` and `-Zmiri-replay` can be set");
+        }
+    }
+    // A coverage report describes a single execution.
+    if miri_config.coverage.is_some() && (many_seeds.is_some() || explore_schedules.is_some()) {
+        show_error!(
+            "`-Zmiri-coverage` cannot be combined with `-Zmiri-many-seeds` or `-Zmiri-explore-schedules`"
+        );
+    }
+    // A heap profile describes a single execution.
+    if miri_config.heap_profile.is_some() && (many_seeds.is_some() || explore_schedules.is_some()) {
+        show_error!(
+            "`-Zmiri-heap-profile` cannot be combined with `-Zmiri-many-seeds` or `-Zmiri-explore-schedules`"
+        );
+    }
+    // A debugging session is a single execution.
+    if miri_config.gdbserver.is_some() && (many_seeds.is_some() || explore_schedules.is_some()) {
+        show_error!(
+            "`-Zmiri-gdbserver` cannot be combined with `-Zmiri-many-seeds` or `-Zmiri-explore-schedules`"
+        );
+    }
+    // A replay runs with the seed and environment of the recorded execution.
+    if let Some(recording) = &miri_config.replay {
+        if miri_config.seed.is_some_and(|seed| seed != recording.seed) {
+            show_error!(
+                "`-Zmiri-seed` conflicts with the seed {} of the recording passed to `-Zmiri-replay`",
+                recording.seed
+            );
+        }
+        miri_config.seed = Some(recording.seed);
+        miri_config.env = recording.env.clone();
+    }
+
     // Ensure we have parallelism for many-seeds mode.
     if many_seeds.is_some() && !rustc_args.iter().any(|arg| arg.starts_with("-Zthreads=")) {
         // Clamp to 20 threads; things get a less efficient beyond that due to lock contention.
@@ -729,5 +853,8 @@ fn main() {
 
     debug!("rustc arguments: {:?}", rustc_args);
     debug!("crate arguments: {:?}", miri_config.args);
-    run_compiler_and_exit(&rustc_args, &mut MiriCompilerCalls::new(miri_config, many_seeds))
+    run_compiler_and_exit(
+        &rustc_args,
+        &mut MiriCompilerCalls::new(miri_config, many_seeds, explore_schedules),
+    )
 }
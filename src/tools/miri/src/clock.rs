@@ -1,5 +1,6 @@
 use std::cell::Cell;
-use std::time::{Duration, Instant as StdInstant};
+use std::rc::Rc;
+use std::time::{Duration, Instant as StdInstant, SystemTime};
 
 /// When using a virtual clock, this defines how many nanoseconds we pretend are passing for each
 /// basic block.
@@ -64,6 +65,8 @@ pub fn duration_since(&self, earlier: Instant) -> Duration {
 #[derive(Debug)]
 pub struct Clock {
     kind: ClockKind,
+    /// Records or replays the readings of the host clock.
+    recorder: Rc<crate::Recorder>,
 }
 
 #[derive(Debug)]
@@ -81,14 +84,14 @@ enum ClockKind {
 
 impl Clock {
     /// Create a new clock based on the availability of communication with the host.
-    pub fn new(communicate: bool) -> Self {
+    pub fn new(communicate: bool, recorder: Rc<crate::Recorder>) -> Self {
         let kind = if communicate {
             ClockKind::Host { epoch: StdInstant::now() }
         } else {
             ClockKind::Virtual { nanoseconds: 0.into() }
         };
 
-        Self { kind }
+        Self { kind, recorder }
     }
 
     /// Let the time pass for a small interval.
@@ -128,9 +131,19 @@ pub fn epoch(&self) -> Instant {
 
     pub fn now(&self) -> Instant {
         match &self.kind {
-            ClockKind::Host { .. } => Instant { kind: InstantKind::Host(StdInstant::now()) },
+            ClockKind::Host { epoch } => {
+                let elapsed =
+                    self.recorder.monotonic_clock(|| StdInstant::now().duration_since(*epoch));
+                Instant { kind: InstantKind::Host(*epoch + elapsed) }
+            }
             ClockKind::Virtual { nanoseconds } =>
                 Instant { kind: InstantKind::Virtual { nanoseconds: nanoseconds.get() } },
         }
     }
+
+    /// Return the current time of the host's system clock. This is not virtualized, so it must
+    /// only be used when communication with the host is enabled.
+    pub fn system_time(&self) -> SystemTime {
+        self.recorder.system_time(SystemTime::now)
+    }
 }
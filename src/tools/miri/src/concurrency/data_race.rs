@@ -830,7 +830,8 @@ fn atomic_compare_exchange_scalar(
         let success_rate = 1.0 - this.machine.cmpxchg_weak_failure_rate;
         let cmpxchg_success = eq.to_scalar().to_bool()?
             && if can_fail_spuriously {
-                this.machine.rng.get_mut().random_bool(success_rate)
+                let success = this.machine.rng.get_mut().random_bool(success_rate);
+                this.machine.recorder.cmpxchg_weak(success)
             } else {
                 true
             };
@@ -859,6 +860,7 @@ fn atomic_compare_exchange_scalar(
     /// Update the data-race detector for an atomic fence on the current thread.
     fn atomic_fence(&mut self, atomic: AtomicFenceOrd) -> InterpResult<'tcx> {
         let this = self.eval_context_mut();
+        this.machine.threads.visible_operation();
         let current_span = this.machine.current_span();
         if let Some(data_race) = &mut this.machine.data_race {
             data_race.maybe_perform_sync_operation(
@@ -923,6 +925,7 @@ fn allow_data_races_all_threads_done(&mut self) {
     /// The closure will only be invoked if data race handling is on.
     fn release_clock<R>(&self, callback: impl FnOnce(&VClock) -> R) -> Option<R> {
         let this = self.eval_context_ref();
+        this.machine.threads.visible_operation();
         Some(this.machine.data_race.as_ref()?.release_clock(&this.machine.threads, callback))
     }
 
@@ -930,6 +933,7 @@ fn release_clock<R>(&self, callback: impl FnOnce(&VClock) -> R) -> Option<R> {
     /// the moment when that clock snapshot was taken via `release_clock`.
     fn acquire_clock(&self, clock: &VClock) {
         let this = self.eval_context_ref();
+        this.machine.threads.visible_operation();
         if let Some(data_race) = &this.machine.data_race {
             data_race.acquire_clock(clock, &this.machine.threads);
         }
@@ -1394,6 +1398,8 @@ fn atomic_access_check(
                 }
             }
         }
+        // Other threads can observe this access, so a fixed schedule may preempt us after it.
+        this.machine.threads.visible_operation();
         interp_ok(())
     }
 
@@ -2,6 +2,7 @@
 pub mod data_race;
 pub mod init_once;
 mod range_object_map;
+pub mod schedule;
 pub mod sync;
 pub mod thread;
 mod vector_clock;
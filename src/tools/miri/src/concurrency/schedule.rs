@@ -0,0 +1,270 @@
+//! Systematic exploration of thread interleavings.
+//!
+//! Without preemption, Miri's scheduler runs the active thread until it blocks, yields or
+//! terminates, and then picks the next enabled thread in round-robin order. A schedule describes
+//! an execution by the scheduling decisions that deviate from this default policy. The only points
+//! where such deviations are allowed are right after *visible operations*, i.e. atomic accesses,
+//! fences and operations on synchronization objects, and whenever the active thread stops running.
+//! Switching threads anywhere else cannot lead to a new behavior, as non-atomic accesses that are
+//! not ordered by synchronization are data races and thus reported independently of the schedule.
+//!
+//! Exploration is stateless: the program is executed once per schedule, from the beginning. Every
+//! execution records the scheduling points after its last deviation, and every alternative choice
+//! at those points leads to a new schedule to explore. This is bounded by the number of
+//! preemptions, i.e. deviations that switch away from an active thread that could keep going, as
+//! most concurrency bugs only need very few preemptions to show up.
+
+use std::cell::Cell;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::*;
+
+/// A scheduling decision that deviates from the default scheduling policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deviation {
+    /// The index of the scheduling point, counting all points where there was a choice.
+    point: u64,
+    /// The thread to run at that point.
+    thread: ThreadId,
+}
+
+/// A schedule, given as the list of its deviations from the default scheduling policy, ordered by
+/// their point. It is written as comma-separated `point:thread` pairs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schedule(Vec<Deviation>);
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut deviations = Vec::new();
+        for deviation in s.split(',').filter(|deviation| !deviation.is_empty()) {
+            let Some((point, thread)) = deviation.split_once(':') else {
+                return Err(format!("`{deviation}` is not of the form `point:thread`"));
+            };
+            let point = point.parse::<u64>().map_err(|err| format!("`{point}`: {err}"))?;
+            let thread = thread.parse::<u32>().map_err(|err| format!("`{thread}`: {err}"))?;
+            if deviations.last().is_some_and(|last: &Deviation| last.point >= point) {
+                return Err(format!("the points must be strictly increasing, but {point} is not"));
+            }
+            deviations.push(Deviation { point, thread: ThreadId::new_unchecked(thread) });
+        }
+        Ok(Schedule(deviations))
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, deviation) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}:{}", deviation.point, deviation.thread.to_u32())?;
+        }
+        Ok(())
+    }
+}
+
+/// A point of an execution where the scheduler had a choice between several threads.
+#[derive(Debug, Clone)]
+pub struct SchedulingPoint {
+    /// The index of the point.
+    point: u64,
+    /// The threads that could have been run instead of the one picked by the default policy.
+    alternatives: Vec<ThreadId>,
+    /// Whether running one of the alternatives preempts the active thread.
+    is_preemption: bool,
+}
+
+/// The scheduling points of an execution that followed a fixed schedule.
+#[derive(Debug, Default)]
+pub struct ScheduleTrace {
+    /// The scheduling points after the last deviation of the schedule.
+    pub points: Vec<SchedulingPoint>,
+    /// Whether the schedule could not be followed, because a thread it picked was not enabled.
+    /// This happens when the program does not behave deterministically.
+    pub diverged: bool,
+}
+
+/// Makes the scheduler of an execution follow a fixed schedule.
+#[derive(Debug)]
+pub struct FixedSchedule {
+    schedule: Schedule,
+    /// The index of the next deviation of the schedule.
+    next_deviation: usize,
+    /// The index of the next scheduling point.
+    next_point: u64,
+    /// Whether a visible operation happened since the last scheduling decision.
+    visible_operation: Cell<bool>,
+    trace: ScheduleTrace,
+}
+
+impl FixedSchedule {
+    pub fn new(schedule: Schedule) -> Self {
+        FixedSchedule {
+            schedule,
+            next_deviation: 0,
+            next_point: 0,
+            visible_operation: Cell::new(false),
+            trace: ScheduleTrace::default(),
+        }
+    }
+
+    /// Records that the active thread performed a visible operation, so it may be preempted.
+    pub fn visible_operation(&self) {
+        self.visible_operation.set(true);
+    }
+
+    /// Returns whether the active thread may be preempted now.
+    pub fn take_visible_operation(&self) -> bool {
+        self.visible_operation.replace(false)
+    }
+
+    /// Decides which thread to run at a scheduling point: either `default`, which is what the
+    /// default policy picks, or one of the `alternatives`.
+    pub fn decide(
+        &mut self,
+        default: ThreadId,
+        alternatives: Vec<ThreadId>,
+        is_preemption: bool,
+    ) -> ThreadId {
+        let point = self.next_point;
+        self.next_point += 1;
+        if let Some(deviation) = self.schedule.0.get(self.next_deviation) {
+            if deviation.point == point {
+                self.next_deviation += 1;
+                if alternatives.contains(&deviation.thread) {
+                    return deviation.thread;
+                }
+                self.trace.diverged = true;
+            }
+            return default;
+        }
+        self.trace.points.push(SchedulingPoint { point, alternatives, is_preemption });
+        default
+    }
+
+    pub fn into_trace(self) -> ScheduleTrace {
+        self.trace
+    }
+}
+
+/// A schedule that has been executed, with the scheduling points where it can be extended.
+struct ExploredSchedule {
+    schedule: Schedule,
+    preemptions: u32,
+    trace: Vec<SchedulingPoint>,
+    /// The next point of `trace` and alternative at that point to explore.
+    next: (usize, usize),
+}
+
+/// Enumerates the schedules of a program, in depth-first order.
+#[derive(Default)]
+pub struct ScheduleExplorer {
+    /// The maximum number of preemptions of a schedule.
+    preemption_bound: u32,
+    stack: Vec<ExploredSchedule>,
+    /// The schedule returned by `next_schedule`, until its trace is added.
+    current: Option<(Schedule, u32)>,
+    started: bool,
+}
+
+impl ScheduleExplorer {
+    pub fn new(preemption_bound: u32) -> Self {
+        ScheduleExplorer { preemption_bound, ..Default::default() }
+    }
+
+    /// Returns the next schedule to execute, or `None` if all schedules have been explored. The
+    /// trace of each execution must be passed to `add_trace` before calling this again.
+    pub fn next_schedule(&mut self) -> Option<Schedule> {
+        assert!(self.current.is_none(), "the trace of the last schedule is missing");
+        if !self.started {
+            self.started = true;
+            self.current = Some((Schedule::default(), 0));
+            return Some(Schedule::default());
+        }
+        while let Some(explored) = self.stack.last_mut() {
+            let (point_idx, alternative_idx) = &mut explored.next;
+            let Some(point) = explored.trace.get(*point_idx) else {
+                self.stack.pop();
+                continue;
+            };
+            let Some(&thread) = point.alternatives.get(*alternative_idx) else {
+                *point_idx += 1;
+                *alternative_idx = 0;
+                continue;
+            };
+            *alternative_idx += 1;
+            let preemptions = explored.preemptions + u32::from(point.is_preemption);
+            if preemptions > self.preemption_bound {
+                continue;
+            }
+            let mut schedule = explored.schedule.clone();
+            schedule.0.push(Deviation { point: point.point, thread });
+            self.current = Some((schedule.clone(), preemptions));
+            return Some(schedule);
+        }
+        None
+    }
+
+    /// Adds the scheduling points of the execution of the schedule last returned by `next_schedule`.
+    pub fn add_trace(&mut self, trace: Vec<SchedulingPoint>) {
+        let (schedule, preemptions) =
+            self.current.take().expect("`add_trace` called without a schedule");
+        self.stack.push(ExploredSchedule { schedule, preemptions, trace, next: (0, 0) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(point: u64, alternatives: &[u32], is_preemption: bool) -> SchedulingPoint {
+        let alternatives = alternatives.iter().map(|&id| ThreadId::new_unchecked(id)).collect();
+        SchedulingPoint { point, alternatives, is_preemption }
+    }
+
+    #[test]
+    fn parse_and_print() {
+        let schedule = "3:1,17:0".parse::<Schedule>().unwrap();
+        assert_eq!(schedule.to_string(), "3:1,17:0");
+        assert_eq!("".parse::<Schedule>().unwrap(), Schedule::default());
+        assert!("3".parse::<Schedule>().is_err());
+        assert!("3:x".parse::<Schedule>().is_err());
+        assert!("3:1,2:0".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn follow() {
+        let thread = ThreadId::new_unchecked;
+        let mut fixed = FixedSchedule::new("1:2".parse().unwrap());
+        assert_eq!(fixed.decide(thread(0), vec![thread(1)], true), thread(0));
+        assert_eq!(fixed.decide(thread(1), vec![thread(2)], false), thread(2));
+        assert_eq!(fixed.decide(thread(2), vec![thread(0)], true), thread(2));
+        let trace = fixed.into_trace();
+        assert!(!trace.diverged);
+        // Only the points after the last deviation are recorded.
+        assert_eq!(trace.points.len(), 1);
+        assert_eq!(trace.points[0].point, 2);
+
+        let mut fixed = FixedSchedule::new("0:3".parse().unwrap());
+        assert_eq!(fixed.decide(thread(0), vec![thread(1)], true), thread(0));
+        assert!(fixed.into_trace().diverged);
+    }
+
+    #[test]
+    fn explore() {
+        let mut explorer = ScheduleExplorer::new(1);
+        assert_eq!(explorer.next_schedule().unwrap().to_string(), "");
+        explorer.add_trace(vec![point(0, &[1], true), point(1, &[1], true)]);
+        assert_eq!(explorer.next_schedule().unwrap().to_string(), "0:1");
+        // The preemption bound is reached, but switching threads at other points is still allowed.
+        explorer.add_trace(vec![point(1, &[0], true), point(2, &[2], false)]);
+        assert_eq!(explorer.next_schedule().unwrap().to_string(), "0:1,2:2");
+        explorer.add_trace(vec![]);
+        assert_eq!(explorer.next_schedule().unwrap().to_string(), "1:1");
+        explorer.add_trace(vec![point(2, &[0], true)]);
+        assert!(explorer.next_schedule().is_none());
+    }
+}
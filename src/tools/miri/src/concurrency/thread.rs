@@ -16,6 +16,7 @@
 use rustc_span::Span;
 
 use crate::concurrency::data_race;
+use crate::concurrency::schedule::{FixedSchedule, Schedule, ScheduleTrace};
 use crate::shims::tls;
 use crate::*;
 
@@ -109,6 +110,10 @@ pub enum BlockReason {
     Eventfd,
     /// Blocked on unnamed_socket.
     UnnamedSocket,
+    /// Blocked on a socket.
+    Socket,
+    /// Blocked in `poll` or `select`.
+    Poll,
 }
 
 /// The state of a thread.
@@ -351,7 +356,7 @@ fn get_wait_time(&self, clock: &Clock) -> Duration {
         match self {
             Timeout::Monotonic(instant) => instant.duration_since(clock.now()),
             Timeout::RealTime(time) =>
-                time.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO),
+                time.duration_since(clock.system_time()).unwrap_or(Duration::ZERO),
         }
     }
 
@@ -401,6 +406,8 @@ pub struct ThreadManager<'tcx> {
     thread_local_allocs: FxHashMap<(DefId, ThreadId), StrictPointer>,
     /// A flag that indicates that we should change the active thread.
     yield_active_thread: bool,
+    /// The schedule to follow instead of the default scheduling policy, if any.
+    schedule: Option<FixedSchedule>,
 }
 
 impl VisitProvenance for ThreadManager<'_> {
@@ -410,6 +417,7 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             thread_local_allocs,
             active_thread: _,
             yield_active_thread: _,
+            schedule: _,
         } = self;
 
         for thread in threads {
@@ -431,6 +439,7 @@ fn default() -> Self {
             threads,
             thread_local_allocs: Default::default(),
             yield_active_thread: false,
+            schedule: None,
         }
     }
 }
@@ -495,6 +504,7 @@ pub fn all_stacks(
     fn create_thread(&mut self, on_stack_empty: StackEmptyCallback<'tcx>) -> ThreadId {
         let new_thread_id = ThreadId::new(self.threads.len());
         self.threads.push(Thread::new(None, Some(on_stack_empty)));
+        self.visible_operation();
         new_thread_id
     }
 
@@ -509,6 +519,24 @@ fn set_active_thread_id(&mut self, id: ThreadId) -> ThreadId {
         std::mem::replace(&mut self.active_thread, id)
     }
 
+    /// Makes the scheduler follow the given schedule, and record its scheduling points.
+    pub(crate) fn follow_schedule(&mut self, schedule: Schedule) {
+        self.schedule = Some(FixedSchedule::new(schedule));
+    }
+
+    /// Returns the scheduling points recorded while following a fixed schedule.
+    pub(crate) fn take_schedule_trace(&mut self) -> Option<ScheduleTrace> {
+        self.schedule.take().map(FixedSchedule::into_trace)
+    }
+
+    /// Records that the active thread performed an operation that can be observed by other
+    /// threads, so a fixed schedule may preempt it.
+    pub(crate) fn visible_operation(&self) {
+        if let Some(schedule) = &self.schedule {
+            schedule.visible_operation();
+        }
+    }
+
     /// Get the id of the currently active thread.
     pub fn active_thread(&self) -> ThreadId {
         self.active_thread
@@ -526,7 +554,7 @@ pub fn get_live_thread_count(&self) -> usize {
     }
 
     /// Has the given thread terminated?
-    fn has_terminated(&self, thread_id: ThreadId) -> bool {
+    pub fn has_terminated(&self, thread_id: ThreadId) -> bool {
         self.threads[thread_id].state.is_terminated()
     }
 
@@ -590,6 +618,7 @@ fn join_thread(
         // Mark the joined thread as being joined so that we detect if other
         // threads try to join it.
         self.threads[joined_thread_id].join_status = ThreadJoinStatus::Joined;
+        self.visible_operation();
         if !self.threads[joined_thread_id].state.is_terminated() {
             trace!(
                 "{:?} blocked on {:?} when trying to join",
@@ -696,15 +725,43 @@ fn next_callback_wait_time(&self, clock: &Clock) -> Option<Duration> {
             .min()
     }
 
+    /// Returns the enabled threads other than the active thread, in round-robin order.
+    fn enabled_threads_after_active(&self) -> impl Iterator<Item = ThreadId> {
+        // Crucially, we start searching at the current active thread ID, rather than at 0, since we
+        // want to avoid always scheduling threads 0 and 1 without ever making progress in thread 2.
+        //
+        // `skip(N)` means we start iterating at thread N, so we skip 1 more to start just *after*
+        // the active thread. Then after that we look at `take(N)`, i.e., the threads *before* the
+        // active thread.
+        self.threads
+            .iter_enumerated()
+            .skip(self.active_thread.index() + 1)
+            .chain(self.threads.iter_enumerated().take(self.active_thread.index()))
+            .filter(|(_, thread)| thread.state.is_enabled())
+            .map(|(id, _)| id)
+    }
+
     /// Decide which action to take next and on which thread.
     ///
     /// The currently implemented scheduling policy is the one that is commonly
     /// used in stateless model checkers such as Loom: run the active thread as
     /// long as we can and switch only when we have to (the active thread was
-    /// blocked, terminated, or has explicitly asked to be preempted).
+    /// blocked, terminated, or has explicitly asked to be preempted). A fixed
+    /// schedule can deviate from this, see the `schedule` module.
     fn schedule(&mut self, clock: &Clock) -> InterpResult<'tcx, SchedulingAction> {
         // This thread and the program can keep going.
         if self.threads[self.active_thread].state.is_enabled() && !self.yield_active_thread {
+            // A fixed schedule may preempt the active thread after a visible operation.
+            if self.schedule.as_ref().is_some_and(FixedSchedule::take_visible_operation) {
+                let alternatives = self.enabled_threads_after_active().collect::<Vec<_>>();
+                if !alternatives.is_empty() {
+                    let schedule = self.schedule.as_mut().unwrap();
+                    let thread = schedule.decide(self.active_thread, alternatives, true);
+                    if thread != self.active_thread {
+                        self.set_active_thread_id(thread);
+                    }
+                }
+            }
             // The currently active thread is still enabled, just continue with it.
             return interp_ok(SchedulingAction::ExecuteStep);
         }
@@ -720,28 +777,20 @@ fn schedule(&mut self, clock: &Clock) -> InterpResult<'tcx, SchedulingAction> {
         }
         // No callbacks immediately scheduled, pick a regular thread to execute.
         // The active thread blocked or yielded. So we go search for another enabled thread.
-        // Crucially, we start searching at the current active thread ID, rather than at 0, since we
-        // want to avoid always scheduling threads 0 and 1 without ever making progress in thread 2.
-        //
-        // `skip(N)` means we start iterating at thread N, so we skip 1 more to start just *after*
-        // the active thread. Then after that we look at `take(N)`, i.e., the threads *before* the
-        // active thread.
-        let threads = self
-            .threads
-            .iter_enumerated()
-            .skip(self.active_thread.index() + 1)
-            .chain(self.threads.iter_enumerated().take(self.active_thread.index()));
-        for (id, thread) in threads {
-            debug_assert_ne!(self.active_thread, id);
-            if thread.state.is_enabled() {
-                info!(
-                    "---------- Now executing on thread `{}` (previous: `{}`) ----------------------------------------",
-                    self.get_thread_display_name(id),
-                    self.get_thread_display_name(self.active_thread)
-                );
-                self.active_thread = id;
-                break;
+        if let Some(schedule) = &self.schedule {
+            // The last visible operation of the active thread no longer matters.
+            schedule.take_visible_operation();
+        }
+        let mut threads = self.enabled_threads_after_active().collect::<Vec<_>>();
+        if !threads.is_empty() {
+            let mut id = threads.remove(0);
+            // A fixed schedule may pick any of the enabled threads.
+            if let Some(schedule) = &mut self.schedule
+                && !threads.is_empty()
+            {
+                id = schedule.decide(id, threads, false);
             }
+            self.set_active_thread_id(id);
         }
         self.yield_active_thread = false;
         if self.threads[self.active_thread].state.is_enabled() {
@@ -1001,7 +1050,7 @@ fn block_thread(
                     );
                     Timeout::RealTime(match anchor {
                         TimeoutAnchor::Absolute => SystemTime::UNIX_EPOCH,
-                        TimeoutAnchor::Relative => SystemTime::now(),
+                        TimeoutAnchor::Relative => this.machine.clock.system_time(),
                     })
                 }
                 TimeoutClock::Monotonic =>
@@ -1138,7 +1187,8 @@ fn maybe_preempt_active_thread(&mut self) {
         use rand::Rng as _;
 
         let this = self.eval_context_mut();
-        if this.machine.rng.get_mut().random_bool(this.machine.preemption_rate) {
+        let preempt = this.machine.rng.get_mut().random_bool(this.machine.preemption_rate);
+        if this.machine.recorder.preemption(this.machine.basic_block_count, preempt) {
             this.yield_active_thread();
         }
     }
@@ -1154,6 +1204,7 @@ fn run_threads(&mut self) -> InterpResult<'tcx, !> {
             }
             match this.machine.threads.schedule(&this.machine.clock)? {
                 SchedulingAction::ExecuteStep => {
+                    this.gdbserver_before_step()?;
                     if !this.step()? {
                         // See if this thread can do something else.
                         match this.run_on_stack_empty()? {
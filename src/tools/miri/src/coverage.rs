@@ -0,0 +1,349 @@
+//! Coverage of the interpreted code (`-Zmiri-coverage`).
+//!
+//! We count how often the terminator of each basic block of the local crates is executed, per
+//! function instance. At the end of the execution, the counts are mapped to the source spans of the
+//! statements of each block and written to a file: as an lcov tracefile, or in the JSON format of
+//! `llvm-cov export` if the file name ends in `.json`. Functions of the crate being run that were
+//! never executed are reported with a count of zero, so untested code shows up as well.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir::def::DefKind;
+use rustc_index::IndexVec;
+use rustc_middle::mir;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_span::FileName;
+use rustc_span::hygiene::walk_chain;
+
+/// The execution counts of the basic blocks of a function instance.
+struct BlockCounts<'tcx> {
+    body: &'tcx mir::Body<'tcx>,
+    counts: IndexVec<mir::BasicBlock, u64>,
+}
+
+/// The coverage collected during an execution.
+pub struct Coverage<'tcx> {
+    /// The file the report is written to.
+    path: PathBuf,
+    instances: FxHashMap<ty::Instance<'tcx>, BlockCounts<'tcx>>,
+}
+
+impl<'tcx> Coverage<'tcx> {
+    pub fn new(path: PathBuf) -> Self {
+        Coverage { path, instances: FxHashMap::default() }
+    }
+
+    /// Counts an execution of the terminator of `block`.
+    pub fn record(
+        &mut self,
+        instance: ty::Instance<'tcx>,
+        body: &'tcx mir::Body<'tcx>,
+        block: mir::BasicBlock,
+    ) {
+        let entry = self.instances.entry(instance).or_insert_with(|| {
+            BlockCounts { body, counts: IndexVec::from_elem_n(0, body.basic_blocks.len()) }
+        });
+        entry.counts[block] += 1;
+    }
+
+    /// Writes the report.
+    pub fn finish(&self, tcx: TyCtxt<'tcx>) {
+        let functions = self.functions(tcx);
+        let report = if self.path.extension().is_some_and(|ext| ext == "json") {
+            llvm_cov_json(&functions)
+        } else {
+            lcov(&functions)
+        };
+        if let Err(err) = fs::write(&self.path, report) {
+            tcx.dcx().err(format!(
+                "failed to write the coverage report to `{}`: {err}",
+                self.path.display()
+            ));
+        }
+    }
+
+    /// Maps the counts to the source code, including the functions that were never executed.
+    fn functions(&self, tcx: TyCtxt<'tcx>) -> Vec<Function> {
+        let mut functions = Vec::new();
+        let mut executed = FxHashSet::default();
+        for (instance, blocks) in &self.instances {
+            executed.insert(instance.def_id());
+            functions.extend(Function::new(tcx, instance.to_string(), blocks.body, &blocks.counts));
+        }
+        for &def_id in tcx.mir_keys(()) {
+            let def_id = def_id.to_def_id();
+            if executed.contains(&def_id)
+                || !matches!(
+                    tcx.def_kind(def_id),
+                    DefKind::Fn | DefKind::AssocFn | DefKind::Closure
+                )
+            {
+                continue;
+            }
+            let body = tcx.optimized_mir(def_id);
+            let counts = IndexVec::from_elem_n(0, body.basic_blocks.len());
+            functions.extend(Function::new(tcx, tcx.def_path_str(def_id), body, &counts));
+        }
+        functions.sort_by(|a, b| (&a.file, a.line, &a.name).cmp(&(&b.file, b.line, &b.name)));
+        functions
+    }
+}
+
+/// A source region executed by a basic block. Lines and columns start at 1.
+#[derive(Debug)]
+struct Region {
+    start: (usize, usize),
+    end: (usize, usize),
+    count: u64,
+}
+
+/// A function as it appears in the report.
+#[derive(Debug)]
+struct Function {
+    name: String,
+    file: String,
+    /// The line the function starts at.
+    line: usize,
+    /// How often the function was called.
+    count: u64,
+    regions: Vec<Region>,
+}
+
+impl Function {
+    /// Returns `None` if the function does not come from a source file.
+    fn new<'tcx>(
+        tcx: TyCtxt<'tcx>,
+        name: String,
+        body: &'tcx mir::Body<'tcx>,
+        counts: &IndexVec<mir::BasicBlock, u64>,
+    ) -> Option<Function> {
+        let source_map = tcx.sess.source_map();
+        let start = source_map.lookup_char_pos(body.span.lo());
+        if !matches!(start.file.name, FileName::Real(_)) {
+            return None;
+        }
+        let mut regions = Vec::new();
+        for (block, data) in body.basic_blocks.iter_enumerated() {
+            // Cleanup blocks only run during unwinding; they are not part of the code as written.
+            if data.is_cleanup {
+                continue;
+            }
+            let spans = data.statements.iter().map(|statement| statement.source_info.span);
+            let mut spans: Vec<_> = spans
+                .chain([data.terminator().source_info.span])
+                // Code that comes from a macro is attributed to the invocation of that macro.
+                .map(|span| walk_chain(span, body.span.ctxt()))
+                .filter(|span| !span.is_dummy() && body.span.contains(*span))
+                .collect();
+            spans.sort_unstable();
+            spans.dedup();
+            for span in spans {
+                let lo = source_map.lookup_char_pos(span.lo());
+                let hi = source_map.lookup_char_pos(span.hi());
+                regions.push(Region {
+                    start: (lo.line, lo.col.0 + 1),
+                    end: (hi.line, hi.col.0 + 1),
+                    count: counts[block],
+                });
+            }
+        }
+        Some(Function {
+            name,
+            file: start.file.name.prefer_local().to_string(),
+            line: start.line,
+            count: counts[mir::START_BLOCK],
+            regions,
+        })
+    }
+
+    /// The execution count of each line with code of this function: the highest count of the
+    /// regions starting on that line.
+    fn lines(&self) -> BTreeMap<usize, u64> {
+        let mut lines = BTreeMap::new();
+        for region in &self.regions {
+            let count = lines.entry(region.start.0).or_insert(0);
+            *count = region.count.max(*count);
+        }
+        lines
+    }
+}
+
+/// The coverage of a source file.
+#[derive(Default)]
+struct File<'a> {
+    functions: Vec<&'a Function>,
+    /// The execution count of each line, summed over the functions.
+    lines: BTreeMap<usize, u64>,
+}
+
+impl File<'_> {
+    fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|&&count| count > 0).count()
+    }
+
+    fn functions_hit(&self) -> usize {
+        self.functions.iter().filter(|function| function.count > 0).count()
+    }
+}
+
+fn files(functions: &[Function]) -> BTreeMap<&str, File<'_>> {
+    let mut files = BTreeMap::<_, File<'_>>::new();
+    for function in functions {
+        let file = files.entry(function.file.as_str()).or_default();
+        file.functions.push(function);
+        for (line, count) in function.lines() {
+            *file.lines.entry(line).or_insert(0) += count;
+        }
+    }
+    files
+}
+
+/// Renders the report as an lcov tracefile.
+fn lcov(functions: &[Function]) -> String {
+    let mut out = String::new();
+    for (name, file) in files(functions) {
+        writeln!(out, "TN:").unwrap();
+        writeln!(out, "SF:{name}").unwrap();
+        for function in &file.functions {
+            writeln!(out, "FN:{},{}", function.line, function.name).unwrap();
+        }
+        for function in &file.functions {
+            writeln!(out, "FNDA:{},{}", function.count, function.name).unwrap();
+        }
+        writeln!(out, "FNF:{}", file.functions.len()).unwrap();
+        writeln!(out, "FNH:{}", file.functions_hit()).unwrap();
+        for (line, count) in &file.lines {
+            writeln!(out, "DA:{line},{count}").unwrap();
+        }
+        writeln!(out, "LF:{}", file.lines.len()).unwrap();
+        writeln!(out, "LH:{}", file.lines_hit()).unwrap();
+        writeln!(out, "end_of_record").unwrap();
+    }
+    out
+}
+
+/// Quotes `s` as a JSON string.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => write!(out, "\\u{:04x}", u32::from(c)).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_summary(count: usize, covered: usize) -> String {
+    let percent = if count == 0 { 0.0 } else { covered as f64 * 100.0 / count as f64 };
+    format!(r#"{{"count":{count},"covered":{covered},"percent":{percent}}}"#)
+}
+
+/// Renders the report in the JSON format of `llvm-cov export`. Every line with code gets a segment
+/// starting at its first column, and every source span of a basic block becomes a code region.
+fn llvm_cov_json(functions: &[Function]) -> String {
+    let files = files(functions);
+    let file_entries: Vec<String> = files
+        .iter()
+        .map(|(name, file)| {
+            let segments: Vec<String> = file
+                .lines
+                .iter()
+                .map(|(line, count)| format!("[{line},1,{count},true,true,false]"))
+                .collect();
+            format!(
+                r#"{{"filename":{},"segments":[{}],"summary":{{"lines":{},"functions":{}}}}}"#,
+                json_string(name),
+                segments.join(","),
+                json_summary(file.lines.len(), file.lines_hit()),
+                json_summary(file.functions.len(), file.functions_hit()),
+            )
+        })
+        .collect();
+    let function_entries: Vec<String> = functions
+        .iter()
+        .map(|function| {
+            let regions: Vec<String> = function
+                .regions
+                .iter()
+                .map(|region| {
+                    format!(
+                        "[{},{},{},{},{},0,0,0]",
+                        region.start.0, region.start.1, region.end.0, region.end.1, region.count
+                    )
+                })
+                .collect();
+            format!(
+                r#"{{"name":{},"count":{},"regions":[{}],"filenames":[{}]}}"#,
+                json_string(&function.name),
+                function.count,
+                regions.join(","),
+                json_string(&function.file),
+            )
+        })
+        .collect();
+    let lines = files.values().map(|file| file.lines.len()).sum();
+    let lines_hit = files.values().map(File::lines_hit).sum();
+    let functions_hit = functions.iter().filter(|function| function.count > 0).count();
+    format!(
+        r#"{{"type":"llvm.coverage.json.export","version":"2.0.1","data":[{{"files":[{}],"functions":[{}],"totals":{{"lines":{},"functions":{}}}}}]}}"#,
+        file_entries.join(","),
+        function_entries.join(","),
+        json_summary(lines, lines_hit),
+        json_summary(functions.len(), functions_hit),
+    ) + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn functions() -> Vec<Function> {
+        let region = |line, count| Region { start: (line, 5), end: (line, 20), count };
+        vec![
+            Function {
+                name: "main".into(),
+                file: "src/main.rs".into(),
+                line: 1,
+                count: 1,
+                regions: vec![region(2, 1), region(3, 0), region(3, 1), region(5, 0)],
+            },
+            Function {
+                name: "helper::<\"x\">".into(),
+                file: "src/main.rs".into(),
+                line: 8,
+                count: 0,
+                regions: vec![region(9, 0)],
+            },
+        ]
+    }
+
+    #[test]
+    fn lcov_report() {
+        assert_eq!(
+            lcov(&functions()),
+            "TN:\nSF:src/main.rs\nFN:1,main\nFN:8,helper::<\"x\">\nFNDA:1,main\n\
+             FNDA:0,helper::<\"x\">\nFNF:2\nFNH:1\nDA:2,1\nDA:3,1\nDA:5,0\nDA:9,0\nLF:4\nLH:2\n\
+             end_of_record\n"
+        );
+    }
+
+    #[test]
+    fn llvm_cov_report() {
+        let report = llvm_cov_json(&functions());
+        assert!(report.contains(r#""segments":[[2,1,1,true,true,false],[3,1,1,true,true,false],"#));
+        assert!(
+            report.contains(r#""name":"helper::<\"x\">","count":0,"regions":[[9,5,9,20,0,0,0,0]]"#)
+        );
+        assert!(report.contains(r#""totals":{"lines":{"count":4,"covered":2,"percent":50},"#));
+    }
+}
@@ -144,10 +144,28 @@ pub struct MiriConfig {
     pub mute_stdout_stderr: bool,
     /// The probability of the active thread being preempted at the end of each basic block.
     pub preemption_rate: f64,
+    /// If `Some`, follow this schedule instead of the default scheduling policy, and record the
+    /// scheduling points of the execution.
+    pub fixed_schedule: Option<Schedule>,
+    /// If `Some`, record the nondeterministic decisions of the execution to this file.
+    pub record: Option<PathBuf>,
+    /// If `Some`, replay these recorded decisions. The seed and environment are taken from the
+    /// recording as well.
+    pub replay: Option<Recording>,
+    /// If `Some`, write a coverage report of the local crates to this file.
+    pub coverage: Option<PathBuf>,
+    /// If `Some`, write a profile of the heap allocations to this file.
+    pub heap_profile: Option<PathBuf>,
+    /// If `Some`, wait for a debugger to connect to this TCP port or Unix socket before running
+    /// the program.
+    pub gdbserver: Option<String>,
     /// Report the current instruction being executed every N basic blocks.
     pub report_progress: Option<u32>,
     /// Whether Stacked Borrows and Tree Borrows retagging should recurse into fields of datatypes.
     pub retag_fields: RetagFields,
+    /// If `Some`, file system accesses under isolation use a virtual filesystem with these
+    /// initial contents instead of being rejected.
+    pub vfs: Option<VfsSnapshot>,
     /// The location of a shared object file to load when calling external functions
     /// FIXME! consider allowing users to specify paths to multiple files, or to a directory
     pub native_lib: Option<PathBuf>,
@@ -191,8 +209,15 @@ fn default() -> MiriConfig {
             provenance_mode: ProvenanceMode::Default,
             mute_stdout_stderr: false,
             preemption_rate: 0.01, // 1%
+            fixed_schedule: None,
+            record: None,
+            replay: None,
+            coverage: None,
+            heap_profile: None,
+            gdbserver: None,
             report_progress: None,
             retag_fields: RetagFields::Yes,
+            vfs: None,
             native_lib: None,
             gc_interval: 10_000,
             num_cpus: 1,
@@ -441,6 +466,18 @@ pub fn eval_entry<'tcx>(
     entry_type: MiriEntryFnType,
     config: MiriConfig,
 ) -> Option<i32> {
+    eval_entry_with_schedule_trace(tcx, entry_id, entry_type, config).0
+}
+
+/// Like `eval_entry`, but also returns the scheduling points of the execution if it followed a
+/// fixed schedule.
+#[expect(clippy::needless_lifetimes)]
+pub fn eval_entry_with_schedule_trace<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    entry_id: DefId,
+    entry_type: MiriEntryFnType,
+    config: MiriConfig,
+) -> (Option<i32>, Option<ScheduleTrace>) {
     // Copy setting before we move `config`.
     let ignore_leaks = config.ignore_leaks;
 
@@ -473,8 +510,30 @@ pub fn eval_entry<'tcx>(
         EnvVars::cleanup(&mut ecx).expect("error during env var cleanup");
     }
 
-    // Process the result.
-    let (return_code, leak_check) = report_error(&ecx, err)?;
+    ecx.machine.recorder.finish(tcx);
+    if let Some(coverage) = &ecx.machine.coverage {
+        coverage.finish(tcx);
+    }
+    if let Some(heap_profile) = &ecx.machine.heap_profile {
+        heap_profile.borrow_mut().finish(tcx, ecx.machine.basic_block_count);
+    }
+
+    ecx.gdbserver_stopped_by(&err);
+    let return_code = process_result(&mut ecx, err, ignore_leaks);
+    if let Some(gdbserver) = &mut ecx.machine.gdbserver {
+        gdbserver.exited(return_code);
+    }
+    (return_code, ecx.machine.threads.take_schedule_trace())
+}
+
+/// Reports the result of the execution, and checks for leaks.
+fn process_result<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    err: InterpErrorInfo<'tcx>,
+    ignore_leaks: bool,
+) -> Option<i32> {
+    let tcx = *ecx.tcx;
+    let (return_code, leak_check) = report_error(ecx, err)?;
     if leak_check && !ignore_leaks {
         // Check for thread leaks.
         if !ecx.have_all_terminated() {
@@ -486,7 +545,7 @@ pub fn eval_entry<'tcx>(
         info!("Additional static roots: {:?}", ecx.machine.static_roots);
         let leaks = ecx.take_leaked_allocations(|ecx| &ecx.machine.static_roots);
         if !leaks.is_empty() {
-            report_leaks(&ecx, leaks);
+            report_leaks(ecx, leaks);
             tcx.dcx().note("set `MIRIFLAGS=-Zmiri-ignore-leaks` to disable this check");
             // Ignore the provided return code - let the reported error
             // determine the return code.
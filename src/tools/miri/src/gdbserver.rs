@@ -0,0 +1,881 @@
+//! A server for the GDB remote serial protocol (`-Zmiri-gdbserver`), so that the interpreted program
+//! can be debugged with `gdb` or any other client of that protocol.
+//!
+//! The interpreted program has no machine code, so there are no machine registers and no
+//! instruction addresses for the client to work with. Instead, the target description of the
+//! server has a single register, `pc`, whose value is the position of the current statement of the
+//! selected frame in the source map; software breakpoints (`Z0`) are set at such positions, like
+//! with `break *ADDR`. Clients that need the registers of a real architecture, as GDB does for most
+//! of them, see `pc` as the first register of that architecture and all other registers as
+//! unavailable, so GDB's own frame and variable commands do not work. Breakpoints are more easily
+//! set on source lines and functions with `monitor break`, and single-stepping (`stepi`) executes
+//! one MIR statement or terminator. The call stack and the local variables of the selected thread
+//! are shown with `monitor backtrace` and `monitor locals`, and memory can be read at the addresses
+//! Miri assigned to the allocations. GDB's thread `N` is Miri's thread `N - 1`, since GDB reserves
+//! the id 0.
+//!
+//! When the program hits an error, it stops with `SIGABRT` so its state can be inspected before
+//! Miri reports the error.
+
+use std::fmt::{self, Write as _};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+use either::Either;
+use rustc_abi::Endian;
+use rustc_middle::mir;
+use rustc_span::hygiene::walk_chain;
+use rustc_span::{BytePos, Span};
+
+use crate::*;
+
+/// The signal reported when the program stops at a breakpoint or after a step.
+const SIGTRAP: u8 = 5;
+/// The signal reported when the program stops because of an error.
+const SIGABRT: u8 = 6;
+/// How many steps to take between checks for an interrupt from the client.
+const INTERRUPT_POLL_INTERVAL: u32 = 1024;
+/// The most memory sent in reply to a single read.
+const MAX_READ: u64 = 4096;
+/// The most bytes of a local variable stored in memory shown by `monitor locals`.
+const MAX_LOCAL_BYTES: u64 = 32;
+
+/// The target description, which has a single register: the program counter.
+const TARGET_XML: &str = r#"<?xml version="1.0"?>
+<!DOCTYPE target SYSTEM "gdb-target.dtd">
+<target version="1.0">
+  <feature name="org.rust-lang.miri">
+    <reg name="pc" bitsize="64" type="code_ptr" regnum="0"/>
+  </feature>
+</target>
+"#;
+
+/// The request to read a part of the target description.
+const TARGET_XML_READ: &str = "qXfer:features:read:target.xml:";
+
+const HELP: &str = "\
+Miri monitor commands:
+  break FILE:LINE    stop when a thread reaches the line
+  break FUNCTION     stop when a thread enters the function
+  break *ADDRESS     stop when a thread reaches the program counter
+  breakpoints        list the breakpoints
+  delete [N]         delete breakpoint N, or all breakpoints
+  backtrace          show the call stack of the selected thread
+  locals [FRAME]     show the local variables of a frame of the selected thread
+";
+
+/// A connection to the client.
+trait Stream: Read + Write {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+}
+
+impl Stream for TcpStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+#[cfg(unix)]
+impl Stream for UnixStream {
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum BreakpointLocation {
+    /// The first statement of a line. The file matches all source files whose path ends with it.
+    Line { file: PathBuf, line: usize },
+    /// The first statement of a function. The path matches all functions whose path ends with it.
+    Function(String),
+    /// The statements at a program counter, as set by the client with a `Z0` packet.
+    Address(u64),
+}
+
+impl BreakpointLocation {
+    fn parse(s: &str) -> Option<Self> {
+        if s.is_empty() {
+            return None;
+        }
+        if let Some(addr) = s.strip_prefix('*') {
+            let addr = addr.strip_prefix("0x").unwrap_or(addr);
+            return u64::from_str_radix(addr, 16).ok().map(BreakpointLocation::Address);
+        }
+        // Function paths contain `::`, so only a number after the last colon makes this a line.
+        if let Some((file, line)) = s.rsplit_once(':') {
+            if let Ok(line) = line.parse() {
+                return (!file.is_empty())
+                    .then(|| BreakpointLocation::Line { file: file.into(), line });
+            }
+        }
+        Some(BreakpointLocation::Function(s.to_owned()))
+    }
+}
+
+impl fmt::Display for BreakpointLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BreakpointLocation::Line { file, line } => write!(f, "{}:{line}", file.display()),
+            BreakpointLocation::Function(path) => write!(f, "{path}"),
+            BreakpointLocation::Address(addr) => write!(f, "*{addr:#x}"),
+        }
+    }
+}
+
+struct Breakpoint {
+    id: u32,
+    location: BreakpointLocation,
+}
+
+/// What the client asked the program to do next.
+#[derive(Debug, PartialEq, Eq)]
+enum Resume {
+    Continue,
+    Step,
+    Detach,
+    Kill,
+}
+
+/// Something received from the client.
+#[derive(Debug, PartialEq, Eq)]
+enum Incoming {
+    Packet(Vec<u8>),
+    /// The client asks to stop the running program (Ctrl-C).
+    Interrupt,
+}
+
+pub struct GdbServer {
+    stream: Box<dyn Stream>,
+    breakpoints: Vec<Breakpoint>,
+    next_breakpoint: u32,
+    /// Whether to stop before the next step.
+    stepping: bool,
+    /// Whether the client resumed the program and is waiting for it to stop.
+    resumed: bool,
+    /// The signal of the last stop.
+    signal: u8,
+    /// The thread that was active at the last stop.
+    stopped_thread: ThreadId,
+    /// The thread inspected by the client.
+    selected_thread: ThreadId,
+    /// The thread, stack depth, file and line of the last step, so that a line breakpoint is only
+    /// hit when a frame enters its line, not at every statement on it.
+    last_line: Option<(ThreadId, usize, BytePos, usize)>,
+    /// The thread, stack depth and program counter of the last step, for the same reason.
+    last_pc: Option<(ThreadId, usize, u64)>,
+    steps: u32,
+}
+
+impl GdbServer {
+    /// Waits for a client to connect to `addr`: a TCP port on the loopback interface, or the path
+    /// of a Unix socket.
+    pub fn accept(addr: &str) -> io::Result<GdbServer> {
+        let stream: Box<dyn Stream> = if let Ok(port) = addr.parse::<u16>() {
+            let listener = TcpListener::bind(("127.0.0.1", port))?;
+            eprintln!("Waiting for a debugger to connect to 127.0.0.1:{port}...");
+            let (stream, _) = listener.accept()?;
+            stream.set_nodelay(true)?;
+            Box::new(stream)
+        } else {
+            Self::accept_unix(Path::new(addr))?
+        };
+        Ok(GdbServer {
+            stream,
+            breakpoints: Vec::new(),
+            next_breakpoint: 1,
+            // Stop before the first step, to give the client a chance to set breakpoints.
+            stepping: true,
+            resumed: false,
+            signal: SIGTRAP,
+            stopped_thread: ThreadId::MAIN_THREAD,
+            selected_thread: ThreadId::MAIN_THREAD,
+            last_line: None,
+            last_pc: None,
+            steps: 0,
+        })
+    }
+
+    #[cfg(unix)]
+    fn accept_unix(path: &Path) -> io::Result<Box<dyn Stream>> {
+        let listener = UnixListener::bind(path)?;
+        eprintln!("Waiting for a debugger to connect to {}...", path.display());
+        let (stream, _) = listener.accept()?;
+        std::fs::remove_file(path)?;
+        Ok(Box::new(stream))
+    }
+
+    #[cfg(not(unix))]
+    fn accept_unix(_path: &Path) -> io::Result<Box<dyn Stream>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unix sockets are not supported on this host; use a port number",
+        ))
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        self.stream.write_all(&encode_packet(data.as_bytes()))?;
+        self.stream.flush()
+    }
+
+    /// Tells the client that the program exited, if it is waiting for the program to stop.
+    /// `None` means the program was stopped by an error.
+    pub fn exited(&mut self, return_code: Option<i32>) {
+        if !self.resumed {
+            return;
+        }
+        let reply = match return_code {
+            Some(code) => format!("W{:02x}", code & 0xff),
+            None => format!("X{SIGABRT:02x}"),
+        };
+        // The connection is not needed any more, so failing to send this is not worth reporting.
+        let _ = self.send(&reply);
+    }
+
+    /// Decides whether to stop before the next step of the active thread.
+    fn should_stop<'tcx>(&mut self, ecx: &MiriInterpCx<'tcx>) -> bool {
+        let Some(frame) = ecx.active_thread_stack().last() else {
+            // The thread is about to terminate or run its TLS destructors.
+            return false;
+        };
+        if self.stepping || self.interrupted() {
+            return true;
+        }
+        let mut stop = false;
+        if matches!(frame.current_loc(), Either::Left(loc) if loc == mir::Location::START)
+            && self
+                .breakpoints
+                .iter()
+                .any(|bp| matches!(bp.location, BreakpointLocation::Function(_)))
+        {
+            let path = ecx.tcx.def_path_str(frame.instance().def_id());
+            stop |= self.breakpoints.iter().any(|bp| {
+                matches!(&bp.location, BreakpointLocation::Function(f)
+                    if path == *f || path.strip_suffix(f.as_str()).is_some_and(|p| p.ends_with("::")))
+            });
+        }
+        if self.breakpoints.iter().any(|bp| matches!(bp.location, BreakpointLocation::Line { .. }))
+        {
+            // Code that comes from a macro is attributed to the invocation of that macro.
+            let span = walk_chain(frame.current_span(), frame.body().span.ctxt());
+            if let Ok(line) = ecx.tcx.sess.source_map().lookup_line(span.lo()) {
+                let position = (
+                    ecx.machine.threads.active_thread(),
+                    ecx.active_thread_stack().len(),
+                    line.sf.start_pos,
+                    line.line.strict_add(1),
+                );
+                if self.last_line != Some(position) {
+                    let file = line.sf.name.prefer_local().to_string();
+                    stop |= self.breakpoints.iter().any(|bp| {
+                        matches!(&bp.location, BreakpointLocation::Line { file: f, line }
+                            if *line == position.3 && Path::new(&file).ends_with(f))
+                    });
+                }
+                self.last_line = Some(position);
+            }
+        }
+        if self.breakpoints.iter().any(|bp| matches!(bp.location, BreakpointLocation::Address(_))) {
+            let pc = pc(frame);
+            let position =
+                (ecx.machine.threads.active_thread(), ecx.active_thread_stack().len(), pc);
+            if self.last_pc != Some(position) {
+                stop |= self
+                    .breakpoints
+                    .iter()
+                    .any(|bp| bp.location == BreakpointLocation::Address(pc));
+            }
+            self.last_pc = Some(position);
+        }
+        stop
+    }
+
+    /// Checks whether the client sent an interrupt while the program was running.
+    fn interrupted(&mut self) -> bool {
+        self.steps = self.steps.wrapping_add(1);
+        if self.steps % INTERRUPT_POLL_INTERVAL != 0 || self.stream.set_nonblocking(true).is_err() {
+            return false;
+        }
+        let mut interrupted = false;
+        let mut byte = [0];
+        while let Ok(1) = self.stream.read(&mut byte) {
+            // Anything but an interrupt can only be a late acknowledgment.
+            interrupted |= byte[0] == 0x03;
+        }
+        let _ = self.stream.set_nonblocking(false);
+        interrupted
+    }
+
+    /// Reports a stop of the program to the client and handles its requests until it resumes the
+    /// program.
+    fn stop<'tcx>(&mut self, ecx: &MiriInterpCx<'tcx>, signal: u8) -> io::Result<Resume> {
+        self.signal = signal;
+        self.stopped_thread = ecx.machine.threads.active_thread();
+        self.selected_thread = self.stopped_thread;
+        if self.resumed {
+            self.resumed = false;
+            self.send(&self.stop_reply())?;
+        }
+        let resume = self.serve(ecx)?;
+        match resume {
+            Resume::Continue => self.stepping = false,
+            Resume::Step => self.stepping = true,
+            Resume::Detach | Resume::Kill => {}
+        }
+        self.resumed = true;
+        Ok(resume)
+    }
+
+    fn stop_reply(&self) -> String {
+        format!("T{:02x}thread:{:x};", self.signal, gdb_thread_id(self.stopped_thread))
+    }
+
+    fn serve<'tcx>(&mut self, ecx: &MiriInterpCx<'tcx>) -> io::Result<Resume> {
+        loop {
+            let Incoming::Packet(packet) = read_packet(&mut *self.stream)? else {
+                // The program is already stopped.
+                continue;
+            };
+            let packet = String::from_utf8_lossy(&packet);
+            if let Some(resume) = self.handle(ecx, &packet)? {
+                return Ok(resume);
+            }
+        }
+    }
+
+    /// Handles a request of the client. Returns how to resume the program if the request does
+    /// that.
+    fn handle<'tcx>(
+        &mut self,
+        ecx: &MiriInterpCx<'tcx>,
+        packet: &str,
+    ) -> io::Result<Option<Resume>> {
+        let reply = match packet {
+            "?" => self.stop_reply(),
+            "c" => return Ok(Some(Resume::Continue)),
+            "s" => return Ok(Some(Resume::Step)),
+            "vCont?" => "vCont;c;s".to_owned(),
+            _ if packet.starts_with("vCont;") => {
+                // Miri's scheduler decides which thread runs, so the thread of an action is
+                // ignored. Stepping any thread steps the program.
+                let step = packet[6..].split(';').any(|action| action.starts_with('s'));
+                return Ok(Some(if step { Resume::Step } else { Resume::Continue }));
+            }
+            _ if packet.starts_with('D') => {
+                self.send("OK")?;
+                return Ok(Some(Resume::Detach));
+            }
+            "k" => return Ok(Some(Resume::Kill)),
+            _ if packet.starts_with("vKill") => {
+                self.send("OK")?;
+                return Ok(Some(Resume::Kill));
+            }
+            _ if packet.starts_with("qSupported") =>
+                "PacketSize=4000;vContSupported+;qXfer:features:read+".to_owned(),
+            _ if packet.starts_with(TARGET_XML_READ) => {
+                let range =
+                    packet[TARGET_XML_READ.len()..].split_once(',').and_then(|(offset, len)| {
+                        Some((
+                            usize::from_str_radix(offset, 16).ok()?,
+                            usize::from_str_radix(len, 16).ok()?,
+                        ))
+                    });
+                match range {
+                    Some((offset, len)) => {
+                        let rest = TARGET_XML.get(offset..).unwrap_or_default();
+                        // `m` means there is more to read, `l` that this is the last part.
+                        if rest.len() > len {
+                            format!("m{}", &rest[..len])
+                        } else {
+                            format!("l{rest}")
+                        }
+                    }
+                    None => "E00".to_owned(),
+                }
+            }
+            _ if packet.starts_with("qAttached") => "1".to_owned(),
+            "qC" => format!("QC{:x}", gdb_thread_id(self.stopped_thread)),
+            "qfThreadInfo" => {
+                let threads: Vec<_> = live_threads(ecx)
+                    .map(|thread| format!("{:x}", gdb_thread_id(thread)))
+                    .collect();
+                format!("m{}", threads.join(","))
+            }
+            "qsThreadInfo" => "l".to_owned(),
+            _ if packet.starts_with("qThreadExtraInfo,") =>
+                match thread_from_gdb(ecx, &packet[17..]) {
+                    Some(thread) =>
+                        to_hex(ecx.machine.threads.get_thread_display_name(thread).as_bytes()),
+                    None => "E01".to_owned(),
+                },
+            _ if packet.starts_with('H') => {
+                // `-1` and `0` stand for any thread; keep the selected one.
+                let id = packet.get(2..).unwrap_or_default();
+                if id == "-1" || id == "0" {
+                    "OK".to_owned()
+                } else {
+                    match thread_from_gdb(ecx, id) {
+                        Some(thread) => {
+                            if packet.as_bytes().get(1) == Some(&b'g') {
+                                self.selected_thread = thread;
+                            }
+                            "OK".to_owned()
+                        }
+                        None => "E01".to_owned(),
+                    }
+                }
+            }
+            _ if packet.starts_with('T') =>
+                if thread_from_gdb(ecx, &packet[1..]).is_some() {
+                    "OK".to_owned()
+                } else {
+                    "E01".to_owned()
+                },
+            "g" | "p0" => self.pc_register(ecx),
+            // There is no other register.
+            _ if packet.starts_with('p') => "E00".to_owned(),
+            _ if packet.starts_with("Z0,") || packet.starts_with("z0,") => {
+                // The kind of the breakpoint, after the address, only matters for machine code.
+                let addr = packet[3..]
+                    .split(',')
+                    .next()
+                    .and_then(|addr| u64::from_str_radix(addr, 16).ok());
+                match addr {
+                    Some(addr) => {
+                        let location = BreakpointLocation::Address(addr);
+                        if packet.starts_with('Z') {
+                            if !self.breakpoints.iter().any(|bp| bp.location == location) {
+                                self.add_breakpoint(location);
+                            }
+                        } else {
+                            self.breakpoints.retain(|bp| bp.location != location);
+                        }
+                        "OK".to_owned()
+                    }
+                    None => "E00".to_owned(),
+                }
+            }
+            _ if packet.starts_with('m') => {
+                let range = packet[1..].split_once(',').and_then(|(addr, len)| {
+                    Some((u64::from_str_radix(addr, 16).ok()?, u64::from_str_radix(len, 16).ok()?))
+                });
+                match range.and_then(|(addr, len)| read_memory(ecx, addr, len.min(MAX_READ))) {
+                    Some(bytes) => to_hex(&bytes),
+                    // EFAULT
+                    None => "E0e".to_owned(),
+                }
+            }
+            _ if packet.starts_with("qRcmd,") => {
+                let command = from_hex(&packet[6..]).unwrap_or_default();
+                let output = self.monitor(ecx, &String::from_utf8_lossy(&command));
+                self.send(&format!("O{}", to_hex(output.as_bytes())))?;
+                "OK".to_owned()
+            }
+            // An empty reply tells the client that the request is not supported.
+            _ => String::new(),
+        };
+        self.send(&reply)?;
+        Ok(None)
+    }
+
+    /// Runs a `monitor` command and returns its output.
+    fn monitor<'tcx>(&mut self, ecx: &MiriInterpCx<'tcx>, command: &str) -> String {
+        let command = command.trim();
+        let (name, arg) = command.split_once(' ').unwrap_or((command, ""));
+        let arg = arg.trim();
+        match name {
+            "break" | "b" => {
+                let Some(location) = BreakpointLocation::parse(arg) else {
+                    return "Usage: break FILE:LINE, break FUNCTION or break *ADDRESS\n".to_owned();
+                };
+                let output = format!("Breakpoint {} at {location}\n", self.next_breakpoint);
+                self.add_breakpoint(location);
+                output
+            }
+            "breakpoints" => {
+                if self.breakpoints.is_empty() {
+                    return "No breakpoints.\n".to_owned();
+                }
+                let mut output = String::new();
+                for bp in &self.breakpoints {
+                    writeln!(output, "{} {}", bp.id, bp.location).unwrap();
+                }
+                output
+            }
+            "delete" | "d" => {
+                if arg.is_empty() {
+                    self.breakpoints.clear();
+                    return "Deleted all breakpoints.\n".to_owned();
+                }
+                match arg.parse::<u32>() {
+                    Ok(id) if self.breakpoints.iter().any(|bp| bp.id == id) => {
+                        self.breakpoints.retain(|bp| bp.id != id);
+                        format!("Deleted breakpoint {id}.\n")
+                    }
+                    _ => format!("No breakpoint number {arg}.\n"),
+                }
+            }
+            "backtrace" | "bt" => self.backtrace(ecx),
+            "locals" => {
+                let frame = if arg.is_empty() { Ok(0) } else { arg.parse::<usize>() };
+                match frame {
+                    Ok(frame) => self.locals(ecx, frame),
+                    Err(_) => "Usage: locals [FRAME]\n".to_owned(),
+                }
+            }
+            "help" | "" => HELP.to_owned(),
+            _ => format!("Unknown command `{name}`; try `monitor help`.\n"),
+        }
+    }
+
+    fn add_breakpoint(&mut self, location: BreakpointLocation) {
+        let id = self.next_breakpoint;
+        self.next_breakpoint = id.strict_add(1);
+        self.breakpoints.push(Breakpoint { id, location });
+    }
+
+    /// The value of the `pc` register in the target byte order, which is the program counter of
+    /// the innermost frame of the selected thread.
+    fn pc_register<'tcx>(&self, ecx: &MiriInterpCx<'tcx>) -> String {
+        let Some(frame) = self.stack(ecx).last() else {
+            return "x".repeat(16);
+        };
+        let pc = pc(frame);
+        to_hex(&match ecx.tcx.data_layout.endian {
+            Endian::Little => pc.to_le_bytes(),
+            Endian::Big => pc.to_be_bytes(),
+        })
+    }
+
+    /// The stack of the selected thread, innermost frame last.
+    fn stack<'a, 'tcx>(
+        &self,
+        ecx: &'a MiriInterpCx<'tcx>,
+    ) -> &'a [Frame<'tcx, Provenance, FrameExtra<'tcx>>] {
+        ecx.machine
+            .threads
+            .all_stacks()
+            .find(|(thread, _)| *thread == self.selected_thread)
+            .map_or(&[], |(_, stack)| stack)
+    }
+
+    fn backtrace<'tcx>(&self, ecx: &MiriInterpCx<'tcx>) -> String {
+        let mut output = String::new();
+        for (i, frame) in self.stack(ecx).iter().rev().enumerate() {
+            writeln!(
+                output,
+                "#{i} {:#x} in {} at {}",
+                pc(frame),
+                frame.instance(),
+                source_location(ecx, frame.current_span())
+            )
+            .unwrap();
+        }
+        if output.is_empty() {
+            output.push_str("No stack.\n");
+        }
+        output
+    }
+
+    fn locals<'tcx>(&self, ecx: &MiriInterpCx<'tcx>, frame: usize) -> String {
+        let stack = self.stack(ecx);
+        let Some(frame) = stack.len().checked_sub(frame.strict_add(1)).map(|i| &stack[i]) else {
+            return format!("No frame {frame}.\n");
+        };
+        let mut output = String::new();
+        for info in &frame.body().var_debug_info {
+            let value = match &info.value {
+                mir::VarDebugInfoContents::Place(place) if place.projection.is_empty() =>
+                    local_value(ecx, frame, place.local),
+                mir::VarDebugInfoContents::Place(_) => "<optimized out>".to_owned(),
+                mir::VarDebugInfoContents::Const(constant) => format!("{constant}"),
+            };
+            writeln!(output, "{} = {value}", info.name).unwrap();
+        }
+        if output.is_empty() {
+            output.push_str("No locals.\n");
+        }
+        output
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Called before each step of the active thread: stops the program if it reached a breakpoint
+    /// or the client is stepping through it.
+    fn gdbserver_before_step(&mut self) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let Some(mut server) = this.machine.gdbserver.take() else {
+            return interp_ok(());
+        };
+        let resume = if server.should_stop(this) {
+            server.stop(this, SIGTRAP)
+        } else {
+            Ok(Resume::Continue)
+        };
+        this.gdbserver_resume(server, resume)
+    }
+
+    /// Called when the program stopped because of `err`, to let the client inspect it before the
+    /// error is reported.
+    fn gdbserver_stopped_by(&mut self, err: &InterpErrorInfo<'tcx>) {
+        let this = self.eval_context_mut();
+        if let MachineStop(info) = err.kind() {
+            if let Some(TerminationInfo::Exit { .. }) = info.downcast_ref::<TerminationInfo>() {
+                return;
+            }
+        }
+        let Some(mut server) = this.machine.gdbserver.take() else {
+            return;
+        };
+        let resume = server.stop(this, SIGABRT);
+        // The program cannot continue anyway, so there is nothing to kill.
+        this.gdbserver_resume(server, resume).discard_err();
+    }
+}
+
+impl<'tcx> EvalContextPrivExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextPrivExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    fn gdbserver_resume(
+        &mut self,
+        server: GdbServer,
+        resume: io::Result<Resume>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        match resume {
+            Ok(Resume::Continue | Resume::Step) => this.machine.gdbserver = Some(server),
+            Ok(Resume::Detach) => {}
+            Ok(Resume::Kill) =>
+                throw_machine_stop!(TerminationInfo::Abort(
+                    "the program was killed by the debugger".to_owned()
+                )),
+            Err(err) => {
+                this.tcx.dcx().warn(format!("lost the connection to the debugger: {err}"));
+            }
+        }
+        interp_ok(())
+    }
+}
+
+/// The program counter of a frame: the position in the source map of the statement or terminator
+/// it executes next. Code that comes from a macro is attributed to the invocation of that macro.
+fn pc<'tcx>(frame: &Frame<'tcx, Provenance, FrameExtra<'tcx>>) -> u64 {
+    u64::from(walk_chain(frame.current_span(), frame.body().span.ctxt()).lo().0)
+}
+
+fn gdb_thread_id(thread: ThreadId) -> u32 {
+    thread.to_u32().strict_add(1)
+}
+
+/// Parses a thread id of the client, and returns the thread if it is still alive.
+fn thread_from_gdb<'tcx>(ecx: &MiriInterpCx<'tcx>, id: &str) -> Option<ThreadId> {
+    let id = u32::from_str_radix(id, 16).ok()?.checked_sub(1)?;
+    let thread = ecx.machine.threads.thread_id_try_from(id).ok()?;
+    (!ecx.machine.threads.has_terminated(thread)).then_some(thread)
+}
+
+fn live_threads<'a, 'tcx>(ecx: &'a MiriInterpCx<'tcx>) -> impl Iterator<Item = ThreadId> + 'a {
+    ecx.machine
+        .threads
+        .all_stacks()
+        .map(|(thread, _)| thread)
+        .filter(|&thread| !ecx.machine.threads.has_terminated(thread))
+}
+
+fn source_location<'tcx>(ecx: &MiriInterpCx<'tcx>, span: Span) -> String {
+    let pos = ecx.tcx.sess.source_map().lookup_char_pos(span.lo());
+    format!("{}:{}:{}", pos.file.name.prefer_local(), pos.line, pos.col.0.strict_add(1))
+}
+
+/// Reads memory of the program without going through the interpreter, so that inspecting it has
+/// no effect on the borrow tracker or the data race detector. The read ends at the end of the
+/// allocation.
+fn read_memory<'tcx>(ecx: &MiriInterpCx<'tcx>, addr: u64, len: u64) -> Option<Vec<u8>> {
+    let (alloc_id, offset) = ecx.alloc_containing_addr(addr)?;
+    let (_kind, alloc) = ecx.memory.alloc_map().get(alloc_id)?;
+    let start = usize::try_from(offset.bytes()).unwrap();
+    let end =
+        usize::try_from(offset.bytes().saturating_add(len).min(alloc.size().bytes())).unwrap();
+    Some(alloc.inspect_with_uninit_and_ptr_outside_interpreter(start..end).to_vec())
+}
+
+/// Formats the value of a local variable without going through the interpreter.
+fn local_value<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    frame: &Frame<'tcx, Provenance, FrameExtra<'tcx>>,
+    local: mir::Local,
+) -> String {
+    let Some(layout) = ecx.layout_of_local(frame, local, None).discard_err() else {
+        return "<unknown type>".to_owned();
+    };
+    match frame.locals[local].as_mplace_or_imm() {
+        None => "<dead>".to_owned(),
+        Some(Either::Right(imm)) => ImmTy::from_immediate(imm, layout).to_string(),
+        Some(Either::Left((ptr, _meta))) => {
+            let addr = ptr.addr().bytes();
+            let mut value = format!("{} at {addr:#x}", layout.ty);
+            if layout.is_sized() {
+                let len = layout.size.bytes().min(MAX_LOCAL_BYTES);
+                if let Some(bytes) = read_memory(ecx, addr, len) {
+                    let bytes: Vec<_> = bytes.iter().map(|b| format!("{b:02x}")).collect();
+                    write!(value, ": [{}]", bytes.join(" ")).unwrap();
+                    if layout.size.bytes() > len {
+                        value.push_str(" ...");
+                    }
+                }
+            }
+            value
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len().strict_mul(2));
+    for byte in bytes {
+        write!(hex, "{byte:02x}").unwrap();
+    }
+    hex
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i.strict_add(2))?, 16).ok())
+        .collect()
+}
+
+/// Frames `data` as a packet: `$data#checksum`, where `$`, `#`, `}` and `*` in the data are
+/// escaped.
+fn encode_packet(data: &[u8]) -> Vec<u8> {
+    let mut packet = vec![b'$'];
+    for &byte in data {
+        if matches!(byte, b'$' | b'#' | b'}' | b'*') {
+            packet.extend([b'}', byte ^ 0x20]);
+        } else {
+            packet.push(byte);
+        }
+    }
+    let checksum = packet[1..].iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+    packet.extend(format!("#{checksum:02x}").bytes());
+    packet
+}
+
+fn read_byte(stream: &mut (impl Read + ?Sized)) -> io::Result<u8> {
+    let mut byte = [0];
+    stream.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+/// Reads the next packet or interrupt from the client, skipping acknowledgments, and acknowledges
+/// the packet. Packets with a wrong checksum are rejected, which makes the client send them again.
+fn read_packet(stream: &mut (impl Read + Write + ?Sized)) -> io::Result<Incoming> {
+    loop {
+        match read_byte(stream)? {
+            b'$' => {}
+            0x03 => return Ok(Incoming::Interrupt),
+            // Acknowledgments, and noise between packets.
+            _ => continue,
+        }
+        let mut data = Vec::new();
+        let mut checksum = 0u8;
+        loop {
+            let byte = read_byte(stream)?;
+            if byte == b'#' {
+                break;
+            }
+            checksum = checksum.wrapping_add(byte);
+            if byte == b'}' {
+                let escaped = read_byte(stream)?;
+                checksum = checksum.wrapping_add(escaped);
+                data.push(escaped ^ 0x20);
+            } else {
+                data.push(byte);
+            }
+        }
+        let expected = [read_byte(stream)?, read_byte(stream)?];
+        let expected =
+            std::str::from_utf8(&expected).ok().and_then(|s| u8::from_str_radix(s, 16).ok());
+        if expected == Some(checksum) {
+            stream.write_all(b"+")?;
+            return Ok(Incoming::Packet(data));
+        }
+        stream.write_all(b"-")?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// A client that sent `input`.
+    struct Client {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Client {
+        fn new(input: &[u8]) -> Self {
+            Client { input: Cursor::new(input.to_vec()), output: Vec::new() }
+        }
+    }
+
+    impl Read for Client {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Client {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn packets() {
+        assert_eq!(encode_packet(b"OK"), b"$OK#9a");
+        assert_eq!(encode_packet(b"a#b"), b"$a}\x03b#43");
+
+        let mut client = Client::new(b"+$qC#b4$a}\x03b#09$m0,1#00\x03");
+        assert_eq!(read_packet(&mut client).unwrap(), Incoming::Packet(b"qC".to_vec()));
+        assert_eq!(read_packet(&mut client).unwrap(), Incoming::Packet(b"a#b".to_vec()));
+        // The packet with the wrong checksum is rejected.
+        assert_eq!(read_packet(&mut client).unwrap(), Incoming::Interrupt);
+        assert_eq!(client.output, b"++-");
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(to_hex(b"bt\n"), "62740a");
+        assert_eq!(from_hex("62740a").unwrap(), b"bt\n");
+        assert_eq!(from_hex("6"), None);
+        assert_eq!(from_hex("zz"), None);
+    }
+
+    #[test]
+    fn breakpoint_locations() {
+        assert_eq!(
+            BreakpointLocation::parse("src/main.rs:12"),
+            Some(BreakpointLocation::Line { file: "src/main.rs".into(), line: 12 })
+        );
+        assert_eq!(
+            BreakpointLocation::parse("foo::bar"),
+            Some(BreakpointLocation::Function("foo::bar".to_owned()))
+        );
+        assert_eq!(BreakpointLocation::parse("*0x1f"), Some(BreakpointLocation::Address(0x1f)));
+        assert_eq!(BreakpointLocation::parse("*g"), None);
+        assert_eq!(BreakpointLocation::parse(":3"), None);
+        assert_eq!(BreakpointLocation::parse(""), None);
+    }
+}
@@ -0,0 +1,327 @@
+//! A heap profile of the interpreted program (`-Zmiri-heap-profile`).
+//!
+//! Every heap allocation is attributed to its allocation site, the call stack of the allocating
+//! thread. Per site, we count the allocated blocks and bytes, the lifetimes of the blocks, how many
+//! bytes were live at the peak of that site and at the global peak, how many were still live at
+//! the end, and how many bytes were read and written. Time is measured in executed basic blocks,
+//! so the profile is as deterministic as the execution itself. The profile is written in the JSON
+//! format of DHAT, which can be viewed with `dh_view.html` from Valgrind.
+//!
+//! For sites whose blocks all have the same (small) size, the accesses are also counted per byte,
+//! which `dh_view` shows as a heat map of the block.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_span::Span;
+
+use crate::coverage::json_string;
+use crate::*;
+
+/// Blocks up to this size get per-byte access counts (the same limit as DHAT's).
+const MAX_COUNTED_BLOCK_SIZE: u64 = 1024;
+
+/// Per-byte access counts of the blocks of a site.
+#[derive(Debug, Default, PartialEq, Eq)]
+enum AccessCounts {
+    /// No block of the site was counted yet.
+    #[default]
+    Unknown,
+    /// The counts, summed over all blocks of the site, which all have this size.
+    Uniform(Vec<u64>),
+    /// The blocks of the site have different sizes, or are too large.
+    Mixed,
+}
+
+impl AccessCounts {
+    fn merge(&mut self, block: Option<Vec<u64>>) {
+        *self = match (std::mem::take(self), block) {
+            (AccessCounts::Unknown, Some(block)) => AccessCounts::Uniform(block),
+            (AccessCounts::Uniform(mut counts), Some(block)) if counts.len() == block.len() => {
+                for (count, block) in counts.iter_mut().zip(block) {
+                    *count = count.saturating_add(block);
+                }
+                AccessCounts::Uniform(counts)
+            }
+            _ => AccessCounts::Mixed,
+        }
+    }
+}
+
+/// An allocation site.
+#[derive(Debug, Default)]
+struct Site {
+    /// Indices into the frame table, innermost frame first.
+    frames: Vec<usize>,
+    total_bytes: u64,
+    total_blocks: u64,
+    /// The summed lifetimes of the blocks.
+    total_lifetimes: u64,
+    max_bytes: u64,
+    max_blocks: u64,
+    live_bytes: u64,
+    live_blocks: u64,
+    /// The live bytes and blocks at the global peak.
+    peak_bytes: u64,
+    peak_blocks: u64,
+    /// The global peak `peak_bytes` and `peak_blocks` belong to. They are only brought up to date
+    /// when the site changes after a new global peak: until then, its live values are those at
+    /// the peak.
+    peak: u64,
+    read_bytes: u64,
+    written_bytes: u64,
+    accesses: AccessCounts,
+}
+
+impl Site {
+    /// Brings `peak_bytes` and `peak_blocks` up to date with the global peak `peak`. Must be
+    /// called before the live values change.
+    fn update_peak(&mut self, peak: u64) {
+        if self.peak != peak {
+            self.peak = peak;
+            self.peak_bytes = self.live_bytes;
+            self.peak_blocks = self.live_blocks;
+        }
+    }
+}
+
+/// A live heap allocation.
+struct Block {
+    site: usize,
+    size: u64,
+    allocated_at: u64,
+    /// Per-byte access counts, if the block is small enough.
+    accesses: Option<Vec<u64>>,
+}
+
+pub struct HeapProfile<'tcx> {
+    /// The file the profile is written to.
+    path: PathBuf,
+    /// The description of each frame of the call stacks.
+    frames: Vec<String>,
+    frame_ids: FxHashMap<(ty::Instance<'tcx>, Span), usize>,
+    sites: Vec<Site>,
+    site_ids: FxHashMap<Vec<usize>, usize>,
+    blocks: FxHashMap<AllocId, Block>,
+    live_bytes: u64,
+    peak_bytes: u64,
+    /// How often the live bytes reached a new peak.
+    peaks: u64,
+    /// When the live bytes reached their peak.
+    peak_time: u64,
+}
+
+impl<'tcx> HeapProfile<'tcx> {
+    pub fn new(path: PathBuf) -> Self {
+        HeapProfile {
+            path,
+            // DHAT expects the root of all call stacks as the first frame.
+            frames: vec!["[root]".to_owned()],
+            frame_ids: FxHashMap::default(),
+            sites: Vec::new(),
+            site_ids: FxHashMap::default(),
+            blocks: FxHashMap::default(),
+            live_bytes: 0,
+            peak_bytes: 0,
+            peaks: 0,
+            peak_time: 0,
+        }
+    }
+
+    /// Whether allocations of this kind are profiled.
+    pub fn is_heap(kind: MemoryKind) -> bool {
+        use MiriMemoryKind::*;
+        matches!(kind, MemoryKind::Machine(Rust | Miri | C | WinHeap | WinLocal | Mmap))
+    }
+
+    /// Records a new heap allocation, made by the thread with the given stack at time `now`.
+    pub fn allocate(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        stack: &[Frame<'tcx, Provenance, FrameExtra<'tcx>>],
+        alloc_id: AllocId,
+        size: u64,
+        now: u64,
+    ) {
+        let frames: Vec<usize> = stack
+            .iter()
+            .rev()
+            .map(|frame| {
+                let key = (frame.instance(), frame.current_span());
+                *self.frame_ids.entry(key).or_insert_with(|| {
+                    let location = tcx.sess.source_map().span_to_embeddable_string(key.1);
+                    self.frames.push(format!("{} ({location})", key.0));
+                    self.frames.len().strict_sub(1)
+                })
+            })
+            .collect();
+        let site = *self.site_ids.entry(frames).or_insert_with_key(|frames| {
+            self.sites.push(Site { frames: frames.clone(), ..Site::default() });
+            self.sites.len().strict_sub(1)
+        });
+
+        let entry = &mut self.sites[site];
+        entry.update_peak(self.peaks);
+        entry.total_bytes = entry.total_bytes.saturating_add(size);
+        entry.total_blocks = entry.total_blocks.saturating_add(1);
+        entry.live_bytes = entry.live_bytes.strict_add(size);
+        entry.live_blocks = entry.live_blocks.strict_add(1);
+        if entry.live_bytes > entry.max_bytes {
+            entry.max_bytes = entry.live_bytes;
+            entry.max_blocks = entry.live_blocks;
+        }
+        let accesses =
+            (size <= MAX_COUNTED_BLOCK_SIZE).then(|| vec![0; usize::try_from(size).unwrap()]);
+        self.blocks.insert(alloc_id, Block { site, size, allocated_at: now, accesses });
+
+        self.live_bytes = self.live_bytes.strict_add(size);
+        if self.live_bytes > self.peak_bytes {
+            self.peak_bytes = self.live_bytes;
+            self.peak_time = now;
+            self.peaks = self.peaks.strict_add(1);
+            // The other sites are updated when they change next.
+            self.sites[site].update_peak(self.peaks);
+        }
+    }
+
+    /// Records an access to `range` of an allocation. Accesses to other than heap allocations are
+    /// ignored.
+    pub fn access(&mut self, alloc_id: AllocId, range: AllocRange, kind: AccessKind) {
+        let Some(block) = self.blocks.get_mut(&alloc_id) else {
+            return;
+        };
+        let site = &mut self.sites[block.site];
+        let size = range.size.bytes();
+        match kind {
+            AccessKind::Read => site.read_bytes = site.read_bytes.saturating_add(size),
+            AccessKind::Write => site.written_bytes = site.written_bytes.saturating_add(size),
+        }
+        if let Some(accesses) = &mut block.accesses {
+            let start = usize::try_from(range.start.bytes()).unwrap();
+            let end = usize::try_from(range.end().bytes()).unwrap();
+            for count in &mut accesses[start..end] {
+                *count = count.saturating_add(1);
+            }
+        }
+    }
+
+    /// Records the deallocation of an allocation at time `now`.
+    pub fn deallocate(&mut self, alloc_id: AllocId, now: u64) {
+        let Some(block) = self.blocks.remove(&alloc_id) else {
+            return;
+        };
+        let site = &mut self.sites[block.site];
+        site.update_peak(self.peaks);
+        site.live_bytes = site.live_bytes.strict_sub(block.size);
+        site.live_blocks = site.live_blocks.strict_sub(1);
+        site.total_lifetimes =
+            site.total_lifetimes.saturating_add(now.strict_sub(block.allocated_at));
+        site.accesses.merge(block.accesses);
+        self.live_bytes = self.live_bytes.strict_sub(block.size);
+    }
+
+    /// Writes the profile. The blocks that are still live count as ending at time `now`.
+    pub fn finish(&mut self, tcx: TyCtxt<'tcx>, now: u64) {
+        for (_, block) in std::mem::take(&mut self.blocks) {
+            let site = &mut self.sites[block.site];
+            site.total_lifetimes =
+                site.total_lifetimes.saturating_add(now.strict_sub(block.allocated_at));
+            site.accesses.merge(block.accesses);
+        }
+        for site in &mut self.sites {
+            site.update_peak(self.peaks);
+        }
+        let command = format!("miri {}", tcx.crate_name(rustc_span::def_id::LOCAL_CRATE));
+        if let Err(err) = fs::write(&self.path, self.dhat_json(&command, now)) {
+            tcx.dcx().err(format!(
+                "failed to write the heap profile to `{}`: {err}",
+                self.path.display()
+            ));
+        }
+    }
+
+    /// Renders the profile in the JSON format of DHAT.
+    fn dhat_json(&self, command: &str, now: u64) -> String {
+        let sites: Vec<String> = self
+            .sites
+            .iter()
+            .map(|site| {
+                let mut entry = format!(
+                    r#"{{"tb":{},"tbk":{},"tl":{},"mb":{},"mbk":{},"gb":{},"gbk":{},"eb":{},"ebk":{},"rb":{},"wb":{},"#,
+                    site.total_bytes,
+                    site.total_blocks,
+                    site.total_lifetimes,
+                    site.max_bytes,
+                    site.max_blocks,
+                    site.peak_bytes,
+                    site.peak_blocks,
+                    site.live_bytes,
+                    site.live_blocks,
+                    site.read_bytes,
+                    site.written_bytes,
+                );
+                if let AccessCounts::Uniform(counts) = &site.accesses {
+                    write!(entry, r#""acc":[{}],"#, run_length_encode(counts)).unwrap();
+                }
+                let frames: Vec<String> = site.frames.iter().map(ToString::to_string).collect();
+                write!(entry, r#""fs":[{}]}}"#, frames.join(",")).unwrap();
+                entry
+            })
+            .collect();
+        let frames: Vec<String> = self.frames.iter().map(|frame| json_string(frame)).collect();
+        format!(
+            r#"{{"dhatFileVersion":2,"mode":"rust-heap","verb":"Allocated","bklt":true,"bkacc":true,"tu":"basic blocks","Mtu":"Mbb","tuth":100,"cmd":{},"pid":{},"tg":{},"te":{},"pps":[{}],"ftbl":[{}]}}"#,
+            json_string(command),
+            std::process::id(),
+            self.peak_time,
+            now,
+            sites.join(","),
+            frames.join(","),
+        ) + "\n"
+    }
+}
+
+/// Encodes access counts like DHAT: a run of `n > 1` equal counts `c` becomes `-n,c`.
+fn run_length_encode(counts: &[u64]) -> String {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < counts.len() {
+        let run = counts[i..].iter().take_while(|&&count| count == counts[i]).count();
+        if run > 1 {
+            out.push(format!("-{run}"));
+        }
+        out.push(counts[i].to_string());
+        i = i.strict_add(run);
+    }
+    out.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn access_counts() {
+        let mut accesses = AccessCounts::default();
+        accesses.merge(Some(vec![1, 0]));
+        accesses.merge(Some(vec![2, 1]));
+        assert_eq!(accesses, AccessCounts::Uniform(vec![3, 1]));
+        accesses.merge(Some(vec![1, 1, 1]));
+        assert_eq!(accesses, AccessCounts::Mixed);
+        accesses.merge(Some(vec![1, 1]));
+        assert_eq!(accesses, AccessCounts::Mixed);
+
+        let mut accesses = AccessCounts::default();
+        accesses.merge(None);
+        assert_eq!(accesses, AccessCounts::Mixed);
+    }
+
+    #[test]
+    fn run_lengths() {
+        assert_eq!(run_length_encode(&[]), "");
+        assert_eq!(run_length_encode(&[4, 4, 4, 0, 7, 7]), "-3,4,0,-2,7");
+    }
+}
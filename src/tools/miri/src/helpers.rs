@@ -417,16 +417,26 @@ fn gen_random(&mut self, ptr: Pointer, len: u64) -> InterpResult<'tcx> {
         }
         let this = self.eval_context_mut();
 
-        let mut data = vec![0; usize::try_from(len).unwrap()];
+        let len = usize::try_from(len).unwrap();
 
-        if this.machine.communicate() {
+        let data = if this.machine.communicate() {
             // Fill the buffer using the host's rng.
-            getrandom::fill(&mut data)
-                .map_err(|err| err_unsup_format!("host getrandom failed: {}", err))?;
+            this.machine
+                .recorder
+                .host_random(|| {
+                    let mut data = vec![0; len];
+                    getrandom::fill(&mut data)?;
+                    Ok(data)
+                })
+                .map_err(|err: getrandom::Error| {
+                    err_unsup_format!("host getrandom failed: {}", err)
+                })?
         } else {
+            let mut data = vec![0; len];
             let rng = this.machine.rng.get_mut();
             rng.fill_bytes(&mut data);
-        }
+            data
+        };
 
         this.write_bytes_ptr(ptr, data.iter().copied())
     }
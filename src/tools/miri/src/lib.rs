@@ -83,8 +83,11 @@
 mod borrow_tracker;
 mod clock;
 mod concurrency;
+mod coverage;
 mod diagnostics;
 mod eval;
+mod gdbserver;
+mod heap_profile;
 mod helpers;
 mod intrinsics;
 mod machine;
@@ -93,6 +96,7 @@
 mod operator;
 mod provenance_gc;
 mod range_map;
+mod recording;
 mod shims;
 
 // Establish a "crate-wide prelude": we often import `crate::*`.
@@ -127,6 +131,7 @@
     AtomicFenceOrd, AtomicReadOrd, AtomicRwOrd, AtomicWriteOrd, EvalContextExt as _,
 };
 pub use crate::concurrency::init_once::{EvalContextExt as _, InitOnceId};
+pub use crate::concurrency::schedule::{Schedule, ScheduleExplorer, ScheduleTrace};
 pub use crate::concurrency::sync::{
     CondvarId, EvalContextExt as _, MutexRef, RwLockId, SynchronizationObjects,
 };
@@ -139,8 +144,9 @@
 };
 pub use crate::eval::{
     AlignmentCheck, BacktraceStyle, IsolatedOp, MiriConfig, MiriEntryFnType, RejectOpWith,
-    ValidationMode, create_ecx, eval_entry,
+    ValidationMode, create_ecx, eval_entry, eval_entry_with_schedule_trace,
 };
+pub use crate::gdbserver::EvalContextExt as _;
 pub use crate::helpers::{AccessKind, EvalContextExt as _};
 pub use crate::intrinsics::EvalContextExt as _;
 pub use crate::machine::{
@@ -151,7 +157,8 @@
 pub use crate::operator::EvalContextExt as _;
 pub use crate::provenance_gc::{EvalContextExt as _, LiveAllocs, VisitProvenance, VisitWith};
 pub use crate::range_map::RangeMap;
-pub use crate::shims::EmulateItemResult;
+pub use crate::recording::{HostValue, Recorder, Recording};
+pub use crate::shims::{EmulateItemResult, VfsSnapshot};
 pub use crate::shims::env::{EnvVars, EvalContextExt as _};
 pub use crate::shims::foreign_items::{DynSym, EvalContextExt as _};
 pub use crate::shims::io_error::{EvalContextExt as _, IoError, LibcError};
@@ -6,8 +6,10 @@
 use std::cell::{Cell, RefCell};
 use std::collections::hash_map::Entry;
 use std::path::Path;
+use std::rc::Rc;
 use std::{fmt, process};
 
+use either::Either;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 use rustc_abi::{Align, ExternAbi, Size};
@@ -30,6 +32,9 @@
 use crate::concurrency::cpu_affinity::{self, CpuAffinityMask};
 use crate::concurrency::data_race::{self, NaReadType, NaWriteType};
 use crate::concurrency::weak_memory;
+use crate::coverage::Coverage;
+use crate::gdbserver::GdbServer;
+use crate::heap_profile::HeapProfile;
 use crate::*;
 
 /// First real-time signal.
@@ -481,13 +486,35 @@ pub struct MiriMachine<'tcx> {
     pub(crate) fds: shims::FdTable,
     /// The table of directory descriptors.
     pub(crate) dirs: shims::DirTable,
+    /// The virtual filesystem that file system accesses use under isolation, if any.
+    pub(crate) vfs: Option<shims::Vfs>,
 
     /// The list of all EpollEventInterest.
     pub(crate) epoll_interests: shims::EpollInterestTable,
 
+    /// The addresses of all bound sockets, and the sockets backed by the host.
+    pub(crate) sockets: shims::SocketTable,
+    /// The threads blocked in `poll` or `select`.
+    pub(crate) poll_waiters: shims::PollWaiters,
+    /// The configuration objects of `posix_spawn`, and the children that have been spawned.
+    pub(crate) processes: shims::ProcessTable,
+
     /// This machine's monotone clock.
     pub(crate) clock: Clock,
 
+    /// Records or replays the nondeterministic decisions of the execution.
+    pub(crate) recorder: Rc<Recorder>,
+
+    /// The execution counts of the basic blocks of the local crates, if a coverage report was
+    /// requested.
+    pub(crate) coverage: Option<Coverage<'tcx>>,
+
+    /// The heap allocations and their accesses, if a heap profile was requested.
+    pub(crate) heap_profile: Option<RefCell<HeapProfile<'tcx>>>,
+
+    /// The connection to the debugger, if one was requested and is still attached.
+    pub(crate) gdbserver: Option<GdbServer>,
+
     /// The set of threads.
     pub(crate) threads: ThreadManager<'tcx>,
 
@@ -632,6 +659,7 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx>) -> Self {
             measureme::Profiler::new(path).expect("Couldn't create `measureme` profiler")
         });
         let rng = StdRng::seed_from_u64(config.seed.unwrap_or(0));
+        let recorder = Rc::new(Recorder::new(config));
         let borrow_tracker = config.borrow_tracker.map(|bt| bt.instantiate_global_state(config));
         let data_race = config.data_race_detector.then(|| data_race::GlobalState::new(config));
         // Determine page size, stack address, and stack size.
@@ -666,7 +694,10 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx>) -> Self {
             cpu_affinity::MAX_CPUS,
             config.num_cpus
         );
-        let threads = ThreadManager::default();
+        let mut threads = ThreadManager::default();
+        if let Some(schedule) = &config.fixed_schedule {
+            threads.follow_schedule(schedule.clone());
+        }
         let mut thread_cpu_affinity = FxHashMap::default();
         if matches!(&*tcx.sess.target.os, "linux" | "freebsd" | "android") {
             thread_cpu_affinity
@@ -688,7 +719,11 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx>) -> Self {
             validation: config.validation,
             fds: shims::FdTable::init(config.mute_stdout_stderr),
             epoll_interests: shims::EpollInterestTable::new(),
+            sockets: shims::SocketTable::new(),
+            poll_waiters: shims::PollWaiters::default(),
+            processes: shims::ProcessTable::default(),
             dirs: Default::default(),
+            vfs: config.vfs.as_ref().map(shims::Vfs::new),
             layouts,
             threads,
             thread_cpu_affinity,
@@ -710,7 +745,18 @@ pub(crate) fn new(config: &MiriConfig, layout_cx: LayoutCx<'tcx>) -> Self {
             preemption_rate: config.preemption_rate,
             report_progress: config.report_progress,
             basic_block_count: 0,
-            clock: Clock::new(config.isolated_op == IsolatedOp::Allow),
+            clock: Clock::new(config.isolated_op == IsolatedOp::Allow, Rc::clone(&recorder)),
+            recorder,
+            coverage: config.coverage.clone().map(Coverage::new),
+            heap_profile: config
+                .heap_profile
+                .clone()
+                .map(|path| RefCell::new(HeapProfile::new(path))),
+            gdbserver: config.gdbserver.as_deref().map(|addr| {
+                GdbServer::accept(addr).unwrap_or_else(|err| {
+                    tcx.dcx().fatal(format!("failed to accept a debugger on `{addr}`: {err}"))
+                })
+            }),
             #[cfg(unix)]
             native_lib: config.native_lib.as_ref().map(|lib_file_path| {
                 let host_triple = rustc_session::config::host_tuple();
@@ -852,6 +898,18 @@ fn init_allocation(
             Some(ecx.generate_stacktrace())
         };
 
+        if let Some(heap_profile) = &ecx.machine.heap_profile {
+            if HeapProfile::is_heap(kind) {
+                heap_profile.borrow_mut().allocate(
+                    *ecx.tcx,
+                    ecx.active_thread_stack(),
+                    id,
+                    size.bytes(),
+                    ecx.machine.basic_block_count,
+                );
+            }
+        }
+
         if matches!(kind, MemoryKind::Machine(kind) if kind.should_save_allocation_span()) {
             ecx.machine
                 .allocation_spans
@@ -889,10 +947,18 @@ fn visit_provenance(&self, visit: &mut VisitWith<'_>) {
             alloc_addresses,
             fds,
             epoll_interests:_,
+            sockets: _,
+            poll_waiters: _,
+            processes: _,
+            vfs: _,
             tcx: _,
             isolated_op: _,
             validation: _,
             clock: _,
+            recorder: _,
+            coverage: _,
+            heap_profile: _,
+            gdbserver: _,
             layouts: _,
             static_roots: _,
             profiler: _,
@@ -1382,6 +1448,9 @@ fn before_memory_read(
         if let Some(weak_memory) = &alloc_extra.weak_memory {
             weak_memory.memory_accessed(range, machine.data_race.as_ref().unwrap());
         }
+        if let Some(heap_profile) = &machine.heap_profile {
+            heap_profile.borrow_mut().access(alloc_id, range, AccessKind::Read);
+        }
         interp_ok(())
     }
 
@@ -1406,6 +1475,9 @@ fn before_memory_write(
         if let Some(weak_memory) = &alloc_extra.weak_memory {
             weak_memory.memory_accessed(range, machine.data_race.as_ref().unwrap());
         }
+        if let Some(heap_profile) = &machine.heap_profile {
+            heap_profile.borrow_mut().access(alloc_id, range, AccessKind::Write);
+        }
         interp_ok(())
     }
 
@@ -1438,6 +1510,9 @@ fn before_memory_deallocation(
         {
             *deallocated_at = Some(machine.current_span());
         }
+        if let Some(heap_profile) = &machine.heap_profile {
+            heap_profile.borrow_mut().deallocate(alloc_id, machine.basic_block_count);
+        }
         machine.free_alloc_id(alloc_id, size, align, kind);
         interp_ok(())
     }
@@ -1561,6 +1636,20 @@ fn before_terminator(ecx: &mut InterpCx<'tcx, Self>) -> InterpResult<'tcx> {
         // Make sure some time passes.
         ecx.machine.clock.tick();
 
+        // Count this block for the coverage report.
+        if ecx.machine.coverage.is_some() {
+            let frame = ecx.frame();
+            let instance = frame.instance();
+            if let (ty::InstanceKind::Item(def_id), Either::Left(loc)) =
+                (instance.def, frame.current_loc())
+            {
+                if def_id.is_local() || ecx.machine.local_crates.contains(&def_id.krate) {
+                    let body = frame.body();
+                    ecx.machine.coverage.as_mut().unwrap().record(instance, body, loc.block);
+                }
+            }
+        }
+
         interp_ok(())
     }
 
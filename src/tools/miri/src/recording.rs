@@ -0,0 +1,562 @@
+//! Recording the nondeterministic decisions of an execution, to replay it later
+//! (`-Zmiri-record` and `-Zmiri-replay`).
+//!
+//! Most of Miri's nondeterminism is derived from its RNG, which is fully determined by the seed.
+//! Those decisions are still recorded, both to detect when a replay diverges and so that a replay
+//! does not depend on the RNG being consumed in exactly the same way. Everything the program
+//! learns from the host is recorded as well: the clocks and randomness when isolation is disabled,
+//! and the results of the shims that ask the host (data read from stdin, files and pipes, the
+//! results of writes and seeks, file metadata, directory entries, and resolved paths). A replay
+//! uses the recorded results instead of asking the host again.
+//!
+//! Host operations that create or change host state, like opening, writing or removing files and
+//! spawning processes, are still performed during a replay, so the files a program opens must
+//! exist on the replaying host as well. Their contents and metadata are taken from the recording.
+//! Communication over sockets backed by the host is not recorded at all, so a replay talks to the
+//! network again.
+
+use std::cell::{Cell, RefCell};
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufWriter, Write as _};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{fmt, fs};
+
+use rustc_middle::ty::TyCtxt;
+
+use crate::MiriConfig;
+
+/// The first line of a recording.
+const HEADER: &str = "miri-recording 1";
+
+/// A nondeterministic decision made during an execution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Event {
+    /// The active thread was preempted at the end of the given basic block. Basic blocks where it
+    /// was not preempted are not recorded.
+    Preemption(u64),
+    /// Whether a `compare_exchange_weak` that could have succeeded did succeed.
+    CmpxchgWeak(bool),
+    /// The base address assigned to an allocation.
+    AllocAddress(u64),
+    /// A reading of the host's monotonic clock, as the time since the start of the execution.
+    MonotonicClock(Duration),
+    /// A reading of the host's system clock, in nanoseconds since the Unix epoch.
+    SystemTime(i128),
+    /// Random bytes from the host.
+    HostRandom(Vec<u8>),
+    /// The result of a shim that asked the host, encoded by [`HostValue`], or the error code of
+    /// the operation.
+    Host(Result<Vec<u8>, Option<i32>>),
+}
+
+fn encode_bytes(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(s, "{byte:02x}").unwrap();
+    }
+    s
+}
+
+fn decode_bytes(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok()).collect()
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Preemption(block) => write!(f, "preemption {block}"),
+            Event::CmpxchgWeak(success) => write!(f, "cmpxchg-weak {}", u8::from(*success)),
+            Event::AllocAddress(addr) => write!(f, "alloc-address {addr}"),
+            Event::MonotonicClock(duration) => write!(f, "monotonic-clock {}", duration.as_nanos()),
+            Event::SystemTime(nanos) => write!(f, "system-time {nanos}"),
+            Event::HostRandom(bytes) => write!(f, "host-random {}", encode_bytes(bytes)),
+            Event::Host(Ok(bytes)) => write!(f, "host ok {}", encode_bytes(bytes)),
+            Event::Host(Err(Some(code))) => write!(f, "host err {code}"),
+            Event::Host(Err(None)) => write!(f, "host err -"),
+        }
+    }
+}
+
+impl Event {
+    fn parse(line: &str) -> Option<Event> {
+        let (kind, value) = line.split_once(' ')?;
+        Some(match kind {
+            "preemption" => Event::Preemption(value.parse().ok()?),
+            "cmpxchg-weak" =>
+                Event::CmpxchgWeak(match value {
+                    "0" => false,
+                    "1" => true,
+                    _ => return None,
+                }),
+            "alloc-address" => Event::AllocAddress(value.parse().ok()?),
+            "monotonic-clock" => {
+                let nanos: u128 = value.parse().ok()?;
+                let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+                let nanos = u32::try_from(nanos % 1_000_000_000).unwrap();
+                Event::MonotonicClock(Duration::new(secs, nanos))
+            }
+            "system-time" => Event::SystemTime(value.parse().ok()?),
+            "host-random" => Event::HostRandom(decode_bytes(value)?),
+            "host" =>
+                Event::Host(match value.split_once(' ')? {
+                    ("ok", bytes) => Ok(decode_bytes(bytes)?),
+                    ("err", "-") => Err(None),
+                    ("err", code) => Err(Some(code.parse().ok()?)),
+                    _ => return None,
+                }),
+            _ => return None,
+        })
+    }
+}
+
+/// A result of the host that can be recorded.
+pub trait HostValue: Sized {
+    fn to_bytes(&self) -> Vec<u8>;
+    /// Returns `None` if `bytes` is not a recorded value of this type.
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+impl HostValue for Vec<u8> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+impl HostValue for u64 {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(u64::from_le_bytes(bytes.try_into().ok()?))
+    }
+}
+
+impl HostValue for usize {
+    fn to_bytes(&self) -> Vec<u8> {
+        u64::try_from(*self).unwrap().to_bytes()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        usize::try_from(u64::from_bytes(bytes)?).ok()
+    }
+}
+
+impl HostValue for SystemTime {
+    fn to_bytes(&self) -> Vec<u8> {
+        system_time_to_nanos(*self).to_le_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(nanos_to_system_time(i128::from_le_bytes(bytes.try_into().ok()?)))
+    }
+}
+
+/// Like the environment, paths are recorded as UTF-8, so that a recording can be replayed on
+/// another platform.
+impl HostValue for PathBuf {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_string_lossy().into_owned().into_bytes()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(PathBuf::from(String::from_utf8(bytes.to_vec()).ok()?))
+    }
+}
+
+impl<T: HostValue> HostValue for Option<T> {
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            None => vec![0],
+            Some(value) => {
+                let mut bytes = vec![1];
+                bytes.extend(value.to_bytes());
+                bytes
+            }
+        }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes.split_first()? {
+            (0, []) => Some(None),
+            (1, value) => Some(Some(T::from_bytes(value)?)),
+            _ => None,
+        }
+    }
+}
+
+fn system_time_to_nanos(time: SystemTime) -> i128 {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(duration) => i128::try_from(duration.as_nanos()).unwrap(),
+        Err(err) => -i128::try_from(err.duration().as_nanos()).unwrap(),
+    }
+}
+
+fn nanos_to_system_time(nanos: i128) -> SystemTime {
+    let duration = Duration::from_nanos(u64::try_from(nanos.unsigned_abs()).unwrap());
+    if nanos >= 0 { SystemTime::UNIX_EPOCH + duration } else { SystemTime::UNIX_EPOCH - duration }
+}
+
+/// The recorded nondeterministic decisions of an execution, and the configuration they depend on.
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    /// The seed of Miri's RNG.
+    pub seed: u64,
+    /// The host environment. Variables that are not valid UTF-8 are not recorded.
+    pub env: Vec<(OsString, OsString)>,
+    events: Vec<Event>,
+}
+
+impl Recording {
+    /// Loads a recording saved by `-Zmiri-record`.
+    pub fn load(path: &Path) -> Result<Recording, String> {
+        let file = fs::File::open(path).map_err(|err| err.to_string())?;
+        let mut lines = io::BufReader::new(file).lines().enumerate();
+        let mut next_line = || -> Result<Option<(usize, String)>, String> {
+            lines
+                .next()
+                .map(|(i, line)| Ok((i + 1, line.map_err(|err| err.to_string())?)))
+                .transpose()
+        };
+        if next_line()?.is_none_or(|(_, line)| line != HEADER) {
+            return Err("this is not a recording of this version of Miri".to_owned());
+        }
+        let mut recording = Recording::default();
+        while let Some((line_num, line)) = next_line()? {
+            let invalid = || format!("invalid line {line_num}: `{line}`");
+            if let Some(seed) = line.strip_prefix("seed ") {
+                recording.seed = seed.parse().map_err(|_| invalid())?;
+            } else if let Some(var) = line.strip_prefix("env ") {
+                let (name, value) = var.split_once(' ').ok_or_else(invalid)?;
+                let decode =
+                    |s: &str| decode_bytes(s).and_then(|bytes| String::from_utf8(bytes).ok());
+                let name = decode(name).ok_or_else(invalid)?;
+                let value = decode(value).ok_or_else(invalid)?;
+                recording.env.push((name.into(), value.into()));
+            } else {
+                recording.events.push(Event::parse(&line).ok_or_else(invalid)?);
+            }
+        }
+        Ok(recording)
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        let mut file = BufWriter::new(fs::File::create(path)?);
+        writeln!(file, "{HEADER}")?;
+        writeln!(file, "seed {}", self.seed)?;
+        for (name, value) in &self.env {
+            if let (Some(name), Some(value)) = (name.to_str(), value.to_str()) {
+                let name = encode_bytes(name.as_bytes());
+                let value = encode_bytes(value.as_bytes());
+                writeln!(file, "env {name} {value}")?;
+            }
+        }
+        for event in &self.events {
+            writeln!(file, "{event}")?;
+        }
+        file.flush()
+    }
+}
+
+#[derive(Debug)]
+enum Mode {
+    Off,
+    Record {
+        path: PathBuf,
+        recording: RefCell<Recording>,
+    },
+    Replay {
+        events: Vec<Event>,
+        /// The index of the next event to replay.
+        next: Cell<usize>,
+        /// Whether the execution diverged from the recording, and thus is no longer replayed.
+        diverged: Cell<bool>,
+    },
+}
+
+/// Records or replays the nondeterministic decisions of an execution.
+#[derive(Debug)]
+pub struct Recorder {
+    mode: Mode,
+}
+
+impl Recorder {
+    pub fn new(config: &MiriConfig) -> Self {
+        let mode = if let Some(recording) = &config.replay {
+            Mode::Replay {
+                events: recording.events.clone(),
+                next: Cell::new(0),
+                diverged: Cell::new(false),
+            }
+        } else if let Some(path) = &config.record {
+            let recording = Recording {
+                seed: config.seed.unwrap_or(0),
+                env: config.env.clone(),
+                events: Vec::new(),
+            };
+            Mode::Record { path: path.clone(), recording: RefCell::new(recording) }
+        } else {
+            Mode::Off
+        };
+        Recorder { mode }
+    }
+
+    /// Returns whether host operations are replayed rather than performed.
+    fn is_replaying(&self) -> bool {
+        matches!(&self.mode, Mode::Replay { diverged, .. } if !diverged.get())
+    }
+
+    fn record(&self, event: Event) {
+        if let Mode::Record { recording, .. } = &self.mode {
+            recording.borrow_mut().events.push(event);
+        }
+    }
+
+    /// If replaying, returns the value of the next event if `extract` accepts it. If it does not,
+    /// the execution diverged from the recording, so replaying stops.
+    fn replay<T>(&self, extract: impl FnOnce(&Event) -> Option<T>) -> Option<T> {
+        let Mode::Replay { events, next, diverged } = &self.mode else {
+            return None;
+        };
+        if diverged.get() {
+            return None;
+        }
+        match events.get(next.get()).and_then(extract) {
+            Some(value) => {
+                next.set(next.get() + 1);
+                Some(value)
+            }
+            None => {
+                diverged.set(true);
+                None
+            }
+        }
+    }
+
+    /// Decides whether to preempt the active thread at the end of the given basic block, where
+    /// `preempt` is Miri's own decision.
+    pub fn preemption(&self, block: u64, preempt: bool) -> bool {
+        if let Mode::Replay { events, next, diverged } = &self.mode
+            && !diverged.get()
+        {
+            return match events.get(next.get()) {
+                Some(&Event::Preemption(recorded)) if recorded == block => {
+                    next.set(next.get() + 1);
+                    true
+                }
+                Some(&Event::Preemption(recorded)) if recorded < block => {
+                    diverged.set(true);
+                    preempt
+                }
+                // The next preemption, if any, happens later.
+                _ => false,
+            };
+        }
+        if preempt {
+            self.record(Event::Preemption(block));
+        }
+        preempt
+    }
+
+    /// Decides whether a `compare_exchange_weak` that could succeed does succeed, where `success`
+    /// is Miri's own decision.
+    pub fn cmpxchg_weak(&self, success: bool) -> bool {
+        if let Some(success) = self.replay(|event| {
+            match event {
+                Event::CmpxchgWeak(success) => Some(*success),
+                _ => None,
+            }
+        }) {
+            return success;
+        }
+        self.record(Event::CmpxchgWeak(success));
+        success
+    }
+
+    /// Records the base address assigned to an allocation. When replaying, this only checks that
+    /// the address is the recorded one, since the choice of addresses depends on more state.
+    pub fn alloc_address(&self, addr: u64) {
+        self.replay(|event| (*event == Event::AllocAddress(addr)).then_some(()));
+        self.record(Event::AllocAddress(addr));
+    }
+
+    /// Reads the host's monotonic clock via `now`, which returns the time since the start of the
+    /// execution.
+    pub fn monotonic_clock(&self, now: impl FnOnce() -> Duration) -> Duration {
+        if let Some(duration) = self.replay(|event| {
+            match event {
+                Event::MonotonicClock(duration) => Some(*duration),
+                _ => None,
+            }
+        }) {
+            return duration;
+        }
+        let duration = now();
+        self.record(Event::MonotonicClock(duration));
+        duration
+    }
+
+    /// Reads the host's system clock via `now`.
+    pub fn system_time(&self, now: impl FnOnce() -> SystemTime) -> SystemTime {
+        if let Some(nanos) = self.replay(|event| {
+            match event {
+                Event::SystemTime(nanos) => Some(*nanos),
+                _ => None,
+            }
+        }) {
+            return nanos_to_system_time(nanos);
+        }
+        let time = now();
+        self.record(Event::SystemTime(system_time_to_nanos(time)));
+        time
+    }
+
+    /// Gets random bytes from the host via `fill`.
+    pub fn host_random<E>(&self, fill: impl FnOnce() -> Result<Vec<u8>, E>) -> Result<Vec<u8>, E> {
+        if let Some(bytes) = self.replay(|event| {
+            match event {
+                Event::HostRandom(bytes) => Some(bytes.clone()),
+                _ => None,
+            }
+        }) {
+            return Ok(bytes);
+        }
+        let bytes = fill()?;
+        self.record(Event::HostRandom(bytes.clone()));
+        Ok(bytes)
+    }
+
+    /// Asks the host via `op`, which must not have any effect on the host other than computing
+    /// its result, since it is not called when replaying.
+    pub fn host<T: HostValue>(&self, op: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+        if let Some(result) = self.replay(|event| {
+            match event {
+                Event::Host(Ok(bytes)) => T::from_bytes(bytes).map(Ok),
+                Event::Host(Err(code)) => Some(Err(*code)),
+                _ => None,
+            }
+        }) {
+            return result.map_err(|code| {
+                match code {
+                    Some(code) => io::Error::from_raw_os_error(code),
+                    None => io::Error::other("replayed host error"),
+                }
+            });
+        }
+        let result = op();
+        let recorded = match &result {
+            Ok(value) => Ok(value.to_bytes()),
+            Err(err) => Err(err.raw_os_error()),
+        };
+        self.record(Event::Host(recorded));
+        result
+    }
+
+    /// Performs an operation that changes the host via `op`. Unlike [`Recorder::host`], the
+    /// operation is performed when replaying as well, but its recorded result is used.
+    pub fn host_effect<T: HostValue>(&self, op: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+        if !self.is_replaying() {
+            return self.host(op);
+        }
+        let result = op();
+        // Unless the execution diverged here, the result on this host is replaced.
+        self.host(|| result)
+    }
+
+    /// Saves the recording, or reports whether the replay diverged from the recording. To be
+    /// called at the end of the execution.
+    pub fn finish(&self, tcx: TyCtxt<'_>) {
+        match &self.mode {
+            Mode::Off => {}
+            Mode::Record { path, recording } =>
+                if let Err(err) = recording.borrow().save(path) {
+                    tcx.dcx().err(format!(
+                        "failed to save the recording to `{}`: {err}",
+                        path.display()
+                    ));
+                },
+            Mode::Replay { events, next, diverged } => {
+                let replayed = next.get();
+                if diverged.get() || replayed < events.len() {
+                    tcx.dcx().warn(format!(
+                        "the execution diverged from the recording after {replayed} of its {} decisions",
+                        events.len()
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn print_and_parse() {
+        let events = [
+            Event::Preemption(42),
+            Event::CmpxchgWeak(false),
+            Event::AllocAddress(0x1000),
+            Event::MonotonicClock(Duration::new(3, 14)),
+            Event::SystemTime(-5),
+            Event::HostRandom(vec![0, 0xab, 0xff]),
+            Event::Host(Ok(vec![])),
+            Event::Host(Ok(vec![0, 0xab, 0xff])),
+            Event::Host(Err(Some(2))),
+            Event::Host(Err(None)),
+        ];
+        for event in events {
+            assert_eq!(Event::parse(&event.to_string()), Some(event));
+        }
+        assert_eq!(Event::parse("host ok abc"), None);
+        assert_eq!(Event::parse("host-random abc"), None);
+        assert_eq!(Event::parse("cmpxchg-weak 2"), None);
+        assert_eq!(Event::parse("preemption"), None);
+    }
+
+    #[test]
+    fn host_values() {
+        fn roundtrip<T: HostValue + PartialEq + fmt::Debug>(value: T) {
+            assert_eq!(T::from_bytes(&value.to_bytes()), Some(value));
+        }
+        roundtrip(vec![1u8, 2, 3]);
+        roundtrip(42u64);
+        roundtrip(Some(7usize));
+        roundtrip(None::<u64>);
+        roundtrip(PathBuf::from("a/b.txt"));
+        roundtrip(SystemTime::UNIX_EPOCH - Duration::new(1, 5));
+        assert_eq!(u64::from_bytes(&[1, 2]), None);
+        assert_eq!(Option::<u64>::from_bytes(&[2]), None);
+    }
+
+    #[test]
+    fn replay() {
+        let config = MiriConfig {
+            replay: Some(Recording {
+                events: vec![Event::Preemption(3), Event::CmpxchgWeak(false), Event::Preemption(7)],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let recorder = Recorder::new(&config);
+        let diverged = || matches!(&recorder.mode, Mode::Replay { diverged, .. } if diverged.get());
+        assert!(!recorder.preemption(1, true));
+        assert!(recorder.preemption(3, false));
+        assert!(!recorder.cmpxchg_weak(true));
+        assert!(!diverged());
+        // The recorded preemption at block 7 is missed, so the execution diverged.
+        assert!(!recorder.preemption(9, false));
+        assert!(diverged());
+        assert!(recorder.cmpxchg_weak(true));
+    }
+}
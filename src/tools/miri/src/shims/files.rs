@@ -192,6 +192,12 @@ fn is_tty(&self, _communicate_allowed: bool) -> bool {
         false
     }
 
+    /// Whether `UnixFileDescription::get_epoll_ready_events` reports the readiness of this file
+    /// description. Those that don't are always ready for reading and writing.
+    fn tracks_readiness(&self) -> bool {
+        false
+    }
+
     fn as_unix(&self) -> &dyn UnixFileDescription {
         panic!("Not a unix file descriptor: {}", self.name());
     }
@@ -396,30 +402,53 @@ pub fn is_fd_num(&self, fd_num: i32) -> bool {
 impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
 pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
     /// Read data from a host `Read` type, store the result into machine memory,
-    /// and return whether that worked.
+    /// and return whether that worked. The data is recorded for `-Zmiri-record`, so when
+    /// replaying, `file` is not read at all.
     fn read_from_host(
         &mut self,
-        mut file: impl io::Read,
+        file: impl io::Read,
         len: usize,
         ptr: Pointer,
     ) -> InterpResult<'tcx, Result<usize, IoError>> {
         let this = self.eval_context_mut();
 
-        let mut bytes = vec![0; len];
-        let result = file.read(&mut bytes);
+        let result = this.machine.recorder.host(|| read_bytes(file, len));
+        this.write_read_result(result, ptr)
+    }
+
+    /// Like `read_from_host`, but for the buffers Miri keeps itself, e.g. for sockets. Reading
+    /// them is deterministic, so this is not recorded.
+    fn read_from_buffer(
+        &mut self,
+        buf: impl io::Read,
+        len: usize,
+        ptr: Pointer,
+    ) -> InterpResult<'tcx, Result<usize, IoError>> {
+        let this = self.eval_context_mut();
+
+        let result = read_bytes(buf, len);
+        this.write_read_result(result, ptr)
+    }
+
+    fn write_read_result(
+        &mut self,
+        result: io::Result<Vec<u8>>,
+        ptr: Pointer,
+    ) -> InterpResult<'tcx, Result<usize, IoError>> {
+        let this = self.eval_context_mut();
+
         match result {
-            Ok(read_size) => {
+            Ok(bytes) => {
                 // If reading to `bytes` did not fail, we write those bytes to the buffer.
-                // Crucially, if fewer than `bytes.len()` bytes were read, only write
-                // that much into the output buffer!
-                this.write_bytes_ptr(ptr, bytes[..read_size].iter().copied())?;
-                interp_ok(Ok(read_size))
+                this.write_bytes_ptr(ptr, bytes.iter().copied())?;
+                interp_ok(Ok(bytes.len()))
             }
             Err(e) => interp_ok(Err(IoError::HostError(e))),
         }
     }
 
-    /// Write data to a host `Write` type, withthe bytes taken from machine memory.
+    /// Write data to a host `Write` type, with the bytes taken from machine memory. The write is
+    /// performed when replaying a recording as well, but its result is recorded.
     fn write_to_host(
         &mut self,
         mut file: impl io::Write,
@@ -429,7 +458,31 @@ fn write_to_host(
         let this = self.eval_context_mut();
 
         let bytes = this.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?;
-        let result = file.write(bytes);
+        let result = this.machine.recorder.host_effect(|| file.write(bytes));
         interp_ok(result.map_err(IoError::HostError))
     }
+
+    /// Like `write_to_host`, but for the buffers Miri keeps itself, which is not recorded.
+    fn write_to_buffer(
+        &mut self,
+        mut buf: impl io::Write,
+        len: usize,
+        ptr: Pointer,
+    ) -> InterpResult<'tcx, Result<usize, IoError>> {
+        let this = self.eval_context_mut();
+
+        let bytes = this.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?;
+        let result = buf.write(bytes);
+        interp_ok(result.map_err(IoError::HostError))
+    }
+}
+
+/// Reads up to `len` bytes from `file`.
+fn read_bytes(mut file: impl io::Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0; len];
+    let read_size = file.read(&mut bytes)?;
+    // Crucially, if fewer than `len` bytes were read, only keep that many, so that we only write
+    // that much into the output buffer!
+    bytes.truncate(read_size);
+    Ok(bytes)
 }
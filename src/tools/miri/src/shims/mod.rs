@@ -21,7 +21,9 @@
 pub mod tls;
 
 pub use self::files::FdTable;
-pub use self::unix::{DirTable, EpollInterestTable};
+pub use self::unix::{
+    DirTable, EpollInterestTable, PollWaiters, ProcessTable, SocketTable, Vfs, VfsSnapshot,
+};
 
 /// What needs to be done after emulating an item (a shim or an intrinsic) is done.
 pub enum EmulateItemResult {
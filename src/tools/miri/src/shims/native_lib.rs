@@ -1,11 +1,12 @@
 //! Implements calling functions from a native library.
 use std::ops::Deref;
 
-use libffi::high::call as ffi;
 use libffi::low::CodePtr;
+use libffi::middle as ffi;
 use rustc_abi::{BackendRepr, HasDataLayout, Size};
-use rustc_middle::mir::interpret::Pointer;
-use rustc_middle::ty::{self as ty, IntTy, UintTy};
+use rustc_middle::mir::interpret::{Pointer, read_target_uint, write_target_uint};
+use rustc_middle::ty::layout::TyAndLayout;
+use rustc_middle::ty::{self as ty, FloatTy, IntTy, UintTy};
 use rustc_span::Symbol;
 
 use crate::*;
@@ -13,12 +14,12 @@
 impl<'tcx> EvalContextExtPriv<'tcx> for crate::MiriInterpCx<'tcx> {}
 trait EvalContextExtPriv<'tcx>: crate::MiriInterpCxExt<'tcx> {
     /// Call native host function and return the output as an immediate.
-    fn call_native_with_args<'a>(
+    fn call_native_with_args(
         &mut self,
         link_name: Symbol,
         dest: &MPlaceTy<'tcx>,
         ptr: CodePtr,
-        libffi_args: Vec<libffi::high::Arg<'a>>,
+        libffi_args: &[CArg],
     ) -> InterpResult<'tcx, ImmTy<'tcx>> {
         let this = self.eval_context_mut();
 
@@ -30,54 +31,54 @@ fn call_native_with_args<'a>(
                 // Unsafe because of the call to native code.
                 // Because this is calling a C function it is not necessarily sound,
                 // but there is no way around this and we've checked as much as we can.
-                let x = unsafe { ffi::call::<i8>(ptr, libffi_args.as_slice()) };
+                let x = unsafe { call::<i8>(ptr, libffi_args, ffi::Type::i8()) };
                 Scalar::from_i8(x)
             }
             ty::Int(IntTy::I16) => {
-                let x = unsafe { ffi::call::<i16>(ptr, libffi_args.as_slice()) };
+                let x = unsafe { call::<i16>(ptr, libffi_args, ffi::Type::i16()) };
                 Scalar::from_i16(x)
             }
             ty::Int(IntTy::I32) => {
-                let x = unsafe { ffi::call::<i32>(ptr, libffi_args.as_slice()) };
+                let x = unsafe { call::<i32>(ptr, libffi_args, ffi::Type::i32()) };
                 Scalar::from_i32(x)
             }
             ty::Int(IntTy::I64) => {
-                let x = unsafe { ffi::call::<i64>(ptr, libffi_args.as_slice()) };
+                let x = unsafe { call::<i64>(ptr, libffi_args, ffi::Type::i64()) };
                 Scalar::from_i64(x)
             }
             ty::Int(IntTy::Isize) => {
-                let x = unsafe { ffi::call::<isize>(ptr, libffi_args.as_slice()) };
+                let x = unsafe { call::<isize>(ptr, libffi_args, ffi::Type::isize()) };
                 Scalar::from_target_isize(x.try_into().unwrap(), this)
             }
             // uints
             ty::Uint(UintTy::U8) => {
-                let x = unsafe { ffi::call::<u8>(ptr, libffi_args.as_slice()) };
+                let x = unsafe { call::<u8>(ptr, libffi_args, ffi::Type::u8()) };
                 Scalar::from_u8(x)
             }
             ty::Uint(UintTy::U16) => {
-                let x = unsafe { ffi::call::<u16>(ptr, libffi_args.as_slice()) };
+                let x = unsafe { call::<u16>(ptr, libffi_args, ffi::Type::u16()) };
                 Scalar::from_u16(x)
             }
             ty::Uint(UintTy::U32) => {
-                let x = unsafe { ffi::call::<u32>(ptr, libffi_args.as_slice()) };
+                let x = unsafe { call::<u32>(ptr, libffi_args, ffi::Type::u32()) };
                 Scalar::from_u32(x)
             }
             ty::Uint(UintTy::U64) => {
-                let x = unsafe { ffi::call::<u64>(ptr, libffi_args.as_slice()) };
+                let x = unsafe { call::<u64>(ptr, libffi_args, ffi::Type::u64()) };
                 Scalar::from_u64(x)
             }
             ty::Uint(UintTy::Usize) => {
-                let x = unsafe { ffi::call::<usize>(ptr, libffi_args.as_slice()) };
+                let x = unsafe { call::<usize>(ptr, libffi_args, ffi::Type::usize()) };
                 Scalar::from_target_usize(x.try_into().unwrap(), this)
             }
             // Functions with no declared return type (i.e., the default return)
             // have the output_type `Tuple([])`.
             ty::Tuple(t_list) if t_list.is_empty() => {
-                unsafe { ffi::call::<()>(ptr, libffi_args.as_slice()) };
+                unsafe { call::<()>(ptr, libffi_args, ffi::Type::void()) };
                 return interp_ok(ImmTy::uninit(dest.layout));
             }
             ty::RawPtr(..) => {
-                let x = unsafe { ffi::call::<*const ()>(ptr, libffi_args.as_slice()) };
+                let x = unsafe { call::<*const ()>(ptr, libffi_args, ffi::Type::pointer()) };
                 let ptr = Pointer::new(Provenance::Wildcard, Size::from_bytes(x.addr()));
                 Scalar::from_pointer(ptr, this)
             }
@@ -127,6 +128,125 @@ fn get_func_ptr_explicitly_from_lib(&mut self, link_name: Symbol) -> Option<Code
         // Return a pointer to the function.
         Some(CodePtr(*func.deref() as *mut _))
     }
+
+    /// Expose the provenance of a pointer passed to native code. Below, all exposed memory
+    /// (previously exposed and new exposed) will then be properly prepared.
+    fn expose_ptr_for_native_call(&mut self, ptr: Pointer) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        let Some(prov) = ptr.provenance else {
+            // Pointer without provenance may not access any memory anyway, skip.
+            return interp_ok(());
+        };
+        // The first time this happens, print a warning.
+        if !this.machine.native_call_mem_warned.replace(true) {
+            // Newly set, so first time we get here.
+            this.emit_diagnostic(NonHaltingDiagnostic::NativeCallSharedMem);
+        }
+        this.expose_provenance(prov)
+    }
+
+    /// Compute the `libffi` type of a `repr(C)` struct passed to or returned from native code by
+    /// value, or of one of its fields.
+    fn struct_field_ffi_type(&self, layout: TyAndLayout<'tcx>) -> InterpResult<'tcx, ffi::Type> {
+        let this = self.eval_context_ref();
+        interp_ok(match layout.ty.kind() {
+            ty::Int(IntTy::I8) => ffi::Type::i8(),
+            ty::Int(IntTy::I16) => ffi::Type::i16(),
+            ty::Int(IntTy::I32) => ffi::Type::i32(),
+            ty::Int(IntTy::I64) => ffi::Type::i64(),
+            ty::Int(IntTy::Isize) => ffi::Type::isize(),
+            ty::Uint(UintTy::U8) => ffi::Type::u8(),
+            ty::Uint(UintTy::U16) => ffi::Type::u16(),
+            ty::Uint(UintTy::U32) => ffi::Type::u32(),
+            ty::Uint(UintTy::U64) => ffi::Type::u64(),
+            ty::Uint(UintTy::Usize) => ffi::Type::usize(),
+            ty::Float(FloatTy::F32) => ffi::Type::f32(),
+            ty::Float(FloatTy::F64) => ffi::Type::f64(),
+            // `bool` has the same size and ABI as C's `_Bool`, which `libffi` passes like a `u8`.
+            ty::Bool => ffi::Type::u8(),
+            ty::RawPtr(..) => ffi::Type::pointer(),
+            _ if is_repr_c_struct(layout.ty) => {
+                // `libffi` cannot represent empty structs.
+                if layout.fields.count() == 0 {
+                    throw_unsup_format!(
+                        "empty struct `{}` cannot be passed to a native call",
+                        layout.ty
+                    );
+                }
+                let fields = (0..layout.fields.count())
+                    .map(|i| this.struct_field_ffi_type(layout.field(this, i)))
+                    .collect::<InterpResult<'tcx, Vec<_>>>()?;
+                ffi::Type::structure(fields)
+            }
+            _ =>
+                throw_unsup_format!(
+                    "unsupported field type for a struct passed to a native call: {}",
+                    layout.ty
+                ),
+        })
+    }
+
+    /// Write the fields of the `repr(C)` struct `op` to `bytes`, starting at `offset`, in the
+    /// representation native code expects. Padding is left zeroed.
+    fn write_struct_for_native_call(
+        &mut self,
+        op: &OpTy<'tcx>,
+        bytes: &mut [u8],
+        offset: Size,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        for i in 0..op.layout.fields.count() {
+            let field = this.project_field(op, i)?;
+            let offset = offset + op.layout.fields.offset(i);
+            if is_repr_c_struct(field.layout.ty) {
+                this.write_struct_for_native_call(&field, bytes, offset)?;
+                continue;
+            }
+            let scalar = this.read_scalar(&field)?;
+            let bits = if matches!(field.layout.ty.kind(), ty::RawPtr(..)) {
+                let ptr = scalar.to_pointer(this)?;
+                this.expose_ptr_for_native_call(ptr)?;
+                ptr.addr().bytes().into()
+            } else {
+                scalar.to_bits(field.layout.size)?
+            };
+            let range = offset.bytes_usize()..(offset + field.layout.size).bytes_usize();
+            write_target_uint(this.data_layout().endian, &mut bytes[range], bits).unwrap();
+        }
+        interp_ok(())
+    }
+
+    /// Write the fields of a `repr(C)` struct returned by native code, read from `bytes` starting
+    /// at `offset`, to `dest`. Pointers get wildcard provenance, like pointers returned directly.
+    fn read_struct_from_native_call(
+        &mut self,
+        dest: &MPlaceTy<'tcx>,
+        bytes: &[u8],
+        offset: Size,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+        for i in 0..dest.layout.fields.count() {
+            let field = this.project_field(dest, i)?;
+            let offset = offset + dest.layout.fields.offset(i);
+            if is_repr_c_struct(field.layout.ty) {
+                this.read_struct_from_native_call(&field, bytes, offset)?;
+                continue;
+            }
+            let range = offset.bytes_usize()..(offset + field.layout.size).bytes_usize();
+            let bits = read_target_uint(this.data_layout().endian, &bytes[range]).unwrap();
+            let scalar = if matches!(field.layout.ty.kind(), ty::RawPtr(..)) {
+                let addr = u64::try_from(bits).unwrap();
+                Scalar::from_pointer(
+                    Pointer::new(Provenance::Wildcard, Size::from_bytes(addr)),
+                    this,
+                )
+            } else {
+                Scalar::from_uint(bits, field.layout.size)
+            };
+            this.write_scalar(scalar, &field)?;
+        }
+        interp_ok(())
+    }
 }
 
 impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
@@ -155,50 +275,108 @@ fn call_native_fn(
         // Get the function arguments, and convert them to `libffi`-compatible form.
         let mut libffi_args = Vec::<CArg>::with_capacity(args.len());
         for arg in args.iter() {
+            // `repr(C)` structs are passed by value, with the same layout as in C.
+            if is_repr_c_struct(arg.layout.ty) {
+                let ty = this.struct_field_ffi_type(arg.layout)?;
+                let mut bytes = vec![0; arg.layout.size.bytes_usize()];
+                // Pointers in the struct get exposed here.
+                this.write_struct_for_native_call(arg, &mut bytes, Size::ZERO)?;
+                // `u64`s make sure the struct is aligned enough, like for returned structs.
+                let words = bytes
+                    .chunks(8)
+                    .map(|chunk| {
+                        let mut word = [0; 8];
+                        word[..chunk.len()].copy_from_slice(chunk);
+                        u64::from_ne_bytes(word)
+                    })
+                    .collect();
+                libffi_args.push(CArg::Struct { ty, words });
+                continue;
+            }
             if !matches!(arg.layout.backend_repr, BackendRepr::Scalar(_)) {
-                throw_unsup_format!("only scalar argument types are support for native calls")
+                throw_unsup_format!(
+                    "only scalar and `repr(C)` struct argument types are supported for native calls, \
+                     and the structs cannot be `packed` or have an `align`"
+                )
             }
             let imm = this.read_immediate(arg)?;
             libffi_args.push(imm_to_carg(&imm, this)?);
-            // If we are passing a pointer, expose its provenance. Below, all exposed memory
-            // (previously exposed and new exposed) will then be properly prepared.
+            // If we are passing a pointer, expose its provenance.
             if matches!(arg.layout.ty.kind(), ty::RawPtr(..)) {
                 let ptr = imm.to_scalar().to_pointer(this)?;
-                let Some(prov) = ptr.provenance else {
-                    // Pointer without provenance may not access any memory anyway, skip.
-                    continue;
-                };
-                // The first time this happens, print a warning.
-                if !this.machine.native_call_mem_warned.replace(true) {
-                    // Newly set, so first time we get here.
-                    this.emit_diagnostic(NonHaltingDiagnostic::NativeCallSharedMem);
-                }
-
-                this.expose_provenance(prov)?;
+                this.expose_ptr_for_native_call(ptr)?;
             }
         }
 
         // Prepare all exposed memory.
         this.prepare_exposed_for_native_call()?;
 
-        // Convert them to `libffi::high::Arg` type.
-        let libffi_args = libffi_args
-            .iter()
-            .map(|arg| arg.arg_downcast())
-            .collect::<Vec<libffi::high::Arg<'_>>>();
+        // `repr(C)` structs are returned by value as well.
+        if is_repr_c_struct(dest.layout.ty) {
+            let ty = this.struct_field_ffi_type(dest.layout)?;
+            // Unsafe because of the call to native code.
+            let bytes = unsafe {
+                call_returning_struct(code_ptr, &libffi_args, ty, dest.layout.size.bytes_usize())
+            };
+            this.read_struct_from_native_call(dest, &bytes, Size::ZERO)?;
+            return interp_ok(true);
+        }
 
         // Call the function and store output, depending on return type in the function signature.
-        let ret = this.call_native_with_args(link_name, dest, code_ptr, libffi_args)?;
+        let ret = this.call_native_with_args(link_name, dest, code_ptr, &libffi_args)?;
         this.write_immediate(*ret, dest)?;
         interp_ok(true)
     }
 }
 
+/// Calls `fun` with `args`, where `ret` is the `libffi` type of `R`.
+///
+/// Unsafe because this calls native code, which can do anything.
+unsafe fn call<R>(fun: CodePtr, args: &[CArg], ret: ffi::Type) -> R {
+    let cif = ffi::Cif::new(args.iter().map(CArg::ffi_type), ret);
+    let args = args.iter().map(CArg::arg_downcast).collect::<Vec<_>>();
+    unsafe { cif.call(fun, &args) }
+}
+
+/// Calls `fun` with `args`, where `ret` is the `libffi` type of a struct of `size` bytes, and
+/// returns the bytes of the struct `fun` returns.
+///
+/// Unsafe because this calls native code, which can do anything.
+unsafe fn call_returning_struct(
+    fun: CodePtr,
+    args: &[CArg],
+    ret: ffi::Type,
+    size: usize,
+) -> Vec<u8> {
+    let cif = ffi::Cif::new(args.iter().map(CArg::ffi_type), ret);
+    let mut args = args.iter().map(CArg::arg_downcast).collect::<Vec<_>>();
+    // `libffi` stores the returned struct here. `u64`s make sure it is aligned enough.
+    let mut ret = vec![0u64; size.div_ceil(8).max(1)];
+    unsafe {
+        libffi::raw::ffi_call(
+            cif.as_raw_ptr(),
+            Some(*fun.as_fun()),
+            ret.as_mut_ptr().cast(),
+            args.as_mut_ptr().cast(),
+        )
+    };
+    ret.iter().flat_map(|word| word.to_ne_bytes()).take(size).collect()
+}
+
+/// Returns whether `ty` is a `repr(C)` struct, which can be passed by value to native code.
+/// `libffi` only knows the natural layout of C structs, so `packed` and `align` are not supported.
+fn is_repr_c_struct(ty: ty::Ty<'_>) -> bool {
+    matches!(ty.kind(), ty::Adt(adt, _) if adt.is_struct()
+        && adt.repr().c()
+        && !adt.repr().packed()
+        && adt.repr().align.is_none())
+}
+
 #[derive(Debug, Clone)]
 /// Enum of supported arguments to external C functions.
-// We introduce this enum instead of just calling `ffi::arg` and storing a list
-// of `libffi::high::Arg` directly, because the `libffi::high::Arg` just wraps a reference
-// to the value it represents: https://docs.rs/libffi/latest/libffi/high/call/struct.Arg.html
+// We introduce this enum instead of just storing a list of `libffi::middle::Arg` directly,
+// because the `libffi::middle::Arg` just wraps a pointer to the value it represents:
+// https://docs.rs/libffi/latest/libffi/middle/struct.Arg.html
 // and we need to store a copy of the value, and pass a reference to this copy to C instead.
 enum CArg {
     /// 8-bit signed integer.
@@ -223,23 +401,53 @@ enum CArg {
     USize(usize),
     /// Raw pointer, stored as C's `void*`.
     RawPtr(*mut std::ffi::c_void),
+    /// `repr(C)` struct, stored as its bytes in C's representation, in native-endian `u64`s so
+    /// that it is aligned enough.
+    Struct { ty: ffi::Type, words: Box<[u64]> },
 }
 
-impl<'a> CArg {
-    /// Convert a `CArg` to a `libffi` argument type.
-    fn arg_downcast(&'a self) -> libffi::high::Arg<'a> {
+impl CArg {
+    /// Get the `libffi` type of a `CArg`.
+    fn ffi_type(&self) -> ffi::Type {
+        match self {
+            CArg::Int8(_) => ffi::Type::i8(),
+            CArg::Int16(_) => ffi::Type::i16(),
+            CArg::Int32(_) => ffi::Type::i32(),
+            CArg::Int64(_) => ffi::Type::i64(),
+            CArg::ISize(_) => ffi::Type::isize(),
+            CArg::UInt8(_) => ffi::Type::u8(),
+            CArg::UInt16(_) => ffi::Type::u16(),
+            CArg::UInt32(_) => ffi::Type::u32(),
+            CArg::UInt64(_) => ffi::Type::u64(),
+            CArg::USize(_) => ffi::Type::usize(),
+            CArg::RawPtr(_) => ffi::Type::pointer(),
+            CArg::Struct { ty, .. } => ty.clone(),
+        }
+    }
+
+    /// Convert a `CArg` to a `libffi` argument. The result points into `self`.
+    fn arg_downcast(&self) -> ffi::Arg {
         match self {
-            CArg::Int8(i) => ffi::arg(i),
-            CArg::Int16(i) => ffi::arg(i),
-            CArg::Int32(i) => ffi::arg(i),
-            CArg::Int64(i) => ffi::arg(i),
-            CArg::ISize(i) => ffi::arg(i),
-            CArg::UInt8(i) => ffi::arg(i),
-            CArg::UInt16(i) => ffi::arg(i),
-            CArg::UInt32(i) => ffi::arg(i),
-            CArg::UInt64(i) => ffi::arg(i),
-            CArg::USize(i) => ffi::arg(i),
-            CArg::RawPtr(i) => ffi::arg(i),
+            CArg::Int8(i) => ffi::Arg::new(i),
+            CArg::Int16(i) => ffi::Arg::new(i),
+            CArg::Int32(i) => ffi::Arg::new(i),
+            CArg::Int64(i) => ffi::Arg::new(i),
+            CArg::ISize(i) => ffi::Arg::new(i),
+            CArg::UInt8(i) => ffi::Arg::new(i),
+            CArg::UInt16(i) => ffi::Arg::new(i),
+            CArg::UInt32(i) => ffi::Arg::new(i),
+            CArg::UInt64(i) => ffi::Arg::new(i),
+            CArg::USize(i) => ffi::Arg::new(i),
+            CArg::RawPtr(i) => ffi::Arg::new(i),
+            // `libffi` reads the entire struct through this pointer, so it has to be derived from
+            // the entire buffer rather than from a reference to its first word, as `ffi::Arg::new`
+            // would. `ffi::Arg` is a `repr(C)` wrapper around the pointer, which `libffi` itself
+            // relies on when passing the arguments on.
+            CArg::Struct { words, .. } => unsafe {
+                std::mem::transmute::<*mut std::ffi::c_void, ffi::Arg>(
+                    words.as_ptr().cast_mut().cast(),
+                )
+            },
         }
     }
 }
@@ -77,7 +77,7 @@ fn clock_gettime(
 
         let duration = if absolute_clocks.contains(&clk_id) {
             this.check_no_isolation("`clock_gettime` with `REALTIME` clocks")?;
-            system_time_to_duration(&SystemTime::now())?
+            system_time_to_duration(&this.machine.clock.system_time())?
         } else if relative_clocks.contains(&clk_id) {
             this.machine.clock.now().duration_since(this.machine.clock.epoch())
         } else {
@@ -110,7 +110,7 @@ fn gettimeofday(
             return this.set_last_error_and_return_i32(LibcError("EINVAL"));
         }
 
-        let duration = system_time_to_duration(&SystemTime::now())?;
+        let duration = system_time_to_duration(&this.machine.clock.system_time())?;
         let tv_sec = duration.as_secs();
         let tv_usec = duration.subsec_micros();
 
@@ -225,7 +225,7 @@ fn GetSystemTimeAsFileTime(
         let NANOS_PER_INTERVAL = NANOS_PER_SEC / INTERVALS_PER_SEC;
         let SECONDS_TO_UNIX_EPOCH = INTERVALS_TO_UNIX_EPOCH / INTERVALS_PER_SEC;
 
-        let duration = system_time_to_duration(&SystemTime::now())?
+        let duration = system_time_to_duration(&this.machine.clock.system_time())?
             + Duration::from_secs(SECONDS_TO_UNIX_EPOCH);
         let duration_ticks = u64::try_from(duration.as_nanos() / u128::from(NANOS_PER_INTERVAL))
             .map_err(|_| err_unsup_format!("programs running more than 2^64 Windows ticks after the Windows epoch are not supported"))?;
@@ -212,14 +212,20 @@ fn getcwd(&mut self, buf_op: &OpTy<'tcx>, size_op: &OpTy<'tcx>) -> InterpResult<
         let buf = this.read_pointer(buf_op)?;
         let size = this.read_target_usize(size_op)?;
 
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`getcwd`", reject_with)?;
-            this.set_last_error(ErrorKind::PermissionDenied)?;
-            return interp_ok(Pointer::null());
-        }
+        let result = if let Some(vfs) = &this.machine.vfs {
+            vfs.current_dir()
+        } else {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`getcwd`", reject_with)?;
+                this.set_last_error(ErrorKind::PermissionDenied)?;
+                return interp_ok(Pointer::null());
+            }
+
+            this.machine.recorder.host(env::current_dir).map_err(IoError::HostError)
+        };
 
         // If we cannot get the current directory, we return null
-        match env::current_dir() {
+        match result {
             Ok(cwd) => {
                 if this.write_path_to_c_str(&cwd, buf, size)?.0 {
                     return interp_ok(buf);
@@ -238,6 +244,13 @@ fn chdir(&mut self, path_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
 
         let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
 
+        if let Some(vfs) = &mut this.machine.vfs {
+            return match vfs.set_current_dir(&path) {
+                Ok(()) => interp_ok(Scalar::from_i32(0)),
+                Err(err) => this.set_last_error_and_return_i32(err),
+            };
+        }
+
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`chdir`", reject_with)?;
             return this.set_last_error_and_return_i32(ErrorKind::PermissionDenied);
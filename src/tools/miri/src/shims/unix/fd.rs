@@ -64,6 +64,15 @@ fn flock<'tcx>(
     fn get_epoll_ready_events<'tcx>(&self) -> InterpResult<'tcx, EpollReadyEvents> {
         throw_unsup_format!("{}: epoll does not support this file description", self.name());
     }
+
+    /// Switch the file description between blocking and non-blocking mode.
+    fn set_nonblocking<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        _nonblocking: bool,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        throw_unsup_format!("cannot change whether {} is non-blocking", self.name());
+    }
 }
 
 impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
@@ -157,9 +166,9 @@ fn fcntl(
             }
             cmd if cmd == f_dupfd || cmd == f_dupfd_cloexec => {
                 // Note that we always assume the FD_CLOEXEC flag is set for every open file, in part
-                // because exec() isn't supported. The F_DUPFD and F_DUPFD_CLOEXEC commands only
-                // differ in whether the FD_CLOEXEC flag is pre-set on the new file descriptor,
-                // thus they can share the same implementation here.
+                // because child processes only inherit the standard streams. The F_DUPFD and
+                // F_DUPFD_CLOEXEC commands only differ in whether the FD_CLOEXEC flag is pre-set on
+                // the new file descriptor, thus they can share the same implementation here.
                 let cmd_name = if cmd == f_dupfd {
                     "fcntl(fd, F_DUPFD, ...)"
                 } else {
@@ -192,6 +201,38 @@ fn fcntl(
         }
     }
 
+    fn ioctl(
+        &mut self,
+        fd_num: &OpTy<'tcx>,
+        cmd: &OpTy<'tcx>,
+        varargs: &[OpTy<'tcx>],
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fioclex = this.eval_libc_u64("FIOCLEX");
+        let fionbio = this.eval_libc_u64("FIONBIO");
+
+        let fd_num = this.read_scalar(fd_num)?.to_i32()?;
+        let cmd = this.read_scalar(cmd)?.to_u64()?;
+
+        let Some(fd) = this.machine.fds.get(fd_num) else {
+            return this.set_last_error_and_return_i32(LibcError("EBADF"));
+        };
+        if cmd == fioclex {
+            // Child processes only ever inherit the standard streams, so this is a NOP.
+            interp_ok(Scalar::from_i32(0))
+        } else if cmd == fionbio {
+            let [nonblocking] = check_min_vararg_count("ioctl(fd, FIONBIO, ...)", varargs)?;
+            let nonblocking = this.deref_pointer_as(nonblocking, this.machine.layouts.i32)?;
+            let nonblocking = this.read_scalar(&nonblocking)?.to_i32()? != 0;
+            let result = fd.as_unix().set_nonblocking(this.machine.communicate(), nonblocking)?;
+            let result = result.map(|()| 0i32);
+            interp_ok(Scalar::from_i32(this.try_unwrap_io_result(result)?))
+        } else {
+            throw_unsup_format!("ioctl: unsupported command {cmd:#x}");
+        }
+    }
+
     fn close(&mut self, fd_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_mut();
 
@@ -26,6 +26,8 @@ pub fn is_dyn_sym(name: &str, target_os: &str) -> bool {
         "signal" => true,
         // needed at least on macOS to avoid file-based fallback in getrandom
         "getentropy" | "getrandom" => true,
+        // std looks this up to decide whether it can use `posix_spawn` with a working directory.
+        "posix_spawn_file_actions_addchdir_np" => true,
         // Give specific OSes a chance to allow their symbols.
         _ =>
             match target_os {
@@ -210,6 +212,14 @@ fn emulate_foreign_item_inner(
                 let result = this.fcntl(fd_num, cmd, varargs)?;
                 this.write_scalar(result, dest)?;
             }
+            "ioctl" => {
+                // The type of `cmd` differs between Unixes, so only allow those where we checked it.
+                this.check_target_os(&["linux", "macos"], link_name)?;
+                let ([fd_num, cmd], varargs) =
+                    this.check_shim_variadic(abi, Conv::C, link_name, args)?;
+                let result = this.ioctl(fd_num, cmd, varargs)?;
+                this.write_scalar(result, dest)?;
+            }
             "dup" => {
                 let [old_fd] = this.check_shim(abi, Conv::C, link_name, args)?;
                 let old_fd = this.read_scalar(old_fd)?.to_i32()?;
@@ -360,6 +370,195 @@ fn emulate_foreign_item_inner(
                 this.write_scalar(result, dest)?;
             }
 
+            // Sockets
+            "socket" => {
+                let [domain, type_, protocol] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.socket(domain, type_, protocol)?;
+                this.write_scalar(result, dest)?;
+            }
+            "bind" => {
+                let [socket, address, address_len] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.bind(socket, address, address_len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "listen" => {
+                let [socket, backlog] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.listen(socket, backlog)?;
+                this.write_scalar(result, dest)?;
+            }
+            "accept" => {
+                let [socket, address, address_len] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                this.accept4(socket, address, address_len, /*flags*/ None, dest)?;
+            }
+            "accept4" => {
+                // Currently this function does not exist on all Unixes, e.g. on macOS.
+                this.check_target_os(&["linux", "android", "freebsd"], link_name)?;
+                let [socket, address, address_len, flags] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                this.accept4(socket, address, address_len, Some(flags), dest)?;
+            }
+            "connect" => {
+                let [socket, address, address_len] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.connect(socket, address, address_len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "send" => {
+                let [socket, buf, len, flags] = this.check_shim(abi, Conv::C, link_name, args)?;
+                this.send(socket, buf, len, flags, /*dest_addr*/ None, dest)?;
+            }
+            "sendto" => {
+                let [socket, buf, len, flags, dest_addr, dest_len] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                this.send(socket, buf, len, flags, Some((dest_addr, dest_len)), dest)?;
+            }
+            "recv" => {
+                let [socket, buf, len, flags] = this.check_shim(abi, Conv::C, link_name, args)?;
+                this.recv(socket, buf, len, flags, /*address*/ None, dest)?;
+            }
+            "recvfrom" => {
+                let [socket, buf, len, flags, address, address_len] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                this.recv(socket, buf, len, flags, Some((address, address_len)), dest)?;
+            }
+            "setsockopt" => {
+                let [socket, level, option_name, option_value, option_len] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                let result =
+                    this.setsockopt(socket, level, option_name, option_value, option_len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "getsockopt" => {
+                let [socket, level, option_name, option_value, option_len] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                let result =
+                    this.getsockopt(socket, level, option_name, option_value, option_len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "getsockname" => {
+                let [socket, address, address_len] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.getsockname(socket, address, address_len)?;
+                this.write_scalar(result, dest)?;
+            }
+            "getpeername" => {
+                let [socket, address, address_len] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.getpeername(socket, address, address_len)?;
+                this.write_scalar(result, dest)?;
+            }
+
+            // Polling
+            "poll" => {
+                let [fds, nfds, timeout] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let timeout = this.read_poll_timeout(timeout)?;
+                this.poll(fds, nfds, timeout, dest)?;
+            }
+            "ppoll" => {
+                this.check_target_os(&["linux", "android", "freebsd"], link_name)?;
+                let [fds, nfds, timeout, _sigmask] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                // There are no signals, so the signal mask can be ignored.
+                match this.read_ppoll_timeout(timeout)? {
+                    Ok(timeout) => this.poll(fds, nfds, timeout, dest)?,
+                    Err(()) => this.set_last_error_and_return(LibcError("EINVAL"), dest)?,
+                }
+            }
+            "select" => {
+                let [nfds, readfds, writefds, exceptfds, timeout] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                this.select(nfds, readfds, writefds, exceptfds, timeout, dest)?;
+            }
+
+            // Processes
+            "posix_spawn" | "posix_spawnp" => {
+                let [pid, path, file_actions, attr, argv, envp] =
+                    this.check_shim(abi, Conv::C, link_name, args)?;
+                let search_path = link_name.as_str() == "posix_spawnp";
+                let result =
+                    this.posix_spawn(pid, path, file_actions, attr, argv, envp, search_path)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_init" => {
+                let [file_actions] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_init(file_actions)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_destroy" => {
+                let [file_actions] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_destroy(file_actions)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_adddup2" => {
+                let [file_actions, fd, new_fd] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_adddup2(file_actions, fd, new_fd)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_addclose" => {
+                let [file_actions, fd] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_addclose(file_actions, fd)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawn_file_actions_addchdir_np" => {
+                let [file_actions, path] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.posix_spawn_file_actions_addchdir(file_actions, path)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawnattr_init" => {
+                let [attr] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.posix_spawnattr_init(attr)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawnattr_destroy" => {
+                let [attr] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.posix_spawnattr_destroy(attr)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawnattr_setflags" => {
+                let [attr, flags] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.posix_spawnattr_setflags(attr, flags)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawnattr_setpgroup" => {
+                let [attr, pgroup] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.posix_spawnattr_setpgroup(attr, pgroup)?;
+                this.write_scalar(result, dest)?;
+            }
+            "posix_spawnattr_setsigdefault" => {
+                let [attr, set] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.posix_spawnattr_setsigdefault(attr, set)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigemptyset" => {
+                let [set] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.sigemptyset(set)?;
+                this.write_scalar(result, dest)?;
+            }
+            "sigaddset" => {
+                let [set, signum] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.sigaddset(set, signum)?;
+                this.write_scalar(result, dest)?;
+            }
+            "waitpid" => {
+                let [pid, status, options] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.waitpid(pid, status, options)?;
+                this.write_scalar(result, dest)?;
+            }
+            "kill" => {
+                let [pid, sig] = this.check_shim(abi, Conv::C, link_name, args)?;
+                let result = this.kill(pid, sig)?;
+                this.write_scalar(result, dest)?;
+            }
+            "fork" => {
+                let [] = this.check_shim(abi, Conv::C, link_name, args)?;
+                throw_unsup_format!(
+                    "`fork` is not supported, only `posix_spawn` is; \
+                    this means `Command` options like `pre_exec`, `uid` and `gid` cannot be used"
+                );
+            }
+
             // Time
             "gettimeofday" => {
                 let [tv, tz] = this.check_shim(abi, Conv::C, link_name, args)?;
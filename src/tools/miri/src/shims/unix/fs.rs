@@ -1,6 +1,7 @@
 //! File and file system access
 
 use std::borrow::Cow;
+use std::ffi::OsString;
 use std::fs::{
     DirBuilder, File, FileType, Metadata, OpenOptions, ReadDir, read_dir, remove_dir, remove_file,
     rename,
@@ -17,11 +18,12 @@
 use crate::shims::files::{EvalContextExt as _, FileDescription, FileDescriptionRef};
 use crate::shims::os_str::bytes_to_os_str;
 use crate::shims::unix::fd::{FlockOp, UnixFileDescription};
+use crate::shims::unix::vfs::{OpenFlags, VfsDirEntry, VfsFile, VfsFileType, VfsMetadata};
 use crate::*;
 
 #[derive(Debug)]
-struct FileHandle {
-    file: File,
+pub(crate) struct FileHandle {
+    pub(crate) file: File,
     writable: bool,
 }
 
@@ -122,22 +124,23 @@ fn pread<'tcx>(
         // Correctness of this emulation relies on sequential nature of Miri execution.
         // The closure is used to emulate `try` block, since we "bubble" `io::Error` using `?`.
         let file = &mut &self.file;
-        let mut f = || {
+        let f = || {
             let cursor_pos = file.stream_position()?;
             file.seek(SeekFrom::Start(offset))?;
             let res = file.read(&mut bytes);
             // Attempt to restore cursor position even if the read has failed
             file.seek(SeekFrom::Start(cursor_pos))
                 .expect("failed to restore file position, this shouldn't be possible");
-            res
+            // Crucially, if fewer than `bytes.len()` bytes were read, only keep that many, so
+            // that we only write that much into the output buffer!
+            bytes.truncate(res?);
+            Ok(bytes)
         };
-        let result = match f() {
-            Ok(read_size) => {
+        let result = match ecx.machine.recorder.host(f) {
+            Ok(bytes) => {
                 // If reading to `bytes` did not fail, we write those bytes to the buffer.
-                // Crucially, if fewer than `bytes.len()` bytes were read, only write
-                // that much into the output buffer!
-                ecx.write_bytes_ptr(ptr, bytes[..read_size].iter().copied())?;
-                Ok(read_size)
+                ecx.write_bytes_ptr(ptr, bytes.iter().copied())?;
+                Ok(bytes.len())
             }
             Err(e) => Err(IoError::HostError(e)),
         };
@@ -159,7 +162,7 @@ fn pwrite<'tcx>(
         // The closure is used to emulate `try` block, since we "bubble" `io::Error` using `?`.
         let file = &mut &self.file;
         let bytes = ecx.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?;
-        let mut f = || {
+        let f = || {
             let cursor_pos = file.stream_position()?;
             file.seek(SeekFrom::Start(offset))?;
             let res = file.write(bytes);
@@ -168,7 +171,7 @@ fn pwrite<'tcx>(
                 .expect("failed to restore file position, this shouldn't be possible");
             res
         };
-        let result = f();
+        let result = ecx.machine.recorder.host_effect(f);
         finish.call(ecx, result.map_err(IoError::HostError))
     }
 
@@ -324,60 +327,126 @@ fn macos_fbsd_solarish_write_stat_buf(
 
         interp_ok(0)
     }
+}
 
-    fn file_type_to_d_type(
-        &mut self,
-        file_type: std::io::Result<FileType>,
-    ) -> InterpResult<'tcx, i32> {
-        #[cfg(unix)]
-        use std::os::unix::fs::FileTypeExt;
-
-        let this = self.eval_context_mut();
-        match file_type {
-            Ok(file_type) => {
-                match () {
-                    _ if file_type.is_dir() => interp_ok(this.eval_libc("DT_DIR").to_u8()?.into()),
-                    _ if file_type.is_file() => interp_ok(this.eval_libc("DT_REG").to_u8()?.into()),
-                    _ if file_type.is_symlink() =>
-                        interp_ok(this.eval_libc("DT_LNK").to_u8()?.into()),
-                    // Certain file types are only supported when the host is a Unix system.
-                    #[cfg(unix)]
-                    _ if file_type.is_block_device() =>
-                        interp_ok(this.eval_libc("DT_BLK").to_u8()?.into()),
-                    #[cfg(unix)]
-                    _ if file_type.is_char_device() =>
-                        interp_ok(this.eval_libc("DT_CHR").to_u8()?.into()),
-                    #[cfg(unix)]
-                    _ if file_type.is_fifo() =>
-                        interp_ok(this.eval_libc("DT_FIFO").to_u8()?.into()),
-                    #[cfg(unix)]
-                    _ if file_type.is_socket() =>
-                        interp_ok(this.eval_libc("DT_SOCK").to_u8()?.into()),
-                    // Fallback
-                    _ => interp_ok(this.eval_libc("DT_UNKNOWN").to_u8()?.into()),
-                }
-            }
-            Err(_) => {
-                // Fallback on error
-                interp_ok(this.eval_libc("DT_UNKNOWN").to_u8()?.into())
+/// Returns the name of the `d_type` constant for a file type on the host.
+fn host_d_type(file_type: std::io::Result<FileType>) -> &'static str {
+    #[cfg(unix)]
+    use std::os::unix::fs::FileTypeExt;
+
+    match file_type {
+        Ok(file_type) => {
+            match () {
+                _ if file_type.is_dir() => "DT_DIR",
+                _ if file_type.is_file() => "DT_REG",
+                _ if file_type.is_symlink() => "DT_LNK",
+                // Certain file types are only supported when the host is a Unix system.
+                #[cfg(unix)]
+                _ if file_type.is_block_device() => "DT_BLK",
+                #[cfg(unix)]
+                _ if file_type.is_char_device() => "DT_CHR",
+                #[cfg(unix)]
+                _ if file_type.is_fifo() => "DT_FIFO",
+                #[cfg(unix)]
+                _ if file_type.is_socket() => "DT_SOCK",
+                // Fallback
+                _ => "DT_UNKNOWN",
             }
         }
+        // Fallback on error
+        Err(_) => "DT_UNKNOWN",
     }
 }
 
+/// A directory entry, as returned by readdir().
+struct DirEntry {
+    name: OsString,
+    ino: u64,
+    /// The name of the `d_type` constant for the type of the entry.
+    d_type: &'static str,
+}
+
+/// The values `host_d_type` can return.
+const D_TYPES: [&str; 8] =
+    ["DT_DIR", "DT_REG", "DT_LNK", "DT_BLK", "DT_CHR", "DT_FIFO", "DT_SOCK", "DT_UNKNOWN"];
+
+/// Like paths, the names of the entries of host directories are recorded as UTF-8.
+impl HostValue for DirEntry {
+    fn to_bytes(&self) -> Vec<u8> {
+        let d_type = D_TYPES.iter().position(|&d_type| d_type == self.d_type).unwrap();
+        let mut bytes = self.ino.to_bytes();
+        bytes.push(u8::try_from(d_type).unwrap());
+        bytes.extend(PathBuf::from(&self.name).to_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (ino, bytes) = bytes.split_at_checked(8)?;
+        let (&d_type, name) = bytes.split_first()?;
+        Some(DirEntry {
+            name: PathBuf::from_bytes(name)?.into_os_string(),
+            ino: u64::from_bytes(ino)?,
+            d_type: *D_TYPES.get(usize::from(d_type))?,
+        })
+    }
+}
+
+/// Where the entries of an open directory come from.
+#[derive(Debug)]
+enum DirEntries {
+    /// A directory reader on the host.
+    Host(ReadDir),
+    /// The entries of a directory of the virtual filesystem, as of the call to opendir().
+    Vfs(std::vec::IntoIter<VfsDirEntry>),
+}
+
 /// An open directory, tracked by DirHandler.
 #[derive(Debug)]
 struct OpenDir {
-    /// The directory reader on the host.
-    read_dir: ReadDir,
+    /// The source of the directory entries.
+    entries: DirEntries,
     /// The most recent entry returned by readdir().
     /// Will be freed by the next call.
     entry: Option<Pointer>,
 }
 
 impl OpenDir {
-    fn new(read_dir: ReadDir) -> Self {
-        Self { read_dir, entry: None }
+    fn new(entries: DirEntries) -> Self {
+        Self { entries, entry: None }
+    }
+
+    fn next_entry(&mut self, recorder: &Recorder) -> Option<std::io::Result<DirEntry>> {
+        match &mut self.entries {
+            DirEntries::Host(read_dir) => {
+                let entry = recorder.host(|| {
+                    let entry = read_dir.next().transpose()?;
+                    Ok(entry.map(|dir_entry| {
+                        // If the host is a Unix system, fill in the inode number with its real
+                        // value. If not, use 0 as a fallback value.
+                        #[cfg(unix)]
+                        let ino = std::os::unix::fs::DirEntryExt::ino(&dir_entry);
+                        #[cfg(not(unix))]
+                        let ino = 0u64;
+
+                        DirEntry {
+                            name: dir_entry.file_name(), // not a Path as there are no separators!
+                            ino,
+                            d_type: host_d_type(dir_entry.file_type()),
+                        }
+                    }))
+                });
+                entry.transpose()
+            }
+            DirEntries::Vfs(entries) => {
+                let entry = entries.next()?;
+                let d_type = match entry.file_type {
+                    VfsFileType::File => "DT_REG",
+                    VfsFileType::Dir => "DT_DIR",
+                    VfsFileType::Symlink => "DT_LNK",
+                };
+                Some(Ok(DirEntry { name: entry.name, ino: entry.ino, d_type }))
+            }
+        }
     }
 }
 
@@ -390,7 +459,8 @@ pub struct DirTable {
     /// and closedir.
     ///
     /// When opendir is called, a directory iterator is created on the host for the target
-    /// directory, and an entry is stored in this hash map, indexed by an ID which represents
+    /// directory (or the entries of the directory are taken from the virtual filesystem), and an
+    /// entry is stored in this hash map, indexed by an ID which represents
     /// the directory stream. When readdir is called, the directory stream ID is used to look up
     /// the corresponding ReadDir iterator from this map, and information from the next
     /// directory entry is returned. When closedir is called, the ReadDir iterator is removed from
@@ -402,10 +472,10 @@ pub struct DirTable {
 
 impl DirTable {
     #[expect(clippy::arithmetic_side_effects)]
-    fn insert_new(&mut self, read_dir: ReadDir) -> u64 {
+    fn insert_new(&mut self, entries: DirEntries) -> u64 {
         let id = self.next_id;
         self.next_id += 1;
-        self.streams.try_insert(id, OpenDir::new(read_dir)).unwrap();
+        self.streams.try_insert(id, OpenDir::new(entries)).unwrap();
         id
     }
 }
@@ -577,6 +647,26 @@ fn open(
             throw_unsup_format!("unsupported flags {:#x}", flag & !mirror);
         }
 
+        if this.machine.vfs.is_some() {
+            let flag_set = |name| {
+                let bit = this.eval_libc_i32(name);
+                flag & bit == bit
+            };
+            let flags = OpenFlags {
+                readable: access_mode != o_wronly,
+                writable,
+                append: flag_set("O_APPEND"),
+                truncate: flag_set("O_TRUNC"),
+                create: flag_set("O_CREAT"),
+                exclusive: flag_set("O_EXCL"),
+                nofollow: flag_set("O_NOFOLLOW"),
+            };
+            return match this.machine.vfs.as_mut().unwrap().open(&path, &flags) {
+                Ok(file) => interp_ok(Scalar::from_i32(this.machine.fds.insert_new(file))),
+                Err(err) => this.set_last_error_and_return_i32(err),
+            };
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`open`", reject_with)?;
@@ -615,8 +705,14 @@ fn lseek64(&mut self, fd_num: i32, offset: i128, whence: i32) -> InterpResult<'t
         let Some(fd) = this.machine.fds.get(fd_num) else {
             return this.set_last_error_and_return_i64(LibcError("EBADF"));
         };
-        let result = fd.seek(communicate, seek_from)?.map(|offset| i64::try_from(offset).unwrap());
+        let is_host_file = fd.clone().downcast::<FileHandle>().is_some();
+        let mut result = fd.seek(communicate, seek_from)?;
         drop(fd);
+        if is_host_file {
+            // The offset depends on the host file, e.g. when seeking from its end.
+            result = this.machine.recorder.host_effect(|| result);
+        }
+        let result = result.map(|offset| i64::try_from(offset).unwrap());
 
         let result = this.try_unwrap_io_result(result)?;
         interp_ok(Scalar::from_i64(result))
@@ -627,6 +723,13 @@ fn unlink(&mut self, path_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
 
         let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
 
+        if let Some(vfs) = &mut this.machine.vfs {
+            return match vfs.unlink(&path) {
+                Ok(()) => interp_ok(Scalar::from_i32(0)),
+                Err(err) => this.set_last_error_and_return_i32(err),
+            };
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`unlink`", reject_with)?;
@@ -657,6 +760,13 @@ fn create_link(src: &Path, dst: &Path) -> std::io::Result<()> {
         let target = this.read_path_from_c_str(this.read_pointer(target_op)?)?;
         let linkpath = this.read_path_from_c_str(this.read_pointer(linkpath_op)?)?;
 
+        if let Some(vfs) = &mut this.machine.vfs {
+            return match vfs.symlink(&target, &linkpath) {
+                Ok(()) => interp_ok(Scalar::from_i32(0)),
+                Err(err) => this.set_last_error_and_return_i32(err),
+            };
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`symlink`", reject_with)?;
@@ -681,10 +791,12 @@ fn macos_fbsd_solarish_stat(
         let path_scalar = this.read_pointer(path_op)?;
         let path = this.read_path_from_c_str(path_scalar)?.into_owned();
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`stat`", reject_with)?;
-            return this.set_last_error_and_return_i32(LibcError("EACCES"));
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`stat`", reject_with)?;
+                return this.set_last_error_and_return_i32(LibcError("EACCES"));
+            }
         }
 
         // `stat` always follows symlinks.
@@ -714,10 +826,12 @@ fn macos_fbsd_solarish_lstat(
         let path_scalar = this.read_pointer(path_op)?;
         let path = this.read_path_from_c_str(path_scalar)?.into_owned();
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`lstat`", reject_with)?;
-            return this.set_last_error_and_return_i32(LibcError("EACCES"));
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`lstat`", reject_with)?;
+                return this.set_last_error_and_return_i32(LibcError("EACCES"));
+            }
         }
 
         let metadata = match FileMetadata::from_path(this, &path, false)? {
@@ -744,11 +858,13 @@ fn macos_fbsd_solarish_fstat(
 
         let fd = this.read_scalar(fd_op)?.to_i32()?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`fstat`", reject_with)?;
-            // Set error code as "EBADF" (bad fd)
-            return this.set_last_error_and_return_i32(LibcError("EBADF"));
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`fstat`", reject_with)?;
+                // Set error code as "EBADF" (bad fd)
+                return this.set_last_error_and_return_i32(LibcError("EBADF"));
+            }
         }
 
         let metadata = match FileMetadata::from_fd_num(this, fd)? {
@@ -805,21 +921,23 @@ fn linux_statx(
             )
         }
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`statx`", reject_with)?;
-            let ecode = if path.is_absolute() || dirfd == this.eval_libc_i32("AT_FDCWD") {
-                // since `path` is provided, either absolute or
-                // relative to CWD, `EACCES` is the most relevant.
-                LibcError("EACCES")
-            } else {
-                // `dirfd` is set to target file, and `path` is empty
-                // (or we would have hit the `throw_unsup_format`
-                // above). `EACCES` would violate the spec.
-                assert!(empty_path_flag);
-                LibcError("EBADF")
-            };
-            return this.set_last_error_and_return_i32(ecode);
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`statx`", reject_with)?;
+                let ecode = if path.is_absolute() || dirfd == this.eval_libc_i32("AT_FDCWD") {
+                    // since `path` is provided, either absolute or
+                    // relative to CWD, `EACCES` is the most relevant.
+                    LibcError("EACCES")
+                } else {
+                    // `dirfd` is set to target file, and `path` is empty
+                    // (or we would have hit the `throw_unsup_format`
+                    // above). `EACCES` would violate the spec.
+                    assert!(empty_path_flag);
+                    LibcError("EBADF")
+                };
+                return this.set_last_error_and_return_i32(ecode);
+            }
         }
 
         // the `_mask_op` parameter specifies the file information that the caller requested.
@@ -954,6 +1072,13 @@ fn rename(
         let oldpath = this.read_path_from_c_str(oldpath_ptr)?;
         let newpath = this.read_path_from_c_str(newpath_ptr)?;
 
+        if let Some(vfs) = &mut this.machine.vfs {
+            return match vfs.rename(&oldpath, &newpath) {
+                Ok(()) => interp_ok(Scalar::from_i32(0)),
+                Err(err) => this.set_last_error_and_return_i32(err),
+            };
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`rename`", reject_with)?;
@@ -977,6 +1102,13 @@ fn mkdir(&mut self, path_op: &OpTy<'tcx>, mode_op: &OpTy<'tcx>) -> InterpResult<
 
         let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
 
+        if let Some(vfs) = &mut this.machine.vfs {
+            return match vfs.mkdir(&path) {
+                Ok(()) => interp_ok(Scalar::from_i32(0)),
+                Err(err) => this.set_last_error_and_return_i32(err),
+            };
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`mkdir`", reject_with)?;
@@ -1004,6 +1136,13 @@ fn rmdir(&mut self, path_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
 
         let path = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
 
+        if let Some(vfs) = &mut this.machine.vfs {
+            return match vfs.rmdir(&path) {
+                Ok(()) => interp_ok(Scalar::from_i32(0)),
+                Err(err) => this.set_last_error_and_return_i32(err),
+            };
+        }
+
         // Reject if isolation is enabled.
         if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
             this.reject_in_isolation("`rmdir`", reject_with)?;
@@ -1020,18 +1159,22 @@ fn opendir(&mut self, name_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
 
         let name = this.read_path_from_c_str(this.read_pointer(name_op)?)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`opendir`", reject_with)?;
-            this.set_last_error(LibcError("EACCES"))?;
-            return interp_ok(Scalar::null_ptr(this));
-        }
+        let result = if let Some(vfs) = &this.machine.vfs {
+            vfs.read_dir(&name).map(|entries| DirEntries::Vfs(entries.into_iter()))
+        } else {
+            // Reject if isolation is enabled.
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`opendir`", reject_with)?;
+                this.set_last_error(LibcError("EACCES"))?;
+                return interp_ok(Scalar::null_ptr(this));
+            }
 
-        let result = read_dir(name);
+            read_dir(name).map(DirEntries::Host).map_err(IoError::HostError)
+        };
 
         match result {
-            Ok(dir_iter) => {
-                let id = this.machine.dirs.insert_new(dir_iter);
+            Ok(entries) => {
+                let id = this.machine.dirs.insert_new(entries);
 
                 // The libc API for opendir says that this method returns a pointer to an opaque
                 // structure, but we are returning an ID number. Thus, pass it as a scalar of
@@ -1058,18 +1201,20 @@ fn linux_solarish_readdir64(
 
         let dirp = this.read_target_usize(dirp_op)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`readdir`", reject_with)?;
-            this.set_last_error(LibcError("EBADF"))?;
-            return interp_ok(Scalar::null_ptr(this));
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`readdir`", reject_with)?;
+                this.set_last_error(LibcError("EBADF"))?;
+                return interp_ok(Scalar::null_ptr(this));
+            }
         }
 
         let open_dir = this.machine.dirs.streams.get_mut(&dirp).ok_or_else(|| {
             err_unsup_format!("the DIR pointer passed to readdir64 did not come from opendir")
         })?;
 
-        let entry = match open_dir.read_dir.next() {
+        let entry = match open_dir.next_entry(&this.machine.recorder) {
             Some(Ok(dir_entry)) => {
                 // Write the directory entry into a newly allocated buffer.
                 // The name is written with write_bytes, while the rest of the
@@ -1093,7 +1238,7 @@ fn linux_solarish_readdir64(
                 //     pub d_name: [c_char; 3],
                 // }
 
-                let mut name = dir_entry.file_name(); // not a Path as there are no separators!
+                let mut name = dir_entry.name;
                 name.push("\0"); // Add a NUL terminator
                 let name_bytes = name.as_encoded_bytes();
                 let name_len = u64::try_from(name_bytes.len()).unwrap();
@@ -1112,16 +1257,9 @@ fn linux_solarish_readdir64(
                 )?;
                 let entry: Pointer = entry.into();
 
-                // If the host is a Unix system, fill in the inode number with its real value.
-                // If not, use 0 as a fallback value.
-                #[cfg(unix)]
-                let ino = std::os::unix::fs::DirEntryExt::ino(&dir_entry);
-                #[cfg(not(unix))]
-                let ino = 0u64;
-
-                let file_type = this.file_type_to_d_type(dir_entry.file_type())?;
+                let file_type: i32 = this.eval_libc(dir_entry.d_type).to_u8()?.into();
                 this.write_int_fields_named(
-                    &[("d_ino", ino.into()), ("d_off", 0), ("d_reclen", size.into())],
+                    &[("d_ino", dir_entry.ino.into()), ("d_off", 0), ("d_reclen", size.into())],
                     &this.ptr_to_mplace(entry, dirent_layout),
                 )?;
 
@@ -1170,17 +1308,19 @@ fn macos_fbsd_readdir_r(
         let dirp = this.read_target_usize(dirp_op)?;
         let result_place = this.deref_pointer_as(result_op, this.machine.layouts.mut_raw_ptr)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`readdir_r`", reject_with)?;
-            // Return error code, do *not* set `errno`.
-            return interp_ok(this.eval_libc("EBADF"));
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`readdir_r`", reject_with)?;
+                // Return error code, do *not* set `errno`.
+                return interp_ok(this.eval_libc("EBADF"));
+            }
         }
 
         let open_dir = this.machine.dirs.streams.get_mut(&dirp).ok_or_else(|| {
             err_unsup_format!("the DIR pointer passed to readdir_r did not come from opendir")
         })?;
-        interp_ok(match open_dir.read_dir.next() {
+        interp_ok(match open_dir.next_entry(&this.machine.recorder) {
             Some(Ok(dir_entry)) => {
                 // Write into entry, write pointer to result, return 0 on success.
                 // The name is written with write_os_str_to_c_str, while the rest of the
@@ -1199,9 +1339,8 @@ fn macos_fbsd_readdir_r(
                 let entry_place = this.deref_pointer_as(entry_op, this.libc_ty_layout("dirent"))?;
                 let name_place = this.project_field_named(&entry_place, "d_name")?;
 
-                let file_name = dir_entry.file_name(); // not a Path as there are no separators!
                 let (name_fits, file_name_buf_len) = this.write_os_str_to_c_str(
-                    &file_name,
+                    &dir_entry.name,
                     name_place.ptr(),
                     name_place.layout.size.bytes(),
                 )?;
@@ -1212,14 +1351,8 @@ fn macos_fbsd_readdir_r(
                     );
                 }
 
-                // If the host is a Unix system, fill in the inode number with its real value.
-                // If not, use 0 as a fallback value.
-                #[cfg(unix)]
-                let ino = std::os::unix::fs::DirEntryExt::ino(&dir_entry);
-                #[cfg(not(unix))]
-                let ino = 0u64;
-
-                let file_type = this.file_type_to_d_type(dir_entry.file_type())?;
+                let ino = dir_entry.ino;
+                let file_type: i32 = this.eval_libc(dir_entry.d_type).to_u8()?.into();
 
                 // Common fields.
                 this.write_int_fields_named(
@@ -1275,10 +1408,12 @@ fn closedir(&mut self, dirp_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
 
         let dirp = this.read_target_usize(dirp_op)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`closedir`", reject_with)?;
-            return this.set_last_error_and_return_i32(LibcError("EBADF"));
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`closedir`", reject_with)?;
+                return this.set_last_error_and_return_i32(LibcError("EBADF"));
+            }
         }
 
         let Some(mut open_dir) = this.machine.dirs.streams.remove(&dirp) else {
@@ -1296,17 +1431,29 @@ fn closedir(&mut self, dirp_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
     fn ftruncate64(&mut self, fd_num: i32, length: i128) -> InterpResult<'tcx, Scalar> {
         let this = self.eval_context_mut();
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`ftruncate64`", reject_with)?;
-            // Set error code as "EBADF" (bad fd)
-            return this.set_last_error_and_return_i32(LibcError("EBADF"));
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`ftruncate64`", reject_with)?;
+                // Set error code as "EBADF" (bad fd)
+                return this.set_last_error_and_return_i32(LibcError("EBADF"));
+            }
         }
 
         let Some(fd) = this.machine.fds.get(fd_num) else {
             return this.set_last_error_and_return_i32(LibcError("EBADF"));
         };
 
+        if let Some(file) = fd.clone().downcast::<VfsFile>() {
+            let Ok(length) = length.try_into() else {
+                return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+            };
+            return match file.set_len(length) {
+                Ok(()) => interp_ok(Scalar::from_i32(0)),
+                Err(err) => this.set_last_error_and_return_i32(err),
+            };
+        }
+
         // FIXME: Support ftruncate64 for all FDs
         let file = fd.downcast::<FileHandle>().ok_or_else(|| {
             err_unsup_format!("`ftruncate64` is only supported on file-backed file descriptors")
@@ -1336,11 +1483,13 @@ fn fsync(&mut self, fd_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
 
         let fd = this.read_scalar(fd_op)?.to_i32()?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`fsync`", reject_with)?;
-            // Set error code as "EBADF" (bad fd)
-            return this.set_last_error_and_return_i32(LibcError("EBADF"));
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`fsync`", reject_with)?;
+                // Set error code as "EBADF" (bad fd)
+                return this.set_last_error_and_return_i32(LibcError("EBADF"));
+            }
         }
 
         self.ffullsync_fd(fd)
@@ -1351,6 +1500,10 @@ fn ffullsync_fd(&mut self, fd_num: i32) -> InterpResult<'tcx, Scalar> {
         let Some(fd) = this.machine.fds.get(fd_num) else {
             return this.set_last_error_and_return_i32(LibcError("EBADF"));
         };
+        // Files of the virtual filesystem only exist in memory, so there is nothing to do.
+        if fd.clone().downcast::<VfsFile>().is_some() {
+            return interp_ok(Scalar::from_i32(0));
+        }
         // Only regular files support synchronization.
         let file = fd.downcast::<FileHandle>().ok_or_else(|| {
             err_unsup_format!("`fsync` is only supported on file-backed file descriptors")
@@ -1364,16 +1517,22 @@ fn fdatasync(&mut self, fd_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
 
         let fd = this.read_scalar(fd_op)?.to_i32()?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`fdatasync`", reject_with)?;
-            // Set error code as "EBADF" (bad fd)
-            return this.set_last_error_and_return_i32(LibcError("EBADF"));
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`fdatasync`", reject_with)?;
+                // Set error code as "EBADF" (bad fd)
+                return this.set_last_error_and_return_i32(LibcError("EBADF"));
+            }
         }
 
         let Some(fd) = this.machine.fds.get(fd) else {
             return this.set_last_error_and_return_i32(LibcError("EBADF"));
         };
+        // Files of the virtual filesystem only exist in memory, so there is nothing to do.
+        if fd.clone().downcast::<VfsFile>().is_some() {
+            return interp_ok(Scalar::from_i32(0));
+        }
         // Only regular files support synchronization.
         let file = fd.downcast::<FileHandle>().ok_or_else(|| {
             err_unsup_format!("`fdatasync` is only supported on file-backed file descriptors")
@@ -1406,16 +1565,22 @@ fn sync_file_range(
             return this.set_last_error_and_return_i32(LibcError("EINVAL"));
         }
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`sync_file_range`", reject_with)?;
-            // Set error code as "EBADF" (bad fd)
-            return this.set_last_error_and_return_i32(LibcError("EBADF"));
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`sync_file_range`", reject_with)?;
+                // Set error code as "EBADF" (bad fd)
+                return this.set_last_error_and_return_i32(LibcError("EBADF"));
+            }
         }
 
         let Some(fd) = this.machine.fds.get(fd) else {
             return this.set_last_error_and_return_i32(LibcError("EBADF"));
         };
+        // Files of the virtual filesystem only exist in memory, so there is nothing to do.
+        if fd.clone().downcast::<VfsFile>().is_some() {
+            return interp_ok(Scalar::from_i32(0));
+        }
         // Only regular files support synchronization.
         let file = fd.downcast::<FileHandle>().ok_or_else(|| {
             err_unsup_format!("`sync_data_range` is only supported on file-backed file descriptors")
@@ -1436,14 +1601,18 @@ fn readlink(
         let buf = this.read_pointer(buf_op)?;
         let bufsize = this.read_target_usize(bufsize_op)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`readlink`", reject_with)?;
-            this.set_last_error(LibcError("EACCES"))?;
-            return interp_ok(-1);
-        }
+        let result = if let Some(vfs) = &this.machine.vfs {
+            vfs.read_link(&pathname)
+        } else {
+            // Reject if isolation is enabled.
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`readlink`", reject_with)?;
+                this.set_last_error(LibcError("EACCES"))?;
+                return interp_ok(-1);
+            }
 
-        let result = std::fs::read_link(pathname);
+            this.machine.recorder.host(|| std::fs::read_link(pathname)).map_err(IoError::HostError)
+        };
         match result {
             Ok(resolved) => {
                 // 'readlink' truncates the resolved path if the provided buffer is not large
@@ -1498,14 +1667,21 @@ fn realpath(
         let pathname = this.read_path_from_c_str(this.read_pointer(path_op)?)?;
         let processed_ptr = this.read_pointer(processed_path_op)?;
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`realpath`", reject_with)?;
-            this.set_last_error(LibcError("EACCES"))?;
-            return interp_ok(Scalar::from_target_usize(0, this));
-        }
+        let result = if let Some(vfs) = &this.machine.vfs {
+            vfs.canonicalize(&pathname)
+        } else {
+            // Reject if isolation is enabled.
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`realpath`", reject_with)?;
+                this.set_last_error(LibcError("EACCES"))?;
+                return interp_ok(Scalar::from_target_usize(0, this));
+            }
 
-        let result = std::fs::canonicalize(pathname);
+            this.machine
+                .recorder
+                .host(|| std::fs::canonicalize(pathname))
+                .map_err(IoError::HostError)
+        };
         match result {
             Ok(resolved) => {
                 let path_max = this
@@ -1571,10 +1747,12 @@ fn mkstemp(&mut self, template_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
         let mut template = this.eval_context_ref().read_c_str(template_ptr)?.to_owned();
         let template_bytes = template.as_mut_slice();
 
-        // Reject if isolation is enabled.
-        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
-            this.reject_in_isolation("`mkstemp`", reject_with)?;
-            return this.set_last_error_and_return_i32(LibcError("EACCES"));
+        // Reject if isolation is enabled, unless the virtual filesystem takes care of this.
+        if this.machine.vfs.is_none() {
+            if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+                this.reject_in_isolation("`mkstemp`", reject_with)?;
+                return this.set_last_error_and_return_i32(LibcError("EACCES"));
+            }
         }
 
         // Get the bytes of the suffix we expect in _target_ encoding.
@@ -1638,6 +1816,27 @@ fn mkstemp(&mut self, template_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
             // To actually open the file, turn this into a host OsString.
             let p = bytes_to_os_str(template_bytes)?.to_os_string();
 
+            if let Some(vfs) = &mut this.machine.vfs {
+                let flags = OpenFlags {
+                    readable: true,
+                    writable: true,
+                    append: false,
+                    truncate: false,
+                    create: true,
+                    exclusive: true,
+                    nofollow: false,
+                };
+                match vfs.open(Path::new(&p), &flags) {
+                    Ok(file) => {
+                        let fd = this.machine.fds.insert_new(file);
+                        return interp_ok(Scalar::from_i32(fd));
+                    }
+                    // If the random file already exists, keep trying.
+                    Err(LibcError("EEXIST")) => continue,
+                    Err(err) => return this.set_last_error_and_return_i32(err),
+                }
+            }
+
             let possibly_unique = std::env::temp_dir().join::<PathBuf>(p.into());
 
             let file = fopts.open(possibly_unique);
@@ -1669,10 +1868,8 @@ fn mkstemp(&mut self, template_op: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
 /// Extracts the number of seconds and nanoseconds elapsed between `time` and the unix epoch when
 /// `time` is Ok. Returns `None` if `time` is an error. Fails if `time` happens before the unix
 /// epoch.
-fn extract_sec_and_nsec<'tcx>(
-    time: std::io::Result<SystemTime>,
-) -> InterpResult<'tcx, Option<(u64, u32)>> {
-    match time.ok() {
+fn extract_sec_and_nsec<'tcx>(time: Option<SystemTime>) -> InterpResult<'tcx, Option<(u64, u32)>> {
+    match time {
         Some(time) => {
             let duration = system_time_to_duration(&time)?;
             interp_ok(Some((duration.as_secs(), duration.subsec_nanos())))
@@ -1697,10 +1894,21 @@ fn from_path<'tcx>(
         path: &Path,
         follow_symlink: bool,
     ) -> InterpResult<'tcx, Result<FileMetadata, IoError>> {
-        let metadata =
-            if follow_symlink { std::fs::metadata(path) } else { std::fs::symlink_metadata(path) };
+        if let Some(vfs) = &ecx.machine.vfs {
+            let metadata = vfs.metadata(path, follow_symlink);
+            return interp_ok(metadata.map(|metadata| FileMetadata::from_vfs(ecx, metadata)));
+        }
 
-        FileMetadata::from_meta(ecx, metadata)
+        let metadata = ecx.machine.recorder.host(|| {
+            let metadata = if follow_symlink {
+                std::fs::metadata(path)
+            } else {
+                std::fs::symlink_metadata(path)
+            };
+            metadata.map(|metadata| HostMetadata::new(&metadata))
+        });
+
+        FileMetadata::from_host(ecx, metadata)
     }
 
     fn from_fd_num<'tcx>(
@@ -1711,14 +1919,20 @@ fn from_fd_num<'tcx>(
             return interp_ok(Err(LibcError("EBADF")));
         };
 
+        if let Some(file) = fd.clone().downcast::<VfsFile>() {
+            return interp_ok(Ok(FileMetadata::from_vfs(ecx, file.metadata())));
+        }
+
         let metadata = fd.metadata()?;
         drop(fd);
-        FileMetadata::from_meta(ecx, metadata)
+        let metadata =
+            ecx.machine.recorder.host(|| metadata.map(|metadata| HostMetadata::new(&metadata)));
+        FileMetadata::from_host(ecx, metadata)
     }
 
-    fn from_meta<'tcx>(
+    fn from_host<'tcx>(
         ecx: &mut MiriInterpCx<'tcx>,
-        metadata: Result<std::fs::Metadata, std::io::Error>,
+        metadata: Result<HostMetadata, std::io::Error>,
     ) -> InterpResult<'tcx, Result<FileMetadata, IoError>> {
         let metadata = match metadata {
             Ok(metadata) => metadata,
@@ -1727,25 +1941,115 @@ fn from_meta<'tcx>(
             }
         };
 
-        let file_type = metadata.file_type();
-
-        let mode_name = if file_type.is_file() {
-            "S_IFREG"
-        } else if file_type.is_dir() {
-            "S_IFDIR"
-        } else {
-            "S_IFLNK"
+        let mode_name = match metadata.file_type {
+            VfsFileType::File => "S_IFREG",
+            VfsFileType::Dir => "S_IFDIR",
+            VfsFileType::Symlink => "S_IFLNK",
         };
 
         let mode = ecx.eval_libc(mode_name);
 
-        let size = metadata.len();
+        let size = metadata.size;
 
-        let created = extract_sec_and_nsec(metadata.created())?;
-        let accessed = extract_sec_and_nsec(metadata.accessed())?;
-        let modified = extract_sec_and_nsec(metadata.modified())?;
+        let created = extract_sec_and_nsec(metadata.created)?;
+        let accessed = extract_sec_and_nsec(metadata.accessed)?;
+        let modified = extract_sec_and_nsec(metadata.modified)?;
 
         // FIXME: Provide more fields using platform specific methods.
         interp_ok(Ok(FileMetadata { mode, size, created, accessed, modified }))
     }
+
+    fn from_vfs<'tcx>(ecx: &mut MiriInterpCx<'tcx>, metadata: VfsMetadata) -> FileMetadata {
+        let mode_name = match metadata.file_type {
+            VfsFileType::File => "S_IFREG",
+            VfsFileType::Dir => "S_IFDIR",
+            VfsFileType::Symlink => "S_IFLNK",
+        };
+        // The virtual filesystem does not have timestamps.
+        FileMetadata {
+            mode: ecx.eval_libc(mode_name),
+            size: metadata.size,
+            created: None,
+            accessed: None,
+            modified: None,
+        }
+    }
+}
+
+/// The metadata of a host file, as far as Miri uses it. This is what is recorded for
+/// `-Zmiri-record`, as `std::fs::Metadata` cannot be constructed when replaying.
+struct HostMetadata {
+    /// Everything that is neither a file nor a directory is treated as a symlink.
+    file_type: VfsFileType,
+    size: u64,
+    created: Option<SystemTime>,
+    accessed: Option<SystemTime>,
+    modified: Option<SystemTime>,
+}
+
+impl HostMetadata {
+    fn new(metadata: &Metadata) -> Self {
+        let file_type = metadata.file_type();
+        let file_type = if file_type.is_file() {
+            VfsFileType::File
+        } else if file_type.is_dir() {
+            VfsFileType::Dir
+        } else {
+            VfsFileType::Symlink
+        };
+        HostMetadata {
+            file_type,
+            size: metadata.len(),
+            created: metadata.created().ok(),
+            accessed: metadata.accessed().ok(),
+            modified: metadata.modified().ok(),
+        }
+    }
+}
+
+impl HostValue for HostMetadata {
+    fn to_bytes(&self) -> Vec<u8> {
+        let file_type = match self.file_type {
+            VfsFileType::File => 0,
+            VfsFileType::Dir => 1,
+            VfsFileType::Symlink => 2,
+        };
+        let mut bytes = vec![file_type];
+        bytes.extend(self.size.to_bytes());
+        for time in [self.created, self.accessed, self.modified] {
+            // Missing times are zeroed, so that every time takes the same space.
+            bytes.push(time.is_some().into());
+            bytes.extend(time.unwrap_or(SystemTime::UNIX_EPOCH).to_bytes());
+        }
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&file_type, bytes) = bytes.split_first()?;
+        let file_type = match file_type {
+            0 => VfsFileType::File,
+            1 => VfsFileType::Dir,
+            2 => VfsFileType::Symlink,
+            _ => return None,
+        };
+        let (size, bytes) = bytes.split_at_checked(8)?;
+        let mut times = bytes.chunks(17).map(|time| {
+            let (&present, time) = time.split_first()?;
+            let time = SystemTime::from_bytes(time)?;
+            match present {
+                0 => Some(None),
+                1 => Some(Some(time)),
+                _ => None,
+            }
+        });
+        let mut next_time = || times.next().flatten();
+        let metadata = HostMetadata {
+            file_type,
+            size: u64::from_bytes(size)?,
+            created: next_time()?,
+            accessed: next_time()?,
+            modified: next_time()?,
+        };
+        times.next().is_none().then_some(metadata)
+    }
 }
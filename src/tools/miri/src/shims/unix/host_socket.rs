@@ -0,0 +1,607 @@
+//! This implements IPv4 and IPv6 sockets that are backed by sockets of the host. With isolation
+//! disabled, `socket` creates these instead of the emulated sockets of `socket.rs`, so that a
+//! program can talk to other processes and to the network.
+//!
+//! Like the networking types of the standard library, we only create the host socket once it is
+//! bound or connected: binding a stream socket makes it listen right away, connecting a stream
+//! socket blocks the entire interpreter until the connection is established, and sending from or
+//! connecting an unbound datagram socket binds it to an ephemeral port.
+//!
+//! The host sockets are always non-blocking. When an operation would block on the host and the
+//! socket is in blocking mode, the current thread is blocked for `HOST_POLL_INTERVAL` and then
+//! tries again, so that the other threads keep running in the meantime. The readiness of host
+//! sockets can change without Miri noticing, so epoll, `poll` and `select` check it again that
+//! often while they wait, which is only supported on Unix hosts.
+//!
+//! Miri cannot tell which host sockets are connected to each other, so receiving from a host
+//! socket synchronizes with all previous sends to host sockets.
+
+use std::cell::{Cell, RefCell};
+use std::io::{self, ErrorKind, Read as _, Write as _};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::time::Duration;
+
+use rustc_abi::Size;
+
+use crate::concurrency::VClock;
+use crate::shims::files::{FileDescription, FileDescriptionRef};
+use crate::shims::unix::UnixFileDescription;
+use crate::shims::unix::linux_like::epoll::{EpollReadyEvents, EvalContextExt as _};
+use crate::shims::unix::socket::{SocketKind, SocketOption, write_sockaddr};
+use crate::*;
+
+/// How long a thread waits for a host socket before checking it again.
+pub(super) const HOST_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// An IPv4 or IPv6 socket backed by a host socket.
+#[derive(Debug)]
+pub(super) struct HostSocket {
+    kind: SocketKind,
+    pub(super) is_ipv6: bool,
+    state: RefCell<HostSocketState>,
+    pub(super) is_nonblock: Cell<bool>,
+    /// Whether `TCP_NODELAY` was set before the socket was connected, or on the listener that
+    /// accepted it.
+    nodelay: Cell<bool>,
+    /// Whether `SO_BROADCAST` was set before the socket was bound.
+    broadcast: Cell<bool>,
+    /// The readiness last reported to epoll and `poll`.
+    reported_readiness: Cell<EpollReadyEvents>,
+}
+
+#[derive(Debug)]
+enum HostSocketState {
+    /// A stream socket that is neither bound nor connected.
+    Unconnected,
+    /// A bound stream socket. The host socket already listens, but we only accept connections
+    /// once the program called `listen`.
+    Bound(TcpListener),
+    /// A listening stream socket.
+    Listening(TcpListener),
+    /// A connected stream socket.
+    Connected(TcpStream),
+    /// A datagram socket, with its host socket once it is bound.
+    Datagram(Option<UdpSocket>),
+}
+
+impl VisitProvenance for HostSocket {
+    fn visit_provenance(&self, _visit: &mut VisitWith<'_>) {
+        // No provenance anywhere in this type.
+    }
+}
+
+impl HostSocket {
+    pub(super) fn new(kind: SocketKind, is_ipv6: bool, is_nonblock: bool) -> Self {
+        let state = match kind {
+            SocketKind::Stream => HostSocketState::Unconnected,
+            SocketKind::Datagram => HostSocketState::Datagram(None),
+        };
+        HostSocket {
+            kind,
+            is_ipv6,
+            state: RefCell::new(state),
+            is_nonblock: Cell::new(is_nonblock),
+            nodelay: Cell::new(false),
+            broadcast: Cell::new(false),
+            reported_readiness: Cell::new(EpollReadyEvents::new()),
+        }
+    }
+
+    fn unspecified_ip(&self) -> IpAddr {
+        if self.is_ipv6 { Ipv6Addr::UNSPECIFIED.into() } else { Ipv4Addr::UNSPECIFIED.into() }
+    }
+
+    pub(super) fn bind(&self, addr: SocketAddr) -> Result<(), IoError> {
+        let mut state = self.state.borrow_mut();
+        match &*state {
+            HostSocketState::Unconnected => {
+                let listener = TcpListener::bind(addr)?;
+                listener.set_nonblocking(true)?;
+                *state = HostSocketState::Bound(listener);
+            }
+            HostSocketState::Datagram(None) => {
+                let socket = UdpSocket::bind(addr)?;
+                socket.set_nonblocking(true)?;
+                socket.set_broadcast(self.broadcast.get())?;
+                *state = HostSocketState::Datagram(Some(socket));
+            }
+            _ => return Err(LibcError("EINVAL")),
+        }
+        Ok(())
+    }
+
+    /// Binds a datagram socket to an ephemeral port if it isn't bound yet.
+    fn ensure_bound(&self) -> Result<(), IoError> {
+        if matches!(*self.state.borrow(), HostSocketState::Datagram(None)) {
+            self.bind(SocketAddr::new(self.unspecified_ip(), 0))?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn listen(&self) -> Result<(), IoError> {
+        let mut state = self.state.borrow_mut();
+        match std::mem::replace(&mut *state, HostSocketState::Unconnected) {
+            HostSocketState::Unconnected => {
+                drop(state);
+                // Like on Linux, listening on an unbound socket binds it to an ephemeral port.
+                self.bind(SocketAddr::new(self.unspecified_ip(), 0))?;
+                return self.listen();
+            }
+            HostSocketState::Bound(listener) | HostSocketState::Listening(listener) =>
+                *state = HostSocketState::Listening(listener),
+            old_state @ HostSocketState::Connected(_) => {
+                *state = old_state;
+                return Err(LibcError("EINVAL"));
+            }
+            old_state @ HostSocketState::Datagram(_) => {
+                *state = old_state;
+                return Err(LibcError("EOPNOTSUPP"));
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn connect<'tcx>(
+        &self,
+        ecx: &mut MiriInterpCx<'tcx>,
+        addr: SocketAddr,
+    ) -> InterpResult<'tcx, Result<(), IoError>> {
+        if self.kind == SocketKind::Datagram {
+            // This only sets the default destination.
+            if let Err(err) = self.ensure_bound() {
+                return interp_ok(Err(err));
+            }
+            let state = self.state.borrow();
+            let HostSocketState::Datagram(Some(socket)) = &*state else {
+                unreachable!("`ensure_bound` binds datagram sockets")
+            };
+            return interp_ok(socket.connect(addr).map_err(IoError::HostError));
+        }
+
+        match &*self.state.borrow() {
+            HostSocketState::Unconnected => {}
+            HostSocketState::Connected(_) => return interp_ok(Err(LibcError("EISCONN"))),
+            HostSocketState::Bound(_) =>
+                throw_unsup_format!(
+                    "connect: connecting a bound stream socket is unsupported \
+                     with isolation disabled"
+                ),
+            HostSocketState::Listening(_) | HostSocketState::Datagram(_) =>
+                return interp_ok(Err(LibcError("EINVAL"))),
+        }
+        let stream = match connect_stream(addr, self.nodelay.get()) {
+            Ok(stream) => stream,
+            Err(err) => return interp_ok(Err(err.into())),
+        };
+        *self.state.borrow_mut() = HostSocketState::Connected(stream);
+        release_host_clock(ecx);
+        interp_ok(Ok(()))
+    }
+
+    pub(super) fn set_option(&self, option: SocketOption, value: i32) -> io::Result<()> {
+        match option {
+            SocketOption::NoDelay => {
+                self.nodelay.set(value != 0);
+                if let HostSocketState::Connected(stream) = &*self.state.borrow() {
+                    stream.set_nodelay(value != 0)?;
+                }
+            }
+            SocketOption::Broadcast => {
+                self.broadcast.set(value != 0);
+                if let HostSocketState::Datagram(Some(socket)) = &*self.state.borrow() {
+                    socket.set_broadcast(value != 0)?;
+                }
+            }
+            // We cannot set these before binding, which is when they matter. The standard library
+            // sets `SO_REUSEADDR` for listeners on Unix hosts anyway, and takes care of `SIGPIPE`.
+            SocketOption::ReuseAddr | SocketOption::NoSigPipe | SocketOption::V6Only => {}
+        }
+        Ok(())
+    }
+
+    /// Returns and clears the pending error of the host socket.
+    pub(super) fn take_error(&self) -> io::Result<Option<io::Error>> {
+        match &*self.state.borrow() {
+            HostSocketState::Unconnected | HostSocketState::Datagram(None) => Ok(None),
+            HostSocketState::Bound(listener) | HostSocketState::Listening(listener) =>
+                listener.take_error(),
+            HostSocketState::Connected(stream) => stream.take_error(),
+            HostSocketState::Datagram(Some(socket)) => socket.take_error(),
+        }
+    }
+
+    pub(super) fn local_addr(&self) -> io::Result<SocketAddr> {
+        match &*self.state.borrow() {
+            // An unbound socket has the unspecified address with port 0.
+            HostSocketState::Unconnected | HostSocketState::Datagram(None) =>
+                Ok(SocketAddr::new(self.unspecified_ip(), 0)),
+            HostSocketState::Bound(listener) | HostSocketState::Listening(listener) =>
+                listener.local_addr(),
+            HostSocketState::Connected(stream) => stream.local_addr(),
+            HostSocketState::Datagram(Some(socket)) => socket.local_addr(),
+        }
+    }
+
+    pub(super) fn peer_addr(&self) -> Result<SocketAddr, IoError> {
+        match &*self.state.borrow() {
+            HostSocketState::Connected(stream) => Ok(stream.peer_addr()?),
+            HostSocketState::Datagram(Some(socket)) => Ok(socket.peer_addr()?),
+            _ => Err(LibcError("ENOTCONN")),
+        }
+    }
+
+    /// Records that an operation of the program found the socket not to be ready, so that we
+    /// report it to epoll again once it becomes ready.
+    fn clear_readiness(&self, update: impl FnOnce(&mut EpollReadyEvents)) {
+        let mut readiness = self.reported_readiness.get();
+        update(&mut readiness);
+        self.reported_readiness.set(readiness);
+    }
+}
+
+impl FileDescription for HostSocket {
+    fn name(&self) -> &'static str {
+        "socket"
+    }
+
+    fn close<'tcx>(
+        self,
+        _communicate_allowed: bool,
+        _ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        drop(self);
+        interp_ok(Ok(()))
+    }
+
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        assert!(communicate_allowed, "isolation should have prevented even creating a host socket");
+        let is_nonblock = self.is_nonblock.get();
+        host_recv(self, ptr, len, is_nonblock, /* peek */ false, None, ecx, finish)
+    }
+
+    fn write<'tcx>(
+        self: FileDescriptionRef<Self>,
+        communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        assert!(communicate_allowed, "isolation should have prevented even creating a host socket");
+        let is_nonblock = self.is_nonblock.get();
+        host_send(self, ptr, len, is_nonblock, None, ecx, finish)
+    }
+
+    fn tracks_readiness(&self) -> bool {
+        true
+    }
+
+    fn as_unix(&self) -> &dyn UnixFileDescription {
+        self
+    }
+}
+
+impl UnixFileDescription for HostSocket {
+    fn get_epoll_ready_events<'tcx>(&self) -> InterpResult<'tcx, EpollReadyEvents> {
+        let mut epoll_ready_events = EpollReadyEvents::new();
+        let state = self.state.borrow();
+        match &*state {
+            HostSocketState::Unconnected | HostSocketState::Bound(_) => {
+                // Like Linux, report a socket that was never connected as hung up.
+                epoll_ready_events.epollout = true;
+                epoll_ready_events.epollhup = true;
+                return interp_ok(epoll_ready_events);
+            }
+            HostSocketState::Datagram(None) => {
+                // Sending a datagram binds the socket, and never blocks.
+                epoll_ready_events.epollout = true;
+                return interp_ok(epoll_ready_events);
+            }
+            HostSocketState::Listening(_)
+            | HostSocketState::Connected(_)
+            | HostSocketState::Datagram(Some(_)) => {}
+        }
+        cfg_match! {
+            unix => {
+                use std::os::fd::AsRawFd;
+
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                const POLLRDHUP: i16 = libc::POLLRDHUP;
+                #[cfg(not(any(target_os = "linux", target_os = "android")))]
+                const POLLRDHUP: i16 = 0;
+
+                let fd = match &*state {
+                    HostSocketState::Listening(listener) => listener.as_raw_fd(),
+                    HostSocketState::Connected(stream) => stream.as_raw_fd(),
+                    HostSocketState::Datagram(Some(socket)) => socket.as_raw_fd(),
+                    _ => unreachable!(),
+                };
+                let events = libc::POLLIN | libc::POLLOUT | POLLRDHUP;
+                let mut pollfd = libc::pollfd { fd, events, revents: 0 };
+                // SAFETY: `pollfd` is a single valid entry, and `fd` belongs to `self`, so it stays
+                // open during the call.
+                let ret = unsafe { libc::poll(&mut pollfd, 1, 0) };
+                if ret == -1 {
+                    epoll_ready_events.epollerr = true;
+                    return interp_ok(epoll_ready_events);
+                }
+                epoll_ready_events.epollin = pollfd.revents & libc::POLLIN != 0;
+                epoll_ready_events.epollout = pollfd.revents & libc::POLLOUT != 0;
+                epoll_ready_events.epollrdhup = pollfd.revents & POLLRDHUP != 0;
+                epoll_ready_events.epollhup = pollfd.revents & libc::POLLHUP != 0;
+                epoll_ready_events.epollerr = pollfd.revents & libc::POLLERR != 0;
+                interp_ok(epoll_ready_events)
+            }
+            _ => {
+                throw_unsup_format!(
+                    "epoll, poll and select on host sockets are only supported on Unix hosts"
+                );
+            }
+        }
+    }
+
+    fn set_nonblocking<'tcx>(
+        &self,
+        communicate_allowed: bool,
+        nonblocking: bool,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        assert!(communicate_allowed, "isolation should have prevented even creating a host socket");
+        // The host socket is always non-blocking.
+        self.is_nonblock.set(nonblocking);
+        interp_ok(Ok(()))
+    }
+}
+
+/// Connects a non-blocking host stream socket to `addr`.
+fn connect_stream(addr: SocketAddr, nodelay: bool) -> io::Result<TcpStream> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_nonblocking(true)?;
+    stream.set_nodelay(nodelay)?;
+    Ok(stream)
+}
+
+/// Adds `socket` to the file descriptor table, and returns its file descriptor.
+pub(super) fn insert_host_socket(ecx: &mut MiriInterpCx<'_>, socket: HostSocket) -> i32 {
+    let socket = ecx.machine.fds.new_ref(socket);
+    ecx.machine.sockets.host_sockets.push(FileDescriptionRef::downgrade(&socket));
+    ecx.machine.fds.insert(socket)
+}
+
+/// Remembers the clock of the current thread, so that receiving from a host socket can
+/// synchronize with it.
+fn release_host_clock(ecx: &mut MiriInterpCx<'_>) {
+    let mut clock = VClock::default();
+    ecx.release_clock(|release_clock| {
+        clock.join(release_clock);
+    });
+    ecx.machine.sockets.host_clock.join(&clock);
+}
+
+/// Blocks the current thread for `HOST_POLL_INTERVAL`, and then calls `retry`.
+fn block_and_retry<'tcx>(ecx: &mut MiriInterpCx<'tcx>, retry: DynMachineCallback<'tcx, ()>) {
+    ecx.block_thread(
+        BlockReason::Socket,
+        Some((TimeoutClock::Monotonic, TimeoutAnchor::Relative, HOST_POLL_INTERVAL)),
+        callback!(
+            @capture<'tcx> {
+                retry: DynMachineCallback<'tcx, ()>,
+            }
+            |this, unblock: UnblockKind| {
+                assert_eq!(unblock, UnblockKind::TimedOut);
+                retry.call(this, ())
+            }
+        ),
+    );
+}
+
+/// Sends to a host socket, to `dest_addr` for datagram sockets if given.
+pub(super) fn host_send<'tcx>(
+    self_ref: FileDescriptionRef<HostSocket>,
+    ptr: Pointer,
+    len: usize,
+    is_nonblock: bool,
+    dest_addr: Option<SocketAddr>,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+) -> InterpResult<'tcx> {
+    if let Err(err) = self_ref.ensure_bound() {
+        return finish.call(ecx, Err(err));
+    }
+    let bytes = ecx.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?;
+    let result = match &*self_ref.state.borrow() {
+        // Like Linux, we ignore the destination of connected stream sockets.
+        HostSocketState::Connected(stream) => (&*stream).write(bytes).map_err(IoError::HostError),
+        HostSocketState::Datagram(Some(socket)) =>
+            match dest_addr {
+                Some(addr) => socket.send_to(bytes, addr).map_err(IoError::HostError),
+                None if socket.peer_addr().is_err() => Err(LibcError("EDESTADDRREQ")),
+                None => socket.send(bytes).map_err(IoError::HostError),
+            },
+        HostSocketState::Datagram(None) => unreachable!("`ensure_bound` binds datagram sockets"),
+        HostSocketState::Unconnected
+        | HostSocketState::Bound(_)
+        | HostSocketState::Listening(_) => Err(LibcError("ENOTCONN")),
+    };
+
+    match result {
+        Err(IoError::HostError(err)) if err.kind() == ErrorKind::WouldBlock => {
+            self_ref.clear_readiness(|readiness| readiness.epollout = false);
+            if is_nonblock {
+                return finish.call(ecx, Err(err.into()));
+            }
+            let retry = callback!(
+                @capture<'tcx> {
+                    self_ref: FileDescriptionRef<HostSocket>,
+                    ptr: Pointer,
+                    len: usize,
+                    dest_addr: Option<SocketAddr>,
+                    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+                }
+                |this, _unit: ()| {
+                    host_send(self_ref, ptr, len, /* is_nonblock */ false, dest_addr, this, finish)
+                }
+            );
+            block_and_retry(ecx, retry);
+            interp_ok(())
+        }
+        Err(err) => finish.call(ecx, Err(err)),
+        Ok(size) => {
+            release_host_clock(ecx);
+            finish.call(ecx, Ok(size))
+        }
+    }
+}
+
+/// Receives from a host socket. For datagram sockets, the source address is written to `source`
+/// if given, as a pointer to the `sockaddr` and a pointer to its length.
+pub(super) fn host_recv<'tcx>(
+    self_ref: FileDescriptionRef<HostSocket>,
+    ptr: Pointer,
+    len: usize,
+    is_nonblock: bool,
+    peek: bool,
+    source: Option<(Pointer, Pointer)>,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+) -> InterpResult<'tcx> {
+    let mut bytes = vec![0; len];
+    let result = match &*self_ref.state.borrow() {
+        HostSocketState::Connected(stream) => {
+            let result = if peek { stream.peek(&mut bytes) } else { (&*stream).read(&mut bytes) };
+            result.map(|size| (size, None)).map_err(IoError::HostError)
+        }
+        HostSocketState::Datagram(Some(socket)) => {
+            let result =
+                if peek { socket.peek_from(&mut bytes) } else { socket.recv_from(&mut bytes) };
+            result.map(|(size, addr)| (size, Some(addr))).map_err(IoError::HostError)
+        }
+        // Nothing can be sent to a datagram socket that isn't bound.
+        HostSocketState::Datagram(None) => Err(ErrorKind::WouldBlock.into()),
+        HostSocketState::Unconnected
+        | HostSocketState::Bound(_)
+        | HostSocketState::Listening(_) => Err(LibcError("ENOTCONN")),
+    };
+
+    match result {
+        Err(IoError::HostError(err)) if err.kind() == ErrorKind::WouldBlock => {
+            self_ref.clear_readiness(|readiness| readiness.epollin = false);
+            if is_nonblock {
+                return finish.call(ecx, Err(err.into()));
+            }
+            let retry = callback!(
+                @capture<'tcx> {
+                    self_ref: FileDescriptionRef<HostSocket>,
+                    ptr: Pointer,
+                    len: usize,
+                    peek: bool,
+                    source: Option<(Pointer, Pointer)>,
+                    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+                }
+                |this, _unit: ()| {
+                    host_recv(
+                        self_ref,
+                        ptr,
+                        len,
+                        /* is_nonblock */ false,
+                        peek,
+                        source,
+                        this,
+                        finish,
+                    )
+                }
+            );
+            block_and_retry(ecx, retry);
+            interp_ok(())
+        }
+        Err(err) => finish.call(ecx, Err(err)),
+        Ok((size, addr)) => {
+            // Synchronize with all previous sends to host sockets.
+            ecx.acquire_clock(&ecx.machine.sockets.host_clock);
+            ecx.write_bytes_ptr(ptr, bytes[..size].iter().copied())?;
+            if let (Some(addr), Some((address, address_len))) = (addr, source) {
+                write_sockaddr(ecx, addr, address, address_len)?;
+            }
+            finish.call(ecx, Ok(size))
+        }
+    }
+}
+
+/// Accepts a connection of a listening host socket, and writes the new file descriptor to
+/// `dest`.
+pub(super) fn host_accept<'tcx>(
+    self_ref: FileDescriptionRef<HostSocket>,
+    address: Pointer,
+    address_len: Pointer,
+    is_nonblock: bool,
+    dest: MPlaceTy<'tcx>,
+    ecx: &mut MiriInterpCx<'tcx>,
+) -> InterpResult<'tcx> {
+    let result = match &*self_ref.state.borrow() {
+        HostSocketState::Listening(listener) => listener.accept(),
+        _ => return ecx.set_last_error_and_return(LibcError("EINVAL"), &dest),
+    };
+    let (stream, peer_addr) = match result {
+        Ok(connection) => connection,
+        Err(err) if err.kind() == ErrorKind::WouldBlock => {
+            self_ref.clear_readiness(|readiness| readiness.epollin = false);
+            if self_ref.is_nonblock.get() {
+                return ecx.set_last_error_and_return(err, &dest);
+            }
+            let retry = callback!(
+                @capture<'tcx> {
+                    self_ref: FileDescriptionRef<HostSocket>,
+                    address: Pointer,
+                    address_len: Pointer,
+                    is_nonblock: bool,
+                    dest: MPlaceTy<'tcx>,
+                }
+                |this, _unit: ()| {
+                    host_accept(self_ref, address, address_len, is_nonblock, dest, this)
+                }
+            );
+            block_and_retry(ecx, retry);
+            return interp_ok(());
+        }
+        Err(err) => return ecx.set_last_error_and_return(err, &dest),
+    };
+    // Some hosts let the connection inherit the settings of the listener, others don't.
+    let configured =
+        stream.set_nonblocking(true).and_then(|()| stream.set_nodelay(self_ref.nodelay.get()));
+    if let Err(err) = configured {
+        return ecx.set_last_error_and_return(err, &dest);
+    }
+
+    // Synchronize with the thread that connected, if it belongs to this program.
+    ecx.acquire_clock(&ecx.machine.sockets.host_clock);
+    let socket = HostSocket::new(SocketKind::Stream, self_ref.is_ipv6, is_nonblock);
+    socket.nodelay.set(self_ref.nodelay.get());
+    *socket.state.borrow_mut() = HostSocketState::Connected(stream);
+    write_sockaddr(ecx, peer_addr, address, address_len)?;
+    let fd = insert_host_socket(ecx, socket);
+    ecx.write_int(fd, &dest)
+}
+
+/// Notifies epoll and `poll` of the host sockets whose readiness changed since it was last
+/// reported.
+pub(crate) fn update_host_socket_readiness<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+) -> InterpResult<'tcx> {
+    let sockets = &mut ecx.machine.sockets.host_sockets;
+    // Forget the sockets that have been closed.
+    sockets.retain(|socket| socket.upgrade().is_some());
+    let sockets = sockets.iter().filter_map(|socket| socket.upgrade()).collect::<Vec<_>>();
+    for socket in sockets {
+        let readiness = socket.get_epoll_ready_events()?;
+        if socket.reported_readiness.replace(readiness) != readiness {
+            ecx.check_and_update_readiness(socket)?;
+        }
+    }
+    interp_ok(())
+}
@@ -158,6 +158,15 @@ fn emulate_foreign_item_inner(
 
                 this.write_int(SIGRTMAX, dest)?;
             }
+            "gnu_get_libc_version" => {
+                if this.tcx.sess.target.env != "gnu" {
+                    throw_unsup_format!("`gnu_get_libc_version` is only available with glibc");
+                }
+                let [] = this.check_shim(abi, Conv::C, link_name, args)?;
+                // std needs at least 2.24 to spawn processes with `posix_spawn`.
+                let version = this.allocate_bytes_dedup(b"2.40\0")?;
+                this.write_pointer(version, dest)?;
+            }
 
             // Incomplete shims that we "stub out" just to get pre-main initialization code to work.
             // These shims are enabled only when the caller is in the standard library.
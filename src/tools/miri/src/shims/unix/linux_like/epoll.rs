@@ -9,6 +9,8 @@
     DynFileDescriptionRef, FdId, FileDescription, FileDescriptionRef, WeakFileDescriptionRef,
 };
 use crate::shims::unix::UnixFileDescription;
+use crate::shims::unix::host_socket::{HOST_POLL_INTERVAL, update_host_socket_readiness};
+use crate::shims::unix::poll::elapsed;
 use crate::*;
 
 /// An `Epoll` file descriptor connects file handles and epoll events
@@ -78,6 +80,7 @@ pub struct EpollEventInterest {
 }
 
 /// EpollReadyEvents reflects the readiness of a file description.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct EpollReadyEvents {
     /// The associated file is available for read(2) operations, in the sense that a read will not block.
     /// (I.e., returning EOF is considered "ready".)
@@ -458,6 +461,8 @@ fn epoll_wait(
             return this.set_last_error_and_return(LibcError("EBADF"), dest);
         };
 
+        // Sockets backed by the host can become ready without Miri noticing.
+        update_host_socket_readiness(this)?;
         // We just need to know if the ready list is empty and borrow the thread_ids out.
         let ready_list_empty = epfd.ready_list.mapping.borrow().is_empty();
         if timeout == 0 || !ready_list_empty {
@@ -465,10 +470,10 @@ fn epoll_wait(
             return_ready_list(&epfd, dest, &event, this)?;
         } else {
             // Blocking
-            let timeout = match timeout {
+            let deadline = match timeout {
                 0.. => {
                     let duration = Duration::from_millis(timeout.try_into().unwrap());
-                    Some((TimeoutClock::Monotonic, TimeoutAnchor::Relative, duration))
+                    Some(elapsed(this) + duration)
                 }
                 -1 => None,
                 ..-1 => {
@@ -477,40 +482,7 @@ fn epoll_wait(
                     );
                 }
             };
-            // Record this thread as blocked.
-            epfd.blocked_tid.borrow_mut().push(this.active_thread());
-            // And block it.
-            let dest = dest.clone();
-            // We keep a strong ref to the underlying `Epoll` to make sure it sticks around.
-            // This means there'll be a leak if we never wake up, but that anyway would imply
-            // a thread is permanently blocked so this is fine.
-            this.block_thread(
-                BlockReason::Epoll,
-                timeout,
-                callback!(
-                    @capture<'tcx> {
-                        epfd: FileDescriptionRef<Epoll>,
-                        dest: MPlaceTy<'tcx>,
-                        event: MPlaceTy<'tcx>,
-                    }
-                    |this, unblock: UnblockKind| {
-                        match unblock {
-                            UnblockKind::Ready => {
-                                return_ready_list(&epfd, &dest, &event, this)?;
-                                interp_ok(())
-                            },
-                            UnblockKind::TimedOut => {
-                                // Remove the current active thread_id from the blocked thread_id list.
-                                epfd
-                                    .blocked_tid.borrow_mut()
-                                    .retain(|&id| id != this.active_thread());
-                                this.write_int(0, &dest)?;
-                                interp_ok(())
-                            },
-                        }
-                    }
-                ),
-            );
+            block_for_ready_list(epfd, dest.clone(), event, deadline, this);
         }
         interp_ok(())
     }
@@ -557,10 +529,74 @@ fn check_and_update_readiness(
         for thread_id in waiter {
             this.unblock_thread(thread_id, BlockReason::Epoll)?;
         }
+        // Let the threads blocked in `poll` or `select` check whether they can return now.
+        for thread_id in this.machine.poll_waiters.take_waiting_for(id) {
+            this.unblock_thread(thread_id, BlockReason::Poll)?;
+        }
         interp_ok(())
     }
 }
 
+/// Blocks the current thread until the ready list of `epfd` is not empty, or until `deadline`, and
+/// then stores the ready list into `events` like `epoll_wait`.
+fn block_for_ready_list<'tcx>(
+    epfd: FileDescriptionRef<Epoll>,
+    dest: MPlaceTy<'tcx>,
+    event: MPlaceTy<'tcx>,
+    deadline: Option<Duration>,
+    ecx: &mut MiriInterpCx<'tcx>,
+) {
+    // Record this thread as blocked.
+    epfd.blocked_tid.borrow_mut().push(ecx.active_thread());
+    // The readiness of sockets backed by the host can change without Miri noticing, so while
+    // there are any, we wake up regularly to check them.
+    let timeout = if ecx.machine.sockets.has_host_sockets() {
+        let next_check = elapsed(ecx) + HOST_POLL_INTERVAL;
+        Some(deadline.map_or(next_check, |deadline| deadline.min(next_check)))
+    } else {
+        deadline
+    };
+    // And block it.
+    // We keep a strong ref to the underlying `Epoll` to make sure it sticks around.
+    // This means there'll be a leak if we never wake up, but that anyway would imply
+    // a thread is permanently blocked so this is fine.
+    ecx.block_thread(
+        BlockReason::Epoll,
+        timeout.map(|timeout| (TimeoutClock::Monotonic, TimeoutAnchor::Absolute, timeout)),
+        callback!(
+            @capture<'tcx> {
+                epfd: FileDescriptionRef<Epoll>,
+                dest: MPlaceTy<'tcx>,
+                event: MPlaceTy<'tcx>,
+                deadline: Option<Duration>,
+            }
+            |this, unblock: UnblockKind| {
+                match unblock {
+                    UnblockKind::Ready => {
+                        return_ready_list(&epfd, &dest, &event, this)?;
+                        interp_ok(())
+                    },
+                    UnblockKind::TimedOut => {
+                        // Remove the current active thread_id from the blocked thread_id list.
+                        epfd
+                            .blocked_tid.borrow_mut()
+                            .retain(|&id| id != this.active_thread());
+                        update_host_socket_readiness(this)?;
+                        if !epfd.ready_list.mapping.borrow().is_empty() {
+                            return_ready_list(&epfd, &dest, &event, this)?;
+                        } else if deadline.is_none_or(|deadline| elapsed(this) < deadline) {
+                            block_for_ready_list(epfd, dest, event, deadline, this);
+                        } else {
+                            this.write_int(0, &dest)?;
+                        }
+                        interp_ok(())
+                    },
+                }
+            }
+        ),
+    );
+}
+
 /// This function takes in ready list and returns EpollEventInstance with file description
 /// that is not closed.
 fn ready_list_next(
@@ -100,6 +100,10 @@ fn write<'tcx>(
         eventfd_write(buf_place, self, ecx, finish)
     }
 
+    fn tracks_readiness(&self) -> bool {
+        true
+    }
+
     fn as_unix(&self) -> &dyn UnixFileDescription {
         self
     }
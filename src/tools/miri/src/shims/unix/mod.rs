@@ -3,10 +3,15 @@
 mod env;
 mod fd;
 mod fs;
+mod host_socket;
 mod mem;
+mod poll;
+mod process;
+mod socket;
 mod sync;
 mod thread;
 mod unnamed_socket;
+mod vfs;
 
 mod android;
 mod freebsd;
@@ -21,9 +26,13 @@
 pub use self::fs::{DirTable, EvalContextExt as _};
 pub use self::linux_like::epoll::EpollInterestTable;
 pub use self::mem::EvalContextExt as _;
+pub use self::poll::{EvalContextExt as _, PollWaiters};
+pub use self::process::{EvalContextExt as _, ProcessTable};
+pub use self::socket::{EvalContextExt as _, SocketTable};
 pub use self::sync::EvalContextExt as _;
 pub use self::thread::{EvalContextExt as _, ThreadNameResult};
 pub use self::unnamed_socket::EvalContextExt as _;
+pub use self::vfs::{Vfs, VfsSnapshot};
 
 // Make up some constants.
 const UID: u32 = 1000;
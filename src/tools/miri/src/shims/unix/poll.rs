@@ -0,0 +1,424 @@
+//! This implements `poll`, `ppoll` and `select` on top of the readiness tracking used by epoll:
+//! see `UnixFileDescription::get_epoll_ready_events`. File descriptions that don't track their
+//! readiness, like regular files, are always ready, as reads and writes of them never block.
+//!
+//! A blocked thread is woken up whenever the readiness of one of the file descriptions it waits
+//! for changes, i.e. whenever `check_and_update_readiness` is called for it. It then checks all
+//! file descriptors again, and blocks again if none of them is ready yet. The readiness of sockets
+//! backed by the host can change without Miri noticing, so while there are any, blocked threads
+//! also wake up every `HOST_POLL_INTERVAL` to check again.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use crate::shims::files::{DynFileDescriptionRef, FdId};
+use crate::shims::unix::host_socket::HOST_POLL_INTERVAL;
+use crate::*;
+
+/// The threads blocked in `poll` or `select`, with the file descriptions they wait for.
+#[derive(Debug, Default)]
+pub struct PollWaiters(BTreeMap<ThreadId, Vec<FdId>>);
+
+impl PollWaiters {
+    fn insert(&mut self, thread: ThreadId, ids: Vec<FdId>) {
+        self.0.insert(thread, ids);
+    }
+
+    fn remove(&mut self, thread: ThreadId) {
+        self.0.remove(&thread);
+    }
+
+    /// Returns the threads waiting for the file description with the given id, and forgets them.
+    pub(crate) fn take_waiting_for(&mut self, id: FdId) -> Vec<ThreadId> {
+        let threads = self
+            .0
+            .iter()
+            .filter(|(_, ids)| ids.contains(&id))
+            .map(|(&thread, _)| thread)
+            .collect::<Vec<_>>();
+        for thread in &threads {
+            self.0.remove(thread);
+        }
+        threads
+    }
+}
+
+/// The events a file description is ready for.
+#[derive(Debug, Clone, Copy)]
+struct Readiness {
+    read: bool,
+    write: bool,
+    rdhup: bool,
+    hup: bool,
+    err: bool,
+}
+
+fn readiness<'tcx>(fd: &DynFileDescriptionRef) -> InterpResult<'tcx, Readiness> {
+    if !fd.tracks_readiness() {
+        return interp_ok(Readiness {
+            read: true,
+            write: true,
+            rdhup: false,
+            hup: false,
+            err: false,
+        });
+    }
+    let events = fd.as_unix().get_epoll_ready_events()?;
+    interp_ok(Readiness {
+        read: events.epollin,
+        write: events.epollout,
+        rdhup: events.epollrdhup,
+        hup: events.epollhup,
+        err: events.epollerr,
+    })
+}
+
+/// Returns the time elapsed on the monotonic clock.
+pub(super) fn elapsed(ecx: &MiriInterpCx<'_>) -> Duration {
+    ecx.machine.clock.now().duration_since(ecx.machine.clock.epoch())
+}
+
+/// Returns whether `deadline`, as returned by `elapsed`, has passed.
+fn is_expired(ecx: &MiriInterpCx<'_>, deadline: Option<Duration>) -> bool {
+    deadline.is_some_and(|deadline| elapsed(ecx) >= deadline)
+}
+
+/// Blocks the current thread until one of the file descriptions in `ids` changes, until
+/// `deadline`, or until the host sockets need to be checked again, and then calls `retry`.
+fn block_until_change<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    ids: Vec<FdId>,
+    deadline: Option<Duration>,
+    retry: DynMachineCallback<'tcx, ()>,
+) {
+    let thread = ecx.active_thread();
+    ecx.machine.poll_waiters.insert(thread, ids);
+    let timeout = if ecx.machine.sockets.has_host_sockets() {
+        let next_check = elapsed(ecx) + HOST_POLL_INTERVAL;
+        Some(deadline.map_or(next_check, |deadline| deadline.min(next_check)))
+    } else {
+        deadline
+    };
+    ecx.block_thread(
+        BlockReason::Poll,
+        timeout.map(|timeout| (TimeoutClock::Monotonic, TimeoutAnchor::Absolute, timeout)),
+        callback!(
+            @capture<'tcx> {
+                retry: DynMachineCallback<'tcx, ()>,
+            }
+            |this, unblock: UnblockKind| {
+                if unblock == UnblockKind::TimedOut {
+                    let thread = this.active_thread();
+                    this.machine.poll_waiters.remove(thread);
+                }
+                retry.call(this, ())
+            }
+        ),
+    );
+}
+
+/// Checks the `nfds` entries of the `pollfd` array at `fds`, and writes the number of ready ones
+/// to `dest` once there is one, or once `deadline` has passed.
+fn poll_fds<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    fds: Pointer,
+    nfds: u64,
+    deadline: Option<Duration>,
+    dest: MPlaceTy<'tcx>,
+) -> InterpResult<'tcx> {
+    let pollin = ecx.eval_libc("POLLIN").to_i16()?;
+    let pollout = ecx.eval_libc("POLLOUT").to_i16()?;
+    let pollhup = ecx.eval_libc("POLLHUP").to_i16()?;
+    let pollerr = ecx.eval_libc("POLLERR").to_i16()?;
+    let pollnval = ecx.eval_libc("POLLNVAL").to_i16()?;
+    // POLLRDHUP only exists on Linux.
+    let pollrdhup = if matches!(ecx.tcx.sess.target.os.as_ref(), "linux" | "android") {
+        ecx.eval_libc("POLLRDHUP").to_i16()?
+    } else {
+        0
+    };
+
+    let fds_place = ecx.ptr_to_mplace(fds, ecx.libc_array_ty_layout("pollfd", nfds));
+    let mut entries = Vec::new();
+    let mut array_iter = ecx.project_array_fields(&fds_place)?;
+    while let Some((_, pollfd)) = array_iter.next(ecx)? {
+        let fd_field = ecx.project_field_named(&pollfd, "fd")?;
+        let events_field = ecx.project_field_named(&pollfd, "events")?;
+        let fd_num = ecx.read_scalar(&fd_field)?.to_i32()?;
+        let events = ecx.read_scalar(&events_field)?.to_i16()?;
+        entries.push((pollfd, fd_num, events));
+    }
+
+    let mut results = Vec::new();
+    let mut ids = Vec::new();
+    for (pollfd, fd_num, events) in entries {
+        // Negative file descriptors are ignored.
+        let revents = if fd_num < 0 {
+            0
+        } else if let Some(fd) = ecx.machine.fds.get(fd_num) {
+            let ready = readiness(&fd)?;
+            let mut revents = 0;
+            if ready.read {
+                revents |= pollin;
+            }
+            if ready.write {
+                revents |= pollout;
+            }
+            if ready.rdhup {
+                revents |= pollrdhup;
+            }
+            // Hang-ups and errors are always reported, even if not requested.
+            let mut revents = revents & events;
+            if ready.hup {
+                revents |= pollhup;
+            }
+            if ready.err {
+                revents |= pollerr;
+            }
+            if revents == 0 {
+                ids.push(fd.id());
+            }
+            revents
+        } else {
+            pollnval
+        };
+        results.push((pollfd, revents));
+    }
+
+    let num_ready = results.iter().filter(|&&(_, revents)| revents != 0).count();
+    if num_ready == 0 && !is_expired(ecx, deadline) {
+        let retry = callback!(
+            @capture<'tcx> {
+                fds: Pointer,
+                nfds: u64,
+                deadline: Option<Duration>,
+                dest: MPlaceTy<'tcx>,
+            }
+            |this, _unit: ()| {
+                poll_fds(this, fds, nfds, deadline, dest)
+            }
+        );
+        block_until_change(ecx, ids, deadline, retry);
+        return interp_ok(());
+    }
+
+    for (pollfd, revents) in results {
+        let revents_field = ecx.project_field_named(&pollfd, "revents")?;
+        ecx.write_int(revents, &revents_field)?;
+    }
+    ecx.write_int(u64::try_from(num_ready).unwrap(), &dest)
+}
+
+/// The file descriptors of an `fd_set`.
+struct FdSet {
+    ptr: Pointer,
+    fds: Vec<i32>,
+}
+
+impl FdSet {
+    /// Reads the file descriptors below `nfds` in the `fd_set` at `ptr`, which may be null.
+    fn read<'tcx>(ecx: &MiriInterpCx<'tcx>, ptr: Pointer, nfds: i32) -> InterpResult<'tcx, FdSet> {
+        let mut fds = Vec::new();
+        if !ecx.ptr_is_null(ptr)? {
+            let bits = fd_set_bits(ecx, ptr)?;
+            let word_bits = fd_set_word_bits(ecx, &bits)?;
+            for fd in 0..nfds {
+                let word = ecx.project_index(&bits, u64::try_from(fd).unwrap() / word_bits)?;
+                let word = ecx.read_scalar(&word)?.to_uint(word.layout.size)?;
+                if (word >> (u64::try_from(fd).unwrap() % word_bits)) & 1 != 0 {
+                    fds.push(fd);
+                }
+            }
+        }
+        interp_ok(FdSet { ptr, fds })
+    }
+
+    /// Replaces the file descriptors below `nfds` in the `fd_set` with `fds`.
+    fn write<'tcx>(
+        &self,
+        ecx: &mut MiriInterpCx<'tcx>,
+        nfds: i32,
+        fds: &[i32],
+    ) -> InterpResult<'tcx> {
+        if ecx.ptr_is_null(self.ptr)? {
+            return interp_ok(());
+        }
+        let bits = fd_set_bits(ecx, self.ptr)?;
+        let word_bits = fd_set_word_bits(ecx, &bits)?;
+        let nfds = u64::try_from(nfds).unwrap();
+        for index in 0..nfds.div_ceil(word_bits) {
+            let word = ecx.project_index(&bits, index)?;
+            let mut value = ecx.read_scalar(&word)?.to_uint(word.layout.size)?;
+            for bit in 0..word_bits {
+                let fd = index * word_bits + bit;
+                if fd < nfds {
+                    value &= !(1 << bit);
+                    if fds.contains(&i32::try_from(fd).unwrap()) {
+                        value |= 1 << bit;
+                    }
+                }
+            }
+            ecx.write_scalar(Scalar::from_uint(value, word.layout.size), &word)?;
+        }
+        interp_ok(())
+    }
+}
+
+/// Returns the array of words of the `fd_set` at `ptr`.
+fn fd_set_bits<'tcx>(ecx: &MiriInterpCx<'tcx>, ptr: Pointer) -> InterpResult<'tcx, MPlaceTy<'tcx>> {
+    let set = ecx.ptr_to_mplace(ptr, ecx.libc_ty_layout("fd_set"));
+    ecx.project_field_named(&set, "fds_bits")
+}
+
+/// Returns the number of file descriptors stored in each word of an `fd_set`.
+fn fd_set_word_bits<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    bits: &MPlaceTy<'tcx>,
+) -> InterpResult<'tcx, u64> {
+    interp_ok(ecx.project_index(bits, 0)?.layout.size.bits())
+}
+
+/// Checks the file descriptors of the `fd_set`s of `select`, and writes the ready ones back and
+/// their number to `dest` once there is one, or once `deadline` has passed.
+fn select_fds<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    nfds: i32,
+    (readfds, writefds, exceptfds): (Pointer, Pointer, Pointer),
+    deadline: Option<Duration>,
+    dest: MPlaceTy<'tcx>,
+) -> InterpResult<'tcx> {
+    let readfds = FdSet::read(ecx, readfds, nfds)?;
+    let writefds = FdSet::read(ecx, writefds, nfds)?;
+    let exceptfds = FdSet::read(ecx, exceptfds, nfds)?;
+
+    let mut readable = Vec::new();
+    let mut writable = Vec::new();
+    let mut ids = Vec::new();
+    let all_fds = readfds.fds.iter().chain(&writefds.fds).chain(&exceptfds.fds);
+    for &fd_num in all_fds.collect::<BTreeSet<_>>() {
+        let Some(fd) = ecx.machine.fds.get(fd_num) else {
+            return ecx.set_last_error_and_return(LibcError("EBADF"), &dest);
+        };
+        let ready = readiness(&fd)?;
+        // Like Linux, report hang-ups and errors as readable, and errors as writable, since reads
+        // and writes then don't block.
+        if readfds.fds.contains(&fd_num) && (ready.read || ready.hup || ready.err) {
+            readable.push(fd_num);
+        }
+        if writefds.fds.contains(&fd_num) && (ready.write || ready.err) {
+            writable.push(fd_num);
+        }
+        ids.push(fd.id());
+    }
+    // There is no out-of-band data, so there are never exceptional conditions.
+
+    let num_ready = readable.len() + writable.len();
+    if num_ready == 0 && !is_expired(ecx, deadline) {
+        let (readfds, writefds, exceptfds) = (readfds.ptr, writefds.ptr, exceptfds.ptr);
+        let retry = callback!(
+            @capture<'tcx> {
+                nfds: i32,
+                readfds: Pointer,
+                writefds: Pointer,
+                exceptfds: Pointer,
+                deadline: Option<Duration>,
+                dest: MPlaceTy<'tcx>,
+            }
+            |this, _unit: ()| {
+                select_fds(this, nfds, (readfds, writefds, exceptfds), deadline, dest)
+            }
+        );
+        block_until_change(ecx, ids, deadline, retry);
+        return interp_ok(());
+    }
+
+    readfds.write(ecx, nfds, &readable)?;
+    writefds.write(ecx, nfds, &writable)?;
+    exceptfds.write(ecx, nfds, &[])?;
+    ecx.write_int(u64::try_from(num_ready).unwrap(), &dest)
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Implements both `poll` and `ppoll`: `timeout` is `None` if blocking indefinitely.
+    fn poll(
+        &mut self,
+        fds: &OpTy<'tcx>,
+        nfds: &OpTy<'tcx>,
+        timeout: Option<Duration>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fds = this.read_pointer(fds)?;
+        let nfds = this.read_scalar(nfds)?.to_uint(this.libc_ty_layout("nfds_t").size)?;
+        let nfds = u64::try_from(nfds).unwrap();
+
+        let deadline = timeout.map(|timeout| elapsed(this).saturating_add(timeout));
+        poll_fds(this, fds, nfds, deadline, dest.clone())
+    }
+
+    /// Reads the timeout of `poll`, in milliseconds. Negative values mean blocking indefinitely.
+    fn read_poll_timeout(&self, timeout: &OpTy<'tcx>) -> InterpResult<'tcx, Option<Duration>> {
+        let this = self.eval_context_ref();
+        let timeout = this.read_scalar(timeout)?.to_i32()?;
+        interp_ok(u64::try_from(timeout).ok().map(Duration::from_millis))
+    }
+
+    /// Reads the timeout of `ppoll`. A null pointer means blocking indefinitely. Returns
+    /// `Err(())` if the timeout is invalid.
+    fn read_ppoll_timeout(
+        &mut self,
+        timeout: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Result<Option<Duration>, ()>> {
+        let this = self.eval_context_mut();
+        if this.ptr_is_null(this.read_pointer(timeout)?)? {
+            return interp_ok(Ok(None));
+        }
+        let timeout = this.deref_pointer_as(timeout, this.libc_ty_layout("timespec"))?;
+        interp_ok(this.read_timespec(&timeout)?.map(Some).ok_or(()))
+    }
+
+    fn select(
+        &mut self,
+        nfds: &OpTy<'tcx>,
+        readfds: &OpTy<'tcx>,
+        writefds: &OpTy<'tcx>,
+        exceptfds: &OpTy<'tcx>,
+        timeout: &OpTy<'tcx>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let nfds = this.read_scalar(nfds)?.to_i32()?;
+        let readfds = this.read_pointer(readfds)?;
+        let writefds = this.read_pointer(writefds)?;
+        let exceptfds = this.read_pointer(exceptfds)?;
+        let timeout = this.read_pointer(timeout)?;
+
+        let fd_setsize = this.libc_ty_layout("fd_set").size.bits();
+        if nfds < 0 || u64::try_from(nfds).unwrap() > fd_setsize {
+            return this.set_last_error_and_return(LibcError("EINVAL"), dest);
+        }
+
+        // A null pointer means blocking indefinitely.
+        // Unlike Linux, we don't update the timeout with the time that is left.
+        let timeout = if this.ptr_is_null(timeout)? {
+            None
+        } else {
+            let timeval = this.ptr_to_mplace(timeout, this.libc_ty_layout("timeval"));
+            let seconds_field = this.project_field_named(&timeval, "tv_sec")?;
+            let micros_field = this.project_field_named(&timeval, "tv_usec")?;
+            let seconds = this.read_scalar(&seconds_field)?.to_int(seconds_field.layout.size)?;
+            let micros = this.read_scalar(&micros_field)?.to_int(micros_field.layout.size)?;
+            match (u64::try_from(seconds), u32::try_from(micros)) {
+                (Ok(seconds), Ok(micros)) if micros < 1_000_000 =>
+                    Some(Duration::new(seconds, micros * 1000)),
+                _ => return this.set_last_error_and_return(LibcError("EINVAL"), dest),
+            }
+        };
+
+        let deadline = timeout.map(|timeout| elapsed(this).saturating_add(timeout));
+        select_fds(this, nfds, (readfds, writefds, exceptfds), deadline, dest.clone())
+    }
+}
@@ -0,0 +1,734 @@
+//! Spawning child processes with `posix_spawn`.
+//!
+//! Children are not interpreted: they run natively on the host, so this is only available with
+//! isolation disabled. The standard streams of a child can be connected to files, to the standard
+//! streams of Miri itself, and to pipes. A pipe created by the program with `pipe` or
+//! `socketpair` only exists inside Miri, so when one of its ends is handed to a child, we create a
+//! host pipe instead and replace the other end by the corresponding end of that host pipe.
+//!
+//! Reading from, writing to and waiting for a child all block the entire interpreter.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::Duration;
+use std::{iter, thread};
+
+use rustc_abi::Size;
+use rustc_data_structures::fx::FxHashMap;
+
+use crate::shims::files::{
+    DynFileDescriptionRef, EvalContextExt as _, FileDescription, FileDescriptionRef, NullOutput,
+};
+use crate::shims::os_str::bytes_to_os_str;
+use crate::shims::unix::UnixFileDescription;
+use crate::shims::unix::fs::FileHandle;
+use crate::shims::unix::unnamed_socket::AnonSocket;
+use crate::*;
+
+/// An action recorded in a `posix_spawn_file_actions_t`.
+#[derive(Debug, Clone)]
+enum FileAction {
+    Dup2 { fd: i32, new_fd: i32 },
+    Close(i32),
+    Chdir(PathBuf),
+}
+
+/// The settings recorded in a `posix_spawnattr_t`.
+#[derive(Debug, Default, Clone, Copy)]
+struct SpawnAttrs {
+    flags: i32,
+    pgroup: i32,
+}
+
+/// The state of the objects used to configure `posix_spawn`, and the children that have not been
+/// waited for yet.
+///
+/// The configuration objects are identified by their address: their contents are never read,
+/// since the program is not supposed to look inside them anyway.
+#[derive(Debug, Default)]
+pub struct ProcessTable {
+    file_actions: FxHashMap<u64, Vec<FileAction>>,
+    attrs: FxHashMap<u64, SpawnAttrs>,
+    children: BTreeMap<i32, Child>,
+}
+
+impl ProcessTable {
+    fn file_actions_mut<'tcx>(&mut self, addr: u64) -> InterpResult<'tcx, &mut Vec<FileAction>> {
+        match self.file_actions.get_mut(&addr) {
+            Some(actions) => interp_ok(actions),
+            None => throw_ub_format!("`posix_spawn_file_actions_t` used without being initialized"),
+        }
+    }
+
+    fn attrs_mut<'tcx>(&mut self, addr: u64) -> InterpResult<'tcx, &mut SpawnAttrs> {
+        match self.attrs.get_mut(&addr) {
+            Some(attrs) => interp_ok(attrs),
+            None => throw_ub_format!("`posix_spawnattr_t` used without being initialized"),
+        }
+    }
+
+    /// Returns the first child that has exited among those matching `pid`, where `-1` matches
+    /// all children.
+    fn try_wait(&mut self, pid: i32) -> io::Result<Option<(i32, ExitStatus)>> {
+        for (&child_pid, child) in &mut self.children {
+            if pid == -1 || pid == child_pid {
+                if let Some(status) = child.try_wait()? {
+                    return Ok(Some((child_pid, status)));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The end of a host pipe whose other end belongs to a child process.
+#[derive(Debug)]
+enum ChildPipe {
+    Read(io::PipeReader),
+    Write(io::PipeWriter),
+}
+
+impl ChildPipe {
+    fn try_clone_into_stdio(&self) -> io::Result<Stdio> {
+        match self {
+            ChildPipe::Read(reader) => reader.try_clone().map(Stdio::from),
+            ChildPipe::Write(writer) => writer.try_clone().map(Stdio::from),
+        }
+    }
+}
+
+impl FileDescription for ChildPipe {
+    fn name(&self) -> &'static str {
+        "pipe to a child process"
+    }
+
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        assert!(communicate_allowed, "isolation should have prevented even spawning a process");
+
+        let result = match &*self {
+            ChildPipe::Read(reader) => ecx.read_from_host(reader, len, ptr)?,
+            ChildPipe::Write(_) => Err(LibcError("EBADF")),
+        };
+        finish.call(ecx, result)
+    }
+
+    fn write<'tcx>(
+        self: FileDescriptionRef<Self>,
+        communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        assert!(communicate_allowed, "isolation should have prevented even spawning a process");
+
+        let result = match &*self {
+            ChildPipe::Read(_) => Err(LibcError("EBADF")),
+            ChildPipe::Write(writer) => ecx.write_to_host(writer, len, ptr)?,
+        };
+        finish.call(ecx, result)
+    }
+
+    fn close<'tcx>(
+        self,
+        _communicate_allowed: bool,
+        _ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        drop(self);
+        interp_ok(Ok(()))
+    }
+
+    fn as_unix(&self) -> &dyn UnixFileDescription {
+        self
+    }
+}
+
+impl UnixFileDescription for ChildPipe {
+    fn set_nonblocking<'tcx>(
+        &self,
+        communicate_allowed: bool,
+        nonblocking: bool,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        assert!(communicate_allowed, "isolation should have prevented even spawning a process");
+        cfg_match! {
+            unix => {
+                use std::os::fd::AsRawFd;
+
+                let fd = match self {
+                    ChildPipe::Read(reader) => reader.as_raw_fd(),
+                    ChildPipe::Write(writer) => writer.as_raw_fd(),
+                };
+                // SAFETY: `fd` belongs to `self`, so it stays open during these calls.
+                let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+                if flags == -1 {
+                    return interp_ok(Err(io::Error::last_os_error()));
+                }
+                let flags =
+                    if nonblocking { flags | libc::O_NONBLOCK } else { flags & !libc::O_NONBLOCK };
+                // SAFETY: see above.
+                let ret = unsafe { libc::fcntl(fd, libc::F_SETFL, flags) };
+                interp_ok(if ret == -1 { Err(io::Error::last_os_error()) } else { Ok(()) })
+            }
+            _ => {
+                let _ = nonblocking;
+                throw_unsup_format!(
+                    "non-blocking pipes to child processes are only supported on Unix hosts"
+                );
+            }
+        }
+    }
+}
+
+/// One end of a pipe of the program that is handed to a child. The child gets one end of a host
+/// pipe instead, and once the child has been spawned, `parent_end` replaces the other end of the
+/// program's pipe.
+struct PipeToChild {
+    socket: FileDescriptionRef<AnonSocket>,
+    child_end: ChildPipe,
+    parent_end: ChildPipe,
+}
+
+/// The status `waitpid` reports for a child that exited with `status`.
+fn wait_status(status: ExitStatus) -> i32 {
+    cfg_match! {
+        unix => {
+            use std::os::unix::process::ExitStatusExt;
+            status.into_raw()
+        }
+        _ => {
+            // All other hosts only report exit codes.
+            (status.code().unwrap() & 0xff).strict_shl(8)
+        }
+    }
+}
+
+/// Determines what the child gets as its standard stream `fd` when the program passes it the
+/// file description `description`.
+fn child_stdio<'tcx>(
+    fd: usize,
+    description: Option<DynFileDescriptionRef>,
+    pipes: &mut Vec<PipeToChild>,
+) -> InterpResult<'tcx, io::Result<Stdio>> {
+    // A closed stream behaves much like /dev/null for the child.
+    let Some(description) = description else {
+        return interp_ok(Ok(Stdio::null()));
+    };
+
+    if let Some(file) = description.clone().downcast::<FileHandle>() {
+        return interp_ok(file.file.try_clone().map(Stdio::from));
+    }
+    if let Some(pipe) = description.clone().downcast::<ChildPipe>() {
+        return interp_ok(pipe.try_clone_into_stdio());
+    }
+    if description.clone().downcast::<NullOutput>().is_some() {
+        return interp_ok(Ok(Stdio::null()));
+    }
+    if fd == 0 && description.clone().downcast::<io::Stdin>().is_some() {
+        return interp_ok(Ok(Stdio::inherit()));
+    }
+    if description.clone().downcast::<io::Stdout>().is_some() {
+        return interp_ok(Ok(Stdio::from(io::stdout())));
+    }
+    if description.clone().downcast::<io::Stderr>().is_some() {
+        return interp_ok(Ok(Stdio::from(io::stderr())));
+    }
+    if let Some(socket) = description.clone().downcast::<AnonSocket>() {
+        // The child reads from its standard input, and writes to the other streams.
+        let child_reads = fd == 0;
+        if let Some(pipe) = pipes.iter().find(|pipe| pipe.socket.id() == socket.id()) {
+            if matches!(pipe.child_end, ChildPipe::Read(_)) != child_reads {
+                throw_unsup_format!(
+                    "using the same socket as input and output of a child process is not supported"
+                );
+            }
+            return interp_ok(pipe.child_end.try_clone_into_stdio());
+        }
+        // Nobody can ever write to or read from the child.
+        if socket.peer_fd().upgrade().is_none() {
+            return interp_ok(Ok(Stdio::null()));
+        }
+        let (reader, writer) = match io::pipe() {
+            Ok(pipe) => pipe,
+            Err(err) => return interp_ok(Err(err)),
+        };
+        let (child_end, parent_end) = if child_reads {
+            (ChildPipe::Read(reader), ChildPipe::Write(writer))
+        } else {
+            (ChildPipe::Write(writer), ChildPipe::Read(reader))
+        };
+        let stdio = child_end.try_clone_into_stdio();
+        pipes.push(PipeToChild { socket, child_end, parent_end });
+        return interp_ok(stdio);
+    }
+
+    throw_unsup_format!("passing a {} to a child process is not supported", description.name());
+}
+
+impl<'tcx> EvalContextExtPriv<'tcx> for crate::MiriInterpCx<'tcx> {}
+trait EvalContextExtPriv<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    /// Reads a null-terminated array of pointers to C strings, like `argv` and `envp`.
+    fn read_c_str_array(&self, ptr: Pointer) -> InterpResult<'tcx, Vec<Vec<u8>>> {
+        let this = self.eval_context_ref();
+        let ptr_layout = this.machine.layouts.mut_raw_ptr;
+
+        let mut strings = Vec::new();
+        let mut offset = Size::ZERO;
+        loop {
+            let entry = this.ptr_to_mplace(ptr.wrapping_offset(offset, this), ptr_layout);
+            let str_ptr = this.read_pointer(&entry)?;
+            if this.ptr_is_null(str_ptr)? {
+                break;
+            }
+            strings.push(this.read_c_str(str_ptr)?.to_owned());
+            offset += ptr_layout.size;
+        }
+        interp_ok(strings)
+    }
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    fn posix_spawn_file_actions_init(
+        &mut self,
+        file_actions: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(file_actions)?.addr().bytes();
+        this.machine.processes.file_actions.insert(addr, Vec::new());
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_destroy(
+        &mut self,
+        file_actions: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(file_actions)?.addr().bytes();
+        this.machine.processes.file_actions_mut(addr)?;
+        this.machine.processes.file_actions.remove(&addr);
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_adddup2(
+        &mut self,
+        file_actions: &OpTy<'tcx>,
+        fd: &OpTy<'tcx>,
+        new_fd: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(file_actions)?.addr().bytes();
+        let fd = this.read_scalar(fd)?.to_i32()?;
+        let new_fd = this.read_scalar(new_fd)?.to_i32()?;
+
+        if fd < 0 || new_fd < 0 {
+            return interp_ok(this.eval_libc("EBADF"));
+        }
+        this.machine.processes.file_actions_mut(addr)?.push(FileAction::Dup2 { fd, new_fd });
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_addclose(
+        &mut self,
+        file_actions: &OpTy<'tcx>,
+        fd: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(file_actions)?.addr().bytes();
+        let fd = this.read_scalar(fd)?.to_i32()?;
+
+        if fd < 0 {
+            return interp_ok(this.eval_libc("EBADF"));
+        }
+        this.machine.processes.file_actions_mut(addr)?.push(FileAction::Close(fd));
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawn_file_actions_addchdir(
+        &mut self,
+        file_actions: &OpTy<'tcx>,
+        path: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(file_actions)?.addr().bytes();
+        let path = this.read_path_from_c_str(this.read_pointer(path)?)?.into_owned();
+        this.machine.processes.file_actions_mut(addr)?.push(FileAction::Chdir(path));
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawnattr_init(&mut self, attr: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(attr)?.addr().bytes();
+        this.machine.processes.attrs.insert(addr, SpawnAttrs::default());
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawnattr_destroy(&mut self, attr: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(attr)?.addr().bytes();
+        this.machine.processes.attrs_mut(addr)?;
+        this.machine.processes.attrs.remove(&addr);
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawnattr_setflags(
+        &mut self,
+        attr: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(attr)?.addr().bytes();
+        let flags = this.read_scalar(flags)?.to_int(flags.layout.size)?;
+
+        let Ok(flags) = i32::try_from(flags) else {
+            return interp_ok(this.eval_libc("EINVAL"));
+        };
+        this.machine.processes.attrs_mut(addr)?.flags = flags;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawnattr_setpgroup(
+        &mut self,
+        attr: &OpTy<'tcx>,
+        pgroup: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(attr)?.addr().bytes();
+        let pgroup = this.read_scalar(pgroup)?.to_i32()?;
+        this.machine.processes.attrs_mut(addr)?.pgroup = pgroup;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn posix_spawnattr_setsigdefault(
+        &mut self,
+        attr: &OpTy<'tcx>,
+        set: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let addr = this.read_pointer(attr)?.addr().bytes();
+        let set = this.deref_pointer_as(set, this.libc_ty_layout("sigset_t"))?;
+        // Programs only ever ask for the signals they ignore to be reset in the child, and Miri does
+        // not ignore any signals that a child could inherit. So we just check the set is readable.
+        this.read_bytes_ptr_strip_provenance(set.ptr(), set.layout.size)?;
+        this.machine.processes.attrs_mut(addr)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn sigemptyset(&mut self, set: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let set = this.deref_pointer_as(set, this.libc_ty_layout("sigset_t"))?;
+        this.write_bytes_ptr(set.ptr(), iter::repeat_n(0, set.layout.size.bytes_usize()))?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn sigaddset(&mut self, set: &OpTy<'tcx>, signum: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let set = this.deref_pointer_as(set, this.libc_ty_layout("sigset_t"))?;
+        let signum = this.read_scalar(signum)?.to_i32()?;
+
+        // A signal set is an array of words, and signal `n` is bit `n - 1` in that array.
+        let word_layout = if matches!(&*this.tcx.sess.target.os, "linux" | "android") {
+            this.machine.layouts.usize
+        } else {
+            this.machine.layouts.u32
+        };
+        let Some(bit) = u64::try_from(signum)
+            .ok()
+            .and_then(|signum| signum.checked_sub(1))
+            .filter(|&bit| bit < set.layout.size.bits())
+        else {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        };
+        let word_bits = word_layout.size.bits();
+        let word_offset = bit.strict_div(word_bits).strict_mul(word_layout.size.bytes());
+        let word = set.offset(Size::from_bytes(word_offset), word_layout, this)?;
+        let mask = 1u128.strict_shl(u32::try_from(bit.strict_rem(word_bits)).unwrap());
+        let value = this.read_scalar(&word)?.to_uint(word_layout.size)?;
+        this.write_scalar(Scalar::from_uint(value | mask, word_layout.size), &word)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    /// Implements `posix_spawn`, or `posix_spawnp` if `search_path` is set.
+    fn posix_spawn(
+        &mut self,
+        pid: &OpTy<'tcx>,
+        path: &OpTy<'tcx>,
+        file_actions: &OpTy<'tcx>,
+        attr: &OpTy<'tcx>,
+        argv: &OpTy<'tcx>,
+        envp: &OpTy<'tcx>,
+        search_path: bool,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+        let name = if search_path { "`posix_spawnp`" } else { "`posix_spawn`" };
+
+        let pid_ptr = this.read_pointer(pid)?;
+        let path = this.read_path_from_c_str(this.read_pointer(path)?)?.into_owned();
+        let file_actions = this.read_pointer(file_actions)?;
+        let attr = this.read_pointer(attr)?;
+        let argv = this.read_c_str_array(this.read_pointer(argv)?)?;
+        let envp = this.read_c_str_array(this.read_pointer(envp)?)?;
+
+        // Reject if isolation is enabled.
+        if let IsolatedOp::Reject(reject_with) = this.machine.isolated_op {
+            this.reject_in_isolation(name, reject_with)?;
+            return interp_ok(this.eval_libc("EPERM"));
+        }
+
+        let actions = if this.ptr_is_null(file_actions)? {
+            Vec::new()
+        } else {
+            this.machine.processes.file_actions_mut(file_actions.addr().bytes())?.clone()
+        };
+        let attrs = if this.ptr_is_null(attr)? {
+            SpawnAttrs::default()
+        } else {
+            *this.machine.processes.attrs_mut(attr.addr().bytes())?
+        };
+
+        let setpgroup = this.eval_libc_i32("POSIX_SPAWN_SETPGROUP");
+        let setsigdef = this.eval_libc_i32("POSIX_SPAWN_SETSIGDEF");
+        if attrs.flags & !(setpgroup | setsigdef) != 0 {
+            throw_unsup_format!("{name}: unsupported flags {:#x}", attrs.flags);
+        }
+
+        // Figure out the working directory and the file descriptions the child gets as its
+        // standard streams. It does not inherit any other file descriptors.
+        let mut stdio: [Option<DynFileDescriptionRef>; 3] =
+            [0, 1, 2].map(|fd| this.machine.fds.get(fd));
+        let mut cwd: Option<PathBuf> = None;
+        for action in actions {
+            match action {
+                FileAction::Dup2 { fd, new_fd } => {
+                    let Ok(new_fd @ 0..3) = usize::try_from(new_fd) else {
+                        throw_unsup_format!(
+                            "{name}: only the standard streams of the child can be redirected"
+                        );
+                    };
+                    let description = match usize::try_from(fd) {
+                        Ok(fd @ 0..3) => stdio[fd].clone(),
+                        _ => this.machine.fds.get(fd),
+                    };
+                    let Some(description) = description else {
+                        return interp_ok(this.eval_libc("EBADF"));
+                    };
+                    stdio[new_fd] = Some(description);
+                }
+                FileAction::Close(fd) =>
+                    if let Ok(fd @ 0..3) = usize::try_from(fd) {
+                        stdio[fd] = None;
+                    },
+                FileAction::Chdir(path) =>
+                    cwd = Some(match cwd {
+                        Some(cwd) => cwd.join(path),
+                        None => path,
+                    }),
+            }
+        }
+
+        // Without searching `PATH`, a program name without a slash is relative to the working
+        // directory.
+        let program = if !search_path && path.parent() == Some(Path::new("")) {
+            Path::new(".").join(path)
+        } else {
+            path
+        };
+        let mut cmd = Command::new(program);
+        if let Some((arg0, args)) = argv.split_first() {
+            cfg_match! {
+                unix => {
+                    use std::os::unix::process::CommandExt;
+                    cmd.arg0(bytes_to_os_str(arg0)?);
+                }
+                _ => {
+                    let _ = arg0;
+                }
+            }
+            for arg in args {
+                cmd.arg(bytes_to_os_str(arg)?);
+            }
+        }
+        cmd.env_clear();
+        for var in &envp {
+            // Entries without `=` are not valid environment variables; skip them like libc does.
+            if let Some(pos) = var.iter().position(|&b| b == b'=') {
+                let (key, value) = var.split_at(pos);
+                cmd.env(bytes_to_os_str(key)?, bytes_to_os_str(&value[1..])?);
+            }
+        }
+        if let Some(cwd) = cwd {
+            cmd.current_dir(cwd);
+        }
+        if attrs.flags & setpgroup != 0 {
+            cfg_match! {
+                unix => {
+                    use std::os::unix::process::CommandExt;
+                    cmd.process_group(attrs.pgroup);
+                }
+                _ => {
+                    throw_unsup_format!("{name}: process groups are only supported on Unix hosts");
+                }
+            }
+        }
+
+        let mut pipes = Vec::new();
+        for (fd, description) in stdio.into_iter().enumerate() {
+            let stdio = match child_stdio(fd, description, &mut pipes)? {
+                Ok(stdio) => stdio,
+                Err(err) => return interp_ok(this.io_error_to_errnum(err)?),
+            };
+            match fd {
+                0 => cmd.stdin(stdio),
+                1 => cmd.stdout(stdio),
+                _ => cmd.stderr(stdio),
+            };
+        }
+
+        let child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(err) => return interp_ok(this.io_error_to_errnum(err)?),
+        };
+        // Close our copies of the ends of the pipes that the child got.
+        drop(cmd);
+
+        // Connect the program to the host pipes.
+        for PipeToChild { socket, child_end, parent_end } in pipes {
+            drop(child_end);
+            let Some(peer) = socket.peer_fd().upgrade() else {
+                continue;
+            };
+            if let ChildPipe::Write(writer) = &parent_end {
+                // Whatever has been written to the pipe but not read yet is now for the child. If
+                // the child exits without reading it, the data is lost, like with a real pipe.
+                let mut writer: &io::PipeWriter = writer;
+                let _ = writer.write_all(&socket.take_buffered());
+            }
+            let parent_end = this.machine.fds.new_ref(parent_end);
+            for fd in this.machine.fds.fds.values_mut() {
+                if fd.id() == peer.id() {
+                    *fd = parent_end.clone();
+                }
+            }
+        }
+
+        let child_pid = i32::try_from(child.id()).unwrap();
+        this.machine.processes.children.insert(child_pid, child);
+        if !this.ptr_is_null(pid_ptr)? {
+            let pid_place = this.ptr_to_mplace(pid_ptr, this.libc_ty_layout("pid_t"));
+            this.write_int(child_pid, &pid_place)?;
+        }
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn waitpid(
+        &mut self,
+        pid: &OpTy<'tcx>,
+        status: &OpTy<'tcx>,
+        options: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let pid = this.read_scalar(pid)?.to_i32()?;
+        let status = this.read_pointer(status)?;
+        let options = this.read_scalar(options)?.to_i32()?;
+
+        let wnohang = this.eval_libc_i32("WNOHANG");
+        if options & !wnohang != 0 {
+            throw_unsup_format!("`waitpid`: unsupported flags {:#x}", options & !wnohang);
+        }
+        let nohang = options & wnohang != 0;
+        if pid == 0 || pid < -1 {
+            throw_unsup_format!("`waitpid` on process groups is not supported");
+        }
+
+        let processes = &mut this.machine.processes;
+        let has_child = if pid == -1 {
+            !processes.children.is_empty()
+        } else {
+            processes.children.contains_key(&pid)
+        };
+        if !has_child {
+            return this.set_last_error_and_return_i32(LibcError("ECHILD"));
+        }
+
+        let result = if pid != -1 && !nohang {
+            processes.children.get_mut(&pid).unwrap().wait().map(|status| Some((pid, status)))
+        } else {
+            loop {
+                match processes.try_wait(pid) {
+                    Ok(None) if !nohang => thread::sleep(Duration::from_millis(1)),
+                    result => break result,
+                }
+            }
+        };
+
+        match result {
+            Err(err) => this.set_last_error_and_return_i32(err),
+            Ok(None) => interp_ok(Scalar::from_i32(0)),
+            Ok(Some((child_pid, exit_status))) => {
+                this.machine.processes.children.remove(&child_pid);
+                if !this.ptr_is_null(status)? {
+                    let status_place = this.ptr_to_mplace(status, this.machine.layouts.i32);
+                    this.write_int(wait_status(exit_status), &status_place)?;
+                }
+                interp_ok(Scalar::from_i32(child_pid))
+            }
+        }
+    }
+
+    fn kill(&mut self, pid: &OpTy<'tcx>, sig: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let pid = this.read_scalar(pid)?.to_i32()?;
+        let sig = this.read_scalar(sig)?.to_i32()?;
+
+        let sigkill = this.eval_libc_i32("SIGKILL");
+        let Some(child) = this.machine.processes.children.get_mut(&pid) else {
+            throw_unsup_format!("`kill` is only supported on child processes");
+        };
+        let result = if sig == 0 {
+            // This only checks that the process exists.
+            Ok(())
+        } else if sig == sigkill {
+            child.kill()
+        } else {
+            throw_unsup_format!("`kill`: only `SIGKILL` is supported");
+        };
+
+        let result = result.map(|()| 0i32);
+        interp_ok(Scalar::from_i32(this.try_unwrap_io_result(result)?))
+    }
+}
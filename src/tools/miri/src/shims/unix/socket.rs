@@ -0,0 +1,1401 @@
+//! This implements IPv4 and IPv6 sockets. With isolation enabled, they do not correspond to
+//! anything on the host system and are entirely implemented inside Miri: the only network is the
+//! loopback interface, and sockets can only talk to other sockets of the same program. This keeps
+//! networking deterministic. With isolation disabled, `socket` instead creates sockets backed by
+//! the host, which are implemented in `host_socket.rs`.
+//!
+//! A stream socket is connected as soon as `connect` is called, provided that a socket is
+//! listening at the target address; there is no limit on the number of pending connections.
+//! Datagrams are never lost or reordered, unless they are sent to an address no socket is bound
+//! to.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, VecDeque};
+use std::io;
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use rustc_abi::Size;
+
+use crate::concurrency::VClock;
+use crate::shims::files::{
+    EvalContextExt as _, FileDescription, FileDescriptionExt, FileDescriptionRef,
+    WeakFileDescriptionRef,
+};
+use crate::shims::unix::UnixFileDescription;
+use crate::shims::unix::host_socket::{self, HostSocket};
+use crate::shims::unix::linux_like::epoll::{EpollReadyEvents, EvalContextExt as _};
+use crate::*;
+
+/// The maximum number of bytes a stream socket buffers for reading.
+/// Like for `socketpair`, this number is arbitrary.
+const MAX_STREAM_BUFFER_CAPACITY: usize = 212992;
+
+/// The maximum payload of a UDP datagram.
+const MAX_DATAGRAM_SIZE: usize = 65507;
+
+/// The first port handed out when binding to port 0. Ports are then handed out in increasing
+/// order, wrapping around to this one.
+const FIRST_EPHEMERAL_PORT: u16 = 49152;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum SocketKind {
+    Stream,
+    Datagram,
+}
+
+/// The socket options `setsockopt` supports.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum SocketOption {
+    ReuseAddr,
+    Broadcast,
+    NoSigPipe,
+    NoDelay,
+    V6Only,
+}
+
+/// An IPv4 or IPv6 socket.
+#[derive(Debug)]
+struct Socket {
+    kind: SocketKind,
+    is_ipv6: bool,
+    /// The address this socket is bound to, if any.
+    local_addr: Cell<Option<SocketAddr>>,
+    /// For stream sockets, the address of the peer once connected. For datagram sockets, the
+    /// default destination set by `connect`.
+    peer_addr: Cell<Option<SocketAddr>>,
+    state: RefCell<SocketState>,
+    /// A list of thread ids blocked because there was nothing to read or accept.
+    blocked_read_tid: RefCell<Vec<ThreadId>>,
+    /// A list of thread ids blocked because the buffer of the peer was full.
+    blocked_write_tid: RefCell<Vec<ThreadId>>,
+    is_nonblock: Cell<bool>,
+}
+
+#[derive(Debug)]
+enum SocketState {
+    /// A stream socket that is neither listening nor connected.
+    Unconnected,
+    /// A listening stream socket, with the connections that have yet to be accepted and the
+    /// clock of the thread that initiated each of them.
+    Listening(VecDeque<(FileDescriptionRef<Socket>, VClock)>),
+    /// A connected stream socket.
+    Connected {
+        /// The socket at the other end of the connection, which holds the buffer we are writing
+        /// to. This is a weak reference because the peer may be closed before us; reads then
+        /// return EOF once `readbuf` is empty, and writes fail with EPIPE.
+        peer_fd: WeakFileDescriptionRef<Socket>,
+        readbuf: Buffer,
+    },
+    /// A datagram socket, with the datagrams it has received.
+    Datagram(VecDeque<Datagram>),
+}
+
+#[derive(Debug)]
+struct Buffer {
+    buf: VecDeque<u8>,
+    clock: VClock,
+}
+
+impl Buffer {
+    fn new() -> Self {
+        Buffer { buf: VecDeque::new(), clock: VClock::default() }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Datagram {
+    source: SocketAddr,
+    data: Vec<u8>,
+    clock: VClock,
+}
+
+impl Socket {
+    fn new(kind: SocketKind, is_ipv6: bool, is_nonblock: bool) -> Self {
+        let state = match kind {
+            SocketKind::Stream => SocketState::Unconnected,
+            SocketKind::Datagram => SocketState::Datagram(VecDeque::new()),
+        };
+        Socket {
+            kind,
+            is_ipv6,
+            local_addr: Cell::new(None),
+            peer_addr: Cell::new(None),
+            state: RefCell::new(state),
+            blocked_read_tid: RefCell::new(Vec::new()),
+            blocked_write_tid: RefCell::new(Vec::new()),
+            is_nonblock: Cell::new(is_nonblock),
+        }
+    }
+
+    fn unspecified_ip(&self) -> IpAddr {
+        if self.is_ipv6 { Ipv6Addr::UNSPECIFIED.into() } else { Ipv4Addr::UNSPECIFIED.into() }
+    }
+}
+
+/// The addresses sockets are bound to, so that `connect` and `sendto` can find them, and the
+/// sockets backed by the host.
+#[derive(Debug)]
+pub struct SocketTable {
+    /// The entries are weak references so that closed sockets release their address.
+    bound: BTreeMap<(SocketKind, SocketAddr), WeakFileDescriptionRef<Socket>>,
+    next_ephemeral_port: u16,
+    /// The sockets backed by the host, whose readiness needs to be checked while threads wait for
+    /// them.
+    pub(super) host_sockets: Vec<WeakFileDescriptionRef<HostSocket>>,
+    /// The clock of all sends to sockets backed by the host.
+    pub(super) host_clock: VClock,
+}
+
+impl SocketTable {
+    pub(crate) fn new() -> Self {
+        SocketTable {
+            bound: BTreeMap::new(),
+            next_ephemeral_port: FIRST_EPHEMERAL_PORT,
+            host_sockets: Vec::new(),
+            host_clock: VClock::default(),
+        }
+    }
+
+    /// Returns whether there are open sockets backed by the host.
+    pub(crate) fn has_host_sockets(&self) -> bool {
+        self.host_sockets.iter().any(|socket| socket.upgrade().is_some())
+    }
+
+    /// Returns whether binding to `addr` would conflict with a socket that is already bound.
+    /// Binding to the unspecified address conflicts with all addresses with the same port.
+    fn is_in_use(&self, kind: SocketKind, addr: SocketAddr) -> bool {
+        self.bound.iter().any(|(&(bound_kind, bound_addr), fd)| {
+            bound_kind == kind
+                && bound_addr.is_ipv6() == addr.is_ipv6()
+                && bound_addr.port() == addr.port()
+                && (bound_addr.ip() == addr.ip()
+                    || bound_addr.ip().is_unspecified()
+                    || addr.ip().is_unspecified())
+                && fd.upgrade().is_some()
+        })
+    }
+
+    /// Binds `socket` to `addr`, picking an ephemeral port if its port is 0, and returns the
+    /// address it is bound to.
+    fn bind(
+        &mut self,
+        socket: &FileDescriptionRef<Socket>,
+        mut addr: SocketAddr,
+    ) -> Result<SocketAddr, IoError> {
+        if addr.port() == 0 {
+            // Try every ephemeral port once.
+            for _ in FIRST_EPHEMERAL_PORT..=u16::MAX {
+                let port = self.next_ephemeral_port;
+                self.next_ephemeral_port =
+                    if port == u16::MAX { FIRST_EPHEMERAL_PORT } else { port + 1 };
+                if !self.is_in_use(socket.kind, SocketAddr::new(addr.ip(), port)) {
+                    addr.set_port(port);
+                    break;
+                }
+            }
+            if addr.port() == 0 {
+                return Err(LibcError("EADDRINUSE"));
+            }
+        } else if self.is_in_use(socket.kind, addr) {
+            return Err(LibcError("EADDRINUSE"));
+        }
+        self.bound.insert((socket.kind, addr), FileDescriptionRef::downgrade(socket));
+        Ok(addr)
+    }
+
+    /// Returns the socket bound to `addr`, or to the unspecified address with the same port.
+    fn lookup(&self, kind: SocketKind, addr: SocketAddr) -> Option<FileDescriptionRef<Socket>> {
+        let unspecified: IpAddr = if addr.is_ipv6() {
+            Ipv6Addr::UNSPECIFIED.into()
+        } else {
+            Ipv4Addr::UNSPECIFIED.into()
+        };
+        [addr.ip(), unspecified]
+            .into_iter()
+            .find_map(|ip| self.bound.get(&(kind, SocketAddr::new(ip, addr.port())))?.upgrade())
+    }
+
+    /// Removes the entry for `addr`, if the socket bound to it has been closed.
+    fn release(&mut self, kind: SocketKind, addr: SocketAddr) {
+        if self.bound.get(&(kind, addr)).is_some_and(|fd| fd.upgrade().is_none()) {
+            self.bound.remove(&(kind, addr));
+        }
+    }
+}
+
+impl FileDescription for Socket {
+    fn name(&self) -> &'static str {
+        "socket"
+    }
+
+    fn close<'tcx>(
+        self,
+        communicate_allowed: bool,
+        ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        if let Some(addr) = self.local_addr.get() {
+            ecx.machine.sockets.release(self.kind, addr);
+        }
+        match self.state.into_inner() {
+            SocketState::Listening(pending) => {
+                // Connections that were never accepted are closed along with the listener.
+                for (fd, _clock) in pending {
+                    // Closing a socket never fails.
+                    fd.close_ref(communicate_allowed, ecx)?.unwrap();
+                }
+            }
+            SocketState::Connected { peer_fd, .. } => {
+                if let Some(peer_fd) = peer_fd.upgrade() {
+                    // Reads of the peer now return EOF and writes fail, so they no longer block.
+                    unblock_all(ecx, &peer_fd.blocked_read_tid)?;
+                    unblock_all(ecx, &peer_fd.blocked_write_tid)?;
+                    ecx.check_and_update_readiness(peer_fd)?;
+                }
+            }
+            SocketState::Unconnected | SocketState::Datagram(_) => {}
+        }
+        interp_ok(Ok(()))
+    }
+
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let is_nonblock = self.is_nonblock.get();
+        socket_recv(self, ptr, len, is_nonblock, /* peek */ false, None, ecx, finish)
+    }
+
+    fn write<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let is_nonblock = self.is_nonblock.get();
+        socket_send(self, ptr, len, is_nonblock, None, ecx, finish)
+    }
+
+    fn tracks_readiness(&self) -> bool {
+        true
+    }
+
+    fn as_unix(&self) -> &dyn UnixFileDescription {
+        self
+    }
+}
+
+impl UnixFileDescription for Socket {
+    fn get_epoll_ready_events<'tcx>(&self) -> InterpResult<'tcx, EpollReadyEvents> {
+        let mut epoll_ready_events = EpollReadyEvents::new();
+        match &*self.state.borrow() {
+            SocketState::Unconnected => {
+                // Like Linux, report a socket that was never connected as hung up.
+                epoll_ready_events.epollout = true;
+                epoll_ready_events.epollhup = true;
+            }
+            SocketState::Listening(pending) => {
+                epoll_ready_events.epollin = !pending.is_empty();
+            }
+            SocketState::Connected { peer_fd, readbuf } => {
+                epoll_ready_events.epollin = !readbuf.buf.is_empty();
+                if let Some(peer_fd) = peer_fd.upgrade() {
+                    let SocketState::Connected { readbuf: writebuf, .. } = &*peer_fd.state.borrow()
+                    else {
+                        unreachable!("the peer of a connected socket is connected")
+                    };
+                    epoll_ready_events.epollout = writebuf.buf.len() < MAX_STREAM_BUFFER_CAPACITY;
+                } else {
+                    // Reads return EOF and writes fail, so neither of them blocks.
+                    epoll_ready_events.epollrdhup = true;
+                    epoll_ready_events.epollhup = true;
+                    epoll_ready_events.epollin = true;
+                    epoll_ready_events.epollout = true;
+                }
+            }
+            SocketState::Datagram(datagrams) => {
+                epoll_ready_events.epollin = !datagrams.is_empty();
+                // Sending a datagram never blocks.
+                epoll_ready_events.epollout = true;
+            }
+        }
+        interp_ok(epoll_ready_events)
+    }
+
+    fn set_nonblocking<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        nonblocking: bool,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        self.is_nonblock.set(nonblocking);
+        interp_ok(Ok(()))
+    }
+}
+
+/// Unblocks all threads in `blocked_tid`.
+fn unblock_all<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    blocked_tid: &RefCell<Vec<ThreadId>>,
+) -> InterpResult<'tcx> {
+    let waiting_threads = std::mem::take(&mut *blocked_tid.borrow_mut());
+    // FIXME: We can randomize the order of unblocking.
+    for thread_id in waiting_threads {
+        ecx.unblock_thread(thread_id, BlockReason::Socket)?;
+    }
+    interp_ok(())
+}
+
+/// An emulated socket, or a socket backed by the host.
+enum AnySocket {
+    Emulated(FileDescriptionRef<Socket>),
+    Host(FileDescriptionRef<HostSocket>),
+}
+
+impl AnySocket {
+    fn is_ipv6(&self) -> bool {
+        match self {
+            AnySocket::Emulated(socket) => socket.is_ipv6,
+            AnySocket::Host(socket) => socket.is_ipv6,
+        }
+    }
+}
+
+/// Returns the socket with the file descriptor `fd_num`.
+fn get_socket(ecx: &MiriInterpCx<'_>, fd_num: i32) -> Result<AnySocket, IoError> {
+    let fd = ecx.machine.fds.get(fd_num).ok_or(LibcError("EBADF"))?;
+    if let Some(socket) = fd.clone().downcast::<HostSocket>() {
+        return Ok(AnySocket::Host(socket));
+    }
+    fd.downcast::<Socket>().map(AnySocket::Emulated).ok_or(LibcError("ENOTSOCK"))
+}
+
+/// Binds `socket` to an ephemeral port of `ip` if it isn't bound yet, and returns its address.
+fn ensure_bound(
+    ecx: &mut MiriInterpCx<'_>,
+    socket: &FileDescriptionRef<Socket>,
+    ip: IpAddr,
+) -> Result<SocketAddr, IoError> {
+    if let Some(addr) = socket.local_addr.get() {
+        return Ok(addr);
+    }
+    let addr = ecx.machine.sockets.bind(socket, SocketAddr::new(ip, 0))?;
+    socket.local_addr.set(Some(addr));
+    Ok(addr)
+}
+
+/// Sends to a socket, to `dest_addr` for datagram sockets if given.
+fn socket_send<'tcx>(
+    self_ref: FileDescriptionRef<Socket>,
+    ptr: Pointer,
+    len: usize,
+    is_nonblock: bool,
+    dest_addr: Option<SocketAddr>,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+) -> InterpResult<'tcx> {
+    match self_ref.kind {
+        SocketKind::Stream => {
+            // Like Linux, we ignore the destination of connected stream sockets.
+            let peer_fd = match &*self_ref.state.borrow() {
+                SocketState::Connected { peer_fd, .. } => peer_fd.upgrade(),
+                _ => return finish.call(ecx, Err(LibcError("ENOTCONN"))),
+            };
+            if len == 0 {
+                return finish.call(ecx, Ok(0));
+            }
+            let Some(peer_fd) = peer_fd else {
+                // We don't emulate signals, so this behaves as if `MSG_NOSIGNAL` was passed.
+                return finish.call(ecx, Err(ErrorKind::BrokenPipe.into()));
+            };
+
+            let mut peer_state = peer_fd.state.borrow_mut();
+            let SocketState::Connected { readbuf: writebuf, .. } = &mut *peer_state else {
+                unreachable!("the peer of a connected socket is connected")
+            };
+            let available_space = MAX_STREAM_BUFFER_CAPACITY.strict_sub(writebuf.buf.len());
+            if available_space == 0 {
+                drop(peer_state);
+                if is_nonblock {
+                    return finish.call(ecx, Err(ErrorKind::WouldBlock.into()));
+                }
+                self_ref.blocked_write_tid.borrow_mut().push(ecx.active_thread());
+                // Block the current thread; only keep a weak ref for this.
+                let weak_self_ref = FileDescriptionRef::downgrade(&self_ref);
+                ecx.block_thread(
+                    BlockReason::Socket,
+                    None,
+                    callback!(
+                        @capture<'tcx> {
+                            weak_self_ref: WeakFileDescriptionRef<Socket>,
+                            ptr: Pointer,
+                            len: usize,
+                            finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+                        }
+                        |this, unblock: UnblockKind| {
+                            assert_eq!(unblock, UnblockKind::Ready);
+                            // If we got unblocked, then our peer successfully upgraded its weak
+                            // ref to us. That means we can also upgrade our weak ref.
+                            let self_ref = weak_self_ref.upgrade().unwrap();
+                            socket_send(
+                                self_ref,
+                                ptr,
+                                len,
+                                /* is_nonblock */ false,
+                                None,
+                                this,
+                                finish,
+                            )
+                        }
+                    ),
+                );
+                return interp_ok(());
+            }
+
+            // Remember this clock so `recv` can synchronize with us.
+            ecx.release_clock(|clock| {
+                writebuf.clock.join(clock);
+            });
+            let write_size = len.min(available_space);
+            let actual_write_size =
+                ecx.write_to_buffer(&mut writebuf.buf, write_size, ptr)?.unwrap();
+            assert_eq!(actual_write_size, write_size);
+            drop(peer_state);
+
+            unblock_all(ecx, &peer_fd.blocked_read_tid)?;
+            ecx.check_and_update_readiness(peer_fd)?;
+            finish.call(ecx, Ok(write_size))
+        }
+        SocketKind::Datagram => {
+            let Some(dest_addr) = dest_addr.or(self_ref.peer_addr.get()) else {
+                return finish.call(ecx, Err(LibcError("EDESTADDRREQ")));
+            };
+            if len > MAX_DATAGRAM_SIZE {
+                return finish.call(ecx, Err(LibcError("EMSGSIZE")));
+            }
+            let mut source = match ensure_bound(ecx, &self_ref, self_ref.unspecified_ip()) {
+                Ok(addr) => addr,
+                Err(err) => return finish.call(ecx, Err(err)),
+            };
+            if source.ip().is_unspecified() {
+                // The datagram goes out through the loopback interface.
+                source.set_ip(dest_addr.ip());
+            }
+
+            let data = ecx.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?.to_vec();
+            let mut clock = VClock::default();
+            ecx.release_clock(|release_clock| {
+                clock.join(release_clock);
+            });
+            // Datagrams sent to an address no socket is bound to are lost.
+            if let Some(receiver) = ecx.machine.sockets.lookup(SocketKind::Datagram, dest_addr) {
+                match &mut *receiver.state.borrow_mut() {
+                    SocketState::Datagram(datagrams) =>
+                        datagrams.push_back(Datagram { source, data, clock }),
+                    _ => unreachable!("only datagram sockets are bound as such"),
+                }
+                unblock_all(ecx, &receiver.blocked_read_tid)?;
+                ecx.check_and_update_readiness(receiver)?;
+            }
+            finish.call(ecx, Ok(len))
+        }
+    }
+}
+
+/// Receives from a socket. For datagram sockets, the source address is written to `source` if
+/// given, as a pointer to the `sockaddr` and a pointer to its length.
+fn socket_recv<'tcx>(
+    self_ref: FileDescriptionRef<Socket>,
+    ptr: Pointer,
+    len: usize,
+    is_nonblock: bool,
+    peek: bool,
+    source: Option<(Pointer, Pointer)>,
+    ecx: &mut MiriInterpCx<'tcx>,
+    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+) -> InterpResult<'tcx> {
+    let mut state = self_ref.state.borrow_mut();
+    let is_empty = match &mut *state {
+        SocketState::Connected { peer_fd, readbuf } => {
+            if len == 0 {
+                drop(state);
+                return finish.call(ecx, Ok(0));
+            }
+            if readbuf.buf.is_empty() && peer_fd.upgrade().is_none() {
+                // The peer is gone and everything it sent has been read: this is EOF.
+                drop(state);
+                return finish.call(ecx, Ok(0));
+            }
+            readbuf.buf.is_empty()
+        }
+        SocketState::Datagram(datagrams) => datagrams.is_empty(),
+        SocketState::Unconnected | SocketState::Listening(_) => {
+            drop(state);
+            return finish.call(ecx, Err(LibcError("ENOTCONN")));
+        }
+    };
+    if is_empty {
+        drop(state);
+        if is_nonblock {
+            return finish.call(ecx, Err(ErrorKind::WouldBlock.into()));
+        }
+        self_ref.blocked_read_tid.borrow_mut().push(ecx.active_thread());
+        // Block the current thread; only keep a weak ref for this.
+        let weak_self_ref = FileDescriptionRef::downgrade(&self_ref);
+        ecx.block_thread(
+            BlockReason::Socket,
+            None,
+            callback!(
+                @capture<'tcx> {
+                    weak_self_ref: WeakFileDescriptionRef<Socket>,
+                    ptr: Pointer,
+                    len: usize,
+                    peek: bool,
+                    source: Option<(Pointer, Pointer)>,
+                    finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+                }
+                |this, unblock: UnblockKind| {
+                    assert_eq!(unblock, UnblockKind::Ready);
+                    // If we got unblocked, then whoever sent us data successfully upgraded its
+                    // weak ref to us. That means we can also upgrade our weak ref.
+                    let self_ref = weak_self_ref.upgrade().unwrap();
+                    socket_recv(
+                        self_ref,
+                        ptr,
+                        len,
+                        /* is_nonblock */ false,
+                        peek,
+                        source,
+                        this,
+                        finish,
+                    )
+                }
+            ),
+        );
+        return interp_ok(());
+    }
+
+    match &mut *state {
+        SocketState::Connected { peer_fd, readbuf } => {
+            // Synchronize with all previous writes to this buffer.
+            // FIXME: this over-synchronizes; a more precise approach would be to
+            // only sync with the writes whose data we will read.
+            ecx.acquire_clock(&readbuf.clock);
+            let read_size = if peek {
+                let bytes = readbuf.buf.iter().take(len).copied().collect::<Vec<_>>();
+                ecx.write_bytes_ptr(ptr, bytes.iter().copied())?;
+                bytes.len()
+            } else {
+                ecx.read_from_buffer(&mut readbuf.buf, len, ptr)?.unwrap()
+            };
+            let peer_fd = peer_fd.upgrade();
+            drop(state);
+
+            if !peek && let Some(peer_fd) = peer_fd {
+                // The peer can write again.
+                unblock_all(ecx, &peer_fd.blocked_write_tid)?;
+                ecx.check_and_update_readiness(peer_fd)?;
+            }
+            finish.call(ecx, Ok(read_size))
+        }
+        SocketState::Datagram(datagrams) => {
+            let datagram = if peek {
+                datagrams.front().unwrap().clone()
+            } else {
+                datagrams.pop_front().unwrap()
+            };
+            drop(state);
+
+            ecx.acquire_clock(&datagram.clock);
+            // The part of the datagram that doesn't fit is discarded.
+            let read_size = len.min(datagram.data.len());
+            ecx.write_bytes_ptr(ptr, datagram.data[..read_size].iter().copied())?;
+            if let Some((address, address_len)) = source {
+                write_sockaddr(ecx, datagram.source, address, address_len)?;
+            }
+            finish.call(ecx, Ok(read_size))
+        }
+        SocketState::Unconnected | SocketState::Listening(_) => unreachable!(),
+    }
+}
+
+/// Accepts a connection of a listening socket, and writes the new file descriptor to `dest`.
+fn socket_accept<'tcx>(
+    self_ref: FileDescriptionRef<Socket>,
+    address: Pointer,
+    address_len: Pointer,
+    is_nonblock: bool,
+    dest: MPlaceTy<'tcx>,
+    ecx: &mut MiriInterpCx<'tcx>,
+) -> InterpResult<'tcx> {
+    let connection = match &mut *self_ref.state.borrow_mut() {
+        SocketState::Listening(pending) => pending.pop_front(),
+        _ => return ecx.set_last_error_and_return(LibcError("EINVAL"), &dest),
+    };
+    let Some((socket, clock)) = connection else {
+        if self_ref.is_nonblock.get() {
+            return ecx.set_last_error_and_return(ErrorKind::WouldBlock, &dest);
+        }
+        self_ref.blocked_read_tid.borrow_mut().push(ecx.active_thread());
+        // Block the current thread; only keep a weak ref for this.
+        let weak_self_ref = FileDescriptionRef::downgrade(&self_ref);
+        ecx.block_thread(
+            BlockReason::Socket,
+            None,
+            callback!(
+                @capture<'tcx> {
+                    weak_self_ref: WeakFileDescriptionRef<Socket>,
+                    address: Pointer,
+                    address_len: Pointer,
+                    is_nonblock: bool,
+                    dest: MPlaceTy<'tcx>,
+                }
+                |this, unblock: UnblockKind| {
+                    assert_eq!(unblock, UnblockKind::Ready);
+                    // If we got unblocked, then `connect` successfully found us in the socket
+                    // table. That means we can also upgrade our weak ref.
+                    let self_ref = weak_self_ref.upgrade().unwrap();
+                    socket_accept(self_ref, address, address_len, is_nonblock, dest, this)
+                }
+            ),
+        );
+        return interp_ok(());
+    };
+
+    // Synchronize with the thread that connected.
+    ecx.acquire_clock(&clock);
+    socket.is_nonblock.set(is_nonblock);
+    write_sockaddr(ecx, socket.peer_addr.get().unwrap(), address, address_len)?;
+    let fd = ecx.machine.fds.insert(socket);
+    ecx.write_int(fd, &dest)
+}
+
+/// Reads the `sockaddr_in` or `sockaddr_in6` at `address`, depending on the address family of
+/// the socket.
+fn read_sockaddr<'tcx>(
+    ecx: &MiriInterpCx<'tcx>,
+    address: &OpTy<'tcx>,
+    address_len: &OpTy<'tcx>,
+    is_ipv6: bool,
+) -> InterpResult<'tcx, Result<SocketAddr, IoError>> {
+    let address_len = ecx.read_scalar(address_len)?.to_u32()?;
+    let (layout, family, prefix) = if is_ipv6 {
+        (ecx.libc_ty_layout("sockaddr_in6"), ecx.eval_libc_i32("AF_INET6"), "sin6")
+    } else {
+        (ecx.libc_ty_layout("sockaddr_in"), ecx.eval_libc_i32("AF_INET"), "sin")
+    };
+    if u64::from(address_len) < layout.size.bytes() {
+        return interp_ok(Err(LibcError("EINVAL")));
+    }
+    let address = ecx.deref_pointer_as(address, layout)?;
+
+    let family_field = ecx.project_field_named(&address, &format!("{prefix}_family"))?;
+    if ecx.read_scalar(&family_field)?.to_int(family_field.layout.size)? != i128::from(family) {
+        return interp_ok(Err(LibcError("EAFNOSUPPORT")));
+    }
+    // The port and the address are in network byte order.
+    let port_field = ecx.project_field_named(&address, &format!("{prefix}_port"))?;
+    let port = ecx.read_bytes_ptr_strip_provenance(port_field.ptr(), Size::from_bytes(2))?;
+    let port = u16::from_be_bytes(port.try_into().unwrap());
+    let ip_field = ecx.project_field_named(&address, &format!("{prefix}_addr"))?;
+    let ip_bytes = ecx.read_bytes_ptr_strip_provenance(ip_field.ptr(), ip_field.layout.size)?;
+    let ip: IpAddr = if is_ipv6 {
+        <[u8; 16]>::try_from(ip_bytes).unwrap().into()
+    } else {
+        <[u8; 4]>::try_from(ip_bytes).unwrap().into()
+    };
+    interp_ok(Ok(SocketAddr::new(ip, port)))
+}
+
+/// Writes `addr` to the `sockaddr` at `address`, truncated to the length at `address_len`, and
+/// replaces that length with the length of `addr`. Does nothing if `address` is null.
+pub(super) fn write_sockaddr<'tcx>(
+    ecx: &mut MiriInterpCx<'tcx>,
+    addr: SocketAddr,
+    address: Pointer,
+    address_len: Pointer,
+) -> InterpResult<'tcx> {
+    if ecx.ptr_is_null(address)? {
+        return interp_ok(());
+    }
+    let address_len = ecx.ptr_to_mplace(address_len, ecx.libc_ty_layout("socklen_t"));
+    let len = ecx.read_scalar(&address_len)?.to_u32()?;
+
+    let (layout, family, prefix, ip_bytes) = match addr.ip() {
+        IpAddr::V4(ip) =>
+            (
+                ecx.libc_ty_layout("sockaddr_in"),
+                ecx.eval_libc_i32("AF_INET"),
+                "sin",
+                ip.octets().to_vec(),
+            ),
+        IpAddr::V6(ip) =>
+            (
+                ecx.libc_ty_layout("sockaddr_in6"),
+                ecx.eval_libc_i32("AF_INET6"),
+                "sin6",
+                ip.octets().to_vec(),
+            ),
+    };
+    // Build the whole `sockaddr` first, so that it can be truncated.
+    let sockaddr = ecx.allocate(layout, MiriMemoryKind::Machine.into())?;
+    ecx.write_bytes_ptr(sockaddr.ptr(), std::iter::repeat_n(0, layout.size.bytes_usize()))?;
+    // Some targets start all `sockaddr`s with their length.
+    if let Some(len_field) = ecx.try_project_field_named(&sockaddr, &format!("{prefix}_len"))? {
+        ecx.write_int(layout.size.bytes(), &len_field)?;
+    }
+    ecx.write_int_fields_named(&[(format!("{prefix}_family").as_str(), family.into())], &sockaddr)?;
+    // The port and the address are in network byte order.
+    let port_field = ecx.project_field_named(&sockaddr, &format!("{prefix}_port"))?;
+    ecx.write_bytes_ptr(port_field.ptr(), addr.port().to_be_bytes())?;
+    let ip_field = ecx.project_field_named(&sockaddr, &format!("{prefix}_addr"))?;
+    ecx.write_bytes_ptr(ip_field.ptr(), ip_bytes)?;
+
+    let copy_len = u64::from(len).min(layout.size.bytes());
+    ecx.mem_copy(
+        sockaddr.ptr(),
+        address,
+        Size::from_bytes(copy_len),
+        /* nonoverlapping */ true,
+    )?;
+    ecx.deallocate_ptr(sockaddr.ptr(), None, MiriMemoryKind::Machine.into())?;
+    ecx.write_int(layout.size.bytes(), &address_len)
+}
+
+/// Rejects addresses that are not on the loopback interface, which is the only one we emulate.
+/// The unspecified address stands for all interfaces, i.e. the loopback interface.
+fn check_loopback<'tcx>(addr: SocketAddr, op: &str) -> InterpResult<'tcx> {
+    if !addr.ip().is_loopback() && !addr.ip().is_unspecified() {
+        throw_unsup_format!(
+            "{op}: address {} is unsupported, only loopback addresses are supported",
+            addr.ip()
+        );
+    }
+    interp_ok(())
+}
+
+/// Returns the callback that writes the result of a `send` or `recv` of `count` bytes to `dest`.
+fn return_size<'tcx>(
+    count: usize,
+    dest: &MPlaceTy<'tcx>,
+) -> DynMachineCallback<'tcx, Result<usize, IoError>> {
+    let dest = dest.clone();
+    callback!(
+        @capture<'tcx> {
+            count: usize,
+            dest: MPlaceTy<'tcx>,
+        }
+        |this, result: Result<usize, IoError>| {
+            match result {
+                Ok(size) => {
+                    assert!(size <= count);
+                    // This must fit since `count` fits.
+                    this.write_int(u64::try_from(size).unwrap(), &dest)
+                }
+                Err(e) => {
+                    this.set_last_error_and_return(e, &dest)
+                }
+        }}
+    )
+}
+
+impl<'tcx> EvalContextExt<'tcx> for crate::MiriInterpCx<'tcx> {}
+pub trait EvalContextExt<'tcx>: crate::MiriInterpCxExt<'tcx> {
+    fn socket(
+        &mut self,
+        domain: &OpTy<'tcx>,
+        type_: &OpTy<'tcx>,
+        protocol: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let domain = this.read_scalar(domain)?.to_i32()?;
+        let mut flags = this.read_scalar(type_)?.to_i32()?;
+        let protocol = this.read_scalar(protocol)?.to_i32()?;
+
+        let mut is_sock_nonblock = false;
+
+        // Interpret the flag. Every flag we recognize is "subtracted" from `flags`, so
+        // if there is anything left at the end, that's an unsupported flag.
+        if this.tcx.sess.target.os == "linux" {
+            // SOCK_NONBLOCK only exists on Linux.
+            let sock_nonblock = this.eval_libc_i32("SOCK_NONBLOCK");
+            let sock_cloexec = this.eval_libc_i32("SOCK_CLOEXEC");
+            if flags & sock_nonblock == sock_nonblock {
+                is_sock_nonblock = true;
+                flags &= !sock_nonblock;
+            }
+            if flags & sock_cloexec == sock_cloexec {
+                flags &= !sock_cloexec;
+            }
+        }
+
+        let is_ipv6 = if domain == this.eval_libc_i32("AF_INET") {
+            false
+        } else if domain == this.eval_libc_i32("AF_INET6") {
+            true
+        } else {
+            throw_unsup_format!(
+                "socket: domain {domain:#x} is unsupported, only AF_INET and AF_INET6 are allowed"
+            );
+        };
+        let (kind, default_protocol) = if flags == this.eval_libc_i32("SOCK_STREAM") {
+            (SocketKind::Stream, this.eval_libc_i32("IPPROTO_TCP"))
+        } else if flags == this.eval_libc_i32("SOCK_DGRAM") {
+            (SocketKind::Datagram, this.eval_libc_i32("IPPROTO_UDP"))
+        } else {
+            throw_unsup_format!(
+                "socket: type {flags:#x} is unsupported, only SOCK_STREAM, SOCK_DGRAM, \
+                 SOCK_CLOEXEC and SOCK_NONBLOCK are allowed"
+            );
+        };
+        if protocol != 0 && protocol != default_protocol {
+            throw_unsup_format!(
+                "socket: socket protocol {protocol} is unsupported, \
+                 only 0 and the default protocol of the socket type are allowed"
+            );
+        }
+
+        let fd = if this.machine.communicate() {
+            host_socket::insert_host_socket(this, HostSocket::new(kind, is_ipv6, is_sock_nonblock))
+        } else {
+            this.machine.fds.insert_new(Socket::new(kind, is_ipv6, is_sock_nonblock))
+        };
+        interp_ok(Scalar::from_i32(fd))
+    }
+
+    fn bind(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let socket = match get_socket(this, fd_num) {
+            Ok(socket) => socket,
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        };
+        let addr = match read_sockaddr(this, address, address_len, socket.is_ipv6())? {
+            Ok(addr) => addr,
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        };
+        let socket = match socket {
+            AnySocket::Emulated(socket) => socket,
+            AnySocket::Host(socket) =>
+                return match socket.bind(addr) {
+                    Ok(()) => interp_ok(Scalar::from_i32(0)),
+                    Err(err) => this.set_last_error_and_return_i32(err),
+                },
+        };
+        check_loopback(addr, "bind")?;
+
+        if socket.local_addr.get().is_some() {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+        match this.machine.sockets.bind(&socket, addr) {
+            Ok(addr) => socket.local_addr.set(Some(addr)),
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        }
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn listen(&mut self, socket: &OpTy<'tcx>, backlog: &OpTy<'tcx>) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        // There is no limit on the number of pending connections.
+        let _backlog = this.read_scalar(backlog)?.to_i32()?;
+        let socket = match get_socket(this, fd_num) {
+            Ok(AnySocket::Emulated(socket)) => socket,
+            Ok(AnySocket::Host(socket)) =>
+                return match socket.listen() {
+                    Ok(()) => interp_ok(Scalar::from_i32(0)),
+                    Err(err) => this.set_last_error_and_return_i32(err),
+                },
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        };
+
+        match &*socket.state.borrow() {
+            SocketState::Unconnected => {}
+            SocketState::Listening(_) => return interp_ok(Scalar::from_i32(0)),
+            SocketState::Connected { .. } =>
+                return this.set_last_error_and_return_i32(LibcError("EINVAL")),
+            SocketState::Datagram(_) =>
+                return this.set_last_error_and_return_i32(LibcError("EOPNOTSUPP")),
+        }
+        // Like on Linux, listening on an unbound socket binds it to an ephemeral port.
+        if let Err(err) = ensure_bound(this, &socket, socket.unspecified_ip()) {
+            return this.set_last_error_and_return_i32(err);
+        }
+        *socket.state.borrow_mut() = SocketState::Listening(VecDeque::new());
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    /// Implements both `accept` and `accept4`. `accept` has no `flags`.
+    fn accept4(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+        flags: Option<&OpTy<'tcx>>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_pointer(address_len)?;
+        let mut flags = match flags {
+            Some(flags) => this.read_scalar(flags)?.to_i32()?,
+            None => 0,
+        };
+
+        // Interpret the flag. Every flag we recognize is "subtracted" from `flags`, so
+        // if there is anything left at the end, that's an unsupported flag.
+        let mut is_nonblock = false;
+        if flags != 0 {
+            let sock_nonblock = this.eval_libc_i32("SOCK_NONBLOCK");
+            let sock_cloexec = this.eval_libc_i32("SOCK_CLOEXEC");
+            if flags & sock_nonblock == sock_nonblock {
+                is_nonblock = true;
+                flags &= !sock_nonblock;
+            }
+            // As usual we ignore CLOEXEC.
+            if flags & sock_cloexec == sock_cloexec {
+                flags &= !sock_cloexec;
+            }
+        }
+        if flags != 0 {
+            throw_unsup_format!("accept4: flag {flags:#x} is unsupported");
+        }
+
+        match get_socket(this, fd_num) {
+            Ok(AnySocket::Emulated(socket)) =>
+                socket_accept(socket, address, address_len, is_nonblock, dest.clone(), this),
+            Ok(AnySocket::Host(socket)) =>
+                host_socket::host_accept(
+                    socket,
+                    address,
+                    address_len,
+                    is_nonblock,
+                    dest.clone(),
+                    this,
+                ),
+            Err(err) => this.set_last_error_and_return(err, dest),
+        }
+    }
+
+    fn connect(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let socket = match get_socket(this, fd_num) {
+            Ok(socket) => socket,
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        };
+        let mut addr = match read_sockaddr(this, address, address_len, socket.is_ipv6())? {
+            Ok(addr) => addr,
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        };
+        // Connecting to the unspecified address means connecting to the local host.
+        if addr.ip().is_unspecified() {
+            addr.set_ip(if socket.is_ipv6() {
+                Ipv6Addr::LOCALHOST.into()
+            } else {
+                Ipv4Addr::LOCALHOST.into()
+            });
+        }
+        let socket = match socket {
+            AnySocket::Emulated(socket) => socket,
+            AnySocket::Host(socket) =>
+                return match socket.connect(this, addr)? {
+                    Ok(()) => interp_ok(Scalar::from_i32(0)),
+                    Err(err) => this.set_last_error_and_return_i32(err),
+                },
+        };
+        check_loopback(addr, "connect")?;
+
+        if socket.kind == SocketKind::Datagram {
+            // This only sets the default destination.
+            if let Err(err) = ensure_bound(this, &socket, socket.unspecified_ip()) {
+                return this.set_last_error_and_return_i32(err);
+            }
+            socket.peer_addr.set(Some(addr));
+            return interp_ok(Scalar::from_i32(0));
+        }
+
+        match &*socket.state.borrow() {
+            SocketState::Unconnected => {}
+            SocketState::Connected { .. } =>
+                return this.set_last_error_and_return_i32(LibcError("EISCONN")),
+            SocketState::Listening(_) | SocketState::Datagram(_) =>
+                return this.set_last_error_and_return_i32(LibcError("EINVAL")),
+        }
+        let Some(listener) = this
+            .machine
+            .sockets
+            .lookup(SocketKind::Stream, addr)
+            .filter(|listener| matches!(*listener.state.borrow(), SocketState::Listening(_)))
+        else {
+            return this.set_last_error_and_return_i32(LibcError("ECONNREFUSED"));
+        };
+        let local_addr = match ensure_bound(this, &socket, addr.ip()) {
+            Ok(addr) => addr,
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        };
+
+        // Create the socket that `accept` will return, and connect both ends.
+        let server =
+            this.machine.fds.new_ref(Socket::new(SocketKind::Stream, socket.is_ipv6, false));
+        server.local_addr.set(Some(addr));
+        server.peer_addr.set(Some(local_addr));
+        *server.state.borrow_mut() = SocketState::Connected {
+            peer_fd: FileDescriptionRef::downgrade(&socket),
+            readbuf: Buffer::new(),
+        };
+        socket.peer_addr.set(Some(addr));
+        *socket.state.borrow_mut() = SocketState::Connected {
+            peer_fd: FileDescriptionRef::downgrade(&server),
+            readbuf: Buffer::new(),
+        };
+
+        let mut clock = VClock::default();
+        this.release_clock(|release_clock| {
+            clock.join(release_clock);
+        });
+        match &mut *listener.state.borrow_mut() {
+            SocketState::Listening(pending) => pending.push_back((server, clock)),
+            _ => unreachable!(),
+        }
+        unblock_all(this, &listener.blocked_read_tid)?;
+        this.check_and_update_readiness(listener)?;
+        // Our socket became writable.
+        this.check_and_update_readiness(socket)?;
+
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    /// Implements both `send` and `sendto`. `send` has no `dest_addr`.
+    fn send(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        buf: &OpTy<'tcx>,
+        len: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+        dest_addr: Option<(&OpTy<'tcx>, &OpTy<'tcx>)>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let buf = this.read_pointer(buf)?;
+        let count = this.read_target_usize(len)?;
+        let mut flags = this.read_scalar(flags)?.to_i32()?;
+
+        // Interpret the flag. Every flag we recognize is "subtracted" from `flags`, so
+        // if there is anything left at the end, that's an unsupported flag.
+        let mut is_nonblock = false;
+        let msg_dontwait = this.eval_libc_i32("MSG_DONTWAIT");
+        if flags & msg_dontwait == msg_dontwait {
+            is_nonblock = true;
+            flags &= !msg_dontwait;
+        }
+        if matches!(&*this.tcx.sess.target.os, "linux" | "android" | "freebsd") {
+            // We don't emulate signals, so this is what we always do anyway.
+            let msg_nosignal = this.eval_libc_i32("MSG_NOSIGNAL");
+            if flags & msg_nosignal == msg_nosignal {
+                flags &= !msg_nosignal;
+            }
+        }
+        if flags != 0 {
+            throw_unsup_format!("send: flag {flags:#x} is unsupported");
+        }
+
+        // Check that the *entire* buffer is actually valid memory.
+        this.check_ptr_access(buf, Size::from_bytes(count), CheckInAllocMsg::MemoryAccessTest)?;
+        // We cap the number of sent bytes to the largest value that we are able to fit in both the
+        // host's and target's `isize`. This saves us from having to handle overflows later.
+        let count = count
+            .min(u64::try_from(this.target_isize_max()).unwrap())
+            .min(u64::try_from(isize::MAX).unwrap());
+        let count = usize::try_from(count).unwrap(); // now it fits in a `usize`
+
+        let socket = match get_socket(this, fd_num) {
+            Ok(socket) => socket,
+            Err(err) => return this.set_last_error_and_return(err, dest),
+        };
+        let dest_addr = match dest_addr {
+            Some((address, address_len)) => {
+                let mut addr = match read_sockaddr(this, address, address_len, socket.is_ipv6())? {
+                    Ok(addr) => addr,
+                    Err(err) => return this.set_last_error_and_return(err, dest),
+                };
+                if let AnySocket::Emulated(_) = socket {
+                    check_loopback(addr, "sendto")?;
+                }
+                // Sending to the unspecified address means sending to the local host.
+                if addr.ip().is_unspecified() {
+                    addr.set_ip(if socket.is_ipv6() {
+                        Ipv6Addr::LOCALHOST.into()
+                    } else {
+                        Ipv4Addr::LOCALHOST.into()
+                    });
+                }
+                Some(addr)
+            }
+            None => None,
+        };
+        let finish = return_size(count, dest);
+        match socket {
+            AnySocket::Emulated(socket) => {
+                let is_nonblock = is_nonblock || socket.is_nonblock.get();
+                socket_send(socket, buf, count, is_nonblock, dest_addr, this, finish)
+            }
+            AnySocket::Host(socket) => {
+                let is_nonblock = is_nonblock || socket.is_nonblock.get();
+                host_socket::host_send(socket, buf, count, is_nonblock, dest_addr, this, finish)
+            }
+        }
+    }
+
+    /// Implements both `recv` and `recvfrom`. `recv` has no `address`.
+    fn recv(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        buf: &OpTy<'tcx>,
+        len: &OpTy<'tcx>,
+        flags: &OpTy<'tcx>,
+        address: Option<(&OpTy<'tcx>, &OpTy<'tcx>)>,
+        dest: &MPlaceTy<'tcx>,
+    ) -> InterpResult<'tcx> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let buf = this.read_pointer(buf)?;
+        let count = this.read_target_usize(len)?;
+        let mut flags = this.read_scalar(flags)?.to_i32()?;
+        let source = match address {
+            Some((address, address_len)) =>
+                Some((this.read_pointer(address)?, this.read_pointer(address_len)?)),
+            None => None,
+        };
+
+        // Interpret the flag. Every flag we recognize is "subtracted" from `flags`, so
+        // if there is anything left at the end, that's an unsupported flag.
+        let mut is_nonblock = false;
+        let msg_dontwait = this.eval_libc_i32("MSG_DONTWAIT");
+        if flags & msg_dontwait == msg_dontwait {
+            is_nonblock = true;
+            flags &= !msg_dontwait;
+        }
+        let mut peek = false;
+        let msg_peek = this.eval_libc_i32("MSG_PEEK");
+        if flags & msg_peek == msg_peek {
+            peek = true;
+            flags &= !msg_peek;
+        }
+        if flags != 0 {
+            throw_unsup_format!("recv: flag {flags:#x} is unsupported");
+        }
+
+        // Check that the *entire* buffer is actually valid memory.
+        this.check_ptr_access(buf, Size::from_bytes(count), CheckInAllocMsg::MemoryAccessTest)?;
+        // We cap the number of received bytes to the largest value that we are able to fit in
+        // both the host's and target's `isize`. This saves us from having to handle overflows
+        // later.
+        let count = count
+            .min(u64::try_from(this.target_isize_max()).unwrap())
+            .min(u64::try_from(isize::MAX).unwrap());
+        let count = usize::try_from(count).unwrap(); // now it fits in a `usize`
+
+        let finish = return_size(count, dest);
+        match get_socket(this, fd_num) {
+            Ok(AnySocket::Emulated(socket)) => {
+                let is_nonblock = is_nonblock || socket.is_nonblock.get();
+                socket_recv(socket, buf, count, is_nonblock, peek, source, this, finish)
+            }
+            Ok(AnySocket::Host(socket)) => {
+                let is_nonblock = is_nonblock || socket.is_nonblock.get();
+                host_socket::host_recv(socket, buf, count, is_nonblock, peek, source, this, finish)
+            }
+            Err(err) => this.set_last_error_and_return(err, dest),
+        }
+    }
+
+    fn setsockopt(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        level: &OpTy<'tcx>,
+        option_name: &OpTy<'tcx>,
+        option_value: &OpTy<'tcx>,
+        option_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let level = this.read_scalar(level)?.to_i32()?;
+        let option_name = this.read_scalar(option_name)?.to_i32()?;
+        let option_len = this.read_scalar(option_len)?.to_u32()?;
+        let socket = match get_socket(this, fd_num) {
+            Ok(socket) => socket,
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        };
+
+        let option = if level == this.eval_libc_i32("SOL_SOCKET") {
+            if option_name == this.eval_libc_i32("SO_REUSEADDR") {
+                Some(SocketOption::ReuseAddr)
+            } else if option_name == this.eval_libc_i32("SO_BROADCAST") {
+                Some(SocketOption::Broadcast)
+            } else if this.tcx.sess.target.os == "macos"
+                && option_name == this.eval_libc_i32("SO_NOSIGPIPE")
+            {
+                Some(SocketOption::NoSigPipe)
+            } else {
+                None
+            }
+        } else if level == this.eval_libc_i32("IPPROTO_TCP")
+            && option_name == this.eval_libc_i32("TCP_NODELAY")
+        {
+            Some(SocketOption::NoDelay)
+        } else if level == this.eval_libc_i32("IPPROTO_IPV6")
+            && option_name == this.eval_libc_i32("IPV6_V6ONLY")
+        {
+            Some(SocketOption::V6Only)
+        } else {
+            None
+        };
+        let Some(option) = option else {
+            throw_unsup_format!(
+                "setsockopt: option {option_name:#x} at level {level:#x} is unsupported"
+            );
+        };
+        // All these options are `int`s.
+        if u64::from(option_len) < this.machine.layouts.i32.size.bytes() {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+        let option_value = this.deref_pointer_as(option_value, this.machine.layouts.i32)?;
+        let value = this.read_scalar(&option_value)?.to_i32()?;
+        match socket {
+            // None of the options make a difference for the emulation: there is no TIME_WAIT
+            // state that would keep an address in use, no Nagle's algorithm, no signals, and IPv6
+            // sockets never receive IPv4 traffic.
+            AnySocket::Emulated(_) => {}
+            AnySocket::Host(socket) =>
+                if let Err(err) = socket.set_option(option, value) {
+                    return this.set_last_error_and_return_i32(err);
+                },
+        }
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn getsockopt(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        level: &OpTy<'tcx>,
+        option_name: &OpTy<'tcx>,
+        option_value: &OpTy<'tcx>,
+        option_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let level = this.read_scalar(level)?.to_i32()?;
+        let option_name = this.read_scalar(option_name)?.to_i32()?;
+        let option_len = this.deref_pointer_as(option_len, this.libc_ty_layout("socklen_t"))?;
+        let socket = match get_socket(this, fd_num) {
+            Ok(socket) => socket,
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        };
+
+        if level != this.eval_libc_i32("SOL_SOCKET")
+            || option_name != this.eval_libc_i32("SO_ERROR")
+        {
+            throw_unsup_format!(
+                "getsockopt: option {option_name:#x} at level {level:#x} is unsupported"
+            );
+        }
+        let error = match socket {
+            // There are no asynchronous errors: `connect` completes immediately.
+            AnySocket::Emulated(_) => Scalar::from_i32(0),
+            AnySocket::Host(socket) =>
+                match socket.take_error() {
+                    Ok(None) => Scalar::from_i32(0),
+                    Ok(Some(err)) => this.io_error_to_errnum(err)?,
+                    Err(err) => return this.set_last_error_and_return_i32(err),
+                },
+        };
+        let size = this.machine.layouts.i32.size.bytes();
+        if u64::from(this.read_scalar(&option_len)?.to_u32()?) < size {
+            return this.set_last_error_and_return_i32(LibcError("EINVAL"));
+        }
+        let option_value = this.deref_pointer_as(option_value, this.machine.layouts.i32)?;
+        this.write_scalar(error, &option_value)?;
+        this.write_int(size, &option_len)?;
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn getsockname(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_pointer(address_len)?;
+        let addr = match get_socket(this, fd_num) {
+            // An unbound socket has the unspecified address with port 0.
+            Ok(AnySocket::Emulated(socket)) =>
+                socket
+                    .local_addr
+                    .get()
+                    .unwrap_or_else(|| SocketAddr::new(socket.unspecified_ip(), 0)),
+            Ok(AnySocket::Host(socket)) =>
+                match socket.local_addr() {
+                    Ok(addr) => addr,
+                    Err(err) => return this.set_last_error_and_return_i32(err),
+                },
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        };
+        write_sockaddr(this, addr, address, address_len)?;
+        interp_ok(Scalar::from_i32(0))
+    }
+
+    fn getpeername(
+        &mut self,
+        socket: &OpTy<'tcx>,
+        address: &OpTy<'tcx>,
+        address_len: &OpTy<'tcx>,
+    ) -> InterpResult<'tcx, Scalar> {
+        let this = self.eval_context_mut();
+
+        let fd_num = this.read_scalar(socket)?.to_i32()?;
+        let address = this.read_pointer(address)?;
+        let address_len = this.read_pointer(address_len)?;
+        let addr = match get_socket(this, fd_num) {
+            Ok(AnySocket::Emulated(socket)) => socket.peer_addr.get().ok_or(LibcError("ENOTCONN")),
+            Ok(AnySocket::Host(socket)) => socket.peer_addr(),
+            Err(err) => Err(err),
+        };
+        let addr = match addr {
+            Ok(addr) => addr,
+            Err(err) => return this.set_last_error_and_return_i32(err),
+        };
+        write_sockaddr(this, addr, address, address_len)?;
+        interp_ok(Scalar::from_i32(0))
+    }
+}
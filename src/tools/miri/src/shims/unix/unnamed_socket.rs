@@ -22,7 +22,7 @@
 
 /// One end of a pair of connected unnamed sockets.
 #[derive(Debug)]
-struct AnonSocket {
+pub(crate) struct AnonSocket {
     /// The buffer we are reading from, or `None` if this is the writing end of a pipe.
     /// (In that case, the peer FD will be the reading end of that pipe.)
     readbuf: Option<RefCell<Buffer>>,
@@ -56,9 +56,17 @@ fn new() -> Self {
 }
 
 impl AnonSocket {
-    fn peer_fd(&self) -> &WeakFileDescriptionRef<AnonSocket> {
+    pub(crate) fn peer_fd(&self) -> &WeakFileDescriptionRef<AnonSocket> {
         self.peer_fd.get().unwrap()
     }
+
+    /// Removes and returns everything that has been written to this socket but not read yet.
+    pub(crate) fn take_buffered(&self) -> Vec<u8> {
+        match &self.readbuf {
+            Some(readbuf) => readbuf.borrow_mut().buf.drain(..).collect(),
+            None => Vec::new(),
+        }
+    }
 }
 
 impl FileDescription for AnonSocket {
@@ -107,6 +115,10 @@ fn write<'tcx>(
         anonsocket_write(self, ptr, len, ecx, finish)
     }
 
+    fn tracks_readiness(&self) -> bool {
+        true
+    }
+
     fn as_unix(&self) -> &dyn UnixFileDescription {
         self
     }
@@ -178,7 +190,7 @@ fn anonsocket_write<'tcx>(
         });
         // Do full write / partial write based on the space available.
         let write_size = len.min(available_space);
-        let actual_write_size = ecx.write_to_host(&mut writebuf.buf, write_size, ptr)?.unwrap();
+        let actual_write_size = ecx.write_to_buffer(&mut writebuf.buf, write_size, ptr)?.unwrap();
         assert_eq!(actual_write_size, write_size);
 
         // Need to stop accessing peer_fd so that it can be notified.
@@ -265,7 +277,7 @@ fn anonsocket_read<'tcx>(
 
         // Do full read / partial read based on the space available.
         // Conveniently, `read` exists on `VecDeque` and has exactly the desired behavior.
-        let read_size = ecx.read_from_host(&mut readbuf.buf, len, ptr)?.unwrap();
+        let read_size = ecx.read_from_buffer(&mut readbuf.buf, len, ptr)?.unwrap();
 
         // Need to drop before others can access the readbuf again.
         drop(readbuf);
@@ -0,0 +1,685 @@
+//! A deterministic in-memory filesystem (`-Zmiri-vfs`).
+//!
+//! When isolation is enabled and a snapshot was given, the file system shims operate on this
+//! filesystem instead of rejecting the operation. The snapshot is taken from the host before the
+//! program starts and nothing is ever written back, so the program can neither observe later
+//! changes to the host nor modify it, and every run starts from the same state.
+//!
+//! The root of the snapshot becomes `/`, which is also the initial working directory. A `/tmp`
+//! directory always exists. Permissions, ownership, hard links, and timestamps are not modeled:
+//! every file can be read and written, and all timestamps are zero.
+
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::{self, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::{fs, iter};
+
+use rustc_abi::Size;
+
+use crate::shims::files::{FileDescription, FileDescriptionRef};
+use crate::shims::unix::fd::UnixFileDescription;
+use crate::*;
+
+/// The number of symlinks that are followed while resolving a single path before giving up with
+/// `ELOOP`. This is the limit Linux uses.
+const MAX_SYMLINKS: u32 = 40;
+
+/// A file, directory or symlink of a snapshot.
+#[derive(Debug, Clone)]
+enum SnapshotNode {
+    File(Vec<u8>),
+    Dir(BTreeMap<OsString, SnapshotNode>),
+    Symlink(PathBuf),
+}
+
+/// The initial contents of the virtual filesystem, as passed to `-Zmiri-vfs`.
+#[derive(Debug, Clone)]
+pub struct VfsSnapshot {
+    root: BTreeMap<OsString, SnapshotNode>,
+}
+
+impl VfsSnapshot {
+    /// Takes a snapshot of a host directory, or of the files listed in a manifest.
+    ///
+    /// A manifest is a text file in which every line names a file or directory relative to the
+    /// manifest's location, which is put at the same relative path in the snapshot. Empty lines
+    /// and lines starting with `#` are ignored.
+    pub fn load(path: &Path) -> io::Result<VfsSnapshot> {
+        if fs::metadata(path)?.is_dir() {
+            return Ok(VfsSnapshot { root: import_dir(path)? });
+        }
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        let mut root = BTreeMap::new();
+        for line in fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = Path::new(line);
+            let mut names = Vec::new();
+            for component in entry.components() {
+                match component {
+                    Component::Normal(name) => names.push(name.to_owned()),
+                    Component::CurDir => {}
+                    _ =>
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("manifest entry `{line}` is not a path below the manifest"),
+                        )),
+                }
+            }
+            let Some(name) = names.pop() else { continue };
+            // Create the parent directories of the entry if earlier lines did not.
+            let mut dir = &mut root;
+            for parent in names {
+                dir = match dir.entry(parent).or_insert(SnapshotNode::Dir(BTreeMap::new())) {
+                    SnapshotNode::Dir(entries) => entries,
+                    _ =>
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("a parent of manifest entry `{line}` is not a directory"),
+                        )),
+                };
+            }
+            dir.insert(name, import(&base.join(entry))?);
+        }
+        Ok(VfsSnapshot { root })
+    }
+}
+
+/// Adds the path to errors from the host, which would otherwise be hard to make sense of.
+fn with_path(path: &Path) -> impl FnOnce(io::Error) -> io::Error + '_ {
+    move |err| io::Error::new(err.kind(), format!("{}: {err}", path.display()))
+}
+
+fn import(path: &Path) -> io::Result<SnapshotNode> {
+    let file_type = fs::symlink_metadata(path).map_err(with_path(path))?.file_type();
+    if file_type.is_symlink() {
+        Ok(SnapshotNode::Symlink(fs::read_link(path).map_err(with_path(path))?))
+    } else if file_type.is_dir() {
+        Ok(SnapshotNode::Dir(import_dir(path)?))
+    } else if file_type.is_file() {
+        Ok(SnapshotNode::File(fs::read(path).map_err(with_path(path))?))
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{}: only files, directories and symlinks are supported", path.display()),
+        ))
+    }
+}
+
+fn import_dir(path: &Path) -> io::Result<BTreeMap<OsString, SnapshotNode>> {
+    let mut entries = BTreeMap::new();
+    for entry in fs::read_dir(path).map_err(with_path(path))? {
+        let entry = entry.map_err(with_path(path))?;
+        entries.insert(entry.file_name(), import(&entry.path())?);
+    }
+    Ok(entries)
+}
+
+/// The type of a file in the virtual filesystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VfsFileType {
+    File,
+    Dir,
+    Symlink,
+}
+
+/// The metadata of a file in the virtual filesystem.
+#[derive(Debug, Clone, Copy)]
+pub struct VfsMetadata {
+    pub file_type: VfsFileType,
+    pub size: u64,
+}
+
+/// An entry of a directory in the virtual filesystem.
+#[derive(Debug)]
+pub struct VfsDirEntry {
+    pub name: OsString,
+    pub ino: u64,
+    pub file_type: VfsFileType,
+}
+
+/// The flags that `open` passes to the virtual filesystem.
+#[derive(Debug)]
+pub struct OpenFlags {
+    pub readable: bool,
+    pub writable: bool,
+    pub append: bool,
+    pub truncate: bool,
+    pub create: bool,
+    /// Fail if the file already exists. Only meaningful together with `create`.
+    pub exclusive: bool,
+    /// Fail if the last component of the path is a symlink.
+    pub nofollow: bool,
+}
+
+#[derive(Debug)]
+enum NodeKind {
+    File(Vec<u8>),
+    Dir(BTreeMap<OsString, Rc<Node>>),
+    Symlink(PathBuf),
+}
+
+/// A file, directory or symlink. Open files keep their node alive, so a file that is removed while
+/// it is open can still be used through its file descriptions.
+#[derive(Debug)]
+struct Node {
+    ino: u64,
+    kind: RefCell<NodeKind>,
+}
+
+impl Node {
+    fn file_type(&self) -> VfsFileType {
+        match &*self.kind.borrow() {
+            NodeKind::File(_) => VfsFileType::File,
+            NodeKind::Dir(_) => VfsFileType::Dir,
+            NodeKind::Symlink(_) => VfsFileType::Symlink,
+        }
+    }
+
+    fn metadata(&self) -> VfsMetadata {
+        let size = match &*self.kind.borrow() {
+            NodeKind::File(data) => data.len(),
+            NodeKind::Dir(_) => 0,
+            NodeKind::Symlink(target) => target.as_os_str().len(),
+        };
+        VfsMetadata { file_type: self.file_type(), size: size.try_into().unwrap() }
+    }
+
+    /// Looks up `name` in this directory.
+    fn child(&self, name: &OsString) -> Result<Option<Rc<Node>>, IoError> {
+        match &*self.kind.borrow() {
+            NodeKind::Dir(entries) => Ok(entries.get(name).cloned()),
+            _ => Err(LibcError("ENOTDIR")),
+        }
+    }
+
+    fn is_empty_dir(&self) -> bool {
+        matches!(&*self.kind.borrow(), NodeKind::Dir(entries) if entries.is_empty())
+    }
+
+    /// Adds or replaces an entry of this directory. The caller has checked that it is a directory.
+    fn insert(&self, name: OsString, node: Rc<Node>) {
+        let NodeKind::Dir(entries) = &mut *self.kind.borrow_mut() else {
+            panic!("inserting into a file that is not a directory")
+        };
+        entries.insert(name, node);
+    }
+
+    /// Removes an entry of this directory. The caller has checked that it is a directory.
+    fn remove(&self, name: &OsString) {
+        let NodeKind::Dir(entries) = &mut *self.kind.borrow_mut() else {
+            panic!("removing from a file that is not a directory")
+        };
+        entries.remove(name);
+    }
+}
+
+/// A step of a path that is being resolved.
+enum Step {
+    Root,
+    Parent,
+    Name(OsString),
+}
+
+/// Pushes the steps of `path` so that popping them yields them in order.
+fn push_steps(pending: &mut Vec<Step>, path: &Path) {
+    let start = pending.len();
+    for component in path.components() {
+        match component {
+            Component::RootDir => pending.push(Step::Root),
+            Component::ParentDir => pending.push(Step::Parent),
+            Component::Normal(name) => pending.push(Step::Name(name.to_owned())),
+            Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    pending[start..].reverse();
+}
+
+/// The virtual filesystem.
+#[derive(Debug)]
+pub struct Vfs {
+    /// The directories from the root to the current working directory, both included.
+    cwd: Vec<Rc<Node>>,
+    /// The inode number to be used by the next node.
+    next_ino: u64,
+}
+
+impl Vfs {
+    pub fn new(snapshot: &VfsSnapshot) -> Vfs {
+        let mut vfs = Vfs { cwd: Vec::new(), next_ino: 1 };
+        let mut entries = vfs.build_dir(&snapshot.root);
+        entries.entry("tmp".into()).or_insert_with(|| vfs.new_node(NodeKind::Dir(BTreeMap::new())));
+        let root = vfs.new_node(NodeKind::Dir(entries));
+        vfs.cwd.push(root);
+        vfs
+    }
+
+    fn build_dir(
+        &mut self,
+        entries: &BTreeMap<OsString, SnapshotNode>,
+    ) -> BTreeMap<OsString, Rc<Node>> {
+        entries
+            .iter()
+            .map(|(name, node)| {
+                let kind = match node {
+                    SnapshotNode::File(data) => NodeKind::File(data.clone()),
+                    SnapshotNode::Dir(entries) => NodeKind::Dir(self.build_dir(entries)),
+                    SnapshotNode::Symlink(target) => NodeKind::Symlink(target.clone()),
+                };
+                (name.clone(), self.new_node(kind))
+            })
+            .collect()
+    }
+
+    fn new_node(&mut self, kind: NodeKind) -> Rc<Node> {
+        let ino = self.next_ino;
+        self.next_ino = self.next_ino.strict_add(1);
+        Rc::new(Node { ino, kind: RefCell::new(kind) })
+    }
+
+    /// Resolves `path` to the directories leading to it, starting at the root and ending with the
+    /// node `path` refers to. If `follow` is false and `path` refers to a symlink, the symlink
+    /// itself is returned.
+    fn resolve(&self, path: &Path, follow: bool) -> Result<Vec<Rc<Node>>, IoError> {
+        if path.as_os_str().is_empty() {
+            return Err(LibcError("ENOENT"));
+        }
+        let mut chain = self.cwd.clone();
+        let mut pending = Vec::new();
+        push_steps(&mut pending, path);
+        let mut symlinks = 0;
+        while let Some(step) = pending.pop() {
+            let name = match step {
+                Step::Root => {
+                    chain.truncate(1);
+                    continue;
+                }
+                Step::Parent => {
+                    if chain.len() > 1 {
+                        chain.pop();
+                    }
+                    continue;
+                }
+                Step::Name(name) => name,
+            };
+            let node = chain.last().unwrap().child(&name)?.ok_or(LibcError("ENOENT"))?;
+            let target = match &*node.kind.borrow() {
+                NodeKind::Symlink(target) if follow || !pending.is_empty() => Some(target.clone()),
+                _ => None,
+            };
+            match target {
+                Some(target) => {
+                    if symlinks == MAX_SYMLINKS {
+                        return Err(LibcError("ELOOP"));
+                    }
+                    symlinks = symlinks.strict_add(1);
+                    push_steps(&mut pending, &target);
+                }
+                None => chain.push(node),
+            }
+        }
+        Ok(chain)
+    }
+
+    /// Resolves all but the last component of `path`, which must be a directory, and returns it
+    /// together with the name of the last component.
+    fn resolve_parent(&self, path: &Path) -> Result<(Vec<Rc<Node>>, OsString), IoError> {
+        let Some(name) = path.file_name() else {
+            return Err(LibcError("EINVAL"));
+        };
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let chain = self.resolve(parent, true)?;
+        if chain.last().unwrap().file_type() != VfsFileType::Dir {
+            return Err(LibcError("ENOTDIR"));
+        }
+        Ok((chain, name.to_owned()))
+    }
+
+    /// Returns the absolute path of the last node of `chain`.
+    fn path_of(chain: &[Rc<Node>]) -> Result<PathBuf, IoError> {
+        let mut path = PathBuf::from("/");
+        for (dir, node) in iter::zip(chain, &chain[1..]) {
+            let NodeKind::Dir(entries) = &*dir.kind.borrow() else {
+                return Err(LibcError("ENOTDIR"));
+            };
+            // The directory might have been removed since `chain` was resolved.
+            let (name, _) = entries
+                .iter()
+                .find(|(_, entry)| Rc::ptr_eq(entry, node))
+                .ok_or(LibcError("ENOENT"))?;
+            path.push(name);
+        }
+        Ok(path)
+    }
+
+    pub fn open(&mut self, path: &Path, flags: &OpenFlags) -> Result<VfsFile, IoError> {
+        let node = match self.resolve(path, !flags.nofollow) {
+            Ok(_) if flags.create && flags.exclusive => return Err(LibcError("EEXIST")),
+            Ok(chain) => chain.last().unwrap().clone(),
+            Err(LibcError("ENOENT")) if flags.create => {
+                let (chain, name) = self.resolve_parent(path)?;
+                let dir = chain.last().unwrap();
+                // This is a dangling symlink, which we do not create the target of.
+                if dir.child(&name)?.is_some() {
+                    return Err(LibcError("EEXIST"));
+                }
+                let node = self.new_node(NodeKind::File(Vec::new()));
+                dir.insert(name, node.clone());
+                node
+            }
+            Err(err) => return Err(err),
+        };
+        match &mut *node.kind.borrow_mut() {
+            NodeKind::File(data) =>
+                if flags.truncate && flags.writable {
+                    data.clear();
+                },
+            NodeKind::Dir(_) =>
+                if flags.writable {
+                    return Err(LibcError("EISDIR"));
+                },
+            // We only get here for the last component of the path with `O_NOFOLLOW`.
+            NodeKind::Symlink(_) => return Err(LibcError("ELOOP")),
+        }
+        Ok(VfsFile {
+            node,
+            offset: Cell::new(0),
+            readable: flags.readable,
+            writable: flags.writable,
+            append: flags.append,
+        })
+    }
+
+    pub fn metadata(&self, path: &Path, follow: bool) -> Result<VfsMetadata, IoError> {
+        Ok(self.resolve(path, follow)?.last().unwrap().metadata())
+    }
+
+    pub fn unlink(&mut self, path: &Path) -> Result<(), IoError> {
+        let (chain, name) = self.resolve_parent(path)?;
+        let dir = chain.last().unwrap();
+        let node = dir.child(&name)?.ok_or(LibcError("ENOENT"))?;
+        if node.file_type() == VfsFileType::Dir {
+            return Err(LibcError("EISDIR"));
+        }
+        dir.remove(&name);
+        Ok(())
+    }
+
+    pub fn symlink(&mut self, target: &Path, linkpath: &Path) -> Result<(), IoError> {
+        let (chain, name) = self.resolve_parent(linkpath)?;
+        let dir = chain.last().unwrap();
+        if dir.child(&name)?.is_some() {
+            return Err(LibcError("EEXIST"));
+        }
+        let node = self.new_node(NodeKind::Symlink(target.to_owned()));
+        dir.insert(name, node);
+        Ok(())
+    }
+
+    pub fn rename(&mut self, oldpath: &Path, newpath: &Path) -> Result<(), IoError> {
+        let (old_chain, old_name) = self.resolve_parent(oldpath)?;
+        let (new_chain, new_name) = self.resolve_parent(newpath)?;
+        let old_dir = old_chain.last().unwrap();
+        let new_dir = new_chain.last().unwrap();
+        let node = old_dir.child(&old_name)?.ok_or(LibcError("ENOENT"))?;
+        // A directory cannot become a subdirectory of itself.
+        if new_chain.iter().any(|dir| Rc::ptr_eq(dir, &node)) {
+            return Err(LibcError("EINVAL"));
+        }
+        if let Some(existing) = new_dir.child(&new_name)? {
+            if Rc::ptr_eq(&existing, &node) {
+                return Ok(());
+            }
+            match (node.file_type(), existing.file_type()) {
+                (VfsFileType::Dir, VfsFileType::Dir) =>
+                    if !existing.is_empty_dir() {
+                        return Err(LibcError("ENOTEMPTY"));
+                    },
+                (VfsFileType::Dir, _) => return Err(LibcError("ENOTDIR")),
+                (_, VfsFileType::Dir) => return Err(LibcError("EISDIR")),
+                _ => {}
+            }
+        }
+        old_dir.remove(&old_name);
+        new_dir.insert(new_name, node);
+        Ok(())
+    }
+
+    pub fn mkdir(&mut self, path: &Path) -> Result<(), IoError> {
+        if self.resolve(path, false).is_ok() {
+            return Err(LibcError("EEXIST"));
+        }
+        let (chain, name) = self.resolve_parent(path)?;
+        let node = self.new_node(NodeKind::Dir(BTreeMap::new()));
+        chain.last().unwrap().insert(name, node);
+        Ok(())
+    }
+
+    pub fn rmdir(&mut self, path: &Path) -> Result<(), IoError> {
+        let (chain, name) = self.resolve_parent(path)?;
+        let dir = chain.last().unwrap();
+        let node = dir.child(&name)?.ok_or(LibcError("ENOENT"))?;
+        if node.file_type() != VfsFileType::Dir {
+            return Err(LibcError("ENOTDIR"));
+        }
+        if !node.is_empty_dir() {
+            return Err(LibcError("ENOTEMPTY"));
+        }
+        dir.remove(&name);
+        Ok(())
+    }
+
+    /// Returns the entries of a directory, without `.` and `..`.
+    pub fn read_dir(&self, path: &Path) -> Result<Vec<VfsDirEntry>, IoError> {
+        let chain = self.resolve(path, true)?;
+        let NodeKind::Dir(entries) = &*chain.last().unwrap().kind.borrow() else {
+            return Err(LibcError("ENOTDIR"));
+        };
+        Ok(entries
+            .iter()
+            .map(|(name, node)| {
+                VfsDirEntry { name: name.clone(), ino: node.ino, file_type: node.file_type() }
+            })
+            .collect())
+    }
+
+    pub fn read_link(&self, path: &Path) -> Result<PathBuf, IoError> {
+        let chain = self.resolve(path, false)?;
+        match &*chain.last().unwrap().kind.borrow() {
+            NodeKind::Symlink(target) => Ok(target.clone()),
+            _ => Err(LibcError("EINVAL")),
+        }
+    }
+
+    pub fn canonicalize(&self, path: &Path) -> Result<PathBuf, IoError> {
+        Vfs::path_of(&self.resolve(path, true)?)
+    }
+
+    pub fn current_dir(&self) -> Result<PathBuf, IoError> {
+        Vfs::path_of(&self.cwd)
+    }
+
+    pub fn set_current_dir(&mut self, path: &Path) -> Result<(), IoError> {
+        let chain = self.resolve(path, true)?;
+        if chain.last().unwrap().file_type() != VfsFileType::Dir {
+            return Err(LibcError("ENOTDIR"));
+        }
+        self.cwd = chain;
+        Ok(())
+    }
+}
+
+/// A file or directory of the virtual filesystem that was opened with `open`.
+#[derive(Debug)]
+pub struct VfsFile {
+    node: Rc<Node>,
+    offset: Cell<u64>,
+    readable: bool,
+    writable: bool,
+    append: bool,
+}
+
+impl VfsFile {
+    pub fn metadata(&self) -> VfsMetadata {
+        self.node.metadata()
+    }
+
+    pub fn set_len(&self, len: u64) -> Result<(), IoError> {
+        let NodeKind::File(data) = &mut *self.node.kind.borrow_mut() else {
+            return Err(LibcError("EINVAL"));
+        };
+        if !self.writable {
+            return Err(LibcError("EINVAL"));
+        }
+        data.resize(len.try_into().map_err(|_| LibcError("EFBIG"))?, 0);
+        Ok(())
+    }
+
+    fn read_at(&self, offset: u64, len: usize) -> Result<Vec<u8>, IoError> {
+        if !self.readable {
+            return Err(LibcError("EBADF"));
+        }
+        let NodeKind::File(data) = &*self.node.kind.borrow() else {
+            return Err(LibcError("EISDIR"));
+        };
+        let start = usize::try_from(offset).unwrap_or(usize::MAX).min(data.len());
+        let end = start.saturating_add(len).min(data.len());
+        Ok(data[start..end].to_vec())
+    }
+
+    /// Writes `bytes` at `offset`, or at the end of the file if it was opened with `O_APPEND`.
+    /// Returns the offset after the written bytes.
+    fn write_at(&self, offset: u64, bytes: &[u8]) -> Result<u64, IoError> {
+        if !self.writable {
+            return Err(LibcError("EBADF"));
+        }
+        let NodeKind::File(data) = &mut *self.node.kind.borrow_mut() else {
+            return Err(LibcError("EISDIR"));
+        };
+        let start = if self.append {
+            data.len()
+        } else {
+            usize::try_from(offset).map_err(|_| LibcError("EFBIG"))?
+        };
+        let end = start.checked_add(bytes.len()).ok_or(LibcError("EFBIG"))?;
+        if data.len() < end {
+            data.resize(end, 0);
+        }
+        data[start..end].copy_from_slice(bytes);
+        Ok(end.try_into().unwrap())
+    }
+}
+
+impl FileDescription for VfsFile {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn read<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let result = match self.read_at(self.offset.get(), len) {
+            Ok(bytes) => {
+                ecx.write_bytes_ptr(ptr, bytes.iter().copied())?;
+                self.offset.set(self.offset.get().strict_add(bytes.len().try_into().unwrap()));
+                Ok(bytes.len())
+            }
+            Err(err) => Err(err),
+        };
+        finish.call(ecx, result)
+    }
+
+    fn write<'tcx>(
+        self: FileDescriptionRef<Self>,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let bytes = ecx.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?;
+        let result = self.write_at(self.offset.get(), bytes).map(|offset| {
+            self.offset.set(offset);
+            len
+        });
+        finish.call(ecx, result)
+    }
+
+    fn seek<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        offset: SeekFrom,
+    ) -> InterpResult<'tcx, io::Result<u64>> {
+        let new_offset = match offset {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.offset.get().checked_add_signed(delta),
+            SeekFrom::End(delta) => self.metadata().size.checked_add_signed(delta),
+        };
+        let Some(new_offset) = new_offset else {
+            return interp_ok(Err(io::ErrorKind::InvalidInput.into()));
+        };
+        self.offset.set(new_offset);
+        interp_ok(Ok(new_offset))
+    }
+
+    fn close<'tcx>(
+        self,
+        _communicate_allowed: bool,
+        _ecx: &mut MiriInterpCx<'tcx>,
+    ) -> InterpResult<'tcx, io::Result<()>> {
+        interp_ok(Ok(()))
+    }
+
+    fn as_unix(&self) -> &dyn UnixFileDescription {
+        self
+    }
+}
+
+impl UnixFileDescription for VfsFile {
+    fn pread<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        offset: u64,
+        ptr: Pointer,
+        len: usize,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let result = match self.read_at(offset, len) {
+            Ok(bytes) => {
+                ecx.write_bytes_ptr(ptr, bytes.iter().copied())?;
+                Ok(bytes.len())
+            }
+            Err(err) => Err(err),
+        };
+        finish.call(ecx, result)
+    }
+
+    fn pwrite<'tcx>(
+        &self,
+        _communicate_allowed: bool,
+        ptr: Pointer,
+        len: usize,
+        offset: u64,
+        ecx: &mut MiriInterpCx<'tcx>,
+        finish: DynMachineCallback<'tcx, Result<usize, IoError>>,
+    ) -> InterpResult<'tcx> {
+        let bytes = ecx.read_bytes_ptr_strip_provenance(ptr, Size::from_bytes(len))?;
+        let result = self.write_at(offset, bytes).map(|_| len);
+        finish.call(ecx, result)
+    }
+}
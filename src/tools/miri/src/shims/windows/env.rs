@@ -155,7 +155,7 @@ fn GetCurrentDirectoryW(
         }
 
         // If we cannot get the current directory, we return 0
-        match env::current_dir() {
+        match this.machine.recorder.host(env::current_dir) {
             Ok(cwd) => {
                 // This can in fact return 0. It is up to the caller to set last_error to 0
                 // beforehand and check it afterwards to exclude that case.
@@ -0,0 +1,33 @@
+//@revisions: explore replay
+//@ignore-target: windows # No pthreads on Windows
+//@[explore]compile-flags: -Zmiri-explore-schedules
+//@[replay]compile-flags: -Zmiri-fixed-schedule=0:1,1:0
+
+// Two threads increment a counter with a load and a store, so an increment is lost if one thread
+// runs between the load and the store of the other. That needs a preemption right after the load of
+// the second thread, which random preemption rarely does, but exploring the schedules finds it
+// after trying two schedules that do not fail.
+
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering::SeqCst;
+use std::{mem, ptr};
+
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+extern "C" fn increment(_null: *mut libc::c_void) -> *mut libc::c_void {
+    let value = COUNTER.load(SeqCst);
+    COUNTER.store(value + 1, SeqCst);
+    ptr::null_mut()
+}
+
+fn main() {
+    unsafe {
+        let mut native: libc::pthread_t = mem::zeroed();
+        assert_eq!(libc::pthread_create(&mut native, ptr::null(), increment, ptr::null_mut()), 0);
+        increment(ptr::null_mut());
+        assert_eq!(libc::pthread_join(native, ptr::null_mut()), 0);
+        if COUNTER.load(SeqCst) != 2 {
+            std::hint::unreachable_unchecked(); //~ ERROR: entering unreachable code
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::fs;
+
+use crate::miri;
+
+const PROGRAM: &str = "tests/integration/programs/coverage.rs";
+
+/// The line of the coverage program that ends with `marker`.
+fn line_of(marker: &str) -> usize {
+    let source = fs::read_to_string(PROGRAM).unwrap();
+    source.lines().position(|line| line.ends_with(marker)).unwrap() + 1
+}
+
+/// Runs the coverage program and returns the report Miri wrote to a file called `name`.
+fn report(name: &str) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(name);
+    let output = miri("coverage.rs").arg(format!("-Zmiri-coverage={}", path.display())).output();
+    let output = output.unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn lcov() {
+    let report = report("coverage.lcov");
+    assert!(report.starts_with(&format!("TN:\nSF:{PROGRAM}\n")), "{report}");
+    for (count, function) in [(1, "main"), (1, "double_or_zero"), (0, "zero"), (0, "unused")] {
+        assert!(report.contains(&format!("\nFNDA:{count},{function}\n")), "{report}");
+    }
+    assert!(report.contains("\nFNF:4\nFNH:2\n"), "{report}");
+    assert!(report.contains(&format!("\nDA:{},1\n", line_of("// covered"))), "{report}");
+    assert!(report.contains(&format!("\nDA:{},0\n", line_of("// not covered"))), "{report}");
+    assert!(report.ends_with("end_of_record\n"), "{report}");
+}
+
+#[test]
+fn llvm_cov_json() {
+    let report = report("coverage.json");
+    assert!(report.starts_with(r#"{"type":"llvm.coverage.json.export","#), "{report}");
+    for (count, function) in [(1, "main"), (1, "double_or_zero"), (0, "zero"), (0, "unused")] {
+        let entry = format!(r#"{{"name":"{function}","count":{count},"regions":["#);
+        assert!(report.contains(&entry), "{report}");
+    }
+    // Each line with code gets a segment `[line, column, count, ...]`.
+    let segment = |line, count| report.contains(&format!("[{line},1,{count},true,true,false]"));
+    assert!(segment(line_of("// covered"), 1), "{report}");
+    assert!(segment(line_of("// not covered"), 0), "{report}");
+    assert!(report.contains(r#""functions":{"count":4,"covered":2,"percent":50}}"#), "{report}");
+}
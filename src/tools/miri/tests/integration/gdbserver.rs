@@ -0,0 +1,132 @@
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::process::Child;
+use std::thread;
+use std::time::Duration;
+
+use crate::miri;
+
+/// A client of the GDB remote protocol.
+struct Client {
+    stream: UnixStream,
+}
+
+impl Client {
+    /// Connects to the server of `miri` at `socket`, which Miri creates once it compiled the
+    /// program.
+    fn connect(socket: &Path, miri: &mut Child) -> Client {
+        loop {
+            if let Ok(stream) = UnixStream::connect(socket) {
+                stream.set_read_timeout(Some(Duration::from_secs(60))).unwrap();
+                return Client { stream };
+            }
+            if let Some(status) = miri.try_wait().unwrap() {
+                panic!("Miri exited with {status} before a debugger connected");
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    fn byte(&mut self) -> u8 {
+        let mut byte = [0];
+        self.stream.read_exact(&mut byte).unwrap();
+        byte[0]
+    }
+
+    /// Sends a packet and returns the first reply.
+    fn request(&mut self, packet: &str) -> String {
+        let checksum = packet.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        write!(self.stream, "${packet}#{checksum:02x}").unwrap();
+        self.reply()
+    }
+
+    /// Reads and acknowledges a packet of the server, skipping its acknowledgments.
+    fn reply(&mut self) -> String {
+        while self.byte() != b'$' {}
+        let mut data = Vec::new();
+        loop {
+            match self.byte() {
+                b'#' => break,
+                b'}' => {
+                    let escaped = self.byte();
+                    data.push(escaped ^ 0x20);
+                }
+                byte => data.push(byte),
+            }
+        }
+        // The server computes the checksum correctly; the unit tests of the server check that.
+        self.byte();
+        self.byte();
+        self.stream.write_all(b"+").unwrap();
+        String::from_utf8(data).unwrap()
+    }
+
+    /// Runs a monitor command and returns its output.
+    fn monitor(&mut self, command: &str) -> String {
+        let command: String = command.bytes().map(|byte| format!("{byte:02x}")).collect();
+        let mut reply = self.request(&format!("qRcmd,{command}"));
+        let mut output = Vec::new();
+        while let Some(hex) = reply.strip_prefix('O') {
+            for i in (0..hex.len()).step_by(2) {
+                output.push(u8::from_str_radix(&hex[i..i + 2], 16).unwrap());
+            }
+            reply = self.reply();
+        }
+        assert_eq!(reply, "OK");
+        String::from_utf8(output).unwrap()
+    }
+}
+
+#[test]
+fn breakpoints_steps_and_locals() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("gdb.sock");
+    let mut miri =
+        miri("gdbserver.rs").arg(format!("-Zmiri-gdbserver={}", socket.display())).spawn().unwrap();
+    let mut client = Client::connect(&socket, &mut miri);
+
+    // The program stops before its first step.
+    assert!(client.request("?").starts_with("T05"));
+    assert_eq!(client.monitor("break add"), "Breakpoint 1 at add\n");
+    assert!(client.request("c").starts_with("T05"));
+    let backtrace = client.monitor("backtrace");
+    let frames: Vec<_> = backtrace.lines().collect();
+    assert!(
+        frames[0].contains(" in add at tests/integration/programs/gdbserver.rs:"),
+        "{backtrace}"
+    );
+    assert!(
+        frames[1].contains(" in main at tests/integration/programs/gdbserver.rs:7:"),
+        "{backtrace}"
+    );
+    let locals = client.monitor("locals");
+    assert!(locals.contains("a = 40") && locals.contains("b = 2"), "{locals}");
+
+    // The program counter is the only register, in the byte order of the target.
+    let pc = frames[0].strip_prefix("#0 0x").unwrap().split(' ').next().unwrap();
+    let pc = u64::from_str_radix(pc, 16).unwrap();
+    let register = u64::from_str_radix(&client.request("g"), 16).unwrap();
+    assert!(register == pc || register == pc.swap_bytes(), "{register:#x} is not {pc:#x}");
+
+    // Step through `add` until it computed the sum.
+    let mut steps = 0;
+    while !client.monitor("locals").contains("sum = 42") {
+        assert!(steps < 100, "the sum was not computed");
+        assert!(client.request("s").starts_with("T05"));
+        steps += 1;
+    }
+
+    // Stop at the start of `add` again with a breakpoint at its program counter, as set by
+    // `break *ADDRESS`.
+    assert_eq!(client.monitor("delete"), "Deleted all breakpoints.\n");
+    assert_eq!(client.request(&format!("Z0,{pc:x},1")), "OK");
+    assert!(client.request("c").starts_with("T05"));
+    let locals = client.monitor("locals");
+    assert!(locals.contains("a = 42") && locals.contains("b = 1"), "{locals}");
+    assert_eq!(client.request(&format!("z0,{pc:x},1")), "OK");
+
+    // The program runs to completion.
+    assert_eq!(client.request("c"), "W00");
+    assert!(miri.wait().unwrap().success());
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::fs;
+
+use regex::Regex;
+
+use crate::miri;
+
+/// An allocation site of a DHAT profile.
+struct Site {
+    /// The numeric fields, like `tb` for the total bytes.
+    fields: HashMap<String, u64>,
+    /// The run-length encoded per-byte access counts, if any.
+    accesses: Option<String>,
+    /// The descriptions of the frames of the call stack.
+    frames: Vec<String>,
+}
+
+/// Runs the heap profile program and parses the sites of the profile Miri wrote.
+fn profile() -> (String, Vec<Site>) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dhat.json");
+    let output = miri("heap_profile.rs")
+        .arg(format!("-Zmiri-heap-profile={}", path.display()))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let profile = fs::read_to_string(path).unwrap();
+
+    let ftbl = profile.split_once(r#""ftbl":["#).unwrap().1;
+    let frames: Vec<String> = Regex::new(r#""((?:[^"\\]|\\.)*)""#)
+        .unwrap()
+        .captures_iter(ftbl)
+        .map(|captures| captures[1].to_owned())
+        .collect();
+    assert_eq!(frames[0], "[root]");
+    let field = Regex::new(r#""(\w+)":(\d+)"#).unwrap();
+    let sites = Regex::new(r#"\{"tb":[^{}]*?"fs":\[([\d,]*)\]\}"#)
+        .unwrap()
+        .captures_iter(&profile)
+        .map(|site| {
+            Site {
+                fields: field
+                    .captures_iter(&site[0])
+                    .map(|field| (field[1].to_owned(), field[2].parse().unwrap()))
+                    .collect(),
+                accesses: site[0]
+                    .split_once(r#""acc":["#)
+                    .map(|(_, acc)| acc.split_once(']').unwrap().0.to_owned()),
+                frames: site[1]
+                    .split(',')
+                    .map(|i| frames[i.parse::<usize>().unwrap()].clone())
+                    .collect(),
+            }
+        })
+        .collect();
+    (profile, sites)
+}
+
+/// The site that allocates on the line of the heap profile program ending with `marker`.
+fn site<'a>(sites: &'a [Site], marker: &str) -> &'a Site {
+    let source = fs::read_to_string("tests/integration/programs/heap_profile.rs").unwrap();
+    let line = source.lines().position(|line| line.ends_with(marker)).unwrap() + 1;
+    let location = format!("programs/heap_profile.rs:{line}:");
+    let mut matching =
+        sites.iter().filter(|site| site.frames.iter().any(|f| f.contains(&location)));
+    let site = matching.next().unwrap_or_else(|| panic!("no site allocates at {location}"));
+    assert!(matching.next().is_none(), "more than one site allocates at {location}");
+    site
+}
+
+#[test]
+fn dhat() {
+    let (profile, sites) = profile();
+    assert!(profile.starts_with(r#"{"dhatFileVersion":2,"mode":"rust-heap","#), "{profile}");
+
+    let counted = site(&sites, "// site: counted");
+    for (name, value) in [
+        ("tb", 8),
+        ("tbk", 1),
+        ("mb", 8),
+        ("mbk", 1),
+        ("gb", 8),
+        ("gbk", 1),
+        ("eb", 0),
+        ("ebk", 0),
+        ("rb", 2),
+        ("wb", 4),
+    ] {
+        assert_eq!(counted.fields[name], value, "`{name}` of the counted site");
+    }
+    // Bytes 0 and 1 were written and read, bytes 2 and 3 only written.
+    assert_eq!(counted.accesses.as_deref(), Some("-2,2,-2,1,-4,0"));
+
+    // This allocation is freed before the global peak.
+    let before_peak = site(&sites, "// site: before peak");
+    assert_eq!(before_peak.fields["tb"], 10_000);
+    assert_eq!(before_peak.fields["mb"], 10_000);
+    assert_eq!(before_peak.fields["gb"], 0);
+    assert_eq!(before_peak.fields["gbk"], 0);
+    assert_eq!(before_peak.accesses, None);
+
+    // This allocation makes the global peak.
+    let at_peak = site(&sites, "// site: at peak");
+    assert_eq!(at_peak.fields["tb"], 100_000);
+    assert_eq!(at_peak.fields["gb"], 100_000);
+    assert_eq!(at_peak.fields["gbk"], 1);
+    assert_eq!(at_peak.fields["eb"], 0);
+}
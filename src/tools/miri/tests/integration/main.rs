@@ -0,0 +1,30 @@
+//! Tests of the Miri features whose results the ui tests cannot check, like the files Miri writes
+//! and the debugger protocol. Each test runs Miri on a program in `tests/integration/programs`.
+
+mod coverage;
+#[cfg(unix)]
+mod gdbserver;
+mod heap_profile;
+mod recording;
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn miri_path() -> PathBuf {
+    PathBuf::from(env::var("MIRI").unwrap_or_else(|_| env!("CARGO_BIN_EXE_miri").into()))
+}
+
+/// A command that runs `program`, a file in `tests/integration/programs`, with Miri.
+pub fn miri(program: &str) -> Command {
+    let sysroot =
+        env::var("MIRI_SYSROOT").expect("MIRI_SYSROOT must be set to run the integration tests");
+    let mut cmd = Command::new(miri_path());
+    cmd.arg(Path::new("tests/integration/programs").join(program))
+        .arg("--edition=2021")
+        .arg(format!("--sysroot={sysroot}"));
+    if let Ok(target) = env::var("MIRI_TEST_TARGET") {
+        cmd.arg(format!("--target={target}"));
+    }
+    cmd
+}
@@ -0,0 +1,20 @@
+fn double_or_zero(x: u32) -> u32 {
+    if x > 1 {
+        x * 2 // covered
+    } else {
+        zero()
+    }
+}
+
+fn zero() -> u32 {
+    0 // not covered
+}
+
+#[allow(dead_code)]
+fn unused() {
+    println!("never printed");
+}
+
+fn main() {
+    assert_eq!(double_or_zero(3), 6);
+}
@@ -0,0 +1,9 @@
+fn add(a: u32, b: u32) -> u32 {
+    let sum = a + b;
+    sum
+}
+
+fn main() {
+    let x = add(40, 2);
+    assert_eq!(add(x, 1), 43);
+}
@@ -0,0 +1,18 @@
+use std::alloc::{Layout, alloc, dealloc};
+
+fn main() {
+    unsafe {
+        let layout = Layout::new::<[u16; 4]>();
+        let counted = alloc(layout).cast::<u16>(); // site: counted
+        counted.write(1);
+        counted.add(1).write(2);
+        assert_eq!(counted.read(), 1);
+
+        let before_peak = Vec::<u8>::with_capacity(10_000); // site: before peak
+        drop(before_peak);
+        let at_peak = Vec::<u8>::with_capacity(100_000); // site: at peak
+        drop(at_peak);
+
+        dealloc(counted.cast(), layout);
+    }
+}
@@ -0,0 +1,46 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::io::Read;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::Relaxed;
+use std::time::{Instant, SystemTime};
+use std::{env, fs, thread};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn main() {
+    let start = Instant::now();
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input).unwrap();
+    let file = fs::read_to_string(env::var("RECORDING_FILE").unwrap()).unwrap();
+
+    let handles: Vec<_> = (0..3)
+        .map(|i| {
+            thread::spawn(move || {
+                let mut seen = Vec::new();
+                for _ in 0..5 {
+                    let mut current = COUNTER.load(Relaxed);
+                    while let Err(actual) =
+                        COUNTER.compare_exchange_weak(current, current + 1, Relaxed, Relaxed)
+                    {
+                        current = actual;
+                    }
+                    seen.push(current);
+                }
+                (i, seen)
+            })
+        })
+        .collect();
+    for handle in handles {
+        let (i, seen) = handle.join().unwrap();
+        println!("thread {i}: {seen:?}");
+    }
+
+    let boxed = Box::new(0u8);
+    println!("address: {:#x}", &*boxed as *const u8 as usize);
+    println!("time: {:?}", SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap());
+    println!("elapsed: {:?}", start.elapsed());
+    println!("random: {:#x}", RandomState::new().hash_one(0));
+    println!("input: {}", input.trim());
+    println!("file: {}", file.trim());
+}
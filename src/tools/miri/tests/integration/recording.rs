@@ -0,0 +1,89 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Output, Stdio};
+
+use crate::miri;
+
+/// Runs the recording program with `flags`, feeding it `input` on stdin and letting it read
+/// `file`.
+fn run(flags: &[&str], input: &str, file: &Path) -> Output {
+    let mut child = miri("recording.rs")
+        .args(flags)
+        .arg("-Zmiri-disable-isolation")
+        .env("RECORDING_FILE", file)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    output
+}
+
+/// Records an execution that reads "recorded" from stdin and `file`.
+fn record(recording: &Path, file: &Path) -> Output {
+    fs::write(file, "recorded").unwrap();
+    let flags = ["-Zmiri-seed=7", &format!("-Zmiri-record={}", recording.display())];
+    run(&flags, "recorded", file)
+}
+
+/// Replays an execution, with "replayed" on stdin and in `file`.
+fn replay(recording: &Path, file: &Path) -> Output {
+    fs::write(file, "replayed").unwrap();
+    run(&[&format!("-Zmiri-replay={}", recording.display())], "replayed", file)
+}
+
+#[test]
+fn replay_follows_the_recording() {
+    let dir = tempfile::tempdir().unwrap();
+    let recording = dir.path().join("recording");
+    let file = dir.path().join("file");
+    let recorded = record(&recording, &file);
+    let contents = fs::read_to_string(&recording).unwrap();
+    assert!(contents.starts_with("miri-recording 1\nseed 7\n"), "{contents}");
+    let events = ["alloc-address ", "monotonic-clock ", "system-time ", "host-random ", "host ok "];
+    for event in events {
+        assert!(contents.lines().any(|line| line.starts_with(event)), "{event}: {contents}");
+    }
+
+    // The replay uses the recorded clocks, randomness, stdin and file contents instead of the
+    // ones of the host.
+    let replayed = replay(&recording, &file);
+    let stdout = String::from_utf8(replayed.stdout).unwrap();
+    assert!(stdout.ends_with("input: recorded\nfile: recorded\n"), "{stdout}");
+    assert_eq!(stdout, String::from_utf8(recorded.stdout).unwrap());
+    let stderr = String::from_utf8(replayed.stderr).unwrap();
+    assert!(!stderr.contains("diverged"), "{stderr}");
+}
+
+#[test]
+fn replay_detects_divergence() {
+    let dir = tempfile::tempdir().unwrap();
+    let recording = dir.path().join("recording");
+    let file = dir.path().join("file");
+    record(&recording, &file);
+
+    // Move the last recorded allocation, so the replay cannot match it.
+    let contents = fs::read_to_string(&recording).unwrap();
+    let mut lines: Vec<String> = contents.lines().map(str::to_owned).collect();
+    let last = lines.iter().rposition(|line| line.starts_with("alloc-address ")).unwrap();
+    let addr: u64 = lines[last]["alloc-address ".len()..].parse().unwrap();
+    lines[last] = format!("alloc-address {}", addr + 4096);
+    let decisions = lines
+        .iter()
+        .filter(|line| {
+            !line.starts_with("miri-recording ")
+                && !line.starts_with("seed ")
+                && !line.starts_with("env ")
+        })
+        .count();
+    fs::write(&recording, lines.join("\n") + "\n").unwrap();
+
+    let stderr = String::from_utf8(replay(&recording, &file).stderr).unwrap();
+    let expected = format!("of its {decisions} decisions");
+    assert!(stderr.contains("the execution diverged from the recording after"), "{stderr}");
+    assert!(stderr.contains(&expected), "{stderr}");
+}
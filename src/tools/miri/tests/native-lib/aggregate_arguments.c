@@ -0,0 +1,123 @@
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+// See comments in build_native_lib()
+#define EXPORT __attribute__((visibility("default")))
+
+/* Test: test_pass_struct */
+
+typedef struct PassMe {
+  int32_t value;
+  int64_t other_value;
+} PassMe;
+
+EXPORT int64_t pass_struct(const PassMe pass_me) {
+  return pass_me.value + pass_me.other_value;
+}
+
+/* Test: test_pass_struct_complex */
+
+typedef struct Part1 {
+  uint16_t high;
+  uint16_t low;
+} Part1;
+
+typedef struct Part2 {
+  uint32_t bits;
+} Part2;
+
+typedef struct ComplexStruct {
+  Part1 part_1;
+  Part2 part_2;
+  uint32_t part_3;
+} ComplexStruct;
+
+EXPORT int32_t pass_struct_complex(const ComplexStruct complex, uint16_t high, uint16_t low, uint32_t bits) {
+  if (complex.part_1.high == high && complex.part_1.low == low
+      && complex.part_2.bits == bits
+      && complex.part_3 == bits)
+    return 0;
+  else {
+    return 1;
+  }
+}
+
+/* Test: test_pass_struct_with_ptr */
+
+typedef struct Buffer {
+  uint8_t *data;
+  size_t len;
+} Buffer;
+
+EXPORT void fill_buffer(Buffer buffer, uint8_t val) {
+  for (size_t i = 0; i < buffer.len; i++) {
+    buffer.data[i] = val;
+  }
+}
+
+/* Test: test_pass_struct_with_floats */
+
+typedef struct Mixed {
+  float x;
+  double y;
+  bool negate;
+} Mixed;
+
+EXPORT int64_t sum_mixed(const Mixed mixed) {
+  double sum = mixed.x + mixed.y;
+  return (int64_t)(mixed.negate ? -sum : sum);
+}
+
+/* Test: test_return_struct */
+
+typedef struct Point {
+  int32_t x;
+  int32_t y;
+} Point;
+
+EXPORT Point make_point(int32_t x, int32_t y) {
+  Point point = { x, y };
+  return point;
+}
+
+/* Test: test_return_struct_with_floats */
+
+typedef struct Floats {
+  float x;
+  float y;
+  double z;
+} Floats;
+
+EXPORT Floats scale_floats(Floats floats, float factor) {
+  floats.x *= factor;
+  floats.y *= factor;
+  floats.z *= factor;
+  return floats;
+}
+
+/* Test: test_return_large_struct */
+
+typedef struct Values {
+  uint64_t a;
+  uint8_t b;
+  uint16_t c;
+} Values;
+
+typedef struct Large {
+  Point start;
+  Values values;
+  Point end;
+} Large;
+
+EXPORT Large make_large(int32_t x) {
+  Large large = { { x, x + 1 }, { UINT64_MAX, x + 2, x + 3 }, { x + 4, x + 5 } };
+  return large;
+}
+
+/* Test: test_return_struct_with_ptr */
+
+EXPORT Buffer tail_of_buffer(Buffer buffer) {
+  Buffer tail = { buffer.data + 1, buffer.len - 1 };
+  return tail;
+}
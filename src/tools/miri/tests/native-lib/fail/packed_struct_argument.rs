@@ -0,0 +1,20 @@
+// Only works on Unix targets
+//@ignore-target: windows wasm
+//@only-on-host
+
+#[repr(C, packed)]
+struct PassMe {
+    value: i32,
+    other_value: i64,
+}
+
+extern "C" {
+    fn pass_struct(s: PassMe) -> i64;
+}
+
+fn main() {
+    let pass_me = PassMe { value: 42, other_value: 1337 };
+    unsafe {
+        pass_struct(pass_me); //~ ERROR: unsupported operation: only scalar and `repr(C)` struct argument types are supported for native calls, and the structs cannot be `packed` or have an `align`
+    }
+}
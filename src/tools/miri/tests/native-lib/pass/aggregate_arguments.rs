@@ -0,0 +1,184 @@
+// Only works on Unix targets
+//@ignore-target: windows wasm
+//@only-on-host
+//@compile-flags: -Zmiri-permissive-provenance
+
+fn main() {
+    test_pass_struct();
+    test_pass_struct_complex();
+    test_pass_struct_with_ptr();
+    test_pass_struct_with_floats();
+    test_return_struct();
+    test_return_struct_with_floats();
+    test_return_large_struct();
+    test_return_struct_with_ptr();
+}
+
+/// Test passing a basic struct as an argument.
+fn test_pass_struct() {
+    #[repr(C)]
+    struct PassMe {
+        value: i32,
+        other_value: i64,
+    }
+
+    extern "C" {
+        fn pass_struct(s: PassMe) -> i64;
+    }
+
+    let pass_me = PassMe { value: 42, other_value: 1337 };
+    assert_eq!(unsafe { pass_struct(pass_me) }, 42 + 1337);
+}
+
+/// Test passing a more complex struct as an argument.
+fn test_pass_struct_complex() {
+    #[repr(C)]
+    struct Part1 {
+        high: u16,
+        low: u16,
+    }
+    #[repr(C)]
+    struct Part2 {
+        bits: u32,
+    }
+    #[repr(C)]
+    struct ComplexStruct {
+        part_1: Part1,
+        part_2: Part2,
+        part_3: u32,
+    }
+
+    extern "C" {
+        fn pass_struct_complex(s: ComplexStruct, high: u16, low: u16, bits: u32) -> i32;
+    }
+
+    let high = 0xabcd;
+    let low = 0xef01;
+    let bits = 0xabcdef01;
+
+    let complex =
+        ComplexStruct { part_1: Part1 { high, low }, part_2: Part2 { bits }, part_3: bits };
+    assert_eq!(unsafe { pass_struct_complex(complex, high, low, bits) }, 0);
+}
+
+/// Test passing a struct containing a pointer to a buffer that native code writes to.
+fn test_pass_struct_with_ptr() {
+    #[repr(C)]
+    struct Buffer {
+        data: *mut u8,
+        len: usize,
+    }
+
+    extern "C" {
+        fn fill_buffer(buffer: Buffer, val: u8);
+    }
+
+    let mut data = [0u8; 8];
+    let buffer = Buffer { data: data.as_mut_ptr(), len: data.len() };
+    unsafe { fill_buffer(buffer, 7) };
+    assert_eq!(data, [7; 8]);
+}
+
+/// Test passing a struct with floating-point and boolean fields.
+fn test_pass_struct_with_floats() {
+    #[repr(C)]
+    struct Mixed {
+        x: f32,
+        y: f64,
+        negate: bool,
+    }
+
+    extern "C" {
+        fn sum_mixed(s: Mixed) -> i64;
+    }
+
+    let mixed = Mixed { x: 1.5, y: 40.5, negate: true };
+    assert_eq!(unsafe { sum_mixed(mixed) }, -42);
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+/// Test returning a small struct, which is returned in registers.
+fn test_return_struct() {
+    extern "C" {
+        fn make_point(x: i32, y: i32) -> Point;
+    }
+
+    assert_eq!(unsafe { make_point(-3, 4) }, Point { x: -3, y: 4 });
+}
+
+/// Test returning a struct with floating-point fields, which are returned in float registers.
+fn test_return_struct_with_floats() {
+    #[repr(C)]
+    #[derive(Debug, PartialEq)]
+    struct Floats {
+        x: f32,
+        y: f32,
+        z: f64,
+    }
+
+    extern "C" {
+        fn scale_floats(s: Floats, factor: f32) -> Floats;
+    }
+
+    let floats = Floats { x: 1.0, y: -2.5, z: 0.25 };
+    assert_eq!(unsafe { scale_floats(floats, 2.0) }, Floats { x: 2.0, y: -5.0, z: 0.5 });
+}
+
+/// Test returning a struct too large for registers, which the caller provides memory for.
+fn test_return_large_struct() {
+    #[repr(C)]
+    #[derive(Debug, PartialEq)]
+    struct Large {
+        start: Point,
+        values: Values,
+        end: Point,
+    }
+    #[repr(C)]
+    #[derive(Debug, PartialEq)]
+    struct Values {
+        a: u64,
+        b: u8,
+        c: u16,
+    }
+
+    extern "C" {
+        fn make_large(x: i32) -> Large;
+    }
+
+    let large = unsafe { make_large(7) };
+    assert_eq!(
+        large,
+        Large {
+            start: Point { x: 7, y: 8 },
+            values: Values { a: u64::MAX, b: 9, c: 10 },
+            end: Point { x: 11, y: 12 },
+        }
+    );
+}
+
+/// Test returning a struct containing a pointer, which native code may use to access exposed
+/// memory.
+fn test_return_struct_with_ptr() {
+    #[repr(C)]
+    struct Buffer {
+        data: *mut u8,
+        len: usize,
+    }
+
+    extern "C" {
+        fn tail_of_buffer(buffer: Buffer) -> Buffer;
+    }
+
+    let mut data = [1u8, 2, 3, 4];
+    let buffer = Buffer { data: data.as_mut_ptr(), len: data.len() };
+    let tail = unsafe { tail_of_buffer(buffer) };
+    assert_eq!(tail.len, 3);
+    unsafe { *tail.data = 42 };
+    assert_eq!(data, [1, 42, 3, 4]);
+}
@@ -0,0 +1,229 @@
+//@ignore-target: windows # No libc poll on Windows
+//@compile-flags: -Zmiri-preemption-rate=0
+
+use std::mem::MaybeUninit;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn main() {
+    test_poll_ready();
+    test_poll_timeout();
+    test_poll_blocking();
+    test_poll_invalid_fds();
+    test_poll_hangup();
+    test_select_ready();
+    test_select_timeout();
+    test_select_blocking();
+    test_select_errors();
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    test_ppoll();
+}
+
+fn errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap()
+}
+
+fn socketpair() -> [i32; 2] {
+    let mut fds = [-1, -1];
+    let res = unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) };
+    assert_eq!(res, 0);
+    fds
+}
+
+fn write_byte(fd: i32) {
+    let res = unsafe { libc::write(fd, [1u8].as_ptr().cast(), 1) };
+    assert_eq!(res, 1);
+}
+
+fn pollfd(fd: i32, events: i16) -> libc::pollfd {
+    libc::pollfd { fd, events, revents: 0 }
+}
+
+fn test_poll_ready() {
+    let fds = socketpair();
+    let mut pfds = [pollfd(fds[0], libc::POLLIN | libc::POLLOUT), pollfd(fds[1], libc::POLLIN)];
+    // Nothing to read yet, but there is space to write.
+    let res = unsafe { libc::poll(pfds.as_mut_ptr(), 2, 0) };
+    assert_eq!(res, 1);
+    assert_eq!(pfds[0].revents, libc::POLLOUT);
+    assert_eq!(pfds[1].revents, 0);
+
+    write_byte(fds[0]);
+    let res = unsafe { libc::poll(pfds.as_mut_ptr(), 2, 0) };
+    assert_eq!(res, 2);
+    assert_eq!(pfds[0].revents, libc::POLLOUT);
+    assert_eq!(pfds[1].revents, libc::POLLIN);
+
+    // File descriptions without readiness tracking, like stdout, are always ready.
+    let mut pfds = [pollfd(libc::STDOUT_FILENO, libc::POLLOUT)];
+    let res = unsafe { libc::poll(pfds.as_mut_ptr(), 1, -1) };
+    assert_eq!(res, 1);
+    assert_eq!(pfds[0].revents, libc::POLLOUT);
+}
+
+fn test_poll_timeout() {
+    let fds = socketpair();
+    let mut pfds = [pollfd(fds[0], libc::POLLIN)];
+    let start = Instant::now();
+    let res = unsafe { libc::poll(pfds.as_mut_ptr(), 1, 100) };
+    assert_eq!(res, 0);
+    assert_eq!(pfds[0].revents, 0);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+fn test_poll_blocking() {
+    let fds = socketpair();
+    let thread = thread::spawn(move || {
+        let mut pfds = [pollfd(fds[0], libc::POLLIN)];
+        let res = unsafe { libc::poll(pfds.as_mut_ptr(), 1, -1) };
+        assert_eq!(res, 1);
+        assert_eq!(pfds[0].revents, libc::POLLIN);
+    });
+    // Let the other thread block first.
+    thread::yield_now();
+    write_byte(fds[1]);
+    thread.join().unwrap();
+}
+
+fn test_poll_invalid_fds() {
+    let fds = socketpair();
+    write_byte(fds[1]);
+    let mut pfds = [pollfd(-1, libc::POLLIN), pollfd(1000, libc::POLLIN), pollfd(fds[0], 0)];
+    let res = unsafe { libc::poll(pfds.as_mut_ptr(), 3, 0) };
+    // Negative fds are ignored, unknown ones are reported, and unrequested events are not.
+    assert_eq!(res, 1);
+    assert_eq!(pfds[0].revents, 0);
+    assert_eq!(pfds[1].revents, libc::POLLNVAL);
+    assert_eq!(pfds[2].revents, 0);
+}
+
+fn test_poll_hangup() {
+    let fds = socketpair();
+    let thread = thread::spawn(move || {
+        // Hang-ups are reported even if not requested.
+        let mut pfds = [pollfd(fds[0], 0)];
+        let res = unsafe { libc::poll(pfds.as_mut_ptr(), 1, -1) };
+        assert_eq!(res, 1);
+        assert!(pfds[0].revents & libc::POLLHUP != 0);
+    });
+    thread::yield_now();
+    let res = unsafe { libc::close(fds[1]) };
+    assert_eq!(res, 0);
+    thread.join().unwrap();
+}
+
+fn fd_set(fds: &[i32]) -> libc::fd_set {
+    let mut set = MaybeUninit::uninit();
+    unsafe {
+        libc::FD_ZERO(set.as_mut_ptr());
+        for &fd in fds {
+            libc::FD_SET(fd, set.as_mut_ptr());
+        }
+        set.assume_init()
+    }
+}
+
+fn test_select_ready() {
+    let fds = socketpair();
+    write_byte(fds[1]);
+    let mut readfds = fd_set(&[fds[0], fds[1]]);
+    let mut writefds = fd_set(&[fds[1]]);
+    let mut exceptfds = fd_set(&[fds[0]]);
+    let res = unsafe {
+        libc::select(fds[1] + 1, &mut readfds, &mut writefds, &mut exceptfds, std::ptr::null_mut())
+    };
+    assert_eq!(res, 2);
+    unsafe {
+        assert!(libc::FD_ISSET(fds[0], &readfds));
+        assert!(!libc::FD_ISSET(fds[1], &readfds));
+        assert!(libc::FD_ISSET(fds[1], &writefds));
+        assert!(!libc::FD_ISSET(fds[0], &exceptfds));
+    }
+}
+
+fn test_select_timeout() {
+    let fds = socketpair();
+    let mut readfds = fd_set(&[fds[0]]);
+    let mut timeout = libc::timeval { tv_sec: 0, tv_usec: 100_000 };
+    let start = Instant::now();
+    let res = unsafe {
+        libc::select(
+            fds[0] + 1,
+            &mut readfds,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut timeout,
+        )
+    };
+    assert_eq!(res, 0);
+    assert!(!unsafe { libc::FD_ISSET(fds[0], &readfds) });
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+fn test_select_blocking() {
+    let fds = socketpair();
+    let thread = thread::spawn(move || {
+        let mut readfds = fd_set(&[fds[0]]);
+        let res = unsafe {
+            libc::select(
+                fds[0] + 1,
+                &mut readfds,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        assert_eq!(res, 1);
+        assert!(unsafe { libc::FD_ISSET(fds[0], &readfds) });
+    });
+    thread::yield_now();
+    write_byte(fds[1]);
+    thread.join().unwrap();
+}
+
+fn test_select_errors() {
+    let mut readfds = fd_set(&[900]);
+    let res = unsafe {
+        libc::select(
+            901,
+            &mut readfds,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EBADF);
+
+    let mut timeout = libc::timeval { tv_sec: -1, tv_usec: 0 };
+    let res = unsafe {
+        libc::select(
+            0,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut timeout,
+        )
+    };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EINVAL);
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn test_ppoll() {
+    let fds = socketpair();
+    let mut pfds = [pollfd(fds[0], libc::POLLIN)];
+    let timeout = libc::timespec { tv_sec: 0, tv_nsec: 50_000_000 };
+    let res = unsafe { libc::ppoll(pfds.as_mut_ptr(), 1, &timeout, std::ptr::null()) };
+    assert_eq!(res, 0);
+
+    write_byte(fds[1]);
+    let res = unsafe { libc::ppoll(pfds.as_mut_ptr(), 1, std::ptr::null(), std::ptr::null()) };
+    assert_eq!(res, 1);
+    assert_eq!(pfds[0].revents, libc::POLLIN);
+
+    let timeout = libc::timespec { tv_sec: 0, tv_nsec: 2_000_000_000 };
+    let res = unsafe { libc::ppoll(pfds.as_mut_ptr(), 1, &timeout, std::ptr::null()) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EINVAL);
+}
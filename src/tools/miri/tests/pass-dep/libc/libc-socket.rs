@@ -0,0 +1,254 @@
+//@ignore-target: windows # No libc sockets on Windows
+//@revisions: isolation no_isolation
+//@[no_isolation]compile-flags: -Zmiri-disable-isolation
+//@compile-flags: -Zmiri-preemption-rate=0
+
+use std::mem::{MaybeUninit, size_of};
+use std::thread;
+
+fn main() {
+    test_stream();
+    test_blocking_accept();
+    test_datagram();
+    test_errors();
+    #[cfg(target_os = "linux")]
+    {
+        test_nonblocking();
+        test_epoll();
+    }
+}
+
+fn errno() -> i32 {
+    std::io::Error::last_os_error().raw_os_error().unwrap()
+}
+
+fn loopback(port: u16) -> libc::sockaddr_in {
+    let mut addr: libc::sockaddr_in = unsafe { MaybeUninit::zeroed().assume_init() };
+    addr.sin_family = libc::AF_INET as libc::sa_family_t;
+    addr.sin_port = port.to_be();
+    addr.sin_addr.s_addr = u32::from_be_bytes([127, 0, 0, 1]).to_be();
+    addr
+}
+
+fn bind(fd: i32, addr: &libc::sockaddr_in) -> i32 {
+    unsafe {
+        libc::bind(
+            fd,
+            (addr as *const libc::sockaddr_in).cast(),
+            size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        )
+    }
+}
+
+fn connect(fd: i32, addr: &libc::sockaddr_in) -> i32 {
+    unsafe {
+        libc::connect(
+            fd,
+            (addr as *const libc::sockaddr_in).cast(),
+            size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        )
+    }
+}
+
+fn local_addr(fd: i32) -> libc::sockaddr_in {
+    let mut addr = MaybeUninit::<libc::sockaddr_in>::uninit();
+    let mut len = size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    let res = unsafe { libc::getsockname(fd, addr.as_mut_ptr().cast(), &mut len) };
+    assert_eq!(res, 0);
+    assert_eq!(len as usize, size_of::<libc::sockaddr_in>());
+    unsafe { addr.assume_init() }
+}
+
+/// Returns a listening socket and its address.
+fn listener() -> (i32, libc::sockaddr_in) {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert!(fd >= 0);
+    assert_eq!(bind(fd, &loopback(0)), 0);
+    assert_eq!(unsafe { libc::listen(fd, 1) }, 0);
+    (fd, local_addr(fd))
+}
+
+fn test_stream() {
+    let (listener, addr) = listener();
+    assert_eq!(addr.sin_family, libc::AF_INET as libc::sa_family_t);
+    assert_eq!(addr.sin_addr.s_addr, loopback(0).sin_addr.s_addr);
+    assert_ne!(addr.sin_port, 0);
+
+    let client = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(connect(client, &addr), 0);
+    let mut peer = MaybeUninit::<libc::sockaddr_in>::uninit();
+    let mut len = size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    let server = unsafe { libc::accept(listener, peer.as_mut_ptr().cast(), &mut len) };
+    assert!(server >= 0);
+    let peer = unsafe { peer.assume_init() };
+    assert_eq!(peer.sin_port, local_addr(client).sin_port);
+
+    // `send`/`recv` and `write`/`read` are interchangeable.
+    let res = unsafe { libc::send(client, b"abc".as_ptr().cast(), 3, 0) };
+    assert_eq!(res, 3);
+    let res = unsafe { libc::write(client, b"de".as_ptr().cast(), 2) };
+    assert_eq!(res, 2);
+    let mut buf = [0u8; 8];
+    let res = unsafe { libc::recv(server, buf.as_mut_ptr().cast(), 2, libc::MSG_PEEK) };
+    assert_eq!(res, 2);
+    assert_eq!(&buf[..2], b"ab");
+    let res = unsafe { libc::read(server, buf.as_mut_ptr().cast(), buf.len()) };
+    assert_eq!(res, 5);
+    assert_eq!(&buf[..5], b"abcde");
+
+    // Closing one end makes reads on the other end return EOF.
+    assert_eq!(unsafe { libc::close(server) }, 0);
+    let res = unsafe { libc::recv(client, buf.as_mut_ptr().cast(), buf.len(), 0) };
+    assert_eq!(res, 0);
+
+    assert_eq!(unsafe { libc::close(client) }, 0);
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+}
+
+#[cfg(target_os = "linux")]
+fn test_nonblocking() {
+    let listener =
+        unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM | libc::SOCK_NONBLOCK, 0) };
+    assert!(listener >= 0);
+    assert_eq!(bind(listener, &loopback(0)), 0);
+    assert_eq!(unsafe { libc::listen(listener, 1) }, 0);
+    let addr = local_addr(listener);
+
+    // Nothing to accept yet.
+    let res = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EAGAIN);
+
+    let client = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(connect(client, &addr), 0);
+    let server = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+    assert!(server >= 0);
+
+    // Nothing to read yet.
+    let mut buf = [0u8; 4];
+    let res = unsafe { libc::recv(server, buf.as_mut_ptr().cast(), buf.len(), libc::MSG_DONTWAIT) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EAGAIN);
+
+    // There are no asynchronous errors.
+    let mut error = -1;
+    let mut len = size_of::<i32>() as libc::socklen_t;
+    let res = unsafe {
+        libc::getsockopt(
+            client,
+            libc::SOL_SOCKET,
+            libc::SO_ERROR,
+            (&mut error as *mut i32).cast(),
+            &mut len,
+        )
+    };
+    assert_eq!(res, 0);
+    assert_eq!(error, 0);
+}
+
+fn test_blocking_accept() {
+    let (listener, addr) = listener();
+    let thread = thread::spawn(move || {
+        // This blocks until the main thread connects.
+        let server = unsafe { libc::accept(listener, std::ptr::null_mut(), std::ptr::null_mut()) };
+        assert!(server >= 0);
+        let mut buf = [0u8; 3];
+        // This blocks until the main thread sends something.
+        let res = unsafe { libc::recv(server, buf.as_mut_ptr().cast(), buf.len(), 0) };
+        assert_eq!(res, 3);
+        assert_eq!(&buf, b"abc");
+    });
+    thread::yield_now();
+    let client = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(connect(client, &addr), 0);
+    thread::yield_now();
+    let res = unsafe { libc::send(client, b"abc".as_ptr().cast(), 3, 0) };
+    assert_eq!(res, 3);
+    thread.join().unwrap();
+}
+
+fn test_datagram() {
+    let a = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+    let b = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM, 0) };
+    assert_eq!(bind(b, &loopback(0)), 0);
+    let b_addr = local_addr(b);
+
+    // Sending from an unbound socket binds it to an ephemeral port.
+    let res = unsafe {
+        libc::sendto(
+            a,
+            b"hello".as_ptr().cast(),
+            5,
+            0,
+            (&b_addr as *const libc::sockaddr_in).cast(),
+            size_of::<libc::sockaddr_in>() as libc::socklen_t,
+        )
+    };
+    assert_eq!(res, 5);
+    assert_ne!(local_addr(a).sin_port, 0);
+
+    let mut buf = [0u8; 8];
+    let mut from = MaybeUninit::<libc::sockaddr_in>::uninit();
+    let mut len = size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    let res = unsafe {
+        libc::recvfrom(b, buf.as_mut_ptr().cast(), buf.len(), 0, from.as_mut_ptr().cast(), &mut len)
+    };
+    assert_eq!(res, 5);
+    assert_eq!(&buf[..5], b"hello");
+    let from = unsafe { from.assume_init() };
+    assert_eq!(from.sin_port, local_addr(a).sin_port);
+    assert_eq!(from.sin_addr.s_addr, loopback(0).sin_addr.s_addr);
+
+    // Without a default destination, `send` fails.
+    let res = unsafe { libc::send(a, b"x".as_ptr().cast(), 1, 0) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::EDESTADDRREQ);
+}
+
+fn test_errors() {
+    // Connecting to a port nobody listens on.
+    let (listener, addr) = listener();
+    assert_eq!(unsafe { libc::close(listener) }, 0);
+    let client = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(connect(client, &addr), -1);
+    assert_eq!(errno(), libc::ECONNREFUSED);
+
+    // Binding to an address in use.
+    let (_listener, addr) = listener();
+    assert_eq!(bind(client, &addr), -1);
+    assert_eq!(errno(), libc::EADDRINUSE);
+
+    // Receiving on a socket that isn't connected.
+    let mut buf = [0u8; 1];
+    let res = unsafe { libc::recv(client, buf.as_mut_ptr().cast(), 1, 0) };
+    assert_eq!(res, -1);
+    assert_eq!(errno(), libc::ENOTCONN);
+
+    // Socket operations on something that isn't a socket.
+    let mut fds = [-1, -1];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    assert_eq!(unsafe { libc::listen(fds[0], 1) }, -1);
+    assert_eq!(errno(), libc::ENOTSOCK);
+}
+
+#[cfg(target_os = "linux")]
+fn test_epoll() {
+    let (listener, addr) = listener();
+    let epfd = unsafe { libc::epoll_create1(0) };
+    assert_ne!(epfd, -1);
+    let mut ev = libc::epoll_event { events: libc::EPOLLIN as u32, u64: listener as u64 };
+    let res = unsafe { libc::epoll_ctl(epfd, libc::EPOLL_CTL_ADD, listener, &mut ev) };
+    assert_eq!(res, 0);
+
+    let mut events = [libc::epoll_event { events: 0, u64: 0 }; 1];
+    let res = unsafe { libc::epoll_wait(epfd, events.as_mut_ptr(), 1, 0) };
+    assert_eq!(res, 0);
+
+    // A pending connection makes the listener readable.
+    let client = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
+    assert_eq!(connect(client, &addr), 0);
+    let res = unsafe { libc::epoll_wait(epfd, events.as_mut_ptr(), 1, 0) };
+    assert_eq!(res, 1);
+    let event = events[0];
+    assert_eq!((event.events, event.u64), (libc::EPOLLIN as u32, listener as u64));
+}
@@ -0,0 +1,127 @@
+//@ignore-target: windows # No socket emulation on Windows
+//@revisions: isolation no_isolation
+//@[no_isolation]compile-flags: -Zmiri-disable-isolation
+// The tests wait for each other with blocking calls.
+//@compile-flags: -Zmiri-preemption-rate=0
+
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::thread;
+
+fn main() {
+    test_tcp();
+    test_tcp_threaded();
+    test_tcp_refused();
+    test_tcp_ipv6();
+    test_udp();
+    test_addr_in_use();
+}
+
+fn test_tcp() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    assert!(addr.ip().is_loopback());
+    assert_ne!(addr.port(), 0);
+
+    let mut client = TcpStream::connect(addr).unwrap();
+    let (mut server, client_addr) = listener.accept().unwrap();
+    assert_eq!(client.local_addr().unwrap(), client_addr);
+    assert_eq!(client.peer_addr().unwrap(), addr);
+    assert_eq!(server.peer_addr().unwrap(), client_addr);
+    client.set_nodelay(true).unwrap();
+
+    client.write_all(b"hello").unwrap();
+    let mut buf = [0; 5];
+    assert_eq!(server.peek(&mut buf).unwrap(), 5);
+    server.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+
+    server.write_all(b"world").unwrap();
+    drop(server);
+    let mut buf = Vec::new();
+    client.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, b"world");
+    // The peer is gone. A host socket only notices that once the peer rejected a write.
+    #[cfg(isolation)]
+    assert_eq!(client.write(b"!").unwrap_err().kind(), ErrorKind::BrokenPipe);
+}
+
+fn test_tcp_threaded() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+        // This blocks until the client connects.
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 4];
+        stream.read_exact(&mut buf).unwrap();
+        stream.write_all(&buf).unwrap();
+    });
+
+    thread::yield_now();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"ping").unwrap();
+    let mut buf = [0; 4];
+    // This blocks until the server echoes the message.
+    stream.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"ping");
+    server.join().unwrap();
+}
+
+fn test_tcp_refused() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    assert_eq!(TcpStream::connect(addr).unwrap_err().kind(), ErrorKind::ConnectionRefused);
+}
+
+fn test_tcp_ipv6() {
+    let listener = TcpListener::bind("[::1]:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    assert!(addr.is_ipv6());
+
+    let mut client = TcpStream::connect(addr).unwrap();
+    let (mut server, _) = listener.accept().unwrap();
+    client.write_all(b"hello").unwrap();
+    let mut buf = [0; 5];
+    server.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"hello");
+}
+
+fn test_udp() {
+    let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let a_addr = a.local_addr().unwrap();
+    let b_addr = b.local_addr().unwrap();
+
+    assert_eq!(a.send_to(b"first", b_addr).unwrap(), 5);
+    assert_eq!(a.send_to(b"second", b_addr).unwrap(), 6);
+    let mut buf = [0; 16];
+    // Datagrams are received one at a time, in order.
+    let (len, from) = b.recv_from(&mut buf).unwrap();
+    assert_eq!((&buf[..len], from), (&b"first"[..], a_addr));
+    let (len, from) = b.peek_from(&mut buf).unwrap();
+    assert_eq!((&buf[..len], from), (&b"second"[..], a_addr));
+    // Parts of a datagram that don't fit are discarded.
+    let mut small = [0; 3];
+    assert_eq!(b.recv(&mut small).unwrap(), 3);
+    assert_eq!(&small, b"sec");
+
+    b.connect(a_addr).unwrap();
+    b.send(b"reply").unwrap();
+    let (len, from) = a.recv_from(&mut buf).unwrap();
+    assert_eq!((&buf[..len], from), (&b"reply"[..], b_addr));
+
+    // Datagrams to an address nobody is bound to are lost.
+    let nobody = SocketAddr::from((Ipv4Addr::LOCALHOST, 1));
+    assert_eq!(a.send_to(b"lost", nobody).unwrap(), 4);
+}
+
+fn test_addr_in_use() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    assert_eq!(TcpListener::bind(addr).unwrap_err().kind(), ErrorKind::AddrInUse);
+    // A closed socket releases its address right away.
+    drop(listener);
+    TcpListener::bind(addr).unwrap();
+}
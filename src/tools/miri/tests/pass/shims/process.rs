@@ -0,0 +1,66 @@
+//@only-target: linux # std relies on `ioctl(FIONBIO)` to capture output, which is only supported there
+//@compile-flags: -Zmiri-disable-isolation
+
+use std::io::{ErrorKind, Write};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, Stdio};
+
+fn main() {
+    test_output();
+    test_status();
+    test_env_and_cwd();
+    test_stdin();
+    test_kill();
+    test_not_found();
+}
+
+fn test_output() {
+    let output = Command::new("echo").arg("hello").output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"hello\n");
+    assert!(output.stderr.is_empty());
+
+    // Capture both streams at once.
+    let output = Command::new("sh").args(["-c", "echo out; echo err >&2"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"out\n");
+    assert_eq!(output.stderr, b"err\n");
+}
+
+fn test_status() {
+    let status = Command::new("sh").args(["-c", "exit 3"]).status().unwrap();
+    assert_eq!(status.code(), Some(3));
+}
+
+fn test_env_and_cwd() {
+    let dir = std::env::temp_dir().canonicalize().unwrap();
+    let output = Command::new("sh")
+        .args(["-c", "echo $MIRI_TEST_VAR; pwd -P"])
+        .env("MIRI_TEST_VAR", "42")
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), format!("42\n{}\n", dir.display()));
+}
+
+fn test_stdin() {
+    let mut child =
+        Command::new("cat").stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().unwrap();
+    child.stdin.take().unwrap().write_all(b"piped").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"piped");
+}
+
+fn test_kill() {
+    let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+    assert!(child.try_wait().unwrap().is_none());
+    child.kill().unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.signal(), Some(9));
+}
+
+fn test_not_found() {
+    let err = Command::new("/this/program/does/not/exist").output().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
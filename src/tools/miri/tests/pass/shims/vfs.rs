@@ -0,0 +1,105 @@
+//@ignore-target: windows # File handling is not implemented yet
+//@compile-flags: -Zmiri-vfs=tests/pass/shims/vfs
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::symlink;
+use std::path::Path;
+
+fn main() {
+    test_snapshot();
+    test_files();
+    test_dirs();
+    test_symlinks();
+    test_cwd();
+    test_host_is_hidden();
+}
+
+fn test_snapshot() {
+    assert_eq!(fs::read_to_string("/hello.txt").unwrap(), "Hello, World!\n");
+    assert_eq!(fs::read_to_string("dir/nested.txt").unwrap(), "nested\n");
+    // `/tmp` always exists, and entries are listed in a deterministic order.
+    let names: Vec<_> =
+        fs::read_dir("/").unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(names, ["dir", "hello.txt", "tmp"]);
+    assert!(fs::metadata("/dir").unwrap().is_dir());
+    assert_eq!(fs::metadata("/hello.txt").unwrap().len(), 14);
+}
+
+fn test_files() {
+    let path = Path::new("/tmp/file.txt");
+    File::create(path).unwrap().write_all(b"abcdef").unwrap();
+
+    let mut file = OpenOptions::new().read(true).write(true).open(path).unwrap();
+    file.seek(SeekFrom::Start(2)).unwrap();
+    file.write_all(b"XY").unwrap();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "abXYef");
+    assert_eq!(file.metadata().unwrap().len(), 6);
+    file.set_len(2).unwrap();
+    file.sync_all().unwrap();
+    drop(file);
+
+    OpenOptions::new().append(true).open(path).unwrap().write_all(b"cd").unwrap();
+    assert_eq!(fs::read(path).unwrap(), b"abcd");
+    assert_eq!(File::create_new(path).unwrap_err().kind(), ErrorKind::AlreadyExists);
+
+    fs::rename(path, "/tmp/renamed.txt").unwrap();
+    assert_eq!(fs::metadata(path).unwrap_err().kind(), ErrorKind::NotFound);
+    fs::remove_file("/tmp/renamed.txt").unwrap();
+
+    // A file that is removed while it is open can still be used.
+    let mut file = File::options().read(true).write(true).create(true).open(path).unwrap();
+    fs::remove_file(path).unwrap();
+    file.write_all(b"gone").unwrap();
+    file.rewind().unwrap();
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "gone");
+
+    // Files of the snapshot can be modified, without affecting the host.
+    fs::write("/hello.txt", "changed").unwrap();
+    assert_eq!(fs::read_to_string("/hello.txt").unwrap(), "changed");
+}
+
+fn test_dirs() {
+    fs::create_dir_all("/tmp/a/b").unwrap();
+    assert!(fs::metadata("/tmp/a/b").unwrap().is_dir());
+    assert_eq!(fs::create_dir("/tmp/a").unwrap_err().kind(), ErrorKind::AlreadyExists);
+    fs::write("/tmp/a/b/file", "").unwrap();
+    assert_eq!(fs::remove_dir("/tmp/a/b").unwrap_err().kind(), ErrorKind::DirectoryNotEmpty);
+    let entries: Vec<_> = fs::read_dir("/tmp/a/b").unwrap().map(|entry| entry.unwrap()).collect();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].file_type().unwrap().is_file());
+    fs::remove_file("/tmp/a/b/file").unwrap();
+    fs::remove_dir("/tmp/a/b").unwrap();
+    fs::remove_dir("/tmp/a").unwrap();
+}
+
+fn test_symlinks() {
+    symlink("/dir/nested.txt", "/tmp/link").unwrap();
+    assert_eq!(fs::read_link("/tmp/link").unwrap(), Path::new("/dir/nested.txt"));
+    assert!(fs::symlink_metadata("/tmp/link").unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_to_string("/tmp/link").unwrap(), "nested\n");
+    assert_eq!(fs::canonicalize("/tmp/../tmp/link").unwrap(), Path::new("/dir/nested.txt"));
+    fs::remove_file("/tmp/link").unwrap();
+
+    symlink("loop", "/tmp/loop").unwrap();
+    assert!(File::open("/tmp/loop").is_err());
+    fs::remove_file("/tmp/loop").unwrap();
+}
+
+fn test_cwd() {
+    assert_eq!(std::env::current_dir().unwrap(), Path::new("/"));
+    std::env::set_current_dir("dir").unwrap();
+    assert_eq!(std::env::current_dir().unwrap(), Path::new("/dir"));
+    assert_eq!(fs::read_to_string("nested.txt").unwrap(), "nested\n");
+    std::env::set_current_dir("/").unwrap();
+}
+
+fn test_host_is_hidden() {
+    assert_eq!(fs::metadata("/etc").unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(File::open("Cargo.toml").unwrap_err().kind(), ErrorKind::NotFound);
+}
@@ -0,0 +1,56 @@
+// Checks that `-Zdiagnostic-baseline` records the lints of a compilation with
+// `-Zrecord-diagnostic-baseline`, suppresses exactly those lints afterwards, and reports baseline
+// entries that no longer match any lint.
+
+use run_make_support::{Rustc, rfs, rustc};
+
+const BEFORE: &str = "pub fn f(a: u8, b: u8) {}\n";
+
+fn compile(source: &str) -> Rustc {
+    rfs::write("lib.rs", source);
+    let mut rustc = rustc();
+    rustc.input("lib.rs").crate_type("lib").arg("-Zdiagnostic-baseline=baseline.txt");
+    rustc
+}
+
+fn main() {
+    // A missing baseline file is an error, rather than silently recording a new baseline.
+    compile(BEFORE)
+        .run_fail()
+        .assert_stderr_contains("failed to load diagnostic baseline `baseline.txt`");
+    assert!(!std::path::Path::new("baseline.txt").exists());
+
+    // When recording, the lints are reported and recorded.
+    compile(BEFORE)
+        .arg("-Zrecord-diagnostic-baseline")
+        .run()
+        .assert_stderr_contains("unused variable: `a`")
+        .assert_stderr_contains("unused variable: `b`");
+    let baseline = rfs::read_to_string("baseline.txt");
+    assert_eq!(
+        baseline.lines().filter(|line| line.starts_with("unused_variables\tlib.rs\t")).count(),
+        2
+    );
+
+    // The recorded lints are suppressed, even when they are denied, and even after the code
+    // around them moved.
+    compile(&format!("\n\n{BEFORE}"))
+        .arg("-Dwarnings")
+        .run()
+        .assert_stderr_not_contains("unused variable");
+
+    // New lints are still reported.
+    compile("pub fn f(a: u8, b: u8, c: u8) {}\n")
+        .arg("-Dwarnings")
+        .run_fail()
+        .assert_stderr_contains("unused variable: `c`")
+        .assert_stderr_not_contains("unused variable: `a`");
+
+    // Entries without a matching lint are reported as stale.
+    compile("pub fn f(_a: u8, b: u8) {}\n")
+        .run()
+        .assert_stderr_contains("1 stale entry in diagnostic baseline `baseline.txt`")
+        .assert_stderr_contains("no `unused_variables` lint matches `lib.rs`")
+        .assert_stderr_not_contains("unused variable");
+    assert_eq!(rfs::read_to_string("baseline.txt"), baseline);
+}
@@ -0,0 +1,17 @@
+#![crate_name = "doctest"]
+
+/// ```
+/// assert_eq!(doctest::f(), 1);
+/// ```
+pub fn f() -> u32 {
+    1
+}
+
+pub mod m {
+    /// ```standalone_crate
+    /// assert_eq!(doctest::m::g(), 2);
+    /// ```
+    pub fn g() -> u32 {
+        2
+    }
+}
@@ -0,0 +1,36 @@
+// ignore-tidy-linelength
+
+// Check that the results of both merged and standalone doctests in libtest's JSON output include
+// the location of the code block, the item it documents, and the execution time.
+
+//@ ignore-cross-compile
+
+use run_make_support::{assert_contains, cwd, rustc, rustdoc};
+
+fn main() {
+    let dep = cwd().join("libdoctest.rlib");
+    rustc().input("doctest.rs").crate_type("rlib").output(&dep).run();
+
+    let output = rustdoc()
+        .input("doctest.rs")
+        .arg("--test")
+        .edition("2024")
+        .arg("-Zunstable-options")
+        .arg("--test-args=-Zunstable-options")
+        .arg("--test-args=--format=json")
+        .arg("--test-args=--test-threads=1")
+        .extern_("doctest", dep.display().to_string())
+        .run();
+    let stdout = output.stdout_utf8();
+
+    // Merged into the 2024 edition doctest binary.
+    assert_contains(
+        &stdout,
+        r#"{ "type": "test", "name": "doctest.rs - f (line 3)", "event": "ok", "source_path": "doctest.rs", "start_line": 3, "item_path": "f", "exec_time": "#,
+    );
+    // Compiled on its own, and run by rustdoc.
+    assert_contains(
+        &stdout,
+        r#"{ "type": "test", "name": "doctest.rs - m::g (line 11)", "event": "ok", "source_path": "doctest.rs", "start_line": 11, "item_path": "m::g", "exec_time": "#,
+    );
+}
@@ -0,0 +1,64 @@
+// Checks that `-Zincremental-explain` reports the edited item as a changed input, and only blames
+// it for the codegen unit that contains it.
+
+//@ ignore-cross-compile
+
+use run_make_support::serde_json::{self, Value};
+use run_make_support::{rfs, rustc};
+
+const SOURCE: &str = "
+pub mod a {
+    pub fn f() -> u32 { 1 }
+}
+pub mod b {
+    pub fn g() -> u32 { 2 }
+}
+";
+
+/// Builds `lib.rs` with `extra_args`, and returns the reports printed by the compiler.
+fn build(extra_args: &[&str]) -> Vec<Value> {
+    let stderr = rustc()
+        .input("lib.rs")
+        .crate_type("lib")
+        .incremental("incr")
+        .arg("-Zincremental-explain")
+        .arg("-Zincremental-explain-format=json")
+        .args(extra_args)
+        .run()
+        .stderr_utf8();
+    stderr
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn items(inputs: &Value) -> Vec<&str> {
+    inputs.as_array().unwrap().iter().filter_map(|input| input["item"].as_str()).collect()
+}
+
+fn main() {
+    rfs::write("lib.rs", SOURCE);
+    let reports = build(&[]);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0]["previous_session"], "none");
+
+    rfs::write("lib.rs", SOURCE.replace("{ 1 }", "{ 3 }"));
+    let reports = build(&[]);
+    assert_eq!(reports.len(), 1);
+    let report = &reports[0];
+    assert_eq!(report["previous_session"], "loaded");
+    assert!(items(&report["changed_inputs"]).contains(&"lib::a::f"));
+    assert!(!items(&report["changed_inputs"]).contains(&"lib::b::g"));
+
+    let cgus = report["codegen_units"].as_array().unwrap();
+    let invalidated = cgus.iter().filter(|cgu| cgu["status"] == "invalidated").collect::<Vec<_>>();
+    assert_eq!(invalidated.len(), 1);
+    assert!(items(&invalidated[0]["changed_inputs"]).contains(&"lib::a::f"));
+    assert!(cgus.iter().any(|cgu| cgu["status"] == "reused"));
+
+    // Changing a tracked option discards the whole cache.
+    let reports = build(&["-Copt-level=1"]);
+    assert_eq!(reports[0]["previous_session"], "discarded");
+    assert_eq!(reports[0]["reason"], "the command-line arguments changed");
+}
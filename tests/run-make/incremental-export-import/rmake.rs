@@ -0,0 +1,40 @@
+// Checks that an incremental compilation cache exported with `-Zincremental-export` from one
+// checkout is reused by `-Zincremental-import` in a checkout at a different path, as long as both
+// remap their directory to the same path with `--remap-path-prefix`.
+
+//@ ignore-cross-compile
+
+use std::path::Path;
+
+use run_make_support::{assert_not_contains, cwd, rfs, rustc};
+
+/// Builds the checkout in `checkout`, and returns the compiler's stderr.
+fn build(checkout: &str, cache_option: &str, incr_state: &str) -> String {
+    let dir = cwd().join(checkout);
+    let cache_dir = cwd().join("exported");
+    rustc()
+        .current_dir(&dir)
+        .input("lib.rs")
+        .crate_type("lib")
+        .incremental(dir.join("incr"))
+        .remap_path_prefix(&dir, "/checkout")
+        .arg(format!("-Zincremental-{cache_option}={}", cache_dir.display()))
+        .arg(format!("-Zassert-incr-state={incr_state}"))
+        .run()
+        .stderr_utf8()
+}
+
+fn main() {
+    rfs::create_dir("ci");
+    rfs::write("ci/lib.rs", "pub fn answer() -> u32 { 42 }\n");
+    // The working directory is remapped, so exporting succeeds without warnings.
+    assert_not_contains(build("ci", "export", "not-loaded"), "warning");
+    assert!(Path::new("exported").read_dir().unwrap().next().is_some());
+
+    // A checkout at a different path, without a cache of its own, starts from the exported one.
+    rfs::create_dir("dev");
+    rfs::copy("ci/lib.rs", "dev/lib.rs");
+    build("dev", "import", "loaded");
+    // From then on, it uses its own cache.
+    build("dev", "import", "loaded");
+}
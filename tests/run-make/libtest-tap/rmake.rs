@@ -0,0 +1,34 @@
+// Check libtest's TAP output against snapshots.
+
+//@ ignore-cross-compile
+//@ needs-unwind (test file contains #[should_panic] test)
+
+use run_make_support::{cmd, diff, rustc};
+
+fn main() {
+    rustc().arg("--test").input("f.rs").run();
+
+    run_tests(&[], "output-default.tap");
+    run_tests(&["--show-output"], "output-stdout-success.tap");
+
+    let list_stdout =
+        cmd("./f").args(&["-Zunstable-options", "--format=tap", "--list"]).run().stdout_utf8();
+    diff().expected_file("output-list.tap").actual_text("stdout", list_stdout).run();
+}
+
+#[track_caller]
+fn run_tests(extra_args: &[&str], expected_file: &str) {
+    let cmd_out = cmd("./f")
+        .env("RUST_BACKTRACE", "0")
+        .args(&["-Zunstable-options", "--test-threads=1", "--format=tap"])
+        .args(extra_args)
+        .run_fail();
+    let test_stdout = &cmd_out.stdout_utf8();
+
+    diff()
+        .expected_file(expected_file)
+        .actual_text("stdout", test_stdout)
+        .normalize(r#"(?<prefix>duration_ms: )[0-9.]+"#, r#"${prefix}$$DURATION"#)
+        .normalize(r#"(?<prefix>finished in )[0-9.]+s"#, r#"${prefix}$$EXEC_TIME"#)
+        .run();
+}
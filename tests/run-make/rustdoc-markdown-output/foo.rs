@@ -0,0 +1,29 @@
+//! The crate docs, linking to [`Foo`].
+
+/// A struct.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() {
+/// let foo = foo::Foo { bar: 1 };
+/// # }
+/// ```
+pub struct Foo {
+    /// The field, see [`inner::baz`].
+    pub bar: u32,
+}
+
+impl Foo {
+    /// Makes a [`Foo`].
+    pub fn new() -> Self {
+        Foo { bar: 0 }
+    }
+}
+
+pub mod inner {
+    /// A function.
+    pub fn baz<T: Clone>(_: &[T]) -> Option<T> {
+        None
+    }
+}
@@ -0,0 +1,33 @@
+// This test verifies that `--output-format markdown` writes one Markdown page per module and item,
+// with their signatures, docs and impls, and with intra-doc links resolved to the other pages.
+
+use run_make_support::{assert_contains, assert_not_contains, path, rfs, rustdoc};
+
+fn main() {
+    let out_dir = "doc";
+    rustdoc()
+        .input("foo.rs")
+        .out_dir(out_dir)
+        .arg("-Zunstable-options")
+        .output_format("markdown")
+        .run();
+
+    let index = rfs::read_to_string(path(out_dir).join("foo/index.md"));
+    assert_contains(&index, "# Crate `foo`");
+    assert_contains(&index, "[`Foo`]: struct.Foo.md");
+    assert_contains(&index, "- [`Foo`](struct.Foo.md): A struct.");
+    assert_contains(&index, "- [`inner`](inner/index.md)");
+
+    let foo = rfs::read_to_string(path(out_dir).join("foo/struct.Foo.md"));
+    assert_contains(&foo, "pub struct Foo {\n    pub bar: u32,\n}");
+    assert_contains(&foo, "## Examples");
+    assert_contains(&foo, "```rust\nlet foo = foo::Foo { bar: 1 };\n```");
+    assert_not_contains(&foo, "fn main()");
+    assert_contains(&foo, "<a id=\"structfield.bar\"></a>");
+    assert_contains(&foo, "[`inner::baz`]: inner/fn.baz.md");
+    assert_contains(&foo, "### `impl Foo`");
+    assert_contains(&foo, "#### `pub fn new() -> Self`");
+
+    let baz = rfs::read_to_string(path(out_dir).join("foo/inner/fn.baz.md"));
+    assert_contains(&baz, "pub fn baz<T: Clone>(_: &[T]) -> Option<T>");
+}
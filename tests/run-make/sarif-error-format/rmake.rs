@@ -0,0 +1,45 @@
+// Check that `--error-format=sarif` writes a single SARIF log to stderr, which includes both the
+// warnings emitted while parsing the command line and those emitted during the compilation.
+
+//@ ignore-cross-compile
+
+use run_make_support::rustc;
+use run_make_support::serde_json::{self, Value};
+
+fn main() {
+    let output = rustc()
+        .input("lib.rs")
+        .crate_type("lib")
+        .arg("--error-format=sarif")
+        .arg("-Zunstable-options")
+        // Early warnings, emitted before the session is created.
+        .emit("asm")
+        .output("lib.s")
+        .codegen_units(2)
+        .run();
+
+    // The whole of stderr is a single JSON document.
+    let log: Value = serde_json::from_str(&output.stderr_utf8()).unwrap();
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    assert_eq!(run["invocations"][0]["executionSuccessful"], true);
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3, "{results:#?}");
+    assert_eq!(
+        results[0]["message"]["text"],
+        "`--emit=asm` with `-o` incompatible with `-C codegen-units=N` for N > 1"
+    );
+    assert_eq!(results[1]["message"]["text"], "resetting to default -C codegen-units=1");
+
+    let dead_code = &results[2];
+    let text = dead_code["message"]["text"].as_str().unwrap();
+    assert!(text.starts_with("function `unused` is never used"), "{text}");
+    assert_eq!(dead_code["level"], "warning");
+    assert_eq!(dead_code["ruleId"], "dead_code");
+    let rule_index = dead_code["ruleIndex"].as_u64().unwrap() as usize;
+    assert_eq!(run["tool"]["driver"]["rules"][rule_index]["id"], "dead_code");
+    let location = &dead_code["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "lib.rs");
+    assert_eq!(location["region"]["startLine"], 1);
+}
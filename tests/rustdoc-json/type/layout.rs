@@ -0,0 +1,58 @@
+// Check that `--show-type-layout` adds layouts to structs, enums and unions.
+
+//@ compile-flags: -Z unstable-options --show-type-layout
+
+//@ is "$.index[*][?(@.name=='Pair')].inner.struct.layout.size" 8
+//@ is "$.index[*][?(@.name=='Pair')].inner.struct.layout.align" 4
+//@ is "$.index[*][?(@.name=='Pair')].inner.struct.layout.fields[0].name" '"a"'
+//@ is "$.index[*][?(@.name=='Pair')].inner.struct.layout.fields[0].offset" 0
+//@ is "$.index[*][?(@.name=='Pair')].inner.struct.layout.fields[1].name" '"b"'
+//@ is "$.index[*][?(@.name=='Pair')].inner.struct.layout.fields[1].offset" 4
+//@ is "$.index[*][?(@.name=='Pair')].inner.struct.layout.fields[1].size" 4
+#[repr(C)]
+pub struct Pair {
+    pub a: u8,
+    b: u32,
+}
+
+//@ is "$.index[*][?(@.name=='Tuple')].inner.struct.layout.fields[1].name" '"1"'
+//@ is "$.index[*][?(@.name=='Tuple')].inner.struct.layout.fields[1].offset" 2
+#[repr(C)]
+pub struct Tuple(pub u8, pub u16);
+
+//@ is "$.index[*][?(@.name=='Generic')].inner.struct.layout" null
+pub struct Generic<T>(pub T);
+
+//@ is "$.index[*][?(@.name=='Both')].inner.union.layout.size" 4
+//@ is "$.index[*][?(@.name=='Both')].inner.union.layout.fields[1].offset" 0
+#[repr(C)]
+pub union Both {
+    pub small: u8,
+    pub big: u32,
+}
+
+//@ is "$.index[*][?(@.name=='Direct')].inner.enum.layout.size" 4
+//@ is "$.index[*][?(@.name=='Direct')].inner.enum.layout.tag.size" 2
+//@ is "$.index[*][?(@.name=='Direct')].inner.enum.layout.tag.encoding" '"direct"'
+//@ is "$.index[*][?(@.name=='Direct')].inner.enum.layout.variants[0].name" '"A"'
+//@ is "$.index[*][?(@.name=='Direct')].inner.enum.layout.variants[0].discriminant" '"-1"'
+//@ is "$.index[*][?(@.name=='Direct')].inner.enum.layout.variants[1].discriminant" '"0"'
+//@ is "$.index[*][?(@.name=='Direct')].inner.enum.layout.variants[1].fields[0].offset" 2
+#[repr(i16)]
+pub enum Direct {
+    A = -1,
+    B(u8),
+}
+
+//@ is "$.index[*][?(@.name=='Niched')].inner.enum.layout.size" 1
+//@ is "$.index[*][?(@.name=='Niched')].inner.enum.layout.tag.encoding.niche.untagged_variant" 0
+//@ is "$.index[*][?(@.name=='Niched')].inner.enum.layout.tag.encoding.niche.niche_start" '"2"'
+//@ is "$.index[*][?(@.name=='Niched')].inner.enum.layout.niche.valid_range_end" '"2"'
+pub enum Niched {
+    Some(bool),
+    None,
+}
+
+//@ is "$.index[*][?(@.name=='Never')].inner.enum.layout.is_uninhabited" true
+//@ is "$.index[*][?(@.name=='Never')].inner.enum.layout.variants" []
+pub enum Never {}
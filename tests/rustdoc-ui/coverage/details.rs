@@ -0,0 +1,25 @@
+//@ compile-flags:-Z unstable-options --show-coverage --coverage-details
+//@ check-pass
+
+//! Crate documentation.
+
+/// Documented, with an example.
+///
+/// ```
+/// let x = 1;
+/// ```
+pub struct Documented;
+
+pub struct Undocumented {
+    pub field: u8,
+}
+
+impl Undocumented {
+    /// Documented, but without an example.
+    pub fn method(&self) {}
+}
+
+/// Documented module.
+pub mod module {
+    pub fn function() {}
+}
@@ -0,0 +1,11 @@
+//@ compile-flags:-Z unstable-options --show-coverage --coverage-threshold=75
+
+//! The crate as a whole meets the threshold, but one of its modules doesn't.
+
+/// Documented.
+pub fn documented() {}
+
+/// Documented module.
+pub mod module {
+    pub fn undocumented() {}
+}
@@ -74,6 +74,7 @@ fn compile(code: String, output: PathBuf, sysroot: PathBuf, linker: Option<&Path
         registry: rustc_driver::diagnostics_registry(),
         using_internal_features: &rustc_driver::USING_INTERNAL_FEATURES,
         expanded_args: Default::default(),
+        early_sarif_results: Default::default(),
     };
 
     interface::run_compiler(config, |compiler| {
@@ -0,0 +1,23 @@
+//@ edition:2015
+//@ check-pass
+//@ remap-src-base
+//@ compile-flags: -Zdeduplicate-diagnostics=yes -Dbare-trait-objects
+//@ compile-flags: -Zdiagnostic-baseline={{src-base}}/lint/diagnostic-baseline-duplicate.txt
+
+// Checks that a lint suppressed by the diagnostic baseline stays suppressed, and is not counted as
+// an error, when it is emitted again and deduplicated, even though the baseline lists it once.
+
+fn f() -> impl AsRef<Fn(&())> {
+    Foo
+}
+
+struct Foo;
+impl AsRef<dyn Fn(&())> for Foo {
+    fn as_ref(&self) -> &(dyn for<'a> Fn(&'a ()) + 'static) {
+        todo!()
+    }
+}
+
+fn main() {
+    let _ = f();
+}
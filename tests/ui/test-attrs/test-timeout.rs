@@ -0,0 +1,30 @@
+//@ no-prefer-dynamic
+//@ compile-flags: --test -Cpanic=abort -Zpanic_abort_tests
+//@ run-flags: --test-threads=1 -Zunstable-options --test-timeout=1
+//@ run-fail
+//@ check-run-results
+//@ exec-env:RUST_BACKTRACE=0
+//@ normalize-stdout: "finished in \d+\.\d+s" -> "finished in $$TIME"
+
+//@ ignore-android #120567
+//@ needs-subprocess
+
+// Checks that `--test-timeout` kills tests that don't finish in time.
+
+#![cfg(test)]
+
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn it_hangs() {
+    println!("about to hang");
+    loop {
+        thread::sleep(Duration::from_secs(60));
+    }
+}
+
+#[test]
+fn it_works() {
+    assert_eq!(1 + 1, 2);
+}